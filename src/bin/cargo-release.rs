@@ -15,18 +15,25 @@ fn run() -> Result<(), error::CliError> {
 
     let mut builder = get_logging(release_matches.logging.log_level());
     builder.init();
+    if release_matches.logging.quiet_summary {
+        ops::shell::set_filter(ops::shell::Filter::Summary);
+    }
 
     match &release_matches.step {
         Some(Step::Changes(config)) => config.run(),
+        Some(Step::Verify(config)) => config.run(),
         Some(Step::Version(config)) => config.run(),
         Some(Step::Replace(config)) => config.run(),
         Some(Step::Hook(config)) => config.run(),
         Some(Step::Commit(config)) => config.run(),
         Some(Step::Publish(config)) => config.run(),
+        Some(Step::Wait(config)) => config.run(),
         Some(Step::Owner(config)) => config.run(),
         Some(Step::Tag(config)) => config.run(),
         Some(Step::Push(config)) => config.run(),
         Some(Step::Config(config)) => config.run(),
+        Some(Step::Init(config)) => config.run(),
+        Some(Step::GenerateMatrix(config)) => config.run(),
         None => release_matches.release.run(),
     }
 }
@@ -76,15 +83,19 @@ pub struct ReleaseOpt {
 #[derive(Clone, Debug, clap::Subcommand)]
 pub enum Step {
     Changes(steps::changes::ChangesStep),
+    Verify(steps::verify::VerifyStep),
     Version(steps::version::VersionStep),
     Replace(steps::replace::ReplaceStep),
     Hook(steps::hook::HookStep),
     Commit(steps::commit::CommitStep),
     Publish(steps::publish::PublishStep),
+    Wait(steps::wait::WaitStep),
     Owner(steps::owner::OwnerStep),
     Tag(steps::tag::TagStep),
     Push(steps::push::PushStep),
     Config(steps::config::ConfigStep),
+    Init(steps::init::InitStep),
+    GenerateMatrix(steps::generate_matrix::GenerateMatrixStep),
 }
 
 #[derive(clap::Args, Debug, Clone)]
@@ -100,6 +111,10 @@ pub struct Verbosity {
     /// logs, `-vv` adds trace logs.
     #[arg(long, short, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
+
+    /// Only report failures, the release plan, and the final verdict
+    #[arg(long, global = true)]
+    quiet_summary: bool,
 }
 
 impl Verbosity {
@@ -122,3 +137,62 @@ fn verify_app() {
     use clap::CommandFactory;
     Command::command().debug_assert()
 }
+
+/// Each step, with any arguments it needs to parse
+#[cfg(test)]
+const STEPS: &[&[&str]] = &[
+    &["changes"],
+    &["verify"],
+    &["version", "patch"],
+    &["replace"],
+    &["hook"],
+    &["commit"],
+    &["publish"],
+    &["wait"],
+    &["owner"],
+    &["tag"],
+    &["push"],
+    &["config"],
+    &["init"],
+    &["generate-matrix"],
+];
+
+#[cfg(test)]
+fn parse(step: &[&str], flags: &[&str]) -> Result<Command, clap::Error> {
+    let args = ["cargo", "release"].iter().chain(step).chain(flags);
+    Command::try_parse_from(args)
+}
+
+#[test]
+fn dry_run_is_accepted_everywhere() {
+    parse(&[], &["--dry-run"]).unwrap();
+    parse(&[], &["-n"]).unwrap();
+    for step in STEPS {
+        parse(step, &["--dry-run"])
+            .unwrap_or_else(|err| panic!("`{}` rejected `--dry-run`: {}", step[0], err));
+    }
+}
+
+#[test]
+fn dry_run_conflicts_with_execute() {
+    let executable = STEPS
+        .iter()
+        .filter(|step| parse(step, &["--execute"]).is_ok());
+    for step in std::iter::once(&(&[] as &[&str])).chain(executable) {
+        // Parsing succeeds so the conflict can be explained in our own words
+        let Command::Release(release) = parse(step, &["--dry-run", "--execute"]).unwrap();
+        let result = match &release.step {
+            Some(Step::Version(step)) => step.run(),
+            Some(Step::Replace(step)) => step.run(),
+            Some(Step::Hook(step)) => step.run(),
+            Some(Step::Commit(step)) => step.run(),
+            Some(Step::Publish(step)) => step.run(),
+            Some(Step::Owner(step)) => step.run(),
+            Some(Step::Tag(step)) => step.run(),
+            Some(Step::Push(step)) => step.run(),
+            Some(step) => panic!("`{:?}` takes `--execute`, add it here", step),
+            None => release.release.run(),
+        };
+        assert_eq!(error::report(result), 2, "{:?}", step);
+    }
+}
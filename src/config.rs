@@ -13,30 +13,77 @@ pub struct Config {
     #[serde(skip)]
     pub is_workspace: bool,
     pub allow_branch: Option<Vec<String>>,
+    pub clean_check_path: Option<PathBuf>,
+    pub allow_dirty: Option<Vec<String>>,
     pub sign_commit: Option<bool>,
+    pub no_verify_git_hooks: Option<bool>,
     pub sign_tag: Option<bool>,
     pub push_remote: Option<String>,
+    pub push_url: Option<String>,
     pub registry: Option<String>,
+    pub index_url: Option<String>,
     pub release: Option<bool>,
+    pub allow_placeholder: Option<bool>,
+    pub placeholder_versions: Option<Vec<String>>,
     pub publish: Option<bool>,
+    pub publish_wait: Option<bool>,
+    pub publish_log_dir: Option<PathBuf>,
+    pub publish_retries: Option<u32>,
+    pub publish_timeout: Option<u64>,
+    pub publish_grace_sleep: Option<u64>,
+    pub publish_poll_interval: Option<u64>,
+    pub publish_skip_identical: Option<bool>,
     pub verify: Option<bool>,
     pub owners: Option<Vec<String>>,
+    pub verify_owners: Option<bool>,
+    pub verify_tag_annotation: Option<bool>,
     pub push: Option<bool>,
     pub push_options: Option<Vec<String>>,
+    pub push_notes: Option<bool>,
     pub shared_version: Option<SharedVersion>,
     pub consolidate_commits: Option<bool>,
-    pub pre_release_commit_message: Option<String>,
+    pub pre_release_commit_message: Option<MessageTemplate>,
     pub pre_release_replacements: Option<Vec<Replace>>,
     pub pre_release_hook: Option<Command>,
-    pub tag_message: Option<String>,
+    pub post_publish_hook: Option<Command>,
+    pub post_publish_hook_scope: Option<HookScope>,
+    pub post_release_hook: Option<Command>,
+    pub template_vars: Option<std::collections::BTreeMap<String, String>>,
+    pub sync_commit_message: Option<MessageTemplate>,
+    pub tag_message: Option<MessageTemplate>,
+    pub github_release: Option<bool>,
+    pub release_notes: Option<MessageTemplate>,
+    pub tag_message_source: Option<TagMessageSource>,
+    pub tag_message_body: Option<TagMessageBody>,
+    pub notes_ref: Option<String>,
     pub tag_prefix: Option<String>,
+    pub is_root: Option<bool>,
     pub tag_name: Option<String>,
     pub tag: Option<bool>,
+    pub tag_prerelease: Option<bool>,
+    pub tag_unpublished: Option<bool>,
     pub enable_features: Option<Vec<String>>,
     pub enable_all_features: Option<bool>,
+    pub verify_feature_sets: Option<Vec<Vec<String>>>,
     pub dependent_version: Option<DependentVersion>,
+    pub pin_stability_warning: Option<bool>,
     pub metadata: Option<MetadataPolicy>,
+    pub change_paths: Option<ChangePaths>,
+    pub certify_changes_ignore: Option<Vec<String>>,
+    pub publish_as: Option<String>,
+    pub max_package_size: Option<String>,
+    pub commit_types: Option<CommitTypes>,
     pub target: Option<String>,
+    pub config_conflicts: Option<ConfigConflicts>,
+    /// Where this config was read from
+    #[serde(skip)]
+    pub origin: Option<String>,
+    /// Where each tracked field's value was read from
+    #[serde(skip)]
+    pub origins: std::collections::BTreeMap<&'static str, String>,
+    /// Values that a later source changed from what an earlier source set
+    #[serde(skip)]
+    pub conflicts: Vec<String>,
 }
 
 impl Config {
@@ -54,14 +101,35 @@ impl Config {
                     .map(|s| s.to_owned())
                     .collect::<Vec<String>>(),
             ),
+            clean_check_path: None,
+            allow_dirty: Some(empty.allow_dirty().to_vec()),
             sign_commit: Some(empty.sign_commit()),
+            no_verify_git_hooks: Some(empty.no_verify_git_hooks()),
             sign_tag: Some(empty.sign_tag()),
             push_remote: Some(empty.push_remote().to_owned()),
+            push_url: empty.push_url().map(|s| s.to_owned()),
             registry: empty.registry().map(|s| s.to_owned()),
+            index_url: empty.index_url().map(|s| s.to_owned()),
             release: Some(empty.release()),
+            allow_placeholder: Some(empty.allow_placeholder()),
+            placeholder_versions: Some(
+                empty
+                    .placeholder_versions()
+                    .map(|s| s.to_owned())
+                    .collect::<Vec<String>>(),
+            ),
             publish: Some(empty.publish()),
+            publish_wait: Some(empty.publish_wait()),
+            publish_log_dir: None,
+            publish_retries: Some(empty.publish_retries()),
+            publish_timeout: Some(crate::ops::cargo::DEFAULT_PUBLISH_TIMEOUT.as_secs()),
+            publish_grace_sleep: empty.publish_grace_sleep,
+            publish_poll_interval: Some(crate::ops::cargo::DEFAULT_POLL_INTERVAL.as_secs()),
+            publish_skip_identical: Some(empty.publish_skip_identical()),
             verify: Some(empty.verify()),
             owners: Some(empty.owners().to_vec()),
+            verify_owners: Some(empty.verify_owners()),
+            verify_tag_annotation: Some(empty.verify_tag_annotation()),
             push: Some(empty.push()),
             push_options: Some(
                 empty
@@ -69,101 +137,464 @@ impl Config {
                     .map(|s| s.to_owned())
                     .collect::<Vec<String>>(),
             ),
+            push_notes: Some(empty.push_notes()),
             shared_version: empty
                 .shared_version()
                 .map(|s| SharedVersion::Name(s.to_owned())),
             consolidate_commits: Some(empty.consolidate_commits()),
-            pre_release_commit_message: Some(empty.pre_release_commit_message().to_owned()),
+            pre_release_commit_message: Some(MessageTemplate::Inline(
+                empty.pre_release_commit_message().to_owned(),
+            )),
             pre_release_replacements: Some(empty.pre_release_replacements().to_vec()),
             pre_release_hook: empty.pre_release_hook().cloned(),
-            tag_message: Some(empty.tag_message().to_owned()),
+            post_publish_hook: empty.post_publish_hook().cloned(),
+            post_publish_hook_scope: Some(empty.post_publish_hook_scope()),
+            post_release_hook: empty.post_release_hook().cloned(),
+            template_vars: empty.template_vars().cloned(),
+            sync_commit_message: Some(MessageTemplate::Inline(
+                empty.sync_commit_message().to_owned(),
+            )),
+            tag_message: Some(MessageTemplate::Inline(empty.tag_message().to_owned())),
+            github_release: Some(empty.github_release()),
+            release_notes: Some(MessageTemplate::Inline(empty.release_notes().to_owned())),
+            tag_message_source: Some(empty.tag_message_source()),
+            tag_message_body: Some(empty.tag_message_body()),
+            notes_ref: Some(empty.notes_ref().to_owned()),
             tag_prefix: None, // Skipping, its location dependent
+            is_root: None,    // Skipping, its location dependent
             tag_name: Some(empty.tag_name().to_owned()),
             tag: Some(empty.tag()),
+            tag_prerelease: Some(empty.tag_prerelease()),
+            tag_unpublished: Some(empty.tag_unpublished()),
             enable_features: Some(empty.enable_features().to_vec()),
             enable_all_features: Some(empty.enable_all_features()),
+            verify_feature_sets: Some(empty.verify_feature_sets().to_vec()),
             dependent_version: Some(empty.dependent_version()),
+            pin_stability_warning: Some(empty.pin_stability_warning()),
             metadata: Some(empty.metadata()),
+            change_paths: Some(empty.change_paths()),
+            certify_changes_ignore: Some(empty.certify_changes_ignore().to_vec()),
+            publish_as: None,
+            max_package_size: None,
+            commit_types: Some(empty.commit_types().clone()),
             target: None,
+            config_conflicts: Some(empty.config_conflicts()),
+            origin: None,
+            origins: Default::default(),
+            conflicts: Vec::new(),
         }
     }
 
     pub fn update(&mut self, source: &Config) {
+        self.conflicts.extend(source.conflicts.iter().cloned());
         if let Some(allow_branch) = source.allow_branch.as_deref() {
+            self.note_origin("allow-branch", source);
             self.allow_branch = Some(allow_branch.to_owned());
         }
+        if let Some(clean_check_path) = source.clean_check_path.as_deref() {
+            self.clean_check_path = Some(clean_check_path.to_owned());
+        }
+        if let Some(allow_dirty) = source.allow_dirty.as_deref() {
+            self.note_origin("allow-dirty", source);
+            self.allow_dirty = Some(allow_dirty.to_owned());
+        }
         if let Some(sign_commit) = source.sign_commit {
+            self.track("sign-commit", self.sign_commit, sign_commit, source);
             self.sign_commit = Some(sign_commit);
         }
+        if let Some(no_verify_git_hooks) = source.no_verify_git_hooks {
+            self.track(
+                "no-verify-git-hooks",
+                self.no_verify_git_hooks,
+                no_verify_git_hooks,
+                source,
+            );
+            self.no_verify_git_hooks = Some(no_verify_git_hooks);
+        }
         if let Some(sign_tag) = source.sign_tag {
+            self.track("sign-tag", self.sign_tag, sign_tag, source);
             self.sign_tag = Some(sign_tag);
         }
         if let Some(push_remote) = source.push_remote.as_deref() {
+            self.note_origin("push-remote", source);
             self.push_remote = Some(push_remote.to_owned());
         }
+        if let Some(push_url) = source.push_url.as_deref() {
+            self.note_origin("push-url", source);
+            self.push_url = Some(push_url.to_owned());
+        }
         if let Some(registry) = source.registry.as_deref() {
+            self.note_origin("registry", source);
             self.registry = Some(registry.to_owned());
         }
+        if let Some(index_url) = source.index_url.as_deref() {
+            self.note_origin("index-url", source);
+            self.index_url = Some(index_url.to_owned());
+        }
         if let Some(release) = source.release {
+            self.track("release", self.release, release, source);
             self.release = Some(release);
         }
+        if let Some(allow_placeholder) = source.allow_placeholder {
+            self.track(
+                "allow-placeholder",
+                self.allow_placeholder,
+                allow_placeholder,
+                source,
+            );
+            self.allow_placeholder = Some(allow_placeholder);
+        }
+        if let Some(placeholder_versions) = source.placeholder_versions.as_deref() {
+            self.note_origin("placeholder-versions", source);
+            self.placeholder_versions = Some(placeholder_versions.to_owned());
+        }
         if let Some(publish) = source.publish {
+            self.track("publish", self.publish, publish, source);
             self.publish = Some(publish);
         }
+        if let Some(publish_wait) = source.publish_wait {
+            self.track("publish-wait", self.publish_wait, publish_wait, source);
+            self.publish_wait = Some(publish_wait);
+        }
+        if let Some(publish_log_dir) = source.publish_log_dir.as_deref() {
+            self.publish_log_dir = Some(publish_log_dir.to_owned());
+        }
+        if let Some(publish_retries) = source.publish_retries {
+            self.track(
+                "publish-retries",
+                self.publish_retries,
+                publish_retries,
+                source,
+            );
+            self.publish_retries = Some(publish_retries);
+        }
+        if let Some(publish_timeout) = source.publish_timeout {
+            self.track(
+                "publish-timeout",
+                self.publish_timeout,
+                publish_timeout,
+                source,
+            );
+            self.publish_timeout = Some(publish_timeout);
+        }
+        if let Some(publish_grace_sleep) = source.publish_grace_sleep {
+            self.track(
+                "publish-grace-sleep",
+                self.publish_grace_sleep,
+                publish_grace_sleep,
+                source,
+            );
+            self.publish_grace_sleep = Some(publish_grace_sleep);
+        }
+        if let Some(publish_poll_interval) = source.publish_poll_interval {
+            self.track(
+                "publish-poll-interval",
+                self.publish_poll_interval,
+                publish_poll_interval,
+                source,
+            );
+            self.publish_poll_interval = Some(publish_poll_interval);
+        }
+        if let Some(publish_skip_identical) = source.publish_skip_identical {
+            self.track(
+                "publish-skip-identical",
+                self.publish_skip_identical,
+                publish_skip_identical,
+                source,
+            );
+            self.publish_skip_identical = Some(publish_skip_identical);
+        }
         if let Some(verify) = source.verify {
+            self.track("verify", self.verify, verify, source);
             self.verify = Some(verify);
         }
         if let Some(owners) = source.owners.as_deref() {
+            self.note_origin("owners", source);
             self.owners = Some(owners.to_owned());
         }
+        if let Some(verify_owners) = source.verify_owners {
+            self.track("verify-owners", self.verify_owners, verify_owners, source);
+            self.verify_owners = Some(verify_owners);
+        }
+        if let Some(verify_tag_annotation) = source.verify_tag_annotation {
+            self.track(
+                "verify-tag-annotation",
+                self.verify_tag_annotation,
+                verify_tag_annotation,
+                source,
+            );
+            self.verify_tag_annotation = Some(verify_tag_annotation);
+        }
         if let Some(push) = source.push {
+            self.track("push", self.push, push, source);
             self.push = Some(push);
         }
         if let Some(push_options) = source.push_options.as_deref() {
+            self.note_origin("push-options", source);
             self.push_options = Some(push_options.to_owned());
         }
+        if let Some(push_notes) = source.push_notes {
+            self.track("push-notes", self.push_notes, push_notes, source);
+            self.push_notes = Some(push_notes);
+        }
         if let Some(shared_version) = source.shared_version.clone() {
+            self.note_origin("shared-version", source);
             self.shared_version = Some(shared_version);
         }
         if let Some(consolidate_commits) = source.consolidate_commits {
+            self.track(
+                "consolidate-commits",
+                self.consolidate_commits,
+                consolidate_commits,
+                source,
+            );
             self.consolidate_commits = Some(consolidate_commits);
         }
-        if let Some(pre_release_commit_message) = source.pre_release_commit_message.as_deref() {
-            self.pre_release_commit_message = Some(pre_release_commit_message.to_owned());
+        if let Some(pre_release_commit_message) = source.pre_release_commit_message.as_ref() {
+            self.note_origin("pre-release-commit-message", source);
+            self.pre_release_commit_message = Some(pre_release_commit_message.clone());
         }
         if let Some(pre_release_replacements) = source.pre_release_replacements.as_deref() {
+            self.note_origin("pre-release-replacements", source);
             self.pre_release_replacements = Some(pre_release_replacements.to_owned());
         }
         if let Some(pre_release_hook) = source.pre_release_hook.as_ref() {
             self.pre_release_hook = Some(pre_release_hook.to_owned());
         }
-        if let Some(tag_message) = source.tag_message.as_deref() {
-            self.tag_message = Some(tag_message.to_owned());
+        if let Some(post_publish_hook) = source.post_publish_hook.as_ref() {
+            self.post_publish_hook = Some(post_publish_hook.to_owned());
+        }
+        if let Some(post_publish_hook_scope) = source.post_publish_hook_scope {
+            self.track(
+                "post-publish-hook-scope",
+                self.post_publish_hook_scope,
+                post_publish_hook_scope,
+                source,
+            );
+            self.post_publish_hook_scope = Some(post_publish_hook_scope);
+        }
+        if let Some(post_release_hook) = source.post_release_hook.as_ref() {
+            self.post_release_hook = Some(post_release_hook.to_owned());
+        }
+        if let Some(template_vars) = source.template_vars.as_ref() {
+            self.note_origin("template-vars", source);
+            // Variables are merged, rather than replaced, so each source can add its own
+            self.template_vars
+                .get_or_insert_with(Default::default)
+                .extend(template_vars.clone());
+        }
+        if let Some(sync_commit_message) = source.sync_commit_message.as_ref() {
+            self.note_origin("sync-commit-message", source);
+            self.sync_commit_message = Some(sync_commit_message.clone());
+        }
+        if let Some(tag_message) = source.tag_message.as_ref() {
+            self.note_origin("tag-message", source);
+            self.tag_message = Some(tag_message.clone());
+        }
+        if let Some(github_release) = source.github_release {
+            self.track(
+                "github-release",
+                self.github_release,
+                github_release,
+                source,
+            );
+            self.github_release = Some(github_release);
+        }
+        if let Some(release_notes) = source.release_notes.as_ref() {
+            self.note_origin("release-notes", source);
+            self.release_notes = Some(release_notes.clone());
+        }
+        if let Some(tag_message_source) = source.tag_message_source {
+            self.track(
+                "tag-message-source",
+                self.tag_message_source,
+                tag_message_source,
+                source,
+            );
+            self.tag_message_source = Some(tag_message_source);
+        }
+        if let Some(tag_message_body) = source.tag_message_body {
+            self.track(
+                "tag-message-body",
+                self.tag_message_body,
+                tag_message_body,
+                source,
+            );
+            self.tag_message_body = Some(tag_message_body);
+        }
+        if let Some(notes_ref) = source.notes_ref.as_deref() {
+            self.notes_ref = Some(notes_ref.to_owned());
         }
         if let Some(tag_prefix) = source.tag_prefix.as_deref() {
             self.tag_prefix = Some(tag_prefix.to_owned());
         }
+        if let Some(is_root) = source.is_root {
+            self.track("is-root", self.is_root, is_root, source);
+            self.is_root = Some(is_root);
+        }
         if let Some(tag_name) = source.tag_name.as_deref() {
+            self.note_origin("tag-name", source);
             self.tag_name = Some(tag_name.to_owned());
         }
         if let Some(tag) = source.tag {
+            self.track("tag", self.tag, tag, source);
             self.tag = Some(tag);
         }
+        if let Some(tag_prerelease) = source.tag_prerelease {
+            self.track(
+                "tag-prerelease",
+                self.tag_prerelease,
+                tag_prerelease,
+                source,
+            );
+            self.tag_prerelease = Some(tag_prerelease);
+        }
+        if let Some(tag_unpublished) = source.tag_unpublished {
+            self.track(
+                "tag-unpublished",
+                self.tag_unpublished,
+                tag_unpublished,
+                source,
+            );
+            self.tag_unpublished = Some(tag_unpublished);
+        }
         if let Some(enable_features) = source.enable_features.as_deref() {
             self.enable_features = Some(enable_features.to_owned());
         }
         if let Some(enable_all_features) = source.enable_all_features {
+            self.track(
+                "enable-all-features",
+                self.enable_all_features,
+                enable_all_features,
+                source,
+            );
             self.enable_all_features = Some(enable_all_features);
         }
+        if let Some(verify_feature_sets) = source.verify_feature_sets.as_deref() {
+            self.note_origin("verify-feature-sets", source);
+            self.verify_feature_sets = Some(verify_feature_sets.to_owned());
+        }
         if let Some(dependent_version) = source.dependent_version {
+            self.track(
+                "dependent-version",
+                self.dependent_version,
+                dependent_version,
+                source,
+            );
             self.dependent_version = Some(dependent_version);
         }
+        if let Some(pin_stability_warning) = source.pin_stability_warning {
+            self.track(
+                "pin-stability-warning",
+                self.pin_stability_warning,
+                pin_stability_warning,
+                source,
+            );
+            self.pin_stability_warning = Some(pin_stability_warning);
+        }
         if let Some(metadata) = source.metadata {
+            self.track("metadata", self.metadata, metadata, source);
             self.metadata = Some(metadata);
         }
+        if let Some(change_paths) = source.change_paths {
+            self.track("change-paths", self.change_paths, change_paths, source);
+            self.change_paths = Some(change_paths);
+        }
+        if let Some(certify_changes_ignore) = source.certify_changes_ignore.as_deref() {
+            self.note_origin("certify-changes-ignore", source);
+            self.certify_changes_ignore = Some(certify_changes_ignore.to_owned());
+        }
+        if let Some(publish_as) = source.publish_as.as_deref() {
+            self.note_origin("publish-as", source);
+            self.publish_as = Some(publish_as.to_owned());
+        }
+        if let Some(max_package_size) = source.max_package_size.as_deref() {
+            self.note_origin("max-package-size", source);
+            self.max_package_size = Some(max_package_size.to_owned());
+        }
+        if let Some(commit_types) = source.commit_types.as_ref() {
+            self.note_origin("commit-types", source);
+            self.commit_types
+                .get_or_insert_with(CommitTypes::default)
+                .update(commit_types);
+        }
         if let Some(target) = source.target.as_deref() {
+            self.note_origin("target", source);
             self.target = Some(target.to_owned());
         }
+        if let Some(config_conflicts) = source.config_conflicts {
+            self.config_conflicts = Some(config_conflicts);
+        }
+    }
+
+    /// Record where `field`'s new value came from, for fields not checked for conflicts
+    fn note_origin(&mut self, field: &'static str, source: &Config) {
+        match source.origins.get(field).or(source.origin.as_ref()) {
+            Some(origin) => {
+                self.origins.insert(field, origin.clone());
+            }
+            None => {
+                self.origins.remove(field);
+            }
+        }
+    }
+
+    /// Record where `field`'s new `value` came from, noting when it changes a value that an earlier
+    /// source set
+    fn track<T: PartialEq + Serialize>(
+        &mut self,
+        field: &'static str,
+        current: Option<T>,
+        value: T,
+        source: &Config,
+    ) {
+        let origin = match source.origins.get(field).or(source.origin.as_ref()) {
+            Some(origin) => origin.clone(),
+            None => {
+                self.origins.remove(field);
+                return;
+            }
+        };
+        if let (Some(current), Some(earlier)) = (current, self.origins.get(field)) {
+            if current != value {
+                self.conflicts.push(format!(
+                    "`{} = {}` from {} is overridden by `{} = {}` from {}",
+                    field,
+                    display_value(&current),
+                    earlier,
+                    field,
+                    display_value(&value),
+                    origin
+                ));
+            }
+        }
+        self.origins.insert(field, origin);
+    }
+
+    /// Values that a later config source changed from what an earlier source set, to warn about
+    ///
+    /// Fails instead with `config-conflicts = "error"`.
+    pub fn conflict_warnings(&self) -> CargoResult<Vec<String>> {
+        if self.conflicts.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self.config_conflicts() {
+            ConfigConflicts::Warn => Ok(self.conflicts.clone()),
+            ConfigConflicts::Error => {
+                anyhow::bail!(
+                    "conflicting configuration:\n  {}",
+                    self.conflicts.join("\n  ")
+                );
+            }
+        }
+    }
+
+    /// Report values that a later config source changed from what an earlier source set
+    pub fn report_conflicts(&self) -> CargoResult<()> {
+        for conflict in self.conflict_warnings()? {
+            warn_once(conflict);
+        }
+        Ok(())
     }
 
     pub fn allow_branch(&self) -> impl Iterator<Item = &str> {
@@ -173,10 +604,24 @@ impl Config {
             .unwrap_or_else(|| itertools::Either::Right(IntoIterator::into_iter(["*", "!HEAD"])))
     }
 
+    /// Only check this subtree, relative to the workspace root, for uncommitted changes
+    pub fn clean_check_path(&self) -> Option<&Path> {
+        self.clean_check_path.as_deref()
+    }
+
+    /// Globs, relative to the workspace root, for uncommitted files that don't block a release
+    pub fn allow_dirty(&self) -> &[String] {
+        self.allow_dirty.as_ref().map(|v| v.as_ref()).unwrap_or(&[])
+    }
+
     pub fn sign_commit(&self) -> bool {
         self.sign_commit.unwrap_or(false)
     }
 
+    pub fn no_verify_git_hooks(&self) -> bool {
+        self.no_verify_git_hooks.unwrap_or(false)
+    }
+
     pub fn sign_tag(&self) -> bool {
         self.sign_tag.unwrap_or(false)
     }
@@ -185,18 +630,109 @@ impl Config {
         self.push_remote.as_deref().unwrap_or("origin")
     }
 
+    /// Where to push, instead of `push-remote` which is still used for fetching
+    pub fn push_url(&self) -> Option<&str> {
+        self.push_url.as_deref()
+    }
+
     pub fn registry(&self) -> Option<&str> {
         self.registry.as_deref()
     }
 
+    /// The index to publish to when there is no registry defined for it in cargo config
+    pub fn index_url(&self) -> Option<&str> {
+        self.index_url.as_deref()
+    }
+
+    /// Whether publishing goes to crates.io, the only registry indexed locally
+    pub fn is_crates_io(&self) -> bool {
+        self.registry.is_none() && self.index_url.is_none()
+    }
+
     pub fn release(&self) -> bool {
         self.release.unwrap_or(true)
     }
 
+    pub fn allow_placeholder(&self) -> bool {
+        self.allow_placeholder.unwrap_or(false)
+    }
+
+    /// Globs for versions that mark a package as not ready for a real release
+    pub fn placeholder_versions(&self) -> impl Iterator<Item = &str> {
+        self.placeholder_versions
+            .as_deref()
+            .map(|a| itertools::Either::Left(a.iter().map(|s| s.as_str())))
+            .unwrap_or_else(|| itertools::Either::Right(IntoIterator::into_iter(["0.0.0"])))
+    }
+
+    pub fn is_placeholder_version(&self, version: &str) -> bool {
+        self.placeholder_versions().any(|pattern| {
+            globset::Glob::new(pattern)
+                .map(|glob| glob.compile_matcher().is_match(version))
+                .unwrap_or_else(|_| pattern == version)
+        })
+    }
+
     pub fn publish(&self) -> bool {
         self.publish.unwrap_or(true)
     }
 
+    pub fn publish_wait(&self) -> bool {
+        self.publish_wait.unwrap_or(true)
+    }
+
+    /// Where to keep the output of each `cargo publish`, relative to the workspace root
+    pub fn publish_log_dir(&self) -> Option<&Path> {
+        self.publish_log_dir.as_deref()
+    }
+
+    /// How many more times to try a `cargo publish` that failed in a way that looks transient
+    pub fn publish_retries(&self) -> u32 {
+        self.publish_retries.unwrap_or(0)
+    }
+
+    /// How long to wait for a published crate to appear in the index
+    ///
+    /// With `publish-timeout = 0`, this is how long the deferred wait gets, see
+    /// [`Config::publish_wait_deferred`].
+    pub fn publish_timeout(&self) -> std::time::Duration {
+        match self.publish_timeout {
+            Some(0) | None => crate::ops::cargo::DEFAULT_PUBLISH_TIMEOUT,
+            Some(secs) => std::time::Duration::from_secs(secs),
+        }
+    }
+
+    /// Whether to skip waiting on a published crate until a crate depending on it is about to
+    /// publish, with `publish-timeout = 0`
+    pub fn publish_wait_deferred(&self) -> bool {
+        self.publish_timeout == Some(0)
+    }
+
+    /// How often to check the index while waiting on a published crate
+    pub fn publish_poll_interval(&self) -> std::time::Duration {
+        self.publish_poll_interval
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(crate::ops::cargo::DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Treat a version already published with the same `.crate` as published, rather than failing
+    pub fn publish_skip_identical(&self) -> bool {
+        self.publish_skip_identical.unwrap_or(false)
+    }
+
+    /// How long to wait after a published crate appears in the index, for crates.io to catch up
+    ///
+    /// Falls back to `PUBLISH_GRACE_SLEEP`.
+    pub fn publish_grace_sleep(&self) -> std::time::Duration {
+        let secs = self.publish_grace_sleep.unwrap_or_else(|| {
+            std::env::var("PUBLISH_GRACE_SLEEP")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0)
+        });
+        std::time::Duration::from_secs(secs)
+    }
+
     pub fn verify(&self) -> bool {
         self.verify.unwrap_or(true)
     }
@@ -205,6 +741,16 @@ impl Config {
         self.owners.as_ref().map(|v| v.as_ref()).unwrap_or(&[])
     }
 
+    /// Block releasing a published crate when one of `owners` no longer owns it
+    pub fn verify_owners(&self) -> bool {
+        self.verify_owners.unwrap_or(false)
+    }
+
+    /// Block pushing tags that aren't annotated and signed, when `sign-tag` is set
+    pub fn verify_tag_annotation(&self) -> bool {
+        self.verify_tag_annotation.unwrap_or(false)
+    }
+
     pub fn push(&self) -> bool {
         self.push.unwrap_or(true)
     }
@@ -216,17 +762,29 @@ impl Config {
             .flat_map(|v| v.iter().map(|s| s.as_str()))
     }
 
+    pub fn push_notes(&self) -> bool {
+        self.push_notes.unwrap_or(false)
+    }
+
     pub fn shared_version(&self) -> Option<&str> {
         self.shared_version.as_ref().and_then(|s| s.as_name())
     }
 
+    pub fn shared_version_only_on_change(&self) -> bool {
+        self.shared_version
+            .as_ref()
+            .map(|s| s.only_on_change())
+            .unwrap_or(false)
+    }
+
     pub fn consolidate_commits(&self) -> bool {
         self.consolidate_commits.unwrap_or(self.is_workspace)
     }
 
     pub fn pre_release_commit_message(&self) -> &str {
         self.pre_release_commit_message
-            .as_deref()
+            .as_ref()
+            .and_then(|m| m.as_inline())
             .unwrap_or_else(|| {
                 if self.consolidate_commits() {
                     "chore: Release"
@@ -247,12 +805,84 @@ impl Config {
         self.pre_release_hook.as_ref()
     }
 
+    pub fn post_publish_hook(&self) -> Option<&Command> {
+        self.post_publish_hook.as_ref()
+    }
+
+    pub fn post_publish_hook_scope(&self) -> HookScope {
+        self.post_publish_hook_scope.unwrap_or_default()
+    }
+
+    pub fn post_release_hook(&self) -> Option<&Command> {
+        self.post_release_hook.as_ref()
+    }
+
+    /// User-defined placeholders for templates
+    pub fn template_vars(&self) -> Option<&std::collections::BTreeMap<String, String>> {
+        self.template_vars.as_ref()
+    }
+
+    pub fn sync_commit_message(&self) -> &str {
+        self.sync_commit_message
+            .as_ref()
+            .and_then(|m| m.as_inline())
+            .unwrap_or("chore: Sync workspace dependency requirements")
+    }
+
     pub fn tag_message(&self) -> &str {
         self.tag_message
-            .as_deref()
+            .as_ref()
+            .and_then(|m| m.as_inline())
+            // `tag-message = "changes"` selects the source rather than being a template
+            .filter(|m| *m != TAG_MESSAGE_CHANGES)
             .unwrap_or("chore: Release {{crate_name}} version {{version}}")
     }
 
+    /// Create a GitHub release for the tag after pushing it
+    pub fn github_release(&self) -> bool {
+        self.github_release.unwrap_or(false)
+    }
+
+    /// The body of the GitHub release
+    pub fn release_notes(&self) -> &str {
+        self.release_notes
+            .as_ref()
+            .and_then(|m| m.as_inline())
+            .unwrap_or("{{changes}}")
+    }
+
+    /// Whether tags get a message, making them annotated rather than lightweight
+    pub fn annotated_tag(&self) -> bool {
+        self.tag_message_source() != TagMessageSource::Template || !self.tag_message().is_empty()
+    }
+
+    pub fn tag_message_source(&self) -> TagMessageSource {
+        match self.tag_message_source.unwrap_or_default() {
+            TagMessageSource::Template
+                if self.tag_message.as_ref().and_then(|m| m.as_inline())
+                    == Some(TAG_MESSAGE_CHANGES) =>
+            {
+                TagMessageSource::Changes
+            }
+            source => source,
+        }
+    }
+
+    pub fn tag_message_body(&self) -> TagMessageBody {
+        self.tag_message_body.unwrap_or_default()
+    }
+
+    /// The git notes ref holding release notes, for `tag-message-source = "git-notes"`
+    pub fn notes_ref(&self) -> &str {
+        self.notes_ref.as_deref().unwrap_or("refs/notes/release")
+    }
+
+    /// Whether the package is the repo's main crate, overriding comparing its directory to the git
+    /// root
+    pub fn is_root(&self) -> Option<bool> {
+        self.is_root
+    }
+
     pub fn tag_prefix(&self, is_root: bool) -> &str {
         // crate_name as default tag prefix for multi-crate project
         self.tag_prefix
@@ -268,6 +898,15 @@ impl Config {
         self.tag.unwrap_or(true)
     }
 
+    pub fn tag_prerelease(&self) -> bool {
+        self.tag_prerelease.unwrap_or(true)
+    }
+
+    /// Whether to tag packages that don't get published to the target registry
+    pub fn tag_unpublished(&self) -> bool {
+        self.tag_unpublished.unwrap_or(true)
+    }
+
     pub fn enable_features(&self) -> &[String] {
         self.enable_features
             .as_ref()
@@ -279,6 +918,14 @@ impl Config {
         self.enable_all_features.unwrap_or(false)
     }
 
+    /// Feature sets to verify separately, for crates whose features can't all be enabled at once
+    pub fn verify_feature_sets(&self) -> &[Vec<String>] {
+        self.verify_feature_sets
+            .as_ref()
+            .map(|v| v.as_ref())
+            .unwrap_or(&[])
+    }
+
     pub fn features(&self) -> cargo::Features {
         if self.enable_all_features() {
             cargo::Features::All
@@ -296,9 +943,294 @@ impl Config {
         self.dependent_version.unwrap_or_default()
     }
 
+    /// Warn when a patch release breaks the `=` pins that workspace members model
+    pub fn pin_stability_warning(&self) -> bool {
+        self.pin_stability_warning.unwrap_or(false)
+    }
+
     pub fn metadata(&self) -> MetadataPolicy {
         self.metadata.unwrap_or_default()
     }
+
+    pub fn change_paths(&self) -> ChangePaths {
+        self.change_paths.unwrap_or_default()
+    }
+
+    /// Gitignore-style globs, relative to the package root, for files whose changes don't count
+    pub fn certify_changes_ignore(&self) -> &[String] {
+        self.certify_changes_ignore
+            .as_ref()
+            .map(|v| v.as_ref())
+            .unwrap_or(&[])
+    }
+
+    /// How conventional commit types map to version bumps
+    pub fn commit_types(&self) -> &CommitTypes {
+        static DEFAULT: once_cell::sync::Lazy<CommitTypes> =
+            once_cell::sync::Lazy::new(CommitTypes::default);
+        self.commit_types.as_ref().unwrap_or(&DEFAULT)
+    }
+
+    pub fn config_conflicts(&self) -> ConfigConflicts {
+        self.config_conflicts.unwrap_or_default()
+    }
+
+    /// The crate name to publish under, when it differs from the package name
+    pub fn publish_as(&self) -> Option<&str> {
+        self.publish_as.as_deref()
+    }
+
+    /// The largest `.crate` file to allow, in bytes
+    pub fn max_package_size(&self) -> Option<u64> {
+        self.max_package_size
+            .as_deref()
+            .and_then(|size| parse_size(size).ok())
+    }
+
+    /// Replace templates given as `{ file = "..." }` with the file's content
+    ///
+    /// Accessors only see inline templates, so this runs when loading the config.
+    pub fn read_templates(&mut self, workspace_root: &Path) -> CargoResult<()> {
+        for (field, template) in [
+            (
+                "pre-release-commit-message",
+                &mut self.pre_release_commit_message,
+            ),
+            ("sync-commit-message", &mut self.sync_commit_message),
+            ("tag-message", &mut self.tag_message),
+            ("release-notes", &mut self.release_notes),
+        ] {
+            if let Some(MessageTemplate::File { file }) = template {
+                let path = workspace_root.join(&*file);
+                let content = std::fs::read_to_string(&path).with_context(|| {
+                    format!("failed to read `{}` from {}", field, path.display())
+                })?;
+                *template = Some(MessageTemplate::Inline(content));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check value sanity and cross-field invariants, reporting every violation at once
+    ///
+    /// Each violation names the source that set the offending field, when known.
+    pub fn validate(&self) -> CargoResult<()> {
+        let violations: Vec<_> = self
+            .violations()
+            .into_iter()
+            .map(|(field, violation)| match self.origins.get(field) {
+                Some(origin) => format!("{} (from {})", violation, origin),
+                None => violation,
+            })
+            .collect();
+        if !violations.is_empty() {
+            anyhow::bail!("invalid configuration:\n  {}", violations.join("\n  "));
+        }
+        Ok(())
+    }
+
+    fn violations(&self) -> Vec<(&'static str, String)> {
+        let mut violations = Vec::new();
+        let is_blank = |s: &str| s.trim().is_empty();
+
+        if self.publish() && self.registry.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push((
+                "registry",
+                "`registry` must not be empty when `publish` is enabled".to_owned(),
+            ));
+        }
+        if self.push_remote.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push(("push-remote", "`push-remote` must not be empty".to_owned()));
+        }
+        if self.push_url.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push(("push-url", "`push-url` must not be empty".to_owned()));
+        }
+        if self.index_url.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push(("index-url", "`index-url` must not be empty".to_owned()));
+        }
+        if self.registry.is_some() && self.index_url.is_some() {
+            violations.push((
+                "registry",
+                "`registry` and `index-url` can't both be set; `index-url` is for registries not defined in cargo config"
+                    .to_owned(),
+            ));
+        }
+        if self.tag() && self.tag_name.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push((
+                "tag-name",
+                "`tag-name` must not be empty when `tag` is enabled".to_owned(),
+            ));
+        }
+        match self.pre_release_commit_message.as_ref() {
+            Some(MessageTemplate::Inline(message)) if is_blank(message) => {
+                violations.push((
+                    "pre-release-commit-message",
+                    "`pre-release-commit-message` must not be empty".to_owned(),
+                ));
+            }
+            _ => {}
+        }
+        for (field, template) in [
+            (
+                "pre-release-commit-message",
+                &self.pre_release_commit_message,
+            ),
+            ("sync-commit-message", &self.sync_commit_message),
+            ("tag-message", &self.tag_message),
+            ("release-notes", &self.release_notes),
+        ] {
+            if let Some(MessageTemplate::File { file }) = template {
+                if file.as_os_str().is_empty() {
+                    violations.push((field, format!("`{}` file must not be empty", field)));
+                }
+            }
+        }
+        if self.target.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push(("target", "`target` must not be empty".to_owned()));
+        }
+        if self.publish_as.as_deref().map(is_blank).unwrap_or(false) {
+            violations.push(("publish-as", "`publish-as` must not be empty".to_owned()));
+        }
+        if let Some(Err(err)) = self.max_package_size.as_deref().map(parse_size) {
+            violations.push(("max-package-size", format!("`max-package-size` {}", err)));
+        }
+        if self
+            .shared_version
+            .as_ref()
+            .and_then(|s| s.as_name())
+            .map(is_blank)
+            .unwrap_or(false)
+        {
+            violations.push((
+                "shared-version",
+                "`shared-version` name must not be empty".to_owned(),
+            ));
+        }
+        if self.allow_branch().any(is_blank) {
+            violations.push((
+                "allow-branch",
+                "`allow-branch` globs must not be empty".to_owned(),
+            ));
+        }
+        for pattern in self.placeholder_versions() {
+            if is_blank(pattern) {
+                violations.push((
+                    "placeholder-versions",
+                    "`placeholder-versions` must not contain empty patterns".to_owned(),
+                ));
+            } else if let Err(err) = globset::Glob::new(pattern) {
+                violations.push((
+                    "placeholder-versions",
+                    format!(
+                        "`placeholder-versions` has invalid glob `{}`: {}",
+                        pattern, err
+                    ),
+                ));
+            }
+        }
+        for pattern in self.allow_dirty() {
+            if is_blank(pattern) {
+                violations.push((
+                    "allow-dirty",
+                    "`allow-dirty` must not contain empty patterns".to_owned(),
+                ));
+            } else if let Err(err) = globset::Glob::new(pattern) {
+                violations.push((
+                    "allow-dirty",
+                    format!("`allow-dirty` has invalid glob `{}`: {}", pattern, err),
+                ));
+            }
+        }
+        let mut ignored_changes = ignore::gitignore::GitignoreBuilder::new(".");
+        for pattern in self.certify_changes_ignore() {
+            if is_blank(pattern) {
+                violations.push((
+                    "certify-changes-ignore",
+                    "`certify-changes-ignore` must not contain empty patterns".to_owned(),
+                ));
+            } else if let Err(err) = ignored_changes.add_line(None, pattern) {
+                violations.push((
+                    "certify-changes-ignore",
+                    format!(
+                        "`certify-changes-ignore` has invalid glob `{}`: {}",
+                        pattern, err
+                    ),
+                ));
+            }
+        }
+        if self.owners().iter().any(|o| is_blank(o)) {
+            violations.push((
+                "owners",
+                "`owners` must not contain empty logins".to_owned(),
+            ));
+        }
+        if self
+            .verify_feature_sets()
+            .iter()
+            .flatten()
+            .any(|f| is_blank(f))
+        {
+            violations.push((
+                "verify-feature-sets",
+                "`verify-feature-sets` must not contain empty feature names".to_owned(),
+            ));
+        }
+        if self.push_options().any(|o| o.contains(['\n', '\r'])) {
+            violations.push((
+                "push-options",
+                "`push-options` must not contain newlines".to_owned(),
+            ));
+        }
+        // Accessors would otherwise quietly use the default template in place of the file's
+        for (field, template) in [
+            (
+                "pre-release-commit-message",
+                &self.pre_release_commit_message,
+            ),
+            ("sync-commit-message", &self.sync_commit_message),
+            ("tag-message", &self.tag_message),
+            ("release-notes", &self.release_notes),
+        ] {
+            if let Some(MessageTemplate::File { file }) = template {
+                violations.push((
+                    field,
+                    format!("`{}` file `{}` was never read", field, file.display()),
+                ));
+            }
+        }
+        violations.extend(
+            self.commit_types()
+                .violations()
+                .into_iter()
+                .map(|v| ("commit-types", format!("`commit-types`: {}", v))),
+        );
+        for (i, replace) in self.pre_release_replacements().iter().enumerate() {
+            violations.extend(replace.violations().into_iter().map(|v| {
+                (
+                    "pre-release-replacements",
+                    format!("`pre-release-replacements[{}]`: {}", i, v),
+                )
+            }));
+        }
+        for name in self.template_vars().into_iter().flat_map(|v| v.keys()) {
+            if crate::ops::replace::BUILTIN_VARS.contains(&name.as_str()) {
+                violations.push((
+                    "template-vars",
+                    format!(
+                        "`template-vars` can't redefine the built-in `{{{{{}}}}}`",
+                        name
+                    ),
+                ));
+            } else if is_blank(name) || name.contains(['{', '}']) {
+                violations.push((
+                    "template-vars",
+                    format!("`template-vars` has an invalid name `{}`", name),
+                ));
+            }
+        }
+
+        violations
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -312,43 +1244,199 @@ pub struct Replace {
     pub exactly: Option<usize>,
     #[serde(default)]
     pub prerelease: bool,
+    #[serde(default)]
+    pub idempotent: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Command {
-    Line(String),
-    Args(Vec<String>),
-}
-
-impl Command {
-    pub fn args(&self) -> Vec<&str> {
-        match self {
-            Command::Line(ref s) => vec![s.as_str()],
-            Command::Args(ref a) => a.iter().map(|s| s.as_str()).collect(),
+impl Replace {
+    fn violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        if self.file.as_os_str().is_empty() {
+            violations.push("`file` must not be empty".to_owned());
         }
+        if self.search.is_empty() {
+            violations.push("`search` must not be empty".to_owned());
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if max < min {
+                violations.push(format!("`min` ({}) is greater than `max` ({})", min, max));
+            }
+        }
+        if let Some(exactly) = self.exactly {
+            if let Some(min) = self.min.filter(|min| exactly < *min) {
+                violations.push(format!(
+                    "`exactly` ({}) is less than `min` ({})",
+                    exactly, min
+                ));
+            }
+            if let Some(max) = self.max.filter(|max| *max < exactly) {
+                violations.push(format!(
+                    "`exactly` ({}) is greater than `max` ({})",
+                    exactly, max
+                ));
+            }
+        }
+        violations
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+/// Conventional commit types, by the version bump they call for
+///
+/// Each list left unset keeps its conventional default, less any types moved to a list that is
+/// set.  Types not listed are left unclassified.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
 #[serde(rename_all = "kebab-case")]
-#[value(rename_all = "kebab-case")]
-#[derive(Default)]
-pub enum DependentVersion {
-    /// Always upgrade dependents
-    #[default] // This is the safest option as its hard to test `Fix`
-    Upgrade,
-    /// Upgrade when the old version requirement no longer applies
-    Fix,
+pub struct CommitTypes {
+    pub feature: Option<Vec<String>>,
+    pub fix: Option<Vec<String>>,
+    pub ignore: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
-#[serde(rename_all = "kebab-case")]
-#[value(rename_all = "kebab-case")]
-#[derive(Default)]
-pub enum MetadataPolicy {
-    /// Apply when set, clear when not
-    #[default]
+impl CommitTypes {
+    fn conventional() -> Self {
+        let owned = |types: &[&str]| Some(types.iter().map(|t| (*t).to_owned()).collect());
+        Self {
+            feature: owned(&["feat"]),
+            fix: owned(&["docs", "perf", "fix"]),
+            ignore: owned(&["chore", "test", "style", "refactor", "revert"]),
+        }
+    }
+
+    /// Layer the lists set in `source` over ours
+    fn update(&mut self, source: &CommitTypes) {
+        if let Some(feature) = source.feature.as_deref() {
+            self.feature = Some(feature.to_owned());
+        }
+        if let Some(fix) = source.fix.as_deref() {
+            self.fix = Some(fix.to_owned());
+        }
+        if let Some(ignore) = source.ignore.as_deref() {
+            self.ignore = Some(ignore.to_owned());
+        }
+    }
+
+    fn buckets(&self) -> [(CommitKind, &'static str, Vec<String>); 3] {
+        static DEFAULT: once_cell::sync::Lazy<CommitTypes> =
+            once_cell::sync::Lazy::new(CommitTypes::conventional);
+        let set = [&self.feature, &self.fix, &self.ignore];
+        let explicit: Vec<&String> = set.iter().filter_map(|t| t.as_ref()).flatten().collect();
+        let resolve = |types: &Option<Vec<String>>, default: &Option<Vec<String>>| match types {
+            Some(types) => types.clone(),
+            None => default
+                .iter()
+                .flatten()
+                .filter(|t| !explicit.iter().any(|e| e.eq_ignore_ascii_case(t)))
+                .cloned()
+                .collect(),
+        };
+        [
+            (
+                CommitKind::Feature,
+                "feature",
+                resolve(&self.feature, &DEFAULT.feature),
+            ),
+            (CommitKind::Fix, "fix", resolve(&self.fix, &DEFAULT.fix)),
+            (
+                CommitKind::Ignore,
+                "ignore",
+                resolve(&self.ignore, &DEFAULT.ignore),
+            ),
+        ]
+    }
+
+    /// Classify `type_`, compared case-insensitively like conventional commits
+    pub fn kind(&self, type_: &str) -> Option<CommitKind> {
+        self.buckets()
+            .into_iter()
+            .find(|(_, _, types)| types.iter().any(|t| t.eq_ignore_ascii_case(type_)))
+            .map(|(kind, _, _)| kind)
+    }
+
+    fn violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        let buckets = self.buckets();
+        for (i, (_, bucket, types)) in buckets.iter().enumerate() {
+            for type_ in types.iter() {
+                if type_.trim().is_empty() {
+                    violations.push(format!("`{}` must not contain empty types", bucket));
+                }
+                for (_, other, other_types) in &buckets[i + 1..] {
+                    if other_types.iter().any(|t| t.eq_ignore_ascii_case(type_)) {
+                        violations.push(format!(
+                            "`{}` is in both `{}` and `{}`",
+                            type_, bucket, other
+                        ));
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommitKind {
+    Feature,
+    Fix,
+    Ignore,
+}
+
+/// A user-provided command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Command {
+    /// Run through the platform shell (`sh -c` or `cmd /C`)
+    Line(String),
+    /// Executed directly, without a shell
+    Args(Vec<String>),
+}
+
+impl Command {
+    pub fn args(&self) -> Vec<&str> {
+        match self {
+            Command::Line(ref s) => {
+                let [shell, flag] = crate::ops::cmd::SHELL;
+                vec![shell, flag, s.as_str()]
+            }
+            Command::Args(ref a) => a.iter().map(|s| s.as_str()).collect(),
+        }
+    }
+
+    /// Fill in placeholders, producing the argv to execute
+    ///
+    /// Values substituted into a [`Command::Line`] are quoted for the shell; arguments of a
+    /// [`Command::Args`] are substituted verbatim and never re-split.
+    pub fn render(&self, template: &crate::ops::replace::Template<'_>) -> Vec<String> {
+        match self {
+            Command::Line(ref s) => {
+                let [shell, flag] = crate::ops::cmd::SHELL;
+                vec![shell.to_owned(), flag.to_owned(), template.render_shell(s)]
+            }
+            Command::Args(ref a) => a.iter().map(|arg| template.render(arg)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum DependentVersion {
+    /// Always upgrade dependents
+    #[default] // This is the safest option as its hard to test `Fix`
+    Upgrade,
+    /// Upgrade when the old version requirement no longer applies
+    Fix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum MetadataPolicy {
+    /// Apply when set, clear when not
+    #[default]
     Optional,
     /// Error if not set
     Required,
@@ -358,12 +1446,123 @@ pub enum MetadataPolicy {
     Persistent,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum HookScope {
+    /// Run once for each package
+    #[default]
+    Package,
+    /// Run once for the whole release
+    Run,
+}
+
+/// `tag-message` value standing in for `tag-message-source = "changes"`
+const TAG_MESSAGE_CHANGES: &str = "changes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum TagMessageSource {
+    /// Render `tag-message`
+    #[default]
+    Template,
+    /// Render the git note on the tagged commit, from `notes-ref`
+    GitNotes,
+    /// Summarize the package's conventional commits since its prior tag
+    Changes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum TagMessageBody {
+    /// Only the tag message
+    #[default]
+    None,
+    /// Follow the tag message with the package's breaking changes, features, and fixes since its
+    /// prior tag, unless it places them with `{{changes}}`
+    Conventional,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum ConfigConflicts {
+    /// Warn when a config source overrides another's value
+    #[default]
+    Warn,
+    /// Fail when a config source overrides another's value
+    Error,
+}
+
+fn display_value(value: &impl Serialize) -> String {
+    toml::Value::try_from(value)
+        .map(|v| v.to_string())
+        .unwrap_or_default()
+}
+
+/// A message template, inline or read from a file relative to the workspace root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageTemplate {
+    Inline(String),
+    File { file: PathBuf },
+}
+
+impl MessageTemplate {
+    /// The template, unless it still needs to be read from a file
+    pub fn as_inline(&self) -> Option<&str> {
+        match self {
+            Self::Inline(template) => Some(template),
+            Self::File { .. } => None,
+        }
+    }
+}
+
+/// Parse a size like `10MB` or `512 KiB` into bytes
+fn parse_size(size: &str) -> Result<u64, String> {
+    let trimmed = size.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("has no number in `{}`", size))?;
+    let scale: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(format!("has unknown unit in `{}`", size)),
+    };
+    Ok((number * scale as f64) as u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+#[derive(Default)]
+pub enum ChangePaths {
+    /// Files `cargo package` would include
+    #[default]
+    Package,
+    /// Every file under the crate's directory, including ones excluded from packaging, except
+    /// those of other workspace members nested within it
+    CrateDir,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 #[serde(rename_all = "kebab-case")]
 pub enum SharedVersion {
     Enabled(bool),
     Name(String),
+    Group(SharedVersionGroup),
 }
 
 impl SharedVersion {
@@ -374,15 +1573,85 @@ impl SharedVersion {
             SharedVersion::Enabled(true) => Some("default"),
             SharedVersion::Enabled(false) => None,
             SharedVersion::Name(name) => Some(name.as_str()),
+            SharedVersion::Group(group) => Some(group.name.as_str()),
+        }
+    }
+
+    pub fn only_on_change(&self) -> bool {
+        match self {
+            SharedVersion::Enabled(_) | SharedVersion::Name(_) => false,
+            SharedVersion::Group(group) => group.only_on_change,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct SharedVersionGroup {
+    pub name: String,
+    /// Only adopt the group's version when this package is itself being released
+    #[serde(default)]
+    pub only_on_change: bool,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 struct CargoManifest {
     workspace: Option<CargoWorkspace>,
     package: Option<CargoPackage>,
+    dependencies: Option<std::collections::BTreeMap<String, toml::Value>>,
+    #[serde(rename = "dev-dependencies")]
+    dev_dependencies: Option<std::collections::BTreeMap<String, toml::Value>>,
+    #[serde(rename = "build-dependencies")]
+    build_dependencies: Option<std::collections::BTreeMap<String, toml::Value>>,
+}
+
+impl CargoManifest {
+    /// `[package]` fields and dependencies set with `workspace = true`, like `package.edition`
+    fn inherited_keys(&self) -> Vec<String> {
+        let is_inherited = |value: &toml::Value| {
+            value
+                .get("workspace")
+                .and_then(|w| w.as_bool())
+                .unwrap_or(false)
+        };
+
+        let mut keys = Vec::new();
+        if let Some(package) = self.package.as_ref() {
+            if let Some(MaybeWorkspace::Workspace(TomlWorkspaceField { workspace: true })) =
+                package.publish
+            {
+                keys.push("package.publish".to_owned());
+            }
+            if let Some(MaybeWorkspace::Workspace(TomlWorkspaceField { workspace: true })) =
+                package.version
+            {
+                keys.push("package.version".to_owned());
+            }
+            keys.extend(
+                package
+                    .other
+                    .iter()
+                    .filter(|(_, value)| is_inherited(value))
+                    .map(|(key, _)| format!("package.{}", key)),
+            );
+        }
+        for (table, dependencies) in [
+            ("dependencies", &self.dependencies),
+            ("dev-dependencies", &self.dev_dependencies),
+            ("build-dependencies", &self.build_dependencies),
+        ] {
+            keys.extend(
+                dependencies
+                    .iter()
+                    .flatten()
+                    .filter(|(_, value)| is_inherited(value))
+                    .map(|(name, _)| format!("{}.{}", table, name)),
+            );
+        }
+        keys
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -410,6 +1679,9 @@ struct CargoPackage {
     publish: Option<MaybeWorkspace<CargoPublishField>>,
     version: Option<MaybeWorkspace<String>>,
     metadata: Option<CargoMetadata>,
+    /// Fields we don't act on, which may also be inherited from the workspace
+    #[serde(flatten)]
+    other: std::collections::BTreeMap<String, toml::Value>,
 }
 
 impl CargoPackage {
@@ -456,14 +1728,31 @@ pub fn load_workspace_config(
     args: &ConfigArgs,
     ws_meta: &cargo_metadata::Metadata,
 ) -> CargoResult<Config> {
+    let (release_config, warnings) = load_workspace_config_quietly(args, ws_meta)?;
+    for warning in warnings {
+        warn_once(warning);
+    }
+    Ok(release_config)
+}
+
+/// [`load_workspace_config`], returning the warnings rather than reporting them
+pub fn load_workspace_config_quietly(
+    args: &ConfigArgs,
+    ws_meta: &cargo_metadata::Metadata,
+) -> CargoResult<(Config, Vec<String>)> {
     let mut release_config = Config {
         is_workspace: 1 < ws_meta.workspace_members.len(),
         ..Default::default()
     };
+    let mut warnings = Vec::new();
 
     if !args.isolated {
         let is_workspace = 1 < ws_meta.workspace_members.len();
         let cfg = if is_workspace {
+            warnings.extend(shadowed_config_warnings(
+                ws_meta.workspace_root.as_std_path(),
+                None,
+            ));
             resolve_workspace_config(ws_meta.workspace_root.as_std_path())?
         } else {
             // Outside of workspaces, go ahead and treat package config as workspace config so
@@ -473,6 +1762,10 @@ pub fn load_workspace_config(
                 .iter()
                 .find(|p| ws_meta.workspace_members.iter().any(|m| *m == p.id))
                 .unwrap();
+            warnings.extend(shadowed_config_warnings(
+                ws_meta.workspace_root.as_std_path(),
+                Some(pkg.manifest_path.as_std_path()),
+            ));
             resolve_config(
                 ws_meta.workspace_root.as_std_path(),
                 pkg.manifest_path.as_std_path(),
@@ -481,14 +1774,28 @@ pub fn load_workspace_config(
         release_config.update(&cfg);
     }
 
-    if let Some(custom_config_path) = args.custom_config.as_ref() {
+    for custom_config_path in args.config_files() {
         // when calling with -c option
-        let cfg = resolve_custom_config(custom_config_path.as_ref())?.unwrap_or_default();
+        let cfg = resolve_custom_config(custom_config_path)?.unwrap_or_default();
         release_config.update(&cfg);
     }
 
     release_config.update(&args.to_config());
-    Ok(release_config)
+    for config_override in args.config_overrides() {
+        release_config.update(config_override);
+    }
+    release_config.read_templates(ws_meta.workspace_root.as_std_path())?;
+    warnings.extend(release_config.conflict_warnings()?);
+    if !release_config.is_workspace && release_config.consolidate_commits == Some(true) {
+        warnings.push(
+            "`consolidate-commits` has no effect outside of a multi-package workspace".to_owned(),
+        );
+    }
+    // Validated once merged, as a later source can complete what an earlier one started
+    release_config
+        .validate()
+        .context("Invalid workspace config")?;
+    Ok((release_config, warnings))
 }
 
 pub fn load_package_config(
@@ -496,6 +1803,19 @@ pub fn load_package_config(
     ws_meta: &cargo_metadata::Metadata,
     pkg: &cargo_metadata::Package,
 ) -> CargoResult<Config> {
+    let (release_config, warnings) = load_package_config_quietly(args, ws_meta, pkg)?;
+    for warning in warnings {
+        warn_once(warning);
+    }
+    Ok(release_config)
+}
+
+/// [`load_package_config`], returning the warnings rather than reporting them
+pub fn load_package_config_quietly(
+    args: &ConfigArgs,
+    ws_meta: &cargo_metadata::Metadata,
+    pkg: &cargo_metadata::Package,
+) -> CargoResult<(Config, Vec<String>)> {
     let manifest_path = pkg.manifest_path.as_std_path();
 
     let is_workspace = 1 < ws_meta.workspace_members.len();
@@ -503,36 +1823,78 @@ pub fn load_package_config(
         is_workspace,
         ..Default::default()
     };
+    let mut warnings = Vec::new();
 
     if !args.isolated {
+        warnings.extend(shadowed_config_warnings(
+            ws_meta.workspace_root.as_std_path(),
+            Some(manifest_path),
+        ));
         let cfg = resolve_config(ws_meta.workspace_root.as_std_path(), manifest_path)?;
         release_config.update(&cfg);
     }
 
-    if let Some(custom_config_path) = args.custom_config.as_ref() {
+    for custom_config_path in args.config_files() {
         // when calling with -c option
-        let cfg = resolve_custom_config(Path::new(custom_config_path))?.unwrap_or_default();
+        let cfg = resolve_custom_config(custom_config_path)?.unwrap_or_default();
         release_config.update(&cfg);
     }
 
     release_config.update(&args.to_config());
 
-    let overrides = resolve_overrides(ws_meta.workspace_root.as_std_path(), manifest_path)?;
+    let mut overrides = resolve_overrides(ws_meta.workspace_root.as_std_path(), manifest_path)?;
+    if release_config.index_url().is_some() {
+        // The manifest's registry names can't be looked up without cargo config
+        overrides.registry = None;
+    }
     release_config.update(&overrides);
+    for config_override in args.config_overrides() {
+        release_config.update(config_override);
+    }
+    release_config.read_templates(ws_meta.workspace_root.as_std_path())?;
+    warnings.extend(release_config.conflict_warnings()?);
+
+    // Validated once merged, as a later source can complete what an earlier one started
+    release_config
+        .validate()
+        .with_context(|| format!("Invalid config for `{}`", pkg.name))?;
+    Ok((release_config, warnings))
+}
 
-    Ok(release_config)
+/// Report a warning from loading config, once per run
+///
+/// Every package's config repeats the workspace's sources, and so their warnings.
+pub fn warn_once(warning: String) {
+    static REPORTED: std::sync::Mutex<std::collections::BTreeSet<String>> =
+        std::sync::Mutex::new(std::collections::BTreeSet::new());
+    if REPORTED.lock().unwrap().insert(warning.clone()) {
+        let _ = crate::ops::reporter::warn(warning);
+    }
 }
 
 #[derive(Clone, Default, Debug, clap::Args)]
 pub struct ConfigArgs {
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    pub custom_config: Option<std::path::PathBuf>,
+    /// Custom config file, or `KEY=VALUE` to set one field with a TOML value
+    #[arg(
+        short,
+        long = "config",
+        value_name = "PATH|KEY=VALUE",
+        value_parser = parse_config_arg
+    )]
+    pub custom_config: Vec<ConfigArg>,
 
     /// Ignore implicit configuration files.
     #[arg(long)]
     pub isolated: bool,
 
+    /// Skip packages that fail to load, rather than aborting
+    #[arg(long)]
+    pub skip_broken: bool,
+
+    /// Only read manifests and config, leaving out package listings and tag lookups
+    #[arg(skip)]
+    pub metadata_only: bool,
+
     /// Sign both git commit and tag
     #[arg(long, overrides_with("no_sign"))]
     pub sign: bool,
@@ -547,6 +1909,18 @@ pub struct ConfigArgs {
     #[arg(long, value_delimiter = ',', value_name = "GLOB[,...]")]
     pub allow_branch: Option<Vec<String>>,
 
+    /// Release packages whose version is a placeholder, like `0.0.0`
+    #[arg(long)]
+    pub allow_placeholder_versions: bool,
+
+    /// Glob, relative to the workspace root, for uncommitted files to release with anyway
+    #[arg(long, value_name = "GLOB")]
+    pub allow_dirty: Vec<String>,
+
+    /// Define a `{{KEY}}` template placeholder, overriding `template-vars`
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
     #[command(flatten)]
     pub commit: CommitArgs,
 
@@ -560,6 +1934,66 @@ pub struct ConfigArgs {
     pub push: PushArgs,
 }
 
+/// `--config` and `--isolated`, for steps that take them without the rest of [`ConfigArgs`]
+#[derive(Clone, Default, Debug, clap::Args)]
+pub struct ConfigFileArgs {
+    /// Custom config file, or `KEY=VALUE` to set one field with a TOML value
+    #[arg(
+        short,
+        long = "config",
+        value_name = "PATH|KEY=VALUE",
+        value_parser = parse_config_arg
+    )]
+    pub custom_config: Vec<ConfigArg>,
+
+    /// Ignore implicit configuration files.
+    #[arg(long)]
+    pub isolated: bool,
+}
+
+/// A `--config` argument
+#[derive(Clone, Debug)]
+pub enum ConfigArg {
+    /// A config file, applied after the implicit ones
+    Path(PathBuf),
+    /// A single field, like `push=false`, applied after everything else
+    Value(Box<Config>),
+}
+
+/// Parse a `--config PATH|KEY=VALUE` argument
+///
+/// Anything that doesn't start with a field name and `=` is a path.  A value that isn't valid
+/// TOML, like `upstream`, is taken as a string.
+pub fn parse_config_arg(s: &str) -> Result<ConfigArg, String> {
+    let (key, value) = match s.split_once('=') {
+        Some((key, value)) if is_config_key(key) => (key, value),
+        _ => return Ok(ConfigArg::Path(PathBuf::from(s))),
+    };
+    let value = match toml::from_str::<toml::Table>(&format!("value = {}", value)) {
+        Ok(mut table) => table.remove("value").expect("just parsed"),
+        Err(_) => toml::Value::String(value.to_owned()),
+    };
+    // Dotted keys reach into tables, like `template-vars.name=value`
+    let config: Config = toml::from_str(&format!("{} = {}", key, value))
+        .map_err(|err| format!("invalid `--config {}`: {}", key, err.message().trim()))?;
+    Ok(ConfigArg::Value(Box::new(config)))
+}
+
+fn is_config_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Parse a `--var KEY=VALUE` argument
+pub fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("expected `KEY=VALUE`, got `{}`", s)),
+    }
+}
+
 impl ConfigArgs {
     pub fn to_config(&self) -> crate::config::Config {
         let mut config = crate::config::Config {
@@ -567,18 +2001,38 @@ impl ConfigArgs {
             sign_commit: self.sign(),
             sign_tag: self.sign(),
             dependent_version: self.dependent_version,
+            allow_placeholder: self.allow_placeholder_versions.then_some(true),
+            allow_dirty: (!self.allow_dirty.is_empty()).then(|| self.allow_dirty.clone()),
+            template_vars: (!self.vars.is_empty()).then(|| self.vars.iter().cloned().collect()),
             ..Default::default()
         };
         config.update(&self.commit.to_config());
         config.update(&self.publish.to_config());
         config.update(&self.tag.to_config());
         config.update(&self.push.to_config());
+        config.origin = Some("the command line".to_owned());
         config
     }
 
     fn sign(&self) -> Option<bool> {
         resolve_bool_arg(self.sign, self.no_sign)
     }
+
+    /// `--config` files, in the order given
+    pub fn config_files(&self) -> impl Iterator<Item = &Path> {
+        self.custom_config.iter().filter_map(|arg| match arg {
+            ConfigArg::Path(path) => Some(path.as_path()),
+            ConfigArg::Value(_) => None,
+        })
+    }
+
+    /// `--config KEY=VALUE` fields, in the order given
+    pub fn config_overrides(&self) -> impl Iterator<Item = &Config> {
+        self.custom_config.iter().filter_map(|arg| match arg {
+            ConfigArg::Path(_) => None,
+            ConfigArg::Value(config) => Some(config.as_ref()),
+        })
+    }
 }
 
 #[derive(Clone, Default, Debug, clap::Args)]
@@ -589,12 +2043,19 @@ pub struct CommitArgs {
     pub sign_commit: bool,
     #[arg(long, overrides_with("sign_commit"), hide(true))]
     pub no_sign_commit: bool,
+
+    #[arg(long, overrides_with("no_verify_commits"), hide(true))]
+    pub verify_commits: bool,
+    /// Skip git hooks when committing
+    #[arg(long, overrides_with("verify_commits"))]
+    pub no_verify_commits: bool,
 }
 
 impl CommitArgs {
     pub fn to_config(&self) -> crate::config::Config {
         crate::config::Config {
             sign_commit: resolve_bool_arg(self.sign_commit, self.no_sign_commit),
+            no_verify_git_hooks: resolve_bool_arg(self.no_verify_commits, self.verify_commits),
             ..Default::default()
         }
     }
@@ -610,9 +2071,13 @@ pub struct PublishArgs {
     no_publish: bool,
 
     /// Cargo registry to upload to
-    #[arg(long, value_name = "NAME")]
+    #[arg(long, value_name = "NAME", conflicts_with = "index_url")]
     registry: Option<String>,
 
+    /// Registry index to upload to, for registries not defined in cargo config
+    #[arg(long, value_name = "URL")]
+    index_url: Option<String>,
+
     #[arg(long, overrides_with("no_verify"), hide(true))]
     verify: bool,
     /// Don't verify the contents by building them
@@ -630,6 +2095,14 @@ pub struct PublishArgs {
     /// Build for the target triple
     #[arg(long, value_name = "TRIPLE")]
     target: Option<String>,
+
+    /// Retry a publish failing with a timeout or server error up to N times
+    #[arg(long, value_name = "N")]
+    publish_retries: Option<u32>,
+
+    /// Seconds to wait for each crate to appear in the index, 0 deferring the wait
+    #[arg(long, value_name = "SECS")]
+    publish_timeout: Option<u64>,
 }
 
 impl PublishArgs {
@@ -637,10 +2110,13 @@ impl PublishArgs {
         crate::config::Config {
             publish: resolve_bool_arg(self.publish, self.no_publish),
             registry: self.registry.clone(),
+            index_url: self.index_url.clone(),
             verify: resolve_bool_arg(self.verify, self.no_verify),
             enable_features: (!self.features.is_empty()).then(|| self.features.clone()),
             enable_all_features: self.all_features.then_some(true),
             target: self.target.clone(),
+            publish_retries: self.publish_retries,
+            publish_timeout: self.publish_timeout,
             ..Default::default()
         }
     }
@@ -655,6 +2131,12 @@ pub struct TagArgs {
     #[arg(long, overrides_with("tag"))]
     no_tag: bool,
 
+    #[arg(long, overrides_with("no_tag_for_prerelease"), hide(true))]
+    tag_for_prerelease: bool,
+    /// Do not create git tag for pre-release versions
+    #[arg(long, overrides_with("tag_for_prerelease"))]
+    no_tag_for_prerelease: bool,
+
     /// Sign git tag
     #[arg(long, overrides_with("no_sign_tag"))]
     sign_tag: bool,
@@ -668,15 +2150,31 @@ pub struct TagArgs {
     /// The name of the git tag.
     #[arg(long, value_name = "NAME")]
     tag_name: Option<String>,
+
+    /// Use the release commit's git note as the tag message
+    #[arg(long)]
+    from_git_notes: bool,
+
+    /// Summarize the package's conventional commits since its prior tag as the tag message
+    #[arg(long, conflicts_with = "from_git_notes")]
+    message_from_changes: bool,
 }
 
 impl TagArgs {
     pub fn to_config(&self) -> crate::config::Config {
         crate::config::Config {
             tag: resolve_bool_arg(self.tag, self.no_tag),
+            tag_prerelease: resolve_bool_arg(self.tag_for_prerelease, self.no_tag_for_prerelease),
             sign_tag: resolve_bool_arg(self.sign_tag, self.no_sign_tag),
             tag_prefix: self.tag_prefix.clone(),
             tag_name: self.tag_name.clone(),
+            tag_message_source: if self.from_git_notes {
+                Some(crate::config::TagMessageSource::GitNotes)
+            } else if self.message_from_changes {
+                Some(crate::config::TagMessageSource::Changes)
+            } else {
+                None
+            },
             ..Default::default()
         }
     }
@@ -694,6 +2192,10 @@ pub struct PushArgs {
     /// Git remote to push
     #[arg(long, value_name = "NAME")]
     push_remote: Option<String>,
+
+    /// Git URL to push to, rather than the push remote
+    #[arg(long, value_name = "URL")]
+    push_url: Option<String>,
 }
 
 impl PushArgs {
@@ -701,6 +2203,7 @@ impl PushArgs {
         crate::config::Config {
             push: resolve_bool_arg(self.push, self.no_push),
             push_remote: self.push_remote.clone(),
+            push_url: self.push_url.clone(),
             ..Default::default()
         }
     }
@@ -712,7 +2215,14 @@ fn get_pkg_config_from_manifest(manifest_path: &Path) -> CargoResult<Option<Conf
         let c: CargoManifest = toml::from_str(&m)
             .with_context(|| format!("Failed to parse `{}`", manifest_path.display()))?;
 
-        Ok(c.package.and_then(|p| p.into_config()))
+        let mut config = c.package.and_then(|p| p.into_config());
+        if let Some(config) = config.as_mut() {
+            config.origin = Some(format!(
+                "`package.metadata.release` in {}",
+                manifest_path.display()
+            ));
+        }
+        Ok(config)
     } else {
         Ok(None)
     }
@@ -724,7 +2234,14 @@ fn get_ws_config_from_manifest(manifest_path: &Path) -> CargoResult<Option<Confi
         let c: CargoManifest = toml::from_str(&m)
             .with_context(|| format!("Failed to parse `{}`", manifest_path.display()))?;
 
-        Ok(c.workspace.and_then(|p| p.into_config()))
+        let mut config = c.workspace.and_then(|p| p.into_config());
+        if let Some(config) = config.as_mut() {
+            config.origin = Some(format!(
+                "`workspace.metadata.release` in {}",
+                manifest_path.display()
+            ));
+        }
+        Ok(config)
     } else {
         Ok(None)
     }
@@ -733,14 +2250,106 @@ fn get_ws_config_from_manifest(manifest_path: &Path) -> CargoResult<Option<Confi
 fn get_config_from_file(file_path: &Path) -> CargoResult<Option<Config>> {
     if file_path.exists() {
         let c = std::fs::read_to_string(file_path)?;
-        let config = toml::from_str(&c)
+        let mut config = parse_config_file(&c, &|name| std::env::var(name).ok())
             .with_context(|| format!("Failed to parse `{}`", file_path.display()))?;
+        config.origin = Some(file_path.display().to_string());
         Ok(Some(config))
     } else {
         Ok(None)
     }
 }
 
+fn parse_config_file(content: &str, var: &dyn Fn(&str) -> Option<String>) -> CargoResult<Config> {
+    if !content.contains(ENV_START) {
+        // Parse directly to keep line numbers in error messages
+        return Ok(toml::from_str(content)?);
+    }
+
+    let mut table: toml::Table = toml::from_str(content)?;
+    for (key, value) in table.iter_mut() {
+        interpolate_value(value, key, var)?;
+    }
+    Ok(toml::Value::Table(table).try_into()?)
+}
+
+const ENV_START: &str = "${env:";
+
+/// Fields taken literally since `$` is meaningful to them
+const NO_INTERPOLATION: &[&str] = &["search"];
+
+fn interpolate_value(
+    value: &mut toml::Value,
+    key: &str,
+    var: &dyn Fn(&str) -> Option<String>,
+) -> CargoResult<()> {
+    match value {
+        toml::Value::String(value) => {
+            *value = interpolate_env(value, key, var)?;
+        }
+        toml::Value::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                interpolate_value(value, &format!("{}[{}]", key, i), var)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (field, value) in table.iter_mut() {
+                if NO_INTERPOLATION.contains(&field.as_str()) {
+                    continue;
+                }
+                interpolate_value(value, &format!("{}.{}", key, field), var)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expand `${env:NAME}` and `${env:NAME:-default}`, with `$${env:...}` for a literal
+fn interpolate_env(
+    input: &str,
+    key: &str,
+    var: &dyn Fn(&str) -> Option<String>,
+) -> CargoResult<String> {
+    let mut output = String::new();
+    let mut rest = input;
+    while let Some(start) = rest.find(ENV_START) {
+        let after = &rest[start + ENV_START.len()..];
+        if rest[..start].ends_with('$') {
+            output.push_str(&rest[..start - 1]);
+            output.push_str(ENV_START);
+            rest = after;
+            continue;
+        }
+        output.push_str(&rest[..start]);
+
+        let end = after.find('}').ok_or_else(|| {
+            anyhow::format_err!(
+                "`{}` has an unterminated `{}` in {:?}",
+                key,
+                ENV_START,
+                input
+            )
+        })?;
+        let expr = &after[..end];
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        match (var(name), default) {
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => anyhow::bail!(
+                "`{}` references environment variable `{}` which is not set",
+                key,
+                name
+            ),
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
 pub fn resolve_custom_config(file_path: &Path) -> CargoResult<Option<Config>> {
     get_config_from_file(file_path)
 }
@@ -750,8 +2359,9 @@ pub fn resolve_custom_config(file_path: &Path) -> CargoResult<Option<Config>> {
 /// This tries the following sources in order, merging the results:
 /// 1. $HOME/.release.toml
 /// 2. $HOME/.config/cargo-release/release.toml
-/// 3. $(workspace)/release.toml
-/// 3. $(workspace)/Cargo.toml
+/// 3. $(workspace)/.config/cargo-release/release.toml
+/// 4. $(workspace)/release.toml
+/// 5. $(workspace)/Cargo.toml
 pub fn resolve_workspace_config(workspace_root: &Path) -> CargoResult<Config> {
     let mut config = Config::default();
 
@@ -773,7 +2383,13 @@ pub fn resolve_workspace_config(workspace_root: &Path) -> CargoResult<Config> {
     };
 
     // Workspace config
+    let dot_config = workspace_root.join(".config/cargo-release/release.toml");
     let default_config = workspace_root.join("release.toml");
+    let current_dir_config = get_config_from_file(&dot_config)?;
+    if let Some(cfg) = current_dir_config {
+        config.update(&cfg);
+    };
+
     let current_dir_config = get_config_from_file(&default_config)?;
     if let Some(cfg) = current_dir_config {
         config.update(&cfg);
@@ -793,10 +2409,12 @@ pub fn resolve_workspace_config(workspace_root: &Path) -> CargoResult<Config> {
 /// This tries the following sources in order, merging the results:
 /// 1. $HOME/.release.toml
 /// 2. $HOME/.config/cargo-release/release.toml
-/// 3. $(workspace)/release.toml
-/// 3. $(workspace)/Cargo.toml `workspace.metadata.release`
-/// 4. $(crate)/release.toml
-/// 5. $(crate)/Cargo.toml `package.metadata.release`
+/// 3. $(workspace)/.config/cargo-release/release.toml
+/// 4. $(workspace)/release.toml
+/// 5. $(workspace)/Cargo.toml `workspace.metadata.release`
+/// 6. $(crate)/.config/release.toml
+/// 7. $(crate)/release.toml
+/// 8. $(crate)/Cargo.toml `package.metadata.release`
 ///
 /// `$(crate)/Cargo.toml` is a way to differentiate configuration for the root crate and the
 /// workspace.
@@ -805,7 +2423,13 @@ pub fn resolve_config(workspace_root: &Path, manifest_path: &Path) -> CargoResul
 
     // Crate config
     let crate_root = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let dot_config = crate_root.join(".config/release.toml");
     let default_config = crate_root.join("release.toml");
+    let current_dir_config = get_config_from_file(&dot_config)?;
+    if let Some(cfg) = current_dir_config {
+        config.update(&cfg);
+    };
+
     let current_dir_config = get_config_from_file(&default_config)?;
     if let Some(cfg) = current_dir_config {
         config.update(&cfg);
@@ -819,8 +2443,43 @@ pub fn resolve_config(workspace_root: &Path, manifest_path: &Path) -> CargoResul
     Ok(config)
 }
 
+/// Warnings for the `.config/` files [`resolve_config`] reads that a `release.toml` overrides
+///
+/// Without a `manifest_path`, only the workspace's files are checked, as for
+/// [`resolve_workspace_config`].
+fn shadowed_config_warnings(workspace_root: &Path, manifest_path: Option<&Path>) -> Vec<String> {
+    let mut pairs = vec![(
+        workspace_root.join(".config/cargo-release/release.toml"),
+        workspace_root.join("release.toml"),
+    )];
+    if let Some(crate_root) = manifest_path.map(|m| m.parent().unwrap_or_else(|| Path::new("."))) {
+        // A workspace root package shares its `release.toml` with the workspace
+        if crate_root != workspace_root {
+            pairs.push((
+                crate_root.join(".config/release.toml"),
+                crate_root.join("release.toml"),
+            ));
+        }
+    }
+    pairs
+        .into_iter()
+        .filter(|(dot_config, default_config)| dot_config.exists() && default_config.exists())
+        .map(|(dot_config, default_config)| {
+            format!(
+                "both {} and {} exist, values from {} take precedence",
+                dot_config.display(),
+                default_config.display(),
+                default_config.display()
+            )
+        })
+        .collect()
+}
+
 pub fn resolve_overrides(workspace_root: &Path, manifest_path: &Path) -> CargoResult<Config> {
-    let mut release_config = Config::default();
+    let mut release_config = Config {
+        origin: Some(format!("`package` fields in {}", manifest_path.display())),
+        ..Default::default()
+    };
 
     // the publish flag in cargo file
     let manifest = std::fs::read_to_string(manifest_path)?;
@@ -828,7 +2487,7 @@ pub fn resolve_overrides(workspace_root: &Path, manifest_path: &Path) -> CargoRe
         .with_context(|| format!("Failed to parse `{}`", manifest_path.display()))?;
     if let Some(package) = manifest.package.as_ref() {
         let publish = match package.publish.as_ref() {
-            Some(MaybeWorkspace::Defined(publish)) => publish.publishable(),
+            Some(MaybeWorkspace::Defined(publish)) => Some(publish.clone()),
             Some(MaybeWorkspace::Workspace(workspace)) => {
                 if workspace.workspace {
                     let workspace_path = workspace_root.join("Cargo.toml");
@@ -839,20 +2498,32 @@ pub fn resolve_overrides(workspace_root: &Path, manifest_path: &Path) -> CargoRe
                         })?;
                     workspace
                         .workspace
-                        .as_ref()
-                        .and_then(|w| w.package.as_ref())
-                        .and_then(|p| p.publish.as_ref())
-                        .map(|p| p.publishable())
-                        .unwrap_or(true)
+                        .and_then(|w| w.package)
+                        .and_then(|p| p.publish)
                 } else {
-                    true
+                    None
                 }
             }
-            None => true,
+            None => None,
         };
-        if !publish {
+        if !publish.as_ref().map(|p| p.publishable()).unwrap_or(true) {
             release_config.publish = Some(false);
         }
+        match publish {
+            Some(CargoPublishField::Registries(registries)) if registries.len() == 1 => {
+                // Like `cargo publish`, default to the only registry allowed
+                if registries[0] != "crates-io" {
+                    release_config.registry = Some(registries[0].clone());
+                }
+            }
+            Some(CargoPublishField::Registries(registries))
+                if 1 < registries.len() && !registries.iter().any(|r| r == "crates-io") =>
+            {
+                // None of them are crates.io, whose index is the only one waited on
+                release_config.publish_wait = Some(false);
+            }
+            _ => {}
+        }
         if package
             .version
             .as_ref()
@@ -872,6 +2543,14 @@ pub fn resolve_overrides(workspace_root: &Path, manifest_path: &Path) -> CargoRe
     Ok(release_config)
 }
 
+/// `[package]` fields and dependencies `manifest_path` inherits with `workspace = true`
+pub fn inherited_keys(manifest_path: &Path) -> CargoResult<Vec<String>> {
+    let manifest = std::fs::read_to_string(manifest_path)?;
+    let manifest: CargoManifest = toml::from_str(&manifest)
+        .with_context(|| format!("Failed to parse `{}`", manifest_path.display()))?;
+    Ok(manifest.inherited_keys())
+}
+
 fn resolve_bool_arg(yes: bool, no: bool) -> Option<bool> {
     match (yes, no) {
         (true, false) => Some(true),
@@ -885,13 +2564,1102 @@ fn resolve_bool_arg(yes: bool, no: bool) -> Option<bool> {
 mod test {
     use super::*;
 
-    mod resolve_config {
+    mod interpolate {
         use super::*;
 
+        fn var(name: &str) -> Option<String> {
+            match name {
+                "RELEASE_REMOTE" => Some("staging".to_owned()),
+                "RELEASE_REGISTRY" => Some("internal".to_owned()),
+                _ => None,
+            }
+        }
+
+        fn parse(content: &str) -> CargoResult<Config> {
+            parse_config_file(content, &var)
+        }
+
         #[test]
-        fn doesnt_panic() {
-            let release_config = resolve_config(Path::new("."), Path::new("Cargo.toml")).unwrap();
-            assert!(!release_config.sign_commit());
+        fn set() {
+            let config = parse(
+                r#"
+push-remote = "${env:RELEASE_REMOTE}"
+registry = "${env:RELEASE_REGISTRY}-mirror"
+push-options = ["ci.variable=REMOTE=${env:RELEASE_REMOTE}"]
+"#,
+            )
+            .unwrap();
+            assert_eq!(config.push_remote(), "staging");
+            assert_eq!(config.registry(), Some("internal-mirror"));
+            assert_eq!(
+                config.push_options().collect::<Vec<_>>(),
+                ["ci.variable=REMOTE=staging"]
+            );
+        }
+
+        #[test]
+        fn unset() {
+            let err = parse(r#"push-options = ["${env:PUSH_OPTION}"]"#)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("`push-options[0]`"), "{}", err);
+            assert!(err.contains("`PUSH_OPTION`"), "{}", err);
+        }
+
+        #[test]
+        fn default() {
+            let config = parse(
+                r#"
+push-remote = "${env:RELEASE_REMOTE:-origin}"
+registry = "${env:UNSET_REGISTRY:-crates-io}"
+"#,
+            )
+            .unwrap();
+            assert_eq!(config.push_remote(), "staging");
+            assert_eq!(config.registry(), Some("crates-io"));
+        }
+
+        #[test]
+        fn escaped() {
+            let config = parse(r#"tag-message = "$${env:RELEASE_REMOTE}""#).unwrap();
+            assert_eq!(config.tag_message(), "${env:RELEASE_REMOTE}");
+        }
+
+        #[test]
+        fn unterminated() {
+            let err = parse(r#"push-remote = "${env:RELEASE_REMOTE""#)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("`push-remote`"), "{}", err);
+        }
+
+        #[test]
+        fn bare_variables_are_literal() {
+            let config = parse(r#"push-remote = "$RELEASE_REMOTE""#).unwrap();
+            assert_eq!(config.push_remote(), "$RELEASE_REMOTE");
+        }
+
+        #[test]
+        fn search_is_exempt() {
+            let config = parse(
+                r#"
+[[pre-release-replacements]]
+file = "${env:RELEASE_REMOTE}.md"
+search = "${env:UNSET}"
+replace = "${env:RELEASE_REGISTRY}"
+"#,
+            )
+            .unwrap();
+            let replace = &config.pre_release_replacements()[0];
+            assert_eq!(replace.file, Path::new("staging.md"));
+            assert_eq!(replace.search, "${env:UNSET}");
+            assert_eq!(replace.replace, "internal");
+        }
+    }
+
+    mod message_template {
+        use super::*;
+
+        use assert_fs::prelude::*;
+
+        #[test]
+        fn round_trip() {
+            let config: Config = toml::from_str(
+                r#"
+pre-release-commit-message = "chore: Release {{version}}"
+tag-message = { file = "release/tag.txt" }
+"#,
+            )
+            .unwrap();
+            assert_eq!(
+                config.pre_release_commit_message,
+                Some(MessageTemplate::Inline(
+                    "chore: Release {{version}}".to_owned()
+                ))
+            );
+            assert_eq!(
+                config.tag_message,
+                Some(MessageTemplate::File {
+                    file: PathBuf::from("release/tag.txt")
+                })
+            );
+
+            let dumped = toml::to_string(&config).unwrap();
+            let reparsed: Config = toml::from_str(&dumped).unwrap();
+            assert_eq!(
+                reparsed.pre_release_commit_message,
+                config.pre_release_commit_message
+            );
+            assert_eq!(reparsed.tag_message, config.tag_message);
+        }
+
+        #[test]
+        fn render_file() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("release/commit.txt")
+                .write_str(
+                    "chore: Release {{crate_name}} {{version}}\n\nFirst paragraph\nabout {{crate_name}}\n\nSecond paragraph\n",
+                )
+                .unwrap();
+            let mut config = Config {
+                pre_release_commit_message: Some(MessageTemplate::File {
+                    file: PathBuf::from("release/commit.txt"),
+                }),
+                ..Default::default()
+            };
+
+            config.read_templates(temp.path()).unwrap();
+            config.validate().unwrap();
+            let template = crate::ops::replace::Template {
+                crate_name: Some("foo"),
+                version: Some("1.0.0"),
+                ..Default::default()
+            };
+            assert_eq!(
+                template.render(config.pre_release_commit_message()),
+                "chore: Release foo 1.0.0\n\nFirst paragraph\nabout foo\n\nSecond paragraph\n"
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn missing_file() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let mut config = Config {
+                tag_message: Some(MessageTemplate::File {
+                    file: PathBuf::from("missing.txt"),
+                }),
+                ..Default::default()
+            };
+
+            let err = config.read_templates(temp.path()).unwrap_err();
+            let err = format!("{:#}", err);
+            assert!(err.contains("`tag-message`"), "{}", err);
+            assert!(err.contains("missing.txt"), "{}", err);
+            // Never falls back to the default template
+            let err = config.validate().unwrap_err().to_string();
+            assert!(
+                err.contains("`tag-message` file `missing.txt` was never read"),
+                "{}",
+                err
+            );
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod parse_size {
+        use super::*;
+
+        #[test]
+        fn units() {
+            assert_eq!(parse_size("512"), Ok(512));
+            assert_eq!(parse_size("10MB"), Ok(10_000_000));
+            assert_eq!(parse_size("1.5 kB"), Ok(1_500));
+            assert_eq!(parse_size("2MiB"), Ok(2 * 1024 * 1024));
+            assert!(parse_size("MB").is_err());
+            assert!(parse_size("10 parsecs").is_err());
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        fn replace(min: Option<usize>, max: Option<usize>, exactly: Option<usize>) -> Config {
+            Config {
+                pre_release_replacements: Some(vec![Replace {
+                    file: PathBuf::from("README.md"),
+                    search: "v[0-9.]+".to_owned(),
+                    replace: "v{{version}}".to_owned(),
+                    min,
+                    max,
+                    exactly,
+                    prerelease: false,
+                    idempotent: false,
+                }]),
+                ..Default::default()
+            }
+        }
+
+        fn assert_violation(config: Config, field: &str) {
+            let err = config.validate().unwrap_err().to_string();
+            assert!(err.contains(field), "expected `{}` in: {}", field, err);
+        }
+
+        #[test]
+        fn defaults() {
+            Config::default().validate().unwrap();
+            Config::from_defaults().validate().unwrap();
+        }
+
+        #[test]
+        fn registry() {
+            let config = Config {
+                registry: Some("internal".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                publish: Some(false),
+                registry: Some("".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                publish: Some(true),
+                registry: Some("".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`registry`");
+        }
+
+        #[test]
+        fn index_url() {
+            let config = Config {
+                index_url: Some("https://internal.example.com/index".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            assert!(!config.is_crates_io());
+            let config = Config {
+                registry: Some("internal".to_owned()),
+                index_url: Some("https://internal.example.com/index".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`index-url`");
+        }
+
+        #[test]
+        fn certify_changes_ignore() {
+            let config = Config {
+                certify_changes_ignore: Some(vec!["*.md".to_owned(), "benches/**".to_owned()]),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                certify_changes_ignore: Some(vec![" ".to_owned()]),
+                ..Default::default()
+            };
+            assert_violation(config, "`certify-changes-ignore`");
+            let config = Config {
+                certify_changes_ignore: Some(vec!["benches/[".to_owned()]),
+                ..Default::default()
+            };
+            assert_violation(config, "`certify-changes-ignore`");
+        }
+
+        #[test]
+        fn push_remote() {
+            let config = Config {
+                push_remote: Some("upstream".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                push_remote: Some(" ".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`push-remote`");
+        }
+
+        #[test]
+        fn tag_name() {
+            let config = Config {
+                tag: Some(false),
+                tag_name: Some("".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                tag_name: Some("".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`tag-name`");
+        }
+
+        #[test]
+        fn pre_release_commit_message() {
+            let config = Config {
+                pre_release_commit_message: Some(MessageTemplate::Inline(
+                    "chore: Release".to_owned(),
+                )),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                pre_release_commit_message: Some(MessageTemplate::Inline("".to_owned())),
+                ..Default::default()
+            };
+            assert_violation(config, "`pre-release-commit-message`");
+        }
+
+        #[test]
+        fn target() {
+            let config = Config {
+                target: Some("x86_64-unknown-linux-gnu".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                target: Some("".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`target`");
+        }
+
+        #[test]
+        fn publish_as() {
+            let config = Config {
+                publish_as: Some("acme-core-oss".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                publish_as: Some(" ".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`publish-as`");
+        }
+
+        #[test]
+        fn max_package_size() {
+            let config = Config {
+                max_package_size: Some("10MB".to_owned()),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                max_package_size: Some("10 parsecs".to_owned()),
+                ..Default::default()
+            };
+            assert_violation(config, "`max-package-size`");
+        }
+
+        #[test]
+        fn commit_types() {
+            let config: Config = toml::from_str(
+                r#"
+[commit-types]
+feature = ["feat", "perf"]
+fix = ["fix"]
+ignore = ["chore", "ci", "deps"]
+"#,
+            )
+            .unwrap();
+            config.validate().unwrap();
+            assert_eq!(
+                config.commit_types().kind("perf"),
+                Some(CommitKind::Feature)
+            );
+            assert_eq!(config.commit_types().kind("Deps"), Some(CommitKind::Ignore));
+            assert_eq!(config.commit_types().kind("docs"), None);
+
+            // Unset lists keep their defaults, less what moved elsewhere
+            let mut config: Config = toml::from_str(
+                r#"
+[commit-types]
+feature = ["feat", "perf"]
+"#,
+            )
+            .unwrap();
+            config.validate().unwrap();
+            let types = config.commit_types();
+            assert_eq!(types.kind("perf"), Some(CommitKind::Feature));
+            assert_eq!(types.kind("docs"), Some(CommitKind::Fix));
+            assert_eq!(types.kind("chore"), Some(CommitKind::Ignore));
+            let later: Config = toml::from_str(
+                r#"
+[commit-types]
+ignore = ["chore", "docs"]
+"#,
+            )
+            .unwrap();
+            config.update(&later);
+            let types = config.commit_types();
+            assert_eq!(types.kind("perf"), Some(CommitKind::Feature));
+            assert_eq!(types.kind("docs"), Some(CommitKind::Ignore));
+            assert_eq!(types.kind("fix"), Some(CommitKind::Fix));
+            assert_eq!(types.kind("test"), None);
+
+            let config = Config {
+                commit_types: Some(CommitTypes {
+                    feature: Some(vec!["feat".to_owned(), "perf".to_owned()]),
+                    fix: Some(vec!["fix".to_owned(), "PERF".to_owned()]),
+                    ignore: None,
+                }),
+                ..Default::default()
+            };
+            let err = config.validate().unwrap_err().to_string();
+            assert!(
+                err.contains("`perf` is in both `feature` and `fix`"),
+                "{}",
+                err
+            );
+        }
+
+        #[test]
+        fn shared_version() {
+            let config = Config {
+                shared_version: Some(SharedVersion::Name("api".to_owned())),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                shared_version: Some(SharedVersion::Name("".to_owned())),
+                ..Default::default()
+            };
+            assert_violation(config, "`shared-version`");
+        }
+
+        #[test]
+        fn allow_branch() {
+            let config = Config {
+                allow_branch: Some(vec!["main".to_owned()]),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                allow_branch: Some(vec!["main".to_owned(), "".to_owned()]),
+                ..Default::default()
+            };
+            assert_violation(config, "`allow-branch`");
+        }
+
+        #[test]
+        fn owners() {
+            let config = Config {
+                owners: Some(vec!["github:org:team".to_owned()]),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                owners: Some(vec!["".to_owned()]),
+                ..Default::default()
+            };
+            assert_violation(config, "`owners`");
+        }
+
+        #[test]
+        fn verify_feature_sets() {
+            let config = parse_config_file(
+                r#"verify-feature-sets = [["rustls"], ["native-tls", "vendored"]]"#,
+                &|_| None,
+            )
+            .unwrap();
+            assert_eq!(
+                config.verify_feature_sets(),
+                [
+                    vec!["rustls".to_owned()],
+                    vec!["native-tls".to_owned(), "vendored".to_owned()]
+                ]
+            );
+            config.validate().unwrap();
+            let config = Config {
+                verify_feature_sets: Some(vec![vec!["".to_owned()]]),
+                ..Default::default()
+            };
+            assert_violation(config, "`verify-feature-sets`");
+        }
+
+        #[test]
+        fn push_options() {
+            let config = Config {
+                push_options: Some(vec!["ci.skip".to_owned()]),
+                ..Default::default()
+            };
+            config.validate().unwrap();
+            let config = Config {
+                push_options: Some(vec!["ci.skip\nmerge_request.create".to_owned()]),
+                ..Default::default()
+            };
+            assert_violation(config, "`push-options`");
+        }
+
+        #[test]
+        fn replace_min_max() {
+            replace(Some(1), Some(2), None).validate().unwrap();
+            replace(Some(2), Some(2), None).validate().unwrap();
+            assert_violation(replace(Some(3), Some(2), None), "`min` (3)");
+        }
+
+        #[test]
+        fn replace_exactly() {
+            replace(None, None, Some(3)).validate().unwrap();
+            replace(Some(1), Some(5), Some(3)).validate().unwrap();
+            assert_violation(replace(Some(5), None, Some(3)), "`exactly` (3) is less");
+            assert_violation(replace(None, Some(2), Some(3)), "`exactly` (3) is greater");
+        }
+
+        #[test]
+        fn replace_fields() {
+            let mut config = replace(None, None, None);
+            config.pre_release_replacements.as_mut().unwrap()[0].search = "".to_owned();
+            assert_violation(config, "`pre-release-replacements[0]`: `search`");
+            let mut config = replace(None, None, None);
+            config.pre_release_replacements.as_mut().unwrap()[0].file = PathBuf::new();
+            assert_violation(config, "`pre-release-replacements[0]`: `file`");
+        }
+
+        #[test]
+        fn reports_all() {
+            let config = Config {
+                push_remote: Some("".to_owned()),
+                target: Some("".to_owned()),
+                ..replace(Some(3), Some(2), None)
+            };
+            let err = config.validate().unwrap_err().to_string();
+            assert!(err.contains("`push-remote`"), "{}", err);
+            assert!(err.contains("`target`"), "{}", err);
+            assert!(err.contains("`min`"), "{}", err);
+        }
+
+        #[test]
+        fn names_source_file() {
+            use assert_fs::prelude::*;
+
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("Cargo.toml")
+                .write_str("[package]\nname = \"a\"\nversion = \"0.1.0\"\n")
+                .unwrap();
+            temp.child("src/lib.rs").touch().unwrap();
+            let file = temp.child("release.toml");
+            file.write_str("push-remote = \"\"\n").unwrap();
+            let ws_meta = cargo_metadata::MetadataCommand::new()
+                .manifest_path(temp.child("Cargo.toml").path())
+                .exec()
+                .unwrap();
+
+            let err = format!(
+                "{:#}",
+                load_workspace_config(&ConfigArgs::default(), &ws_meta).unwrap_err()
+            );
+            assert!(
+                err.contains("`push-remote` must not be empty (from "),
+                "{}",
+                err
+            );
+            assert!(err.contains("release.toml)"), "{}", err);
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod placeholder {
+        use super::*;
+
+        #[test]
+        fn default() {
+            let config = Config::default();
+            assert!(config.is_placeholder_version("0.0.0"));
+            assert!(!config.is_placeholder_version("0.0.1"));
+            assert!(!config.is_placeholder_version("0.0.0-placeholder"));
+        }
+
+        #[test]
+        fn patterns() {
+            let config = Config {
+                placeholder_versions: Some(vec!["0.0.0".to_owned(), "*-placeholder".to_owned()]),
+                ..Default::default()
+            };
+            assert!(config.is_placeholder_version("0.0.0"));
+            assert!(config.is_placeholder_version("0.0.0-placeholder"));
+            assert!(config.is_placeholder_version("1.0.0-placeholder"));
+            assert!(!config.is_placeholder_version("1.0.0"));
+            assert!(!config.is_placeholder_version("0.0.0-alpha.1"));
+        }
+
+        #[test]
+        fn disabled() {
+            let config = Config {
+                placeholder_versions: Some(vec![]),
+                ..Default::default()
+            };
+            assert!(!config.is_placeholder_version("0.0.0"));
+        }
+
+        #[test]
+        fn invalid_glob() {
+            let config = Config {
+                placeholder_versions: Some(vec!["0.0.[".to_owned()]),
+                ..Default::default()
+            };
+            assert!(config.is_placeholder_version("0.0.["));
+            assert!(config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("placeholder-versions"));
+        }
+    }
+
+    mod conflicts {
+        use super::*;
+
+        use assert_fs::prelude::*;
+
+        fn from_file(temp: &assert_fs::TempDir, path: &str, content: &str) -> Config {
+            let file = temp.child(path);
+            file.write_str(content).unwrap();
+            get_config_from_file(file.path()).unwrap().unwrap()
+        }
+
+        #[test]
+        fn home_vs_workspace() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let home = from_file(&temp, "home/.release.toml", "sign-tag = true\n");
+            let ws = from_file(&temp, "ws/release.toml", "sign-tag = false\npush = false\n");
+
+            let mut config = Config::default();
+            config.update(&home);
+            config.update(&ws);
+            assert_eq!(
+                config.conflicts,
+                [format!(
+                    "`sign-tag = true` from {} is overridden by `sign-tag = false` from {}",
+                    temp.child("home/.release.toml").path().display(),
+                    temp.child("ws/release.toml").path().display()
+                )]
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn workspace_vs_cli() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let ws = from_file(&temp, "release.toml", "dependent-version = \"upgrade\"\n");
+            let args = ConfigArgs {
+                dependent_version: Some(DependentVersion::Fix),
+                ..Default::default()
+            };
+
+            let mut config = Config::default();
+            config.update(&ws);
+            config.update(&args.to_config());
+            assert_eq!(
+                config.conflicts,
+                [format!(
+                    "`dependent-version = \"upgrade\"` from {} is overridden by `dependent-version = \"fix\"` from the command line",
+                    temp.child("release.toml").path().display()
+                )]
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn agreeing_or_defaults() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let home = from_file(&temp, "home/.release.toml", "sign-tag = true\n");
+            let ws = from_file(&temp, "ws/release.toml", "sign-tag = true\npush = false\n");
+
+            let mut config = Config::from_defaults();
+            config.update(&home);
+            config.update(&ws);
+            assert_eq!(config.conflicts, Vec::<String>::new());
+            config.report_conflicts().unwrap();
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn error() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let home = from_file(&temp, "home/.release.toml", "sign-tag = true\n");
+            let ws = from_file(
+                &temp,
+                "ws/release.toml",
+                "sign-tag = false\nconfig-conflicts = \"error\"\n",
+            );
+
+            let mut config = Config::default();
+            config.update(&home);
+            config.update(&ws);
+            assert!(config.report_conflicts().is_err());
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod resolve_config {
+        use super::*;
+
+        #[test]
+        fn doesnt_panic() {
+            let release_config = resolve_config(Path::new("."), Path::new("Cargo.toml")).unwrap();
+            assert!(!release_config.sign_commit());
+        }
+
+        #[test]
+        fn validates_merged() {
+            use assert_fs::prelude::*;
+
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("release.toml")
+                .write_str("registry = \"\"\n")
+                .unwrap();
+            temp.child("Cargo.toml")
+                .write_str("[workspace]\nmembers = [\"a\"]\n")
+                .unwrap();
+            let manifest = temp.child("a/Cargo.toml");
+            manifest
+                .write_str(
+                    r#"[package]
+name = "a"
+version = "0.1.0"
+
+[package.metadata.release]
+publish = false
+"#,
+                )
+                .unwrap();
+
+            let config = resolve_config(temp.path(), manifest.path()).unwrap();
+            config.validate().unwrap();
+
+            manifest
+                .write_str("[package]\nname = \"a\"\nversion = \"0.1.0\"\n")
+                .unwrap();
+            let config = resolve_config(temp.path(), manifest.path()).unwrap();
+            let err = config.validate().unwrap_err().to_string();
+            assert!(err.contains("`registry`"), "{}", err);
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn precedence() {
+            use assert_fs::prelude::*;
+
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child(".config/cargo-release/release.toml")
+                .write_str(
+                    r#"
+push-remote = "ws-dot-config"
+sign-tag = true
+tag-prefix = "ws-dot-config"
+registry = "ws-dot-config"
+"#,
+                )
+                .unwrap();
+            temp.child("release.toml")
+                .write_str(
+                    r#"
+push-remote = "ws-release-toml"
+tag-prefix = "ws-release-toml"
+registry = "ws-release-toml"
+"#,
+                )
+                .unwrap();
+            temp.child("Cargo.toml")
+                .write_str(
+                    r#"
+[workspace]
+members = ["a"]
+
+[workspace.metadata.release]
+push-remote = "ws-manifest"
+"#,
+                )
+                .unwrap();
+            temp.child("a/.config/release.toml")
+                .write_str(
+                    r#"
+tag-prefix = "crate-dot-config"
+registry = "crate-dot-config"
+sign-commit = true
+"#,
+                )
+                .unwrap();
+            temp.child("a/release.toml")
+                .write_str(
+                    r#"
+registry = "crate-release-toml"
+"#,
+                )
+                .unwrap();
+            temp.child("a/Cargo.toml")
+                .write_str(
+                    r#"
+[package]
+name = "a"
+version = "0.1.0"
+"#,
+                )
+                .unwrap();
+
+            let ws_config = resolve_workspace_config(temp.path()).unwrap();
+            assert_eq!(ws_config.push_remote(), "ws-manifest");
+            assert!(ws_config.sign_tag());
+            assert_eq!(ws_config.tag_prefix(false), "ws-release-toml");
+            assert_eq!(ws_config.registry(), Some("ws-release-toml"));
+
+            let pkg_config =
+                resolve_config(temp.path(), &temp.path().join("a/Cargo.toml")).unwrap();
+            assert_eq!(pkg_config.push_remote(), "ws-manifest");
+            assert!(pkg_config.sign_tag());
+            assert!(pkg_config.sign_commit());
+            assert_eq!(pkg_config.tag_prefix(false), "crate-dot-config");
+            assert_eq!(pkg_config.registry(), Some("crate-release-toml"));
+
+            temp.close().unwrap();
+        }
+    }
+    mod publish_registries {
+        use super::*;
+
+        fn load(name: &str) -> Config {
+            let ws_meta = cargo_metadata::MetadataCommand::new()
+                .manifest_path("tests/fixtures/registry_ws/Cargo.toml")
+                .exec()
+                .unwrap();
+            let pkg = ws_meta.packages.iter().find(|p| p.name == name).unwrap();
+            let args = ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            load_package_config(&args, &ws_meta, pkg).unwrap()
+        }
+
+        #[test]
+        fn single_registry() {
+            let config = load("a");
+            assert!(config.publish());
+            assert_eq!(config.registry(), Some("alt"));
+        }
+
+        #[test]
+        fn several_registries() {
+            let config = load("b");
+            assert!(config.publish());
+            assert_eq!(config.registry(), None);
+            assert!(!config.publish_wait());
+        }
+
+        #[test]
+        fn crates_io() {
+            let config = load("c");
+            assert!(config.publish());
+            assert_eq!(config.registry(), None);
+            assert!(config.publish_wait());
+        }
+    }
+
+    mod inherited_fields {
+        use super::*;
+
+        const FIXTURE: &str = "tests/fixtures/inherited_fields";
+
+        #[test]
+        fn parses_unknown_inherited_fields() {
+            let config =
+                resolve_overrides(Path::new(FIXTURE), &Path::new(FIXTURE).join("a/Cargo.toml"))
+                    .unwrap();
+            assert_eq!(config.publish, None);
+        }
+
+        #[test]
+        fn lists_package_fields() {
+            let keys = inherited_keys(&Path::new(FIXTURE).join("a/Cargo.toml")).unwrap();
+            assert_eq!(
+                keys,
+                [
+                    "package.publish",
+                    "package.edition",
+                    "package.license",
+                    "package.rust-version"
+                ]
+            );
+        }
+
+        #[test]
+        fn lists_dependencies() {
+            let keys = inherited_keys(&Path::new(FIXTURE).join("b/Cargo.toml")).unwrap();
+            assert_eq!(keys, ["dependencies.a"]);
+        }
+    }
+
+    mod publish_timeout {
+        use super::*;
+
+        use std::time::Duration;
+
+        #[test]
+        fn default() {
+            let config = Config::from_defaults();
+            assert_eq!(config.publish_timeout(), Duration::from_secs(300));
+        }
+
+        #[test]
+        fn file_then_cli() {
+            let file = parse_config_file("publish-timeout = 600\n", &|_| None).unwrap();
+            let args = PublishArgs {
+                publish_timeout: Some(30),
+                ..Default::default()
+            };
+
+            let mut config = Config::from_defaults();
+            config.update(&file);
+            assert_eq!(config.publish_timeout(), Duration::from_secs(600));
+            config.update(&args.to_config());
+            assert_eq!(config.publish_timeout(), Duration::from_secs(30));
+        }
+
+        #[test]
+        fn zero_defers_wait() {
+            let mut config = Config::from_defaults();
+            assert!(!config.publish_wait_deferred());
+            config.update(&parse_config_file("publish-timeout = 0\n", &|_| None).unwrap());
+            assert!(config.publish_wait_deferred());
+            assert_eq!(config.publish_timeout(), Duration::from_secs(300));
+        }
+
+        #[test]
+        fn poll_interval() {
+            let mut config = Config::from_defaults();
+            assert_eq!(config.publish_poll_interval(), Duration::from_secs(1));
+            config.update(&parse_config_file("publish-poll-interval = 10\n", &|_| None).unwrap());
+            assert_eq!(config.publish_poll_interval(), Duration::from_secs(10));
+        }
+
+        #[test]
+        fn grace_sleep() {
+            let mut config = Config::from_defaults();
+            config.update(&parse_config_file("publish-grace-sleep = 5\n", &|_| None).unwrap());
+            assert_eq!(config.publish_grace_sleep(), Duration::from_secs(5));
+        }
+    }
+
+    mod template_vars {
+        use super::*;
+
+        use assert_fs::prelude::*;
+
+        #[test]
+        fn cli_overrides_file() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let file = temp.child("release.toml");
+            file.write_str(
+                r#"template-vars = { sprint = "Sprint 41", ticket = "REL-1" }
+"#,
+            )
+            .unwrap();
+            let args = ConfigArgs {
+                vars: vec![("sprint".to_owned(), "Sprint 42".to_owned())],
+                ..Default::default()
+            };
+
+            let mut config = Config::default();
+            config.update(&get_config_from_file(file.path()).unwrap().unwrap());
+            config.update(&args.to_config());
+            config.validate().unwrap();
+            let vars = config.template_vars().unwrap();
+            assert_eq!(vars["sprint"], "Sprint 42");
+            assert_eq!(vars["ticket"], "REL-1");
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn rejects_builtin() {
+            let config: Config = toml::from_str(
+                r#"template-vars = { version = "1.0.0" }
+"#,
+            )
+            .unwrap();
+            let err = config.validate().unwrap_err();
+            assert!(err.to_string().contains("{{version}}"), "{}", err);
+        }
+
+        #[test]
+        fn var_arg() {
+            assert_eq!(
+                parse_var("ticket=REL-1=2"),
+                Ok(("ticket".to_owned(), "REL-1=2".to_owned()))
+            );
+            assert!(parse_var("ticket").is_err());
+            assert!(parse_var("=REL-1").is_err());
+        }
+    }
+
+    mod config_arg {
+        use super::*;
+
+        fn value(arg: &str) -> Config {
+            match parse_config_arg(arg).unwrap() {
+                ConfigArg::Value(config) => *config,
+                ConfigArg::Path(path) => panic!("unexpected path {}", path.display()),
+            }
+        }
+
+        #[test]
+        fn string() {
+            assert_eq!(value(r#"push-remote="upstream""#).push_remote(), "upstream");
+            assert_eq!(value("push-remote=upstream").push_remote(), "upstream");
+        }
+
+        #[test]
+        fn bool() {
+            let config = value("push=false");
+            assert!(!config.push());
+            assert_eq!(config.origin, None);
+        }
+
+        #[test]
+        fn array() {
+            let config = value(r#"allow-branch=["main", "hotfix/*"]"#);
+            assert_eq!(
+                config.allow_branch().collect::<Vec<_>>(),
+                ["main", "hotfix/*"]
+            );
+        }
+
+        #[test]
+        fn dotted() {
+            let config = value("template-vars.sprint=42");
+            assert_eq!(config.template_vars().unwrap()["sprint"], "42");
+        }
+
+        #[test]
+        fn path() {
+            match parse_config_arg("ci/release.toml").unwrap() {
+                ConfigArg::Path(path) => assert_eq!(path, Path::new("ci/release.toml")),
+                ConfigArg::Value(_) => panic!("expected a path"),
+            }
+            assert!(matches!(
+                parse_config_arg("ci/a=b.toml").unwrap(),
+                ConfigArg::Path(_)
+            ));
+        }
+
+        #[test]
+        fn unknown_key() {
+            let err = parse_config_arg("pushh=false").unwrap_err();
+            assert!(err.contains("`--config pushh`"), "{}", err);
+            assert!(err.contains("push-remote"), "{}", err);
+        }
+
+        #[test]
+        fn wrong_type() {
+            let err = parse_config_arg("push=yes").unwrap_err();
+            assert!(err.contains("`--config push`"), "{}", err);
+            assert!(err.contains("bool"), "{}", err);
+        }
+
+        #[test]
+        fn overrides_args() {
+            let args = ConfigArgs {
+                custom_config: vec![parse_config_arg("allow-placeholder=false").unwrap()],
+                allow_placeholder_versions: true,
+                ..Default::default()
+            };
+            let mut config = Config::default();
+            config.update(&args.to_config());
+            for config_override in args.config_overrides() {
+                config.update(config_override);
+            }
+            assert!(!config.allow_placeholder());
         }
     }
 }
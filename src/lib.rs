@@ -11,3 +11,6 @@ pub mod error;
 pub mod ops;
 pub mod shell;
 pub mod steps;
+
+pub use steps::changes::ChangesEntry;
+pub use steps::plan::compute as release_plan;
@@ -1,11 +1,16 @@
 use std::env;
 use std::path::Path;
 
+use anyhow::Context as _;
 use bstr::ByteSlice;
 
 use crate::config;
 use crate::error::CargoResult;
 use crate::ops::cmd::call;
+use crate::ops::cmd::call_captured;
+use crate::ops::cmd::call_logged;
+use crate::ops::cmd::call_with_env;
+use crate::ops::overlay::Overlay;
 
 /// Expresses what features flags should be used
 #[derive(Clone, Debug)]
@@ -22,6 +27,43 @@ fn cargo() -> String {
     env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned())
 }
 
+/// The first cargo to read `workspace = true` package fields and dependencies
+pub const MANIFEST_INHERITANCE: semver::Version = semver::Version::new(1, 64, 0);
+
+/// The active cargo's version, from `cargo --version`
+///
+/// Looked up once.  `None` when cargo couldn't be run or its output wasn't understood.
+pub fn cargo_version() -> Option<&'static semver::Version> {
+    static VERSION: once_cell::sync::Lazy<Option<semver::Version>> =
+        once_cell::sync::Lazy::new(|| {
+            let output = std::process::Command::new(cargo())
+                .arg("--version")
+                .output()
+                .ok()?;
+            parse_cargo_version(&String::from_utf8_lossy(&output.stdout))
+        });
+    VERSION.as_ref()
+}
+
+fn parse_cargo_version(stdout: &str) -> Option<semver::Version> {
+    let version = stdout.strip_prefix("cargo ")?.split_whitespace().next()?;
+    let mut version = semver::Version::parse(version).ok()?;
+    // Nightly and beta toolchains already have the features of their release
+    version.pre = semver::Prerelease::EMPTY;
+    Some(version)
+}
+
+/// Whether cargo was told to stay off the network, through `CARGO_NET_OFFLINE`
+///
+/// Fetching, index updates, and publishing are skipped or refused while offline.
+pub fn is_offline() -> bool {
+    parse_offline(env::var("CARGO_NET_OFFLINE").ok().as_deref())
+}
+
+fn parse_offline(value: Option<&str>) -> bool {
+    matches!(value, Some("true") | Some("1"))
+}
+
 pub fn package_content(manifest_path: &Path) -> CargoResult<Vec<std::path::PathBuf>> {
     let mut cmd = std::process::Command::new(cargo());
     cmd.arg("package");
@@ -30,6 +72,9 @@ pub fn package_content(manifest_path: &Path) -> CargoResult<Vec<std::path::PathB
     cmd.arg("--list");
     // Not worth passing around allow_dirty to here since we are just getting a file list.
     cmd.arg("--allow-dirty");
+    // Keep build script and manifest warnings out of the listing
+    cmd.arg("--quiet");
+    cmd.env("CARGO_TERM_QUIET", "true");
     let output = cmd.output()?;
 
     let parent = manifest_path
@@ -37,20 +82,133 @@ pub fn package_content(manifest_path: &Path) -> CargoResult<Vec<std::path::PathB
         .unwrap_or_else(|| std::path::Path::new(""));
 
     if output.status.success() {
-        let paths = ByteSlice::lines(output.stdout.as_slice())
-            .map(|l| parent.join(l.to_path_lossy()))
-            .collect();
-        Ok(paths)
+        Ok(parse_package_list(parent, &output.stdout))
     } else {
         let error = String::from_utf8_lossy(&output.stderr);
         Err(anyhow::format_err!(
             "failed to get package content for {}: {}",
             manifest_path.display(),
-            error
+            relevant_stderr(&error)
         ))
     }
 }
 
+/// Files `cargo package` adds to the `.crate` that needn't exist in the package root
+const GENERATED_PACKAGE_FILES: &[&str] = &["Cargo.toml.orig", ".cargo_vcs_info.json", "Cargo.lock"];
+
+/// Paths from `cargo package --list`, dropping any noise that isn't a file in `parent`
+fn parse_package_list(parent: &Path, stdout: &[u8]) -> Vec<std::path::PathBuf> {
+    ByteSlice::lines(stdout)
+        .filter_map(|l| {
+            let relative = l.to_path_lossy();
+            let is_file = !relative.as_os_str().is_empty()
+                && relative
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)))
+                && (GENERATED_PACKAGE_FILES
+                    .iter()
+                    .any(|g| relative == Path::new(g))
+                    || parent.join(&relative).is_file());
+            if is_file {
+                Some(parent.join(relative))
+            } else {
+                log::debug!(
+                    "ignoring `cargo package --list` output {:?} for {}",
+                    relative,
+                    parent.display()
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Drop the `cargo` progress and warning output leading up to the actual error
+fn relevant_stderr(stderr: &str) -> &str {
+    let start = stderr
+        .match_indices("error")
+        .map(|(i, _)| i)
+        .find(|i| *i == 0 || stderr.as_bytes()[i - 1] == b'\n');
+    let error = start.map(|i| &stderr[i..]).unwrap_or(stderr);
+    error.trim()
+}
+
+/// A `.crate` file built by `cargo package`
+#[derive(Clone, Debug)]
+pub struct PackageArchive {
+    pub path: std::path::PathBuf,
+    pub size: u64,
+}
+
+/// Build the `.crate` for `manifest_path` into `target_dir`
+///
+/// Full packaging is slow, so releases go through [`crate::steps::plan::PackageContentCache`]
+/// to only do it once per version.
+pub fn package_archive(
+    manifest_path: &Path,
+    pkgid: Option<&str>,
+    target_dir: &Path,
+    name: &str,
+    version: &str,
+) -> CargoResult<PackageArchive> {
+    let path = build_archive(manifest_path, pkgid, target_dir, name, version)?;
+    let size = std::fs::metadata(&path)
+        .with_context(|| format!("failed to find package {}", path.display()))?
+        .len();
+    Ok(PackageArchive { path, size })
+}
+
+/// Run `cargo package` for `manifest_path`, returning where the `.crate` was written
+fn build_archive(
+    manifest_path: &Path,
+    pkgid: Option<&str>,
+    target_dir: &Path,
+    name: &str,
+    version: &str,
+) -> CargoResult<std::path::PathBuf> {
+    let mut cmd = std::process::Command::new(cargo());
+    cmd.arg("package");
+    cmd.arg("--manifest-path");
+    cmd.arg(manifest_path);
+    cmd.arg("--target-dir");
+    cmd.arg(target_dir);
+    if let Some(pkgid) = pkgid {
+        cmd.arg("--package");
+        cmd.arg(pkgid);
+    }
+    // Neither a build nor a clean tree changes what gets packaged
+    cmd.arg("--no-verify");
+    cmd.arg("--allow-dirty");
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "failed to package {}: {}",
+            manifest_path.display(),
+            relevant_stderr(&error)
+        );
+    }
+
+    Ok(target_dir
+        .join("package")
+        .join(format!("{}-{}.crate", name, version)))
+}
+
+/// The `count` largest files in the `.crate` at `archive`, largest first
+pub fn largest_files(archive: &Path, count: usize) -> CargoResult<Vec<(std::path::PathBuf, u64)>> {
+    let file = std::fs::File::open(archive)?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let mut files = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        files.push((entry.path()?.into_owned(), entry.header().size()?));
+    }
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+    files.truncate(count);
+    Ok(files)
+}
+
+/// Run `cargo publish`, returning whether it succeeded along with its output when `capture` is set
 #[allow(clippy::too_many_arguments)]
 pub fn publish(
     dry_run: bool,
@@ -59,8 +217,13 @@ pub fn publish(
     pkgid: Option<&str>,
     features: &Features,
     registry: Option<&str>,
+    registry_url: Option<&str>,
+    index_url: Option<&str>,
     target: Option<&str>,
-) -> CargoResult<bool> {
+    allow_dirty: bool,
+    log: Option<&Path>,
+    capture: bool,
+) -> CargoResult<(bool, Option<String>)> {
     let cargo = cargo();
 
     let mut command: Vec<&str> = vec![
@@ -75,13 +238,12 @@ pub fn publish(
         command.push(pkgid);
     }
 
-    if let Some(registry) = registry {
-        command.push("--registry");
-        command.push(registry);
-    }
+    command.extend(registry_args(registry, index_url));
 
     if dry_run {
         command.push("--dry-run");
+    }
+    if dry_run || allow_dirty {
         command.push("--allow-dirty");
     }
 
@@ -107,58 +269,682 @@ pub fn publish(
         }
     };
 
-    call(command, false)
+    let key;
+    let envs = match (registry, registry_url) {
+        (Some(registry), Some(registry_url)) => {
+            key = registry_index_env(registry);
+            Some(maplit::btreemap! {
+                std::ffi::OsStr::new(key.as_str()) => std::ffi::OsStr::new(registry_url),
+            })
+        }
+        (None, Some(_)) => anyhow::bail!("a registry URL requires a registry name"),
+        (_, None) => None,
+    };
+    if capture && !dry_run {
+        let (success, output) = call_captured(command, envs, log)?;
+        return Ok((success, Some(output)));
+    }
+    let success = match (log, envs) {
+        (Some(log), envs) => call_logged(command, envs, log)?,
+        (None, Some(envs)) => call_with_env(command, envs, &env::current_dir()?, false)?,
+        (None, None) => call(command, false)?,
+    };
+    Ok((success, None))
+}
+
+/// The `cargo publish` arguments for where to upload, with `index_url` bypassing cargo config
+fn registry_args<'a>(registry: Option<&'a str>, index_url: Option<&'a str>) -> Vec<&'a str> {
+    match (index_url, registry) {
+        (Some(index_url), _) => vec!["--index", index_url],
+        (None, Some(registry)) => vec!["--registry", registry],
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Whether a failed `cargo publish` is worth retrying, judging by its `output`
+///
+/// Only network trouble and registry server errors are, not a rejected or broken package.
+pub fn is_transient_publish_failure(output: &str) -> bool {
+    const PERMANENT: &[&str] = &[
+        "failed to verify package tarball",
+        "could not compile",
+        "unauthorized",
+        "forbidden",
+    ];
+    const TRANSIENT: &[&str] = &[
+        "timed out",
+        "timeout",
+        "operation too slow",
+        "connection reset",
+        "connection refused",
+        "connection closed",
+        "failed to connect",
+        "couldn't resolve host",
+        "spurious network error",
+        "internal server error",
+        "bad gateway",
+        "service unavailable",
+    ];
+    static SERVER_ERROR: once_cell::sync::Lazy<regex::Regex> =
+        once_cell::sync::Lazy::new(|| regex::Regex::new(r"\b(status|got) 5\d\d\b").unwrap());
+
+    if is_already_uploaded(output) {
+        return false;
+    }
+    let output = output.to_ascii_lowercase();
+    if PERMANENT.iter().any(|p| output.contains(p)) {
+        return false;
+    }
+    TRANSIENT.iter().any(|t| output.contains(t)) || SERVER_ERROR.is_match(&output)
+}
+
+/// Whether a failed `cargo publish` was rejected for the version being in the registry already
+///
+/// On a retry, this is the earlier attempt's upload having gone through.
+pub fn is_already_uploaded(output: &str) -> bool {
+    let output = output.to_ascii_lowercase();
+    output.contains("already uploaded") || output.contains("already exists")
+}
+
+/// How long to wait before publish `attempt`, counting retries from 1
+pub fn publish_retry_delay(attempt: u32) -> std::time::Duration {
+    const BASE: std::time::Duration = std::time::Duration::from_secs(5);
+    const MAX: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+    BASE.checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .unwrap_or(MAX)
+        .min(MAX)
+}
+
+/// Build `manifest_path` with only `features` on top of the defaults, in place of `cargo publish`'s
+/// verification
+pub fn verify_build(
+    manifest_path: &Path,
+    pkgid: Option<&str>,
+    features: &[String],
+    target: Option<&str>,
+    prefix: &str,
+    dry_run: bool,
+) -> CargoResult<bool> {
+    let cargo = cargo();
+
+    let mut command: Vec<&str> = vec![
+        &cargo,
+        "build",
+        "--manifest-path",
+        manifest_path.to_str().unwrap(),
+    ];
+
+    if let Some(pkgid) = pkgid {
+        command.push("--package");
+        command.push(pkgid);
+    }
+
+    let feature_arg = features.join(",");
+    if !features.is_empty() {
+        command.push("--features");
+        command.push(&feature_arg);
+    }
+
+    if let Some(target) = target {
+        command.push("--target");
+        command.push(target);
+    }
+
+    crate::ops::cmd::call_prefixed(command, prefix, dry_run)
+}
+
+/// The environment variable cargo reads `registry`'s index URL from
+fn registry_index_env(registry: &str) -> String {
+    format!(
+        "CARGO_REGISTRIES_{}_INDEX",
+        registry.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+/// The environment variable cargo reads `registry`'s token from
+pub fn registry_token_env(registry: Option<&str>) -> String {
+    match registry {
+        None | Some("crates-io") => "CARGO_REGISTRY_TOKEN".to_owned(),
+        Some(registry) => format!(
+            "CARGO_REGISTRIES_{}_TOKEN",
+            registry.to_ascii_uppercase().replace('-', "_")
+        ),
+    }
+}
+
+fn cargo_home() -> Option<std::path::PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs_next::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Registry names cargo will recognize when run from `cwd`
+pub fn known_registries(cwd: &Path) -> CargoResult<std::collections::BTreeSet<String>> {
+    known_registries_in(cargo_home().as_deref(), cwd, env::vars())
+}
+
+fn known_registries_in(
+    cargo_home: Option<&Path>,
+    cwd: &Path,
+    vars: impl Iterator<Item = (String, String)>,
+) -> CargoResult<std::collections::BTreeSet<String>> {
+    let mut names = std::collections::BTreeSet::new();
+    names.insert("crates-io".to_owned());
+
+    let config_dirs = cwd
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home.map(|dir| dir.to_owned()));
+    for config_dir in config_dirs {
+        for file_name in ["config.toml", "config"] {
+            let config_path = config_dir.join(file_name);
+            if !config_path.is_file() {
+                continue;
+            }
+            let config = std::fs::read_to_string(&config_path)?;
+            let config: toml::Table = toml::from_str(&config)
+                .with_context(|| format!("failed to parse {}", config_path.display()))?;
+            if let Some(registries) = config.get("registries").and_then(|r| r.as_table()) {
+                names.extend(registries.keys().cloned());
+            }
+        }
+    }
+
+    for (key, _) in vars {
+        if let Some(name) = key
+            .strip_prefix("CARGO_REGISTRIES_")
+            .and_then(|k| k.strip_suffix("_INDEX"))
+        {
+            names.insert(name.to_ascii_lowercase());
+        }
+    }
+
+    Ok(names)
+}
+
+/// Whether `registry` is among `known`, as cargo matches names from config and environment
+pub fn is_known_registry(known: &std::collections::BTreeSet<String>, registry: &str) -> bool {
+    let registry = normalize_registry(registry);
+    known.iter().any(|k| normalize_registry(k) == registry)
+}
+
+fn normalize_registry(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+/// Whether cargo has a token for publishing to `registry`, defaulting to crates.io
+///
+/// Only the credentials file and environment are checked, not credential providers like the
+/// system keyring.
+pub fn has_credentials(registry: Option<&str>) -> CargoResult<bool> {
+    has_credentials_in(cargo_home().as_deref(), registry, env::vars())
+}
+
+fn has_credentials_in(
+    cargo_home: Option<&Path>,
+    registry: Option<&str>,
+    mut vars: impl Iterator<Item = (String, String)>,
+) -> CargoResult<bool> {
+    let token_env = registry_token_env(registry);
+    if vars.any(|(key, value)| key == token_env && !value.is_empty()) {
+        return Ok(true);
+    }
+
+    let cargo_home = match cargo_home {
+        Some(cargo_home) => cargo_home,
+        None => return Ok(false),
+    };
+    for file_name in ["credentials.toml", "credentials"] {
+        let credentials_path = cargo_home.join(file_name);
+        if !credentials_path.is_file() {
+            continue;
+        }
+        let credentials = std::fs::read_to_string(&credentials_path)?;
+        let credentials: toml::Table = toml::from_str(&credentials)
+            .with_context(|| format!("failed to parse {}", credentials_path.display()))?;
+        let entry = match registry {
+            None | Some("crates-io") => credentials.get("registry"),
+            Some(registry) => credentials
+                .get("registries")
+                .and_then(|r| r.as_table())
+                .and_then(|r| {
+                    let registry = normalize_registry(registry);
+                    r.iter()
+                        .find(|(name, _)| normalize_registry(name) == registry)
+                        .map(|(_, entry)| entry)
+                }),
+        };
+        let token = entry
+            .and_then(|e| e.get("token"))
+            .and_then(|t| t.as_str())
+            .unwrap_or_default();
+        if !token.is_empty() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// How long to wait for a published crate to show up in the index
+pub const DEFAULT_PUBLISH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+/// How often to check the index while waiting on a publish
+pub const DEFAULT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A registry index that can be polled for newly published versions
+pub trait PublishIndex {
+    fn update(&mut self) -> CargoResult<()>;
+
+    /// Refresh only `name`'s record, for backends that can fetch one crate at a time
+    fn update_crate(&mut self, _name: &str) -> CargoResult<()> {
+        self.update()
+    }
+
+    fn is_published(&self, name: &str, version: &str) -> bool;
+
+    /// Every version of `name` the index currently shows
+    fn versions(&self, _name: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The sha256 of the `.crate` the index records for `version` of `name`
+    fn checksum(&self, _name: &str, _version: &str) -> Option<[u8; 32]> {
+        None
+    }
+
+    /// The published `.crate` for `version` of `name`, when the registry says where to get it
+    fn download(&self, _name: &str, _version: &str) -> CargoResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+/// How a packaged `.crate` compares to the one published for the same version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublishedContent {
+    /// Byte-for-byte what was published
+    Identical,
+    /// Not what was published, with both sha256 checksums in hex
+    Different { local: String, published: String },
+    /// There is no published checksum to compare against
+    Unknown,
+}
+
+/// Compare the `.crate` at `archive` against what `index` has for `version` of `name`
+///
+/// When the checksums differ, the published `.crate` is downloaded to compare file by file,
+/// leaving out `.cargo_vcs_info.json` as it names the commit packaged from.
+pub fn published_content(
+    index: &impl PublishIndex,
+    name: &str,
+    version: &str,
+    archive: &Path,
+) -> CargoResult<PublishedContent> {
+    let published = match index.checksum(name, version) {
+        Some(published) => published,
+        None => return Ok(PublishedContent::Unknown),
+    };
+    let local = file_checksum(archive)?;
+    if local == published {
+        return Ok(PublishedContent::Identical);
+    }
+    let different = PublishedContent::Different {
+        local: hex::encode(local),
+        published: hex::encode(published),
+    };
+
+    let download = match index.download(name, version)? {
+        Some(download) => download,
+        None => return Ok(different),
+    };
+    let downloaded: [u8; 32] = {
+        use sha2::Digest as _;
+        sha2::Sha256::digest(&download).into()
+    };
+    if downloaded != published {
+        anyhow::bail!(
+            "downloaded {} {} doesn't match the index's sha256 {}",
+            name,
+            version,
+            hex::encode(published)
+        );
+    }
+    let local_files = std::fs::File::open(archive)
+        .map_err(anyhow::Error::from)
+        .and_then(crate_files)
+        .with_context(|| format!("failed to read package {}", archive.display()))?;
+    let published_files = crate_files(download.as_slice())
+        .with_context(|| format!("failed to read the published {} {}", name, version))?;
+    if local_files == published_files {
+        Ok(PublishedContent::Identical)
+    } else {
+        Ok(different)
+    }
+}
+
+/// The files of the `.crate` read from `reader`, relative to its `<name>-<version>` directory
+///
+/// `.cargo_vcs_info.json` is left out.
+fn crate_files(
+    reader: impl std::io::Read,
+) -> CargoResult<std::collections::BTreeMap<std::path::PathBuf, Vec<u8>>> {
+    let mut files = std::collections::BTreeMap::new();
+    let mut unpacker = tar::Archive::new(flate2::read::GzDecoder::new(reader));
+    for entry in unpacker.entries()? {
+        let mut entry = entry?;
+        let relpath = entry
+            .path()?
+            .components()
+            .skip(1)
+            .collect::<std::path::PathBuf>();
+        if relpath == Path::new(".cargo_vcs_info.json") {
+            continue;
+        }
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut data)?;
+        files.insert(relpath, data);
+    }
+    Ok(files)
+}
+
+fn file_checksum(path: &Path) -> CargoResult<[u8; 32]> {
+    use sha2::Digest as _;
+
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("failed to read package {}", path.display()))?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().into())
 }
 
+/// Poll `index` until `version` of `name` shows up
+///
+/// `known` are the versions visible before the upload; a newer one among them, like when
+/// backporting a fix, doesn't end the wait.
 pub fn wait_for_publish(
-    index: &mut crates_index::Index,
+    index: &mut impl PublishIndex,
     name: &str,
     version: &str,
+    known: &[String],
     timeout: std::time::Duration,
+    poll_interval: std::time::Duration,
     dry_run: bool,
 ) -> CargoResult<()> {
     if !dry_run {
         let now = std::time::Instant::now();
-        let sleep_time = std::time::Duration::from_secs(1);
         let mut logged = false;
         loop {
-            if let Err(e) = index.update() {
+            let updated = index.update_crate(name);
+            if let Err(e) = &updated {
                 log::debug!("crate index update failed with {}", e);
             }
-            if is_published(index, name, version) {
+            let visible = index.versions(name);
+            log::debug!(
+                "index shows {} at {}",
+                name,
+                if visible.is_empty() {
+                    "no versions".to_owned()
+                } else {
+                    visible.join(", ")
+                }
+            );
+            if index.is_published(name, version) {
+                break;
+            } else if let Some(newer) = newer_release(&visible, known, version) {
+                let _ = crate::ops::reporter::warn(format!(
+                    "{} {} is in the index, newer than {}; no longer waiting on it",
+                    name, newer, version
+                ));
                 break;
             } else if timeout < now.elapsed() {
+                if let Err(e) = updated {
+                    return Err(e.context("timeout waiting for crate to be published"));
+                }
                 anyhow::bail!("timeout waiting for crate to be published");
             }
 
             if !logged {
-                let _ = crate::ops::shell::status(
+                let _ = crate::ops::reporter::status(
                     "Waiting",
                     format!("on {name} to propagate to index"),
                 );
                 logged = true;
             }
-            std::thread::sleep(sleep_time);
+            std::thread::sleep(poll_interval);
         }
     }
 
     Ok(())
 }
 
-pub fn is_published(index: &crates_index::Index, name: &str, version: &str) -> bool {
+pub fn versions(index: &crate::ops::index::CratesIoIndex, name: &str) -> Vec<String> {
+    index
+        .crate_(name)
+        .iter()
+        .flat_map(|c| c.versions().iter())
+        .map(|v| v.version().to_owned())
+        .collect()
+}
+
+pub fn checksum(
+    index: &crate::ops::index::CratesIoIndex,
+    name: &str,
+    version: &str,
+) -> Option<[u8; 32]> {
+    index
+        .crate_(name)
+        .iter()
+        .flat_map(|c| c.versions().iter())
+        .find(|v| is_same_release(v.version(), version))
+        .map(|v| *v.checksum())
+}
+
+pub fn is_published(index: &crate::ops::index::CratesIoIndex, name: &str, version: &str) -> bool {
     let crate_data = index.crate_(name);
     crate_data
         .iter()
         .flat_map(|c| c.versions().iter())
-        .any(|v| v.version() == version)
+        .any(|v| is_same_release(v.version(), version))
+}
+
+/// A version among `visible`, but not `known`, released after `version`, like when someone else
+/// published first
+fn newer_release<'v>(visible: &'v [String], known: &[String], version: &str) -> Option<&'v str> {
+    let release = |v: &str| {
+        semver::Version::parse(v).ok().map(|mut v| {
+            v.build = semver::BuildMetadata::EMPTY;
+            v
+        })
+    };
+    let version = release(version)?;
+    visible
+        .iter()
+        .filter(|v| !known.contains(v))
+        .filter(|v| release(v).map(|v| version < v).unwrap_or(false))
+        .map(|v| v.as_str())
+        .last()
+}
+
+/// Whether crates.io would consider `left` and `right` the same version, ignoring build metadata
+fn is_same_release(left: &str, right: &str) -> bool {
+    match (semver::Version::parse(left), semver::Version::parse(right)) {
+        (Ok(mut left), Ok(mut right)) => {
+            left.build = semver::BuildMetadata::EMPTY;
+            right.build = semver::BuildMetadata::EMPTY;
+            left == right
+        }
+        _ => left == right,
+    }
+}
+
+/// Set `package.name` in `manifest`, for publishing under another name
+pub fn rename_package(manifest: &str, name: &str) -> CargoResult<String> {
+    let mut manifest: toml_edit::Document = manifest.parse()?;
+    set_package_field(&mut manifest, "name", name)?;
+    Ok(manifest.to_string())
+}
+
+fn set_package_field(
+    manifest: &mut toml_edit::Document,
+    field: &str,
+    value: &str,
+) -> CargoResult<()> {
+    let package = manifest
+        .get_mut("package")
+        .and_then(|p| p.as_table_like_mut())
+        .ok_or_else(|| anyhow::format_err!("manifest has no `[package]` to edit"))?;
+    package.insert(field, toml_edit::value(value));
+    Ok(())
+}
+
+/// Build the `.crate` for `manifest_path` as it will be once bumped from `version` to
+/// `planned_version`
+///
+/// Like [`RenamedPackage`], this packages a staged copy, so the workspace is left alone until the
+/// release is confirmed.  Replacements and hooks aren't run against the copy.
+pub fn package_archive_as(
+    manifest_path: &Path,
+    pkgid: Option<&str>,
+    target_dir: &Path,
+    name: &str,
+    version: &str,
+    planned_version: &str,
+) -> CargoResult<PackageArchive> {
+    if version == planned_version {
+        return package_archive(manifest_path, pkgid, target_dir, name, version);
+    }
+    let root = target_dir
+        .join("package")
+        .join("planned")
+        .join(format!("{}-{}", name, planned_version));
+    let staged = StagedPackage::stage(
+        manifest_path,
+        pkgid,
+        target_dir,
+        name,
+        version,
+        root,
+        |manifest| set_package_field(manifest, "version", planned_version),
+    )?;
+    package_archive(
+        &staged.manifest_path(),
+        None,
+        target_dir,
+        name,
+        planned_version,
+    )
+}
+
+/// A package unpacked from its `.crate` with its manifest edited, removed when dropped
+struct StagedPackage {
+    root: std::path::PathBuf,
+}
+
+impl StagedPackage {
+    /// Package `manifest_path` and unpack it into `root`, applying `edit` to the manifest
+    fn stage(
+        manifest_path: &Path,
+        pkgid: Option<&str>,
+        target_dir: &Path,
+        crate_name: &str,
+        version: &str,
+        root: std::path::PathBuf,
+        edit: impl FnOnce(&mut toml_edit::Document) -> CargoResult<()>,
+    ) -> CargoResult<Self> {
+        let archive = build_archive(manifest_path, pkgid, target_dir, crate_name, version)?;
+        if root.exists() {
+            std::fs::remove_dir_all(&root)?;
+        }
+        let file = std::fs::File::open(&archive)?;
+        let mut unpacker = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        for entry in unpacker.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            // Swap the `<crate_name>-<version>` prefix for ours
+            let relpath = path.components().skip(1).collect::<std::path::PathBuf>();
+            let target = root.join(&relpath);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            entry.unpack(&target)?;
+        }
+        let staged = Self { root };
+
+        // `cargo package` generates these itself and refuses to package them
+        for generated in ["Cargo.toml.orig", ".cargo_vcs_info.json"] {
+            let path = staged.root.join(generated);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        let manifest_path = staged.manifest_path();
+        let manifest = std::fs::read_to_string(&manifest_path)?;
+        let mut manifest: toml_edit::Document = manifest.parse()?;
+        edit(&mut manifest)?;
+        // Stand alone rather than being mistaken for a member of the enclosing workspace
+        manifest["workspace"] = toml_edit::table();
+        crate::ops::path::write(&manifest_path, &manifest.to_string())?;
+        Ok(staged)
+    }
+
+    fn manifest_path(&self) -> std::path::PathBuf {
+        self.root.join("Cargo.toml")
+    }
+}
+
+impl Drop for StagedPackage {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.root) {
+            log::debug!("failed to remove {}: {}", self.root.display(), err);
+        }
+    }
+}
+
+/// A package unpacked from its `.crate` under its `publish-as` name, removed when dropped
+///
+/// The workspace is left alone so an interrupted publish can't leave the manifest renamed.
+pub struct RenamedPackage {
+    staged: StagedPackage,
+}
+
+impl RenamedPackage {
+    /// Package `manifest_path` and unpack it into `target_dir`, named `name`
+    pub fn stage(
+        manifest_path: &Path,
+        pkgid: Option<&str>,
+        target_dir: &Path,
+        crate_name: &str,
+        version: &str,
+        name: &str,
+    ) -> CargoResult<Self> {
+        let root = target_dir
+            .join("package")
+            .join("publish-as")
+            .join(format!("{}-{}", name, version));
+        let staged = StagedPackage::stage(
+            manifest_path,
+            pkgid,
+            target_dir,
+            crate_name,
+            version,
+            root,
+            |manifest| set_package_field(manifest, "name", name),
+        )?;
+        Ok(Self { staged })
+    }
+
+    /// The renamed manifest, to publish in place of the original
+    pub fn manifest_path(&self) -> std::path::PathBuf {
+        self.staged.manifest_path()
+    }
 }
 
 pub fn set_workspace_version(
     manifest_path: &Path,
     version: &str,
+    overlay: &mut Overlay,
     dry_run: bool,
 ) -> CargoResult<()> {
-    let original_manifest = std::fs::read_to_string(manifest_path)?;
+    let original_manifest = overlay.read_to_string(manifest_path)?;
     let mut manifest: toml_edit::Document = original_manifest.parse()?;
     manifest["workspace"]["package"]["version"] = toml_edit::value(version);
     let manifest = manifest.to_string();
@@ -181,6 +967,7 @@ pub fn set_workspace_version(
                 0,
             );
             log::debug!("change:\n{}", itertools::join(diff.into_iter(), ""));
+            overlay.stage(manifest_path, manifest);
         }
     } else {
         atomic_write(manifest_path, &manifest)?;
@@ -189,22 +976,41 @@ pub fn set_workspace_version(
     Ok(())
 }
 
-pub fn ensure_owners(
+/// Where the current owners of a crate can be looked up
+pub trait OwnerRegistry {
+    /// The logins owning `name` in `registry`, or at `index_url` when set
+    fn owners(
+        &self,
+        name: &str,
+        registry: Option<&str>,
+        index_url: Option<&str>,
+    ) -> CargoResult<Vec<String>>;
+}
+
+/// Owners as `cargo owner --list` reports them
+pub struct CargoOwners;
+
+impl OwnerRegistry for CargoOwners {
+    fn owners(
+        &self,
+        name: &str,
+        registry: Option<&str>,
+        index_url: Option<&str>,
+    ) -> CargoResult<Vec<String>> {
+        list_owners(name, registry, index_url)
+    }
+}
+
+/// The logins owning `name`, per `cargo owner --list`
+pub fn list_owners(
     name: &str,
-    logins: &[String],
     registry: Option<&str>,
-    dry_run: bool,
-) -> CargoResult<()> {
-    let cargo = cargo();
-
-    // "Look-before-you-leap" in case the user has permission to publish but not set owners.
-    let mut cmd = std::process::Command::new(&cargo);
+    index_url: Option<&str>,
+) -> CargoResult<Vec<String>> {
+    let mut cmd = std::process::Command::new(cargo());
     cmd.arg("owner").arg(name).arg("--color=never");
     cmd.arg("--list");
-    if let Some(registry) = registry {
-        cmd.arg("--registry");
-        cmd.arg(registry);
-    }
+    cmd.args(registry_args(registry, index_url));
     let output = cmd.output()?;
     if !output.status.success() {
         anyhow::bail!(
@@ -214,17 +1020,29 @@ pub fn ensure_owners(
     }
     let raw = String::from_utf8(output.stdout)
         .map_err(|_| anyhow::format_err!("unrecognized response from registry"))?;
+    Ok(parse_owners(&raw)
+        .into_iter()
+        .map(|s| s.to_owned())
+        .collect())
+}
 
-    let mut current = std::collections::BTreeSet::new();
-    // HACK: No programmatic CLI access and don't want to link against `cargo` (yet), so parsing
-    // text output
-    for line in raw.lines() {
-        if let Some((owner, _)) = line.split_once(' ') {
-            if !owner.is_empty() {
-                current.insert(owner);
-            }
-        }
-    }
+/// Ensure `logins` are owners of `name`, removing any others when `remove_extra`
+pub fn ensure_owners(
+    name: &str,
+    logins: &[String],
+    registry: Option<&str>,
+    index_url: Option<&str>,
+    remove_extra: bool,
+    dry_run: bool,
+) -> CargoResult<()> {
+    let cargo = cargo();
+
+    // "Look-before-you-leap" in case the user has permission to publish but not set owners.
+    let current = list_owners(name, registry, index_url)?;
+    let current = current
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<std::collections::BTreeSet<_>>();
 
     let expected = logins
         .iter()
@@ -233,7 +1051,7 @@ pub fn ensure_owners(
 
     let missing = expected.difference(&current).copied().collect::<Vec<_>>();
     if !missing.is_empty() {
-        let _ = crate::ops::shell::status(
+        let _ = crate::ops::reporter::status(
             "Adding",
             format!("owners for {}: {}", name, missing.join(", ")),
         );
@@ -243,15 +1061,12 @@ pub fn ensure_owners(
             for missing in missing {
                 cmd.arg("--add").arg(missing);
             }
-            if let Some(registry) = registry {
-                cmd.arg("--registry");
-                cmd.arg(registry);
-            }
+            cmd.args(registry_args(registry, index_url));
             let output = cmd.output()?;
             if !output.status.success() {
                 // HACK: Can't error as the user might not have permission to set owners and we can't
                 // tell what the error was without parsing it
-                let _ = crate::ops::shell::warn(format!(
+                let _ = crate::ops::reporter::warn(format!(
                     "failed to set owners for {}: {}",
                     name,
                     String::from_utf8_lossy(&output.stderr)
@@ -261,15 +1076,55 @@ pub fn ensure_owners(
     }
 
     let extra = current.difference(&expected).copied().collect::<Vec<_>>();
-    if !extra.is_empty() {
+    if !extra.is_empty() && remove_extra {
+        let _ = crate::ops::reporter::status(
+            "Removing",
+            format!("owners for {}: {}", name, extra.join(", ")),
+        );
+        if !dry_run {
+            let mut cmd = std::process::Command::new(&cargo);
+            cmd.arg("owner").arg(name).arg("--color=never");
+            for extra in extra {
+                cmd.arg("--remove").arg(extra);
+            }
+            cmd.args(registry_args(registry, index_url));
+            let output = cmd.output()?;
+            if !output.status.success() {
+                let _ = crate::ops::reporter::warn(format!(
+                    "failed to remove owners for {}: {}",
+                    name,
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+        }
+    } else if !extra.is_empty() {
         log::debug!("extra owners for {}: {}", name, extra.join(", "));
     }
 
     Ok(())
 }
 
-pub fn set_package_version(manifest_path: &Path, version: &str, dry_run: bool) -> CargoResult<()> {
-    let original_manifest = std::fs::read_to_string(manifest_path)?;
+/// Logins from `cargo owner --list`, one `login (Name)` per line
+fn parse_owners(raw: &str) -> std::collections::BTreeSet<&str> {
+    let mut current = std::collections::BTreeSet::new();
+    // HACK: No programmatic CLI access and don't want to link against `cargo` (yet), so parsing
+    // text output
+    for line in raw.lines() {
+        let owner = line.split_once(' ').map(|(owner, _)| owner).unwrap_or(line);
+        if !owner.is_empty() {
+            current.insert(owner);
+        }
+    }
+    current
+}
+
+pub fn set_package_version(
+    manifest_path: &Path,
+    version: &str,
+    overlay: &mut Overlay,
+    dry_run: bool,
+) -> CargoResult<()> {
+    let original_manifest = overlay.read_to_string(manifest_path)?;
     let mut manifest: toml_edit::Document = original_manifest.parse()?;
     manifest["package"]["version"] = toml_edit::value(version);
     let manifest = manifest.to_string();
@@ -292,6 +1147,7 @@ pub fn set_package_version(manifest_path: &Path, version: &str, dry_run: bool) -
                 0,
             );
             log::debug!("change:\n{}", itertools::join(diff.into_iter(), ""));
+            overlay.stage(manifest_path, manifest);
         }
     } else {
         atomic_write(manifest_path, &manifest)?;
@@ -307,19 +1163,34 @@ pub fn upgrade_dependency_req(
     name: &str,
     version: &semver::Version,
     upgrade: config::DependentVersion,
+    overlay: &mut Overlay,
     dry_run: bool,
-) -> CargoResult<()> {
+) -> CargoResult<Vec<DependencyUpdate>> {
     let manifest_root = manifest_path
         .parent()
         .expect("always at least a parent dir");
-    let original_manifest = std::fs::read_to_string(manifest_path)?;
+    let original_manifest = overlay.read_to_string(manifest_path)?;
     let mut manifest: toml_edit::Document = original_manifest.parse()?;
 
-    for dep_item in find_dependency_tables(manifest.as_table_mut())
-        .flat_map(|t| t.iter_mut().filter_map(|(_, d)| d.as_table_like_mut()))
-        .filter(|d| is_relevant(*d, manifest_root, root))
-    {
-        upgrade_req(manifest_name, dep_item, name, version, upgrade);
+    let mut updates = Vec::new();
+    for (key, dep_item) in find_dependency_tables(manifest.as_table_mut()).flat_map(|t| {
+        t.iter_mut()
+            .filter_map(|(k, d)| Some((k.get().to_owned(), d.as_table_like_mut()?)))
+    }) {
+        if is_inherited(dep_item) {
+            if key == name {
+                log::debug!(
+                    "{}'s dependency on {} is inherited from `workspace.dependencies`",
+                    manifest_name,
+                    name
+                );
+            }
+            continue;
+        }
+        if !is_relevant(dep_item, manifest_root, root) {
+            continue;
+        }
+        updates.extend(upgrade_req(manifest_name, dep_item, name, version, upgrade));
     }
 
     let manifest = manifest.to_string();
@@ -341,12 +1212,46 @@ pub fn upgrade_dependency_req(
                 0,
             );
             log::debug!("change:\n{}", itertools::join(diff.into_iter(), ""));
+            overlay.stage(manifest_path, manifest);
         } else {
             atomic_write(manifest_path, &manifest)?;
         }
     }
 
-    Ok(())
+    Ok(updates)
+}
+
+/// A dependency requirement rewritten to match a released version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyUpdate {
+    /// The package (or `workspace`) whose manifest was rewritten
+    pub manifest_name: String,
+    pub dependency: String,
+    pub old_req: String,
+    pub new_req: String,
+}
+
+impl std::fmt::Display for DependencyUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} {} -> {}",
+            self.manifest_name, self.dependency, self.old_req, self.new_req
+        )
+    }
+}
+
+/// Render `updates` as indented lines, for `{{dependency_updates}}`
+///
+/// Empty when there are no updates so templates don't leave dangling headers.
+pub fn render_dependency_updates<'u>(
+    updates: impl IntoIterator<Item = &'u DependencyUpdate>,
+) -> String {
+    updates
+        .into_iter()
+        .map(|u| format!("  {}", u))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn find_dependency_tables(
@@ -391,6 +1296,11 @@ fn find_dependency_tables(
     })
 }
 
+/// `foo = { workspace = true }`, whose requirement lives in `workspace.dependencies`
+fn is_inherited(d: &dyn toml_edit::TableLike) -> bool {
+    d.get("workspace").and_then(|i| i.as_bool()) == Some(true)
+}
+
 fn is_relevant(d: &dyn toml_edit::TableLike, dep_crate_root: &Path, crate_root: &Path) -> bool {
     if !d.contains_key("version") {
         return false;
@@ -400,7 +1310,7 @@ fn is_relevant(d: &dyn toml_edit::TableLike, dep_crate_root: &Path, crate_root:
         .and_then(|i| i.as_str())
         .and_then(|relpath| dunce::canonicalize(dep_crate_root.join(relpath)).ok())
     {
-        Some(dep_path) => dep_path == crate_root,
+        Some(dep_path) => crate::ops::path::same_path(&dep_path, crate_root),
         None => false,
     }
 }
@@ -411,62 +1321,83 @@ fn upgrade_req(
     name: &str,
     version: &semver::Version,
     upgrade: config::DependentVersion,
-) -> bool {
+) -> Option<DependencyUpdate> {
     let version_value = if let Some(version_value) = dep_item.get_mut("version") {
         version_value
     } else {
         log::debug!("not updating path-only dependency on {}", name);
-        return false;
+        return None;
     };
 
     let existing_req_str = if let Some(existing_req) = version_value.as_str() {
         existing_req
     } else {
         log::debug!("unsupported dependency {}", name);
-        return false;
+        return None;
     };
     let existing_req = if let Ok(existing_req) = semver::VersionReq::parse(existing_req_str) {
         existing_req
     } else {
         log::debug!("unsupported dependency req {}={}", name, existing_req_str);
-        return false;
+        return None;
     };
     let new_req = match upgrade {
         config::DependentVersion::Fix => {
             if !existing_req.matches(version) {
-                let new_req = crate::ops::version::upgrade_requirement(existing_req_str, version)
-                    .ok()
-                    .flatten();
+                let new_req =
+                    try_upgrade_requirement(manifest_name, name, existing_req_str, version);
                 if let Some(new_req) = new_req {
                     new_req
                 } else {
-                    return false;
+                    return None;
                 }
             } else {
-                return false;
+                return None;
             }
         }
         config::DependentVersion::Upgrade => {
-            let new_req = crate::ops::version::upgrade_requirement(existing_req_str, version)
-                .ok()
-                .flatten();
+            let new_req = try_upgrade_requirement(manifest_name, name, existing_req_str, version);
             if let Some(new_req) = new_req {
                 new_req
             } else {
-                return false;
+                return None;
             }
         }
     };
 
-    let _ = crate::ops::shell::status(
+    let _ = crate::ops::reporter::status(
         "Updating",
         format!(
             "{}'s dependency from {} to {}",
             manifest_name, existing_req_str, new_req
         ),
     );
+    let update = DependencyUpdate {
+        manifest_name: manifest_name.to_owned(),
+        dependency: name.to_owned(),
+        old_req: existing_req_str.to_owned(),
+        new_req: new_req.clone(),
+    };
     *version_value = toml_edit::value(new_req);
-    true
+    Some(update)
+}
+
+fn try_upgrade_requirement(
+    manifest_name: &str,
+    name: &str,
+    existing_req_str: &str,
+    version: &semver::Version,
+) -> Option<String> {
+    match crate::ops::version::upgrade_requirement(existing_req_str, version) {
+        Ok(new_req) => new_req,
+        Err(err) => {
+            let _ = crate::ops::reporter::warn(format!(
+                "not updating {}'s dependency on {} ({}) to match {}: {}",
+                manifest_name, name, existing_req_str, version, err
+            ));
+            None
+        }
+    }
 }
 
 pub fn update_lock(manifest_path: &Path) -> CargoResult<()> {
@@ -477,6 +1408,8 @@ pub fn update_lock(manifest_path: &Path) -> CargoResult<()> {
     Ok(())
 }
 
+/// Workspace members with dependencies before their dependents, otherwise in `workspace_members`
+/// order
 pub fn sort_workspace(ws_meta: &cargo_metadata::Metadata) -> Vec<&cargo_metadata::PackageId> {
     let members: std::collections::HashSet<_> = ws_meta.workspace_members.iter().collect();
     let dep_tree: std::collections::HashMap<_, _> = ws_meta
@@ -551,12 +1484,36 @@ fn atomic_write(path: &Path, data: &str) -> std::io::Result<()> {
         .parent()
         .unwrap_or_else(|| Path::new("."))
         .join("Cargo.toml.work");
-    std::fs::write(&temp_path, data)?;
-    std::fs::rename(&temp_path, path)?;
+    crate::ops::path::write(&temp_path, data)?;
+    crate::ops::path::rename(&temp_path, path)?;
 
     Ok(())
 }
 
+/// Indexes for tests
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::PublishIndex;
+    use crate::error::CargoResult;
+
+    /// Reports the crate as published once it has been polled `published_after` times
+    pub(crate) struct FlipIndex {
+        pub(crate) polls: usize,
+        pub(crate) published_after: usize,
+    }
+
+    impl PublishIndex for FlipIndex {
+        fn update(&mut self) -> CargoResult<()> {
+            self.polls += 1;
+            Ok(())
+        }
+
+        fn is_published(&self, _name: &str, _version: &str) -> bool {
+            self.published_after <= self.polls
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -565,6 +1522,664 @@ mod test {
     use assert_fs::prelude::*;
     use predicates::prelude::*;
 
+    mod is_same_release {
+        use super::*;
+
+        #[test]
+        fn ignores_build_metadata() {
+            assert!(is_same_release("1.2.3", "1.2.3"));
+            assert!(is_same_release("1.2.3+build.4", "1.2.3+build.5"));
+            assert!(is_same_release("1.2.3", "1.2.3+build.5"));
+            assert!(!is_same_release("1.2.3", "1.2.4+build.5"));
+            assert!(!is_same_release("1.2.3-alpha.1", "1.2.3"));
+        }
+    }
+
+    mod is_offline {
+        use super::*;
+
+        #[test]
+        fn parse() {
+            assert!(parse_offline(Some("true")));
+            assert!(parse_offline(Some("1")));
+            assert!(!parse_offline(Some("false")));
+            assert!(!parse_offline(None));
+        }
+    }
+
+    mod published_content {
+        use super::*;
+
+        use assert_fs::prelude::*;
+
+        struct ChecksumIndex {
+            checksum: Option<[u8; 32]>,
+            download: Option<Vec<u8>>,
+        }
+
+        impl PublishIndex for ChecksumIndex {
+            fn update(&mut self) -> CargoResult<()> {
+                Ok(())
+            }
+
+            fn is_published(&self, _name: &str, _version: &str) -> bool {
+                true
+            }
+
+            fn checksum(&self, _name: &str, _version: &str) -> Option<[u8; 32]> {
+                self.checksum
+            }
+
+            fn download(&self, _name: &str, _version: &str) -> CargoResult<Option<Vec<u8>>> {
+                Ok(self.download.clone())
+            }
+        }
+
+        /// A `.crate` with `lib.rs` and, when given, a `.cargo_vcs_info.json` naming `sha1`
+        fn crate_bytes(lib: &str, sha1: Option<&str>) -> Vec<u8> {
+            let mut files = vec![("foo-1.0.0/src/lib.rs", lib.to_owned())];
+            if let Some(sha1) = sha1 {
+                files.push((
+                    "foo-1.0.0/.cargo_vcs_info.json",
+                    format!(r#"{{"git": {{"sha1": "{}"}}}}"#, sha1),
+                ));
+            }
+            let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            ));
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, path, content.as_bytes())
+                    .unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap()
+        }
+
+        fn published(download: Vec<u8>) -> ChecksumIndex {
+            use sha2::Digest as _;
+            ChecksumIndex {
+                checksum: Some(sha2::Sha256::digest(&download).into()),
+                download: Some(download),
+            }
+        }
+
+        fn archive(dir: &assert_fs::TempDir) -> std::path::PathBuf {
+            let archive = dir.child("foo-1.0.0.crate");
+            archive.write_binary(b"crate contents").unwrap();
+            archive.path().to_owned()
+        }
+
+        #[test]
+        fn identical() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let archive = archive(&dir);
+            let index = ChecksumIndex {
+                checksum: Some(file_checksum(&archive).unwrap()),
+                download: None,
+            };
+
+            let content = published_content(&index, "foo", "1.0.0", &archive).unwrap();
+            assert_eq!(content, PublishedContent::Identical);
+
+            dir.close().unwrap();
+        }
+
+        #[test]
+        fn different() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let archive = archive(&dir);
+            let index = ChecksumIndex {
+                checksum: Some([0xab; 32]),
+                download: None,
+            };
+
+            let content = published_content(&index, "foo", "1.0.0", &archive).unwrap();
+            assert_eq!(
+                content,
+                PublishedContent::Different {
+                    local: hex::encode(file_checksum(&archive).unwrap()),
+                    published: "ab".repeat(32),
+                }
+            );
+
+            dir.close().unwrap();
+        }
+
+        #[test]
+        fn without_checksum() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let archive = archive(&dir);
+            let index = ChecksumIndex {
+                checksum: None,
+                download: None,
+            };
+
+            let content = published_content(&index, "foo", "1.0.0", &archive).unwrap();
+            assert_eq!(content, PublishedContent::Unknown);
+
+            dir.close().unwrap();
+        }
+
+        #[test]
+        fn ignores_vcs_info() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let archive = dir.child("foo-1.0.0.crate");
+            archive
+                .write_binary(&crate_bytes("pub fn foo() {}", Some("aaaa")))
+                .unwrap();
+            let index = published(crate_bytes("pub fn foo() {}", Some("bbbb")));
+
+            let content = published_content(&index, "foo", "1.0.0", archive.path()).unwrap();
+            assert_eq!(content, PublishedContent::Identical);
+
+            dir.close().unwrap();
+        }
+
+        #[test]
+        fn different_files() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let archive = dir.child("foo-1.0.0.crate");
+            archive
+                .write_binary(&crate_bytes("pub fn foo() {}", Some("aaaa")))
+                .unwrap();
+            let index = published(crate_bytes("pub fn bar() {}", Some("aaaa")));
+
+            let content = published_content(&index, "foo", "1.0.0", archive.path()).unwrap();
+            assert!(
+                matches!(content, PublishedContent::Different { .. }),
+                "{:?}",
+                content
+            );
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod parse_cargo_version {
+        use super::*;
+
+        #[test]
+        fn release() {
+            assert_eq!(
+                parse_cargo_version("cargo 1.63.0 (fd9c4297c 2022-07-01)\n"),
+                Some(semver::Version::new(1, 63, 0))
+            );
+        }
+
+        #[test]
+        fn nightly() {
+            assert_eq!(
+                parse_cargo_version("cargo 1.64.0-nightly (85e457e15 2022-07-29)\n"),
+                Some(MANIFEST_INHERITANCE)
+            );
+        }
+
+        #[test]
+        fn unrecognized() {
+            assert_eq!(parse_cargo_version(""), None);
+            assert_eq!(parse_cargo_version("error: no such command"), None);
+        }
+    }
+
+    mod parse_package_list {
+        use super::*;
+
+        #[test]
+        fn drops_build_script_noise() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/simple", &["**"]).unwrap();
+            let stdout = b"\
+warning: simple@0.1.0: generating bindings
+Cargo.lock
+Cargo.toml
+Cargo.toml.orig
+warning: simple@0.1.0: Cargo.toml
+src/main.rs
+../outside.rs
+/etc/passwd
+
+";
+
+            let actual = parse_package_list(temp.path(), stdout);
+            let expected: Vec<_> = ["Cargo.lock", "Cargo.toml", "Cargo.toml.orig", "src/main.rs"]
+                .iter()
+                .map(|p| temp.path().join(p))
+                .collect();
+            assert_eq!(actual, expected);
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod package_archive {
+        use super::*;
+
+        #[test]
+        fn reports_largest_files() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/simple", &["**"]).unwrap();
+            // Poorly compressible, so the archive stays large
+            let mut state = 0x2545_f491_u32;
+            let generated: Vec<u8> = (0..2_000_000)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect();
+            temp.child("assets/generated.bin")
+                .write_binary(&generated)
+                .unwrap();
+            let target_dir = temp.child("target");
+
+            let archive = package_archive(
+                temp.child("Cargo.toml").path(),
+                None,
+                target_dir.path(),
+                "simple",
+                "0.1.0",
+            )
+            .unwrap();
+            assert!(1_000_000 < archive.size, "{}", archive.size);
+
+            let largest = largest_files(&archive.path, 2).unwrap();
+            assert_eq!(largest.len(), 2);
+            assert_eq!(
+                largest[0].0,
+                std::path::Path::new("simple-0.1.0/assets/generated.bin")
+            );
+            assert_eq!(largest[0].1, 2_000_000);
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn packages_planned_version() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let ws = temp.child("ws");
+            ws.copy_from("tests/fixtures/simple", &["**"]).unwrap();
+            let manifest = ws.child("Cargo.toml");
+            let original = std::fs::read_to_string(manifest.path()).unwrap();
+            let target_dir = temp.child("target");
+
+            let archive = package_archive_as(
+                manifest.path(),
+                None,
+                target_dir.path(),
+                "simple",
+                "0.1.0",
+                "0.2.0",
+            )
+            .unwrap();
+            assert!(
+                archive.path.ends_with("package/simple-0.2.0.crate"),
+                "{}",
+                archive.path.display()
+            );
+            let file = std::fs::File::open(&archive.path).unwrap();
+            let mut unpacker = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            let mut packaged_manifest = String::new();
+            for entry in unpacker.entries().unwrap() {
+                let mut entry = entry.unwrap();
+                if entry.path().unwrap() == std::path::Path::new("simple-0.2.0/Cargo.toml") {
+                    std::io::Read::read_to_string(&mut entry, &mut packaged_manifest).unwrap();
+                }
+            }
+            assert!(
+                packaged_manifest.contains(r#"version = "0.2.0""#),
+                "{}",
+                packaged_manifest
+            );
+            // The workspace is never touched
+            manifest.assert(original.as_str());
+            assert!(!target_dir
+                .child("package/planned/simple-0.2.0")
+                .path()
+                .exists());
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod publish {
+        use super::*;
+
+        #[test]
+        fn logs_failure() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/simple", &["**"]).unwrap();
+            temp.child("src/main.rs")
+                .write_str("fn main() {\n    not_defined_anywhere();\n}\n")
+                .unwrap();
+            let log = temp.child("logs/simple-0.1.0.log");
+            log.write_str("stale\n").unwrap();
+
+            let (published, output) = publish(
+                true,
+                true,
+                temp.child("Cargo.toml").path(),
+                None,
+                &Features::None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                Some(log.path()),
+                false,
+            )
+            .unwrap();
+            assert_eq!(output, None);
+            assert!(!published);
+            log.assert(predicate::str::contains("not_defined_anywhere"));
+            log.assert(predicate::str::contains("stale").not());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn registry_or_index() {
+            assert_eq!(registry_args(None, None), Vec::<&str>::new());
+            assert_eq!(
+                registry_args(Some("internal"), None),
+                ["--registry", "internal"]
+            );
+            assert_eq!(
+                registry_args(None, Some("https://internal.example.com/index")),
+                ["--index", "https://internal.example.com/index"]
+            );
+        }
+    }
+
+    mod publish_retry {
+        use super::*;
+
+        #[test]
+        fn transient() {
+            for output in [
+                "error: failed to publish to registry at https://crates.io\n\nCaused by:\n  the remote server responded with an error (status 503 Service Unavailable): service unavailable",
+                "error: failed to get a 200 OK response, got 502",
+                "Caused by:\n  [28] Timeout was reached (Operation timed out after 30000 milliseconds)",
+                "Caused by:\n  [7] Couldn't connect to server (Failed to connect to crates.io port 443)",
+                "Caused by:\n  [6] Couldn't resolve host name (Could not resolve host: crates.io)",
+            ] {
+                assert!(is_transient_publish_failure(output), "{}", output);
+            }
+        }
+
+        #[test]
+        fn permanent() {
+            for output in [
+                "error: failed to publish to registry at https://crates.io\n\nCaused by:\n  the remote server responded with an error: crate version `0.1.0` is already uploaded",
+                "error: failed to verify package tarball\n\nCaused by:\n  could not compile `simple`",
+                "Caused by:\n  the remote server responded with an error (status 403 Forbidden): must be logged in",
+                "error: 3 files in the working directory contain changes that were not yet committed into git",
+                "Caused by:\n  the remote server responded with an error (status 500 Internal Server Error): crate version `0.1.0` is already uploaded",
+            ] {
+                assert!(!is_transient_publish_failure(output), "{}", output);
+            }
+        }
+
+        #[test]
+        fn already_uploaded() {
+            assert!(is_already_uploaded(
+                "Caused by:\n  the remote server responded with an error: crate version `0.1.0` is already uploaded"
+            ));
+            assert!(is_already_uploaded(
+                "Caused by:\n  the remote server responded with an error (status 400 Bad Request): crate `simple@0.1.0` already exists on crates.io index"
+            ));
+            assert!(!is_already_uploaded(
+                "error: failed to get a 200 OK response, got 502"
+            ));
+        }
+
+        #[test]
+        fn backoff() {
+            assert_eq!(publish_retry_delay(1), std::time::Duration::from_secs(5));
+            assert_eq!(publish_retry_delay(2), std::time::Duration::from_secs(10));
+            assert_eq!(publish_retry_delay(3), std::time::Duration::from_secs(20));
+            assert_eq!(
+                publish_retry_delay(40),
+                std::time::Duration::from_secs(5 * 60)
+            );
+        }
+    }
+
+    mod rename_package {
+        use super::*;
+
+        const MANIFEST: &str = r#"[package]
+name = "acme-core"  # internal name
+version = "0.1.0"
+
+[dependencies]
+serde = "1"
+"#;
+
+        #[test]
+        fn rewrites_name() {
+            let actual = rename_package(MANIFEST, "acme-core-oss").unwrap();
+            assert_eq!(
+                actual,
+                r#"[package]
+name = "acme-core-oss"
+version = "0.1.0"
+
+[dependencies]
+serde = "1"
+"#
+            );
+        }
+
+        #[test]
+        fn requires_package() {
+            assert!(rename_package("[workspace]\n", "acme-core-oss").is_err());
+        }
+
+        #[test]
+        fn stages_copy() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let ws = temp.child("ws");
+            ws.copy_from("tests/fixtures/simple", &["**"]).unwrap();
+            let manifest = ws.child("Cargo.toml");
+            let original = std::fs::read_to_string(manifest.path()).unwrap();
+            let target_dir = temp.child("target");
+
+            let renamed = RenamedPackage::stage(
+                manifest.path(),
+                None,
+                target_dir.path(),
+                "simple",
+                "0.1.0",
+                "acme-simple",
+            )
+            .unwrap();
+            let staged = renamed.manifest_path();
+            assert!(staged.starts_with(target_dir.path()));
+            let staged_manifest = std::fs::read_to_string(&staged).unwrap();
+            assert!(
+                staged_manifest.contains(r#"name = "acme-simple""#),
+                "{}",
+                staged_manifest
+            );
+            assert!(
+                staged_manifest.contains("[workspace]"),
+                "{}",
+                staged_manifest
+            );
+            let root = staged.parent().unwrap().to_owned();
+            assert!(root.join("src/main.rs").exists());
+            assert!(!root.join("Cargo.toml.orig").exists());
+            // The workspace is never touched
+            manifest.assert(original.as_str());
+
+            drop(renamed);
+            assert!(!root.exists());
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod wait_for_publish {
+        use super::*;
+
+        use crate::ops::cargo::fixture::FlipIndex;
+
+        fn wait(index: &mut FlipIndex, timeout_ms: u64, dry_run: bool) -> CargoResult<()> {
+            wait_for_publish(
+                index,
+                "foo",
+                "1.0.0",
+                &[],
+                std::time::Duration::from_millis(timeout_ms),
+                std::time::Duration::from_millis(1),
+                dry_run,
+            )
+        }
+
+        #[test]
+        fn waits_until_published() {
+            let mut index = FlipIndex {
+                polls: 0,
+                published_after: 3,
+            };
+            wait(&mut index, 60_000, false).unwrap();
+            assert_eq!(index.polls, 3);
+        }
+
+        #[test]
+        fn times_out() {
+            let mut index = FlipIndex {
+                polls: 0,
+                published_after: usize::MAX,
+            };
+            let err = wait(&mut index, 10, false).unwrap_err();
+            assert!(err.to_string().contains("timeout"), "{}", err);
+        }
+
+        /// Shows `versions` for every crate, recording which crates were refreshed
+        struct VersionsIndex {
+            versions: Vec<String>,
+            updated: Vec<String>,
+        }
+
+        impl PublishIndex for VersionsIndex {
+            fn update(&mut self) -> CargoResult<()> {
+                self.updated.push("*".to_owned());
+                Ok(())
+            }
+
+            fn update_crate(&mut self, name: &str) -> CargoResult<()> {
+                self.updated.push(name.to_owned());
+                Ok(())
+            }
+
+            fn is_published(&self, _name: &str, version: &str) -> bool {
+                self.versions.iter().any(|v| is_same_release(v, version))
+            }
+
+            fn versions(&self, _name: &str) -> Vec<String> {
+                self.versions.clone()
+            }
+        }
+
+        fn versions_index(versions: &[&str]) -> VersionsIndex {
+            VersionsIndex {
+                versions: versions.iter().map(|v| (*v).to_owned()).collect(),
+                updated: Vec::new(),
+            }
+        }
+
+        #[test]
+        fn updates_only_the_crate() {
+            let mut index = versions_index(&["0.9.0", "1.0.0+build"]);
+            wait_for_publish(
+                &mut index,
+                "foo",
+                "1.0.0",
+                &[],
+                std::time::Duration::from_millis(60_000),
+                std::time::Duration::from_millis(1),
+                false,
+            )
+            .unwrap();
+            assert_eq!(index.updated, ["foo"]);
+        }
+
+        #[test]
+        fn stops_at_newer_version() {
+            let mut index = versions_index(&["0.9.0", "1.1.0"]);
+            wait_for_publish(
+                &mut index,
+                "foo",
+                "1.0.0",
+                &[],
+                std::time::Duration::from_millis(60_000),
+                std::time::Duration::from_millis(1),
+                false,
+            )
+            .unwrap();
+            assert_eq!(index.updated, ["foo"]);
+        }
+
+        #[test]
+        fn backport_keeps_waiting() {
+            let mut index = versions_index(&["0.9.0", "1.1.0"]);
+            let err = wait_for_publish(
+                &mut index,
+                "foo",
+                "1.0.1",
+                &["0.9.0".to_owned(), "1.1.0".to_owned()],
+                std::time::Duration::from_millis(10),
+                std::time::Duration::from_millis(1),
+                false,
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("timeout"), "{}", err);
+        }
+
+        #[test]
+        fn older_versions_keep_waiting() {
+            let mut index = versions_index(&["0.9.0", "1.0.0-alpha.1"]);
+            let err = wait_for_publish(
+                &mut index,
+                "foo",
+                "1.0.0",
+                &[],
+                std::time::Duration::from_millis(10),
+                std::time::Duration::from_millis(1),
+                false,
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("timeout"), "{}", err);
+            assert!(1 < index.updated.len());
+        }
+
+        #[test]
+        fn newer() {
+            let visible = ["0.9.0".to_owned(), "1.0.0+other".to_owned()];
+            assert_eq!(newer_release(&visible, &[], "1.0.0"), None);
+            assert_eq!(newer_release(&visible, &[], "0.9.0"), Some("1.0.0+other"));
+            assert_eq!(newer_release(&visible, &[], "not-semver"), None);
+        }
+
+        #[test]
+        fn dry_run_skips_polling() {
+            let mut index = FlipIndex {
+                polls: 0,
+                published_after: usize::MAX,
+            };
+            wait(&mut index, 10, true).unwrap();
+            assert_eq!(index.polls, 0);
+        }
+    }
+
     mod set_package_version {
         use super::*;
 
@@ -580,7 +2195,13 @@ mod test {
                 .unwrap();
             assert_eq!(meta.packages[0].version.to_string(), "0.1.0");
 
-            set_package_version(manifest_path.path(), "2.0.0", false).unwrap();
+            set_package_version(
+                manifest_path.path(),
+                "2.0.0",
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
 
             let meta = cargo_metadata::MetadataCommand::new()
                 .manifest_path(manifest_path.path())
@@ -592,6 +2213,320 @@ mod test {
         }
     }
 
+    mod upgrade_dependency_req {
+        use super::*;
+
+        #[test]
+        fn renders_updates() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("a/Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "a"
+version = "0.1.0"
+"#,
+                )
+                .unwrap();
+            temp.child("b/Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "b"
+version = "0.1.0"
+
+[dependencies]
+a = { path = "../a", version = "0.1.0" }
+
+[dev-dependencies]
+a = { path = "../a", version = "0.1" }
+"#,
+                )
+                .unwrap();
+            let a_root = dunce::canonicalize(temp.child("a").path()).unwrap();
+            let b_manifest = temp.child("b/Cargo.toml");
+            let version = semver::Version::parse("0.2.0").unwrap();
+
+            let updates = upgrade_dependency_req(
+                "b",
+                b_manifest.path(),
+                &a_root,
+                "a",
+                &version,
+                config::DependentVersion::Upgrade,
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
+            assert_eq!(
+                render_dependency_updates(&updates),
+                "  b: a 0.1.0 -> 0.2.0\n  b: a 0.1 -> 0.2"
+            );
+
+            let updates = upgrade_dependency_req(
+                "b",
+                b_manifest.path(),
+                &a_root,
+                "a",
+                &version,
+                config::DependentVersion::Upgrade,
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
+            assert_eq!(render_dependency_updates(&updates), "");
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod dry_run {
+        use super::*;
+
+        fn workspace() -> assert_fs::TempDir {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("a/Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "a"
+version = "0.1.0"
+"#,
+                )
+                .unwrap();
+            temp.child("b/Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "b"
+version = "0.1.0"
+
+[dependencies]
+a = { path = "../a", version = "0.1.0" }
+"#,
+                )
+                .unwrap();
+            temp
+        }
+
+        /// Release `a` twice, returning `b`'s final manifest and its requirement rewrites
+        fn release(temp: &assert_fs::TempDir, dry_run: bool) -> (String, Vec<DependencyUpdate>) {
+            let a_root = dunce::canonicalize(temp.child("a").path()).unwrap();
+            let a_manifest = temp.child("a/Cargo.toml");
+            let b_manifest = temp.child("b/Cargo.toml");
+
+            let mut overlay = Overlay::default();
+            let mut updates = Vec::new();
+            for version in ["0.2.0", "0.2.1"] {
+                set_package_version(a_manifest.path(), version, &mut overlay, dry_run).unwrap();
+                updates.extend(
+                    upgrade_dependency_req(
+                        "b",
+                        b_manifest.path(),
+                        &a_root,
+                        "a",
+                        &semver::Version::parse(version).unwrap(),
+                        config::DependentVersion::Fix,
+                        &mut overlay,
+                        dry_run,
+                    )
+                    .unwrap(),
+                );
+            }
+            let b = overlay.read_to_string(b_manifest.path()).unwrap();
+            (b, updates)
+        }
+
+        #[test]
+        fn matches_execute() {
+            let dry_run_ws = workspace();
+            let execute_ws = workspace();
+
+            let dry_run = release(&dry_run_ws, true);
+            let execute = release(&execute_ws, false);
+            assert_eq!(dry_run, execute);
+            assert_eq!(dry_run.1.len(), 1);
+
+            dry_run_ws
+                .child("b/Cargo.toml")
+                .assert(predicate::str::contains(r#"version = "0.1.0" }"#));
+
+            dry_run_ws.close().unwrap();
+            execute_ws.close().unwrap();
+        }
+    }
+
+    mod known_registries {
+        use super::*;
+
+        #[test]
+        fn merges_config_and_env() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("cargo-home/config.toml")
+                .write_str(
+                    r#"[registries.internal]
+index = "https://internal.example.com/index"
+"#,
+                )
+                .unwrap();
+            temp.child("ws/.cargo/config.toml")
+                .write_str(
+                    r#"[registries]
+my-registry = { index = "https://my.example.com/index" }
+"#,
+                )
+                .unwrap();
+            temp.child("ws/crate").create_dir_all().unwrap();
+            let vars = [
+                (
+                    "CARGO_REGISTRIES_FROM_ENV_INDEX".to_owned(),
+                    "https://env.example.com/index".to_owned(),
+                ),
+                ("CARGO_HOME".to_owned(), "ignored".to_owned()),
+            ];
+
+            let known = known_registries_in(
+                Some(temp.child("cargo-home").path()),
+                temp.child("ws/crate").path(),
+                vars.into_iter(),
+            )
+            .unwrap();
+            assert_eq!(
+                known.iter().map(String::as_str).collect::<Vec<_>>(),
+                ["crates-io", "from_env", "internal", "my-registry"]
+            );
+            assert!(is_known_registry(&known, "from-env"));
+            assert!(is_known_registry(&known, "my-registry"));
+            assert!(!is_known_registry(&known, "crates-oi"));
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn invalid_config() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("cargo-home/config.toml")
+                .write_str("[registries")
+                .unwrap();
+
+            let err = known_registries_in(
+                Some(temp.child("cargo-home").path()),
+                temp.path(),
+                std::iter::empty(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("config.toml"), "{}", err);
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn index_env() {
+            assert_eq!(
+                registry_index_env("my-registry"),
+                "CARGO_REGISTRIES_MY_REGISTRY_INDEX"
+            );
+        }
+    }
+
+    mod has_credentials {
+        use super::*;
+
+        #[test]
+        fn present() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("credentials.toml")
+                .write_str(
+                    r#"[registry]
+token = "secret"
+"#,
+                )
+                .unwrap();
+
+            assert!(has_credentials_in(Some(temp.path()), None, std::iter::empty()).unwrap());
+            assert!(
+                has_credentials_in(Some(temp.path()), Some("crates-io"), std::iter::empty())
+                    .unwrap()
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn missing() {
+            let temp = assert_fs::TempDir::new().unwrap();
+
+            assert!(!has_credentials_in(Some(temp.path()), None, std::iter::empty()).unwrap());
+            assert!(!has_credentials_in(None, None, std::iter::empty()).unwrap());
+
+            temp.child("credentials.toml")
+                .write_str(
+                    r#"[registries.internal]
+token = "secret"
+"#,
+                )
+                .unwrap();
+            assert!(!has_credentials_in(Some(temp.path()), None, std::iter::empty()).unwrap());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn alternate_registry() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.child("credentials")
+                .write_str(
+                    r#"[registry]
+token = "secret"
+
+[registries.my-registry]
+token = "secret"
+"#,
+                )
+                .unwrap();
+
+            assert!(
+                has_credentials_in(Some(temp.path()), Some("my-registry"), std::iter::empty())
+                    .unwrap()
+            );
+            assert!(
+                !has_credentials_in(Some(temp.path()), Some("internal"), std::iter::empty())
+                    .unwrap()
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn env() {
+            let vars = [
+                ("CARGO_REGISTRY_TOKEN".to_owned(), "secret".to_owned()),
+                ("CARGO_REGISTRIES_INTERNAL_TOKEN".to_owned(), "".to_owned()),
+            ];
+
+            assert!(has_credentials_in(None, None, vars.clone().into_iter()).unwrap());
+            assert!(!has_credentials_in(None, Some("internal"), vars.into_iter()).unwrap());
+            assert_eq!(
+                registry_token_env(Some("my-registry")),
+                "CARGO_REGISTRIES_MY_REGISTRY_TOKEN"
+            );
+        }
+    }
+
+    mod parse_owners {
+        use super::*;
+
+        #[test]
+        fn users_and_teams() {
+            let raw = "epage (Ed Page)\ngithub:crate-ci:publish (Publish)\nsomeone\n\n";
+            assert_eq!(
+                parse_owners(raw).into_iter().collect::<Vec<_>>(),
+                ["epage", "github:crate-ci:publish", "someone"]
+            );
+        }
+
+        #[test]
+        fn empty() {
+            assert!(parse_owners("").is_empty());
+        }
+    }
+
     mod update_lock {
         use super::*;
 
@@ -602,7 +2537,13 @@ mod test {
             let manifest_path = temp.child("Cargo.toml");
             let lock_path = temp.child("Cargo.lock");
 
-            set_package_version(manifest_path.path(), "2.0.0", false).unwrap();
+            set_package_version(
+                manifest_path.path(),
+                "2.0.0",
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
             lock_path.assert(predicate::path::eq_file(Path::new(
                 "tests/fixtures/simple/Cargo.lock",
             )));
@@ -622,7 +2563,13 @@ mod test {
             let manifest_path = temp.child("b/Cargo.toml");
             let lock_path = temp.child("Cargo.lock");
 
-            set_package_version(manifest_path.path(), "2.0.0", false).unwrap();
+            set_package_version(
+                manifest_path.path(),
+                "2.0.0",
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
             lock_path.assert(predicate::path::eq_file(Path::new(
                 "tests/fixtures/pure_ws/Cargo.lock",
             )));
@@ -642,7 +2589,13 @@ mod test {
             let manifest_path = temp.child("Cargo.toml");
             let lock_path = temp.child("Cargo.lock");
 
-            set_package_version(manifest_path.path(), "2.0.0", false).unwrap();
+            set_package_version(
+                manifest_path.path(),
+                "2.0.0",
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
             lock_path.assert(predicate::path::eq_file(Path::new(
                 "tests/fixtures/mixed_ws/Cargo.lock",
             )));
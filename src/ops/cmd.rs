@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::path::Path;
@@ -5,18 +6,60 @@ use std::process::Command;
 
 use crate::error::CargoResult;
 
+/// Platform shell used for running single-line commands
+#[cfg(not(windows))]
+pub const SHELL: [&str; 2] = ["sh", "-c"];
+/// Platform shell used for running single-line commands
+#[cfg(windows)]
+pub const SHELL: [&str; 2] = ["cmd", "/C"];
+
+/// Quote `value` so the platform shell treats it as a single word
+#[cfg(not(windows))]
+pub fn shell_escape(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:+@,%".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("'{}'", value.replace('\'', r#"'\''"#)))
+    }
+}
+
+/// Quote `value` so the platform shell treats it as a single word
+///
+/// `cmd.exe` doubles quotes inside quotes and expands `%VAR%` even there, so `%` is escaped with
+/// `^` between closed quotes.
+#[cfg(windows)]
+pub fn shell_escape(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:+@,\\".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        let escaped = value.replace('"', r#""""#).replace('%', r#""^%""#);
+        Cow::Owned(format!("\"{}\"", escaped))
+    }
+}
+
 fn do_call(
     command: impl IntoIterator<Item = impl Into<String>>,
     path: Option<&Path>,
     envs: Option<BTreeMap<&OsStr, &OsStr>>,
     dry_run: bool,
 ) -> CargoResult<bool> {
-    let command: Vec<_> = command.into_iter().map(|s| s.into()).collect();
+    let command: Vec<String> = command.into_iter().map(|s| s.into()).collect();
+    // Push URLs may carry credentials
+    let displayed: Vec<String> = command
+        .iter()
+        .map(|a| crate::ops::git::redact_url(a).into_owned())
+        .collect();
+    let _ = crate::ops::reporter::command(&displayed, dry_run);
     if dry_run {
         if path.is_some() {
             log::trace!("cd {}", path.unwrap().display());
         }
-        log::trace!("{}", command.join(" "));
+        log::trace!(
+            "{}",
+            itertools::join(displayed.iter().map(|a| shell_escape(a)), " ")
+        );
         return Ok(true);
     }
     let mut iter = command.iter();
@@ -32,11 +75,8 @@ fn do_call(
         cmd.envs(e.iter());
     }
 
-    for arg in iter {
-        if !arg.is_empty() {
-            cmd.arg(arg);
-        }
-    }
+    // Arguments are passed through as-is, never re-split or dropped
+    cmd.args(iter);
 
     let mut child = cmd.spawn()?;
     let result = child.wait()?;
@@ -67,3 +107,228 @@ pub fn call_with_env(
 ) -> CargoResult<bool> {
     do_call(command, Some(path), Some(envs), dry_run)
 }
+
+/// Run `command`, streaming its output to stderr with each line prefixed by `prefix`
+///
+/// Keeps the output of several runs for the same package apart.
+pub fn call_prefixed(
+    command: impl IntoIterator<Item = impl Into<String>>,
+    prefix: &str,
+    dry_run: bool,
+) -> CargoResult<bool> {
+    use std::io::BufRead as _;
+
+    let command: Vec<String> = command.into_iter().map(|s| s.into()).collect();
+    let _ = crate::ops::reporter::command(&command, dry_run);
+    if dry_run {
+        log::trace!(
+            "{}",
+            itertools::join(command.iter().map(|a| shell_escape(a)), " ")
+        );
+        return Ok(true);
+    }
+    let mut iter = command.iter();
+    let cmd_name = iter.next().unwrap();
+
+    let mut child = Command::new(cmd_name)
+        .args(iter)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("piped");
+    let stderr = child.stderr.take().expect("piped");
+
+    let forward = |output: &mut dyn std::io::BufRead| {
+        for line in output.lines().map_while(Result::ok) {
+            let _ = crate::ops::shell::write_stderr(
+                format_args!("{} {}\n", prefix, line),
+                &crate::ops::shell::ColorSpec::new(),
+            );
+        }
+    };
+    std::thread::scope(|scope| {
+        scope.spawn(|| forward(&mut std::io::BufReader::new(stdout)));
+        forward(&mut std::io::BufReader::new(stderr));
+    });
+    let result = child.wait()?;
+
+    Ok(result.success())
+}
+
+/// Run `command`, streaming its output as usual while also writing all of it to `log`
+///
+/// `log` is overwritten so it only holds the latest run.
+pub fn call_logged(
+    command: impl IntoIterator<Item = impl Into<String>>,
+    envs: Option<BTreeMap<&OsStr, &OsStr>>,
+    log: &Path,
+) -> CargoResult<bool> {
+    call_captured(command, envs, Some(log)).map(|(success, _)| success)
+}
+
+/// Run `command`, streaming its output as usual while also returning all of it
+///
+/// The output is also written to `log`, like [`call_logged`].
+pub fn call_captured(
+    command: impl IntoIterator<Item = impl Into<String>>,
+    envs: Option<BTreeMap<&OsStr, &OsStr>>,
+    log: Option<&Path>,
+) -> CargoResult<(bool, String)> {
+    do_call_captured(command, None, envs, log)
+}
+
+/// Like [`call_on_path`], also returning the output
+///
+/// A dry-run returns no output.
+pub fn call_captured_on_path(
+    command: impl IntoIterator<Item = impl Into<String>>,
+    path: &Path,
+    dry_run: bool,
+) -> CargoResult<(bool, String)> {
+    if dry_run {
+        return do_call(command, Some(path), None, dry_run).map(|success| (success, String::new()));
+    }
+    do_call_captured(command, Some(path), None, None)
+}
+
+fn do_call_captured(
+    command: impl IntoIterator<Item = impl Into<String>>,
+    path: Option<&Path>,
+    envs: Option<BTreeMap<&OsStr, &OsStr>>,
+    log: Option<&Path>,
+) -> CargoResult<(bool, String)> {
+    use std::io::BufRead as _;
+    use std::io::Write as _;
+
+    let command: Vec<String> = command.into_iter().map(|s| s.into()).collect();
+    let _ = crate::ops::reporter::command(&command, false);
+    let file = match log {
+        Some(log) => {
+            if let Some(parent) = log.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            Some(std::fs::File::create(log)?)
+        }
+        None => None,
+    };
+    let captured = std::sync::Mutex::new((file, String::new()));
+    let mut iter = command.iter();
+    let cmd_name = iter.next().unwrap();
+
+    let mut cmd = Command::new(cmd_name);
+    if let Some(p) = path {
+        cmd.current_dir(p);
+    }
+    if let Some(e) = envs {
+        cmd.envs(e.iter());
+    }
+    let mut child = cmd
+        .args(iter)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("piped");
+    let stderr = child.stderr.take().expect("piped");
+
+    let tee = |output: &mut dyn std::io::BufRead, console: &mut dyn std::io::Write| {
+        for line in output.lines().map_while(Result::ok) {
+            let _ = writeln!(console, "{}", line);
+            let mut captured = captured.lock().unwrap();
+            let (file, text) = &mut *captured;
+            if let Some(file) = file {
+                let _ = writeln!(file, "{}", line);
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+    };
+    std::thread::scope(|scope| {
+        scope.spawn(|| tee(&mut std::io::BufReader::new(stdout), &mut std::io::stdout()));
+        tee(&mut std::io::BufReader::new(stderr), &mut std::io::stderr());
+    });
+    let result = child.wait()?;
+    let (_, output) = captured.into_inner().unwrap();
+
+    Ok((result.success(), output))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(not(windows))]
+    fn call_prefixed_status() {
+        assert!(call_prefixed(["sh", "-c", "echo out; echo err >&2"], "[test]", false).unwrap());
+        assert!(!call_prefixed(["sh", "-c", "exit 3"], "[test]", false).unwrap());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn call_logged_tees_output() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.path().join("logs/foo-1.0.0.log");
+        std::fs::create_dir_all(log.parent().unwrap()).unwrap();
+        std::fs::write(&log, "stale\n").unwrap();
+
+        let success = call_logged(
+            ["sh", "-c", "echo out; sleep 0.1; echo err >&2; exit 3"],
+            None,
+            &log,
+        )
+        .unwrap();
+        assert!(!success);
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "out\nerr\n");
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn shell_escape_passthrough() {
+        assert_eq!(
+            shell_escape("1.0.0-alpha.1+build.5"),
+            "1.0.0-alpha.1+build.5"
+        );
+        assert_eq!(shell_escape("2023-09-11"), "2023-09-11");
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn shell_escape_quoted() {
+        assert_eq!(shell_escape(""), "''");
+        assert_eq!(shell_escape("a b"), "'a b'");
+        assert_eq!(shell_escape("it's"), r#"'it'\''s'"#);
+        assert_eq!(shell_escape("$(rm -rf)"), "'$(rm -rf)'");
+        assert_eq!(shell_escape("ünïcödé"), "'ünïcödé'");
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn shell_escape_round_trip() {
+        for value in [
+            "a b",
+            "it's",
+            "\"quoted\"",
+            "ünïcödé",
+            "$HOME `id`",
+            "a;b|c&d",
+        ] {
+            let line = format!("printf %s {}", shell_escape(value));
+            let output = Command::new(SHELL[0])
+                .arg(SHELL[1])
+                .arg(&line)
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), value);
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn shell_escape_quoted() {
+        assert_eq!(shell_escape(""), r#""""#);
+        assert_eq!(shell_escape("a b"), r#""a b""#);
+        assert_eq!(shell_escape(r#"say "hi""#), r#""say ""hi""""#);
+        assert_eq!(shell_escape("%PATH%"), r#"""^%"PATH"^%"""#);
+    }
+}
@@ -0,0 +1,416 @@
+use std::io::Read as _;
+use std::path::Path;
+
+use anyhow::Context as _;
+
+use crate::error::CargoResult;
+
+/// How the crates.io index is reached
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum IndexProtocol {
+    /// Clone and pull the full git index
+    #[default]
+    Git,
+    /// Fetch each crate's entry over HTTP, as needed
+    Sparse,
+}
+
+impl IndexProtocol {
+    /// Follow cargo's `CARGO_REGISTRIES_CRATES_IO_PROTOCOL`
+    pub fn from_env() -> Self {
+        parse_protocol(
+            std::env::var("CARGO_REGISTRIES_CRATES_IO_PROTOCOL")
+                .ok()
+                .as_deref(),
+        )
+    }
+}
+
+fn parse_protocol(value: Option<&str>) -> IndexProtocol {
+    match value {
+        Some("sparse") => IndexProtocol::Sparse,
+        _ => IndexProtocol::Git,
+    }
+}
+
+enum RemoteIndex {
+    Git(crates_index::Index),
+    Sparse(crates_index::SparseIndex),
+}
+
+/// The local crates.io index cache, tolerating it being unusable when allowed
+pub struct CratesIoIndex {
+    index: Option<RemoteIndex>,
+    /// Only use the cached index, see [`crate::ops::cargo::is_offline`]
+    offline: bool,
+}
+
+impl CratesIoIndex {
+    /// Open Cargo's crates.io index cache
+    ///
+    /// When the cache is broken, `repair` re-clones it.  Otherwise, it is an error if `required`
+    /// and registry checks are skipped if not.
+    pub fn open(protocol: IndexProtocol, repair: bool, required: bool) -> CargoResult<Self> {
+        let offline = crate::ops::cargo::is_offline();
+        match protocol {
+            IndexProtocol::Git => {
+                let (path, url) =
+                    crates_index::local_path_and_canonical_url(crates_index::INDEX_GIT_URL, None)?;
+                Self::open_at(&path, &url, repair, required, offline)
+            }
+            IndexProtocol::Sparse => Self::open_sparse(required, offline),
+        }
+    }
+
+    /// Warn when `unpublished` selection can't check the registry and so selects everything
+    pub fn warn_unpublished(&self, unpublished: bool) {
+        if unpublished && !self.is_available() {
+            let _ = crate::ops::reporter::warn(
+                "`--unpublished` can't check the registry, treating every package as unpublished",
+            );
+        }
+    }
+
+    /// Open the index at `url`, for `index-url`, with a `sparse+` prefix selecting the sparse
+    /// protocol
+    ///
+    /// Registry checks are skipped when it can't be opened.
+    pub fn open_url(url: &str) -> Self {
+        let offline = crate::ops::cargo::is_offline();
+        let index = if url.starts_with("sparse+") {
+            crates_index::SparseIndex::from_url(url).map(RemoteIndex::Sparse)
+        } else {
+            crates_index::Index::from_url(url).map(RemoteIndex::Git)
+        };
+        match index {
+            Ok(index) => Self {
+                index: Some(index),
+                offline,
+            },
+            Err(err) => {
+                let _ = crate::ops::reporter::warn(format!(
+                    "skipping registry checks, the index at {} is unusable ({})",
+                    crate::ops::git::redact_url(url),
+                    err
+                ));
+                Self {
+                    index: None,
+                    offline,
+                }
+            }
+        }
+    }
+
+    fn open_sparse(required: bool, offline: bool) -> CargoResult<Self> {
+        // Nothing to clone, so nothing to repair
+        match crates_index::SparseIndex::new_cargo_default() {
+            Ok(index) => Ok(Self {
+                index: Some(RemoteIndex::Sparse(index)),
+                offline,
+            }),
+            Err(err) if required => Err(err).context("failed to open the sparse crates.io index"),
+            Err(err) => {
+                let _ = crate::ops::reporter::warn(format!(
+                    "skipping registry checks, the sparse crates.io index is unusable ({})",
+                    err
+                ));
+                Ok(Self {
+                    index: None,
+                    offline,
+                })
+            }
+        }
+    }
+
+    fn open_at(
+        path: &Path,
+        url: &str,
+        repair: bool,
+        required: bool,
+        offline: bool,
+    ) -> CargoResult<Self> {
+        let err = match crates_index::Index::with_path(path, url) {
+            Ok(index) => {
+                return Ok(Self {
+                    index: Some(RemoteIndex::Git(index)),
+                    offline,
+                })
+            }
+            Err(err) => err,
+        };
+        log::debug!("failed to open index at {}: {}", path.display(), err);
+
+        if repair && offline {
+            anyhow::bail!(
+                "failed to open crates.io index at {}: {}\n\nit can't be re-cloned while offline (`CARGO_NET_OFFLINE` is set)",
+                path.display(),
+                err
+            );
+        } else if repair {
+            let _ = crate::ops::reporter::status(
+                "Repairing",
+                format!("crates.io index at {}", path.display()),
+            );
+            remove_cache(path)?;
+            let index = crates_index::Index::with_path(path, url).with_context(|| {
+                format!("failed to re-clone crates.io index at {}", path.display())
+            })?;
+            Ok(Self {
+                index: Some(RemoteIndex::Git(index)),
+                offline,
+            })
+        } else if required {
+            anyhow::bail!(
+                "failed to open crates.io index at {}: {}\n\nto re-clone it, pass `--repair-index`",
+                path.display(),
+                err
+            );
+        } else {
+            let _ = crate::ops::reporter::warn(format!(
+                "skipping registry checks, crates.io index at {} is unusable ({}); to re-clone it, pass `--repair-index`",
+                path.display(),
+                err
+            ));
+            Ok(Self {
+                index: None,
+                offline,
+            })
+        }
+    }
+
+    /// An index that couldn't be opened, skipping registry checks
+    #[cfg(test)]
+    pub(crate) fn unavailable() -> Self {
+        Self {
+            index: None,
+            offline: false,
+        }
+    }
+
+    /// Whether registry checks can be trusted
+    pub fn is_available(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn crate_(&self, name: &str) -> Option<crates_index::Crate> {
+        match self.index.as_ref()? {
+            RemoteIndex::Git(index) => index.crate_(name),
+            RemoteIndex::Sparse(index) if self.offline => index.crate_from_cache(name).ok(),
+            RemoteIndex::Sparse(index) => match fetch_sparse(index, name) {
+                Ok(crate_) => crate_,
+                Err(err) => {
+                    log::debug!("failed to fetch {} from the sparse index: {}", name, err);
+                    index.crate_from_cache(name).ok()
+                }
+            },
+        }
+    }
+}
+
+/// Fetch `name`'s entry, revalidating the local cache
+///
+/// `None` when the crate doesn't exist.
+fn fetch_sparse(
+    index: &crates_index::SparseIndex,
+    name: &str,
+) -> CargoResult<Option<crates_index::Crate>> {
+    let (parts, ()) = index.make_cache_request(name)?.body(())?.into_parts();
+    let mut request = ureq::request(parts.method.as_str(), &parts.uri.to_string());
+    for (key, value) in parts.headers.iter() {
+        if let Ok(value) = value.to_str() {
+            request = request.set(key.as_str(), value);
+        }
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        // `304 Not Modified` and `404 Not Found` are answers, not failures
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut builder = http::Response::builder()
+        .status(response.status())
+        .version(http::Version::HTTP_11);
+    for key in response.headers_names() {
+        if let Some(value) = response.header(&key) {
+            builder = builder.header(key.as_str(), value);
+        }
+    }
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)?;
+    let crate_ = index.parse_cache_response(name, builder.body(body)?, true)?;
+    Ok(crate_)
+}
+
+impl crate::ops::cargo::PublishIndex for CratesIoIndex {
+    fn update(&mut self) -> CargoResult<()> {
+        if self.offline {
+            log::debug!("offline, using the cached crates.io index");
+            return Ok(());
+        }
+        let index = self
+            .index
+            .as_mut()
+            .ok_or_else(|| anyhow::format_err!("crates.io index is unusable"))?;
+        match index {
+            RemoteIndex::Git(index) => index.update()?,
+            // Every lookup is already fresh
+            RemoteIndex::Sparse(_) => {}
+        }
+        Ok(())
+    }
+
+    fn update_crate(&mut self, name: &str) -> CargoResult<()> {
+        if self.offline {
+            log::debug!("offline, using the cached crates.io index");
+            return Ok(());
+        }
+        let index = self
+            .index
+            .as_mut()
+            .ok_or_else(|| anyhow::format_err!("crates.io index is unusable"))?;
+        match index {
+            // The git index can only be fetched whole
+            RemoteIndex::Git(index) => index.update()?,
+            // Surface fetch failures that `crate_` would fall back from
+            RemoteIndex::Sparse(index) => {
+                fetch_sparse(index, name)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_published(&self, name: &str, version: &str) -> bool {
+        crate::ops::cargo::is_published(self, name, version)
+    }
+
+    fn versions(&self, name: &str) -> Vec<String> {
+        crate::ops::cargo::versions(self, name)
+    }
+
+    fn checksum(&self, name: &str, version: &str) -> Option<[u8; 32]> {
+        crate::ops::cargo::checksum(self, name, version)
+    }
+
+    fn download(&self, name: &str, version: &str) -> CargoResult<Option<Vec<u8>>> {
+        if self.offline {
+            log::debug!("offline, not downloading {} {}", name, version);
+            return Ok(None);
+        }
+        let config = match self.index.as_ref() {
+            Some(RemoteIndex::Git(index)) => index.index_config()?,
+            Some(RemoteIndex::Sparse(index)) => index.index_config()?,
+            None => return Ok(None),
+        };
+        let url = match config.download_url(name, version) {
+            Some(url) => url,
+            None => return Ok(None),
+        };
+        log::debug!("downloading {}", url);
+        let mut body = Vec::new();
+        ureq::get(&url)
+            .call()
+            .with_context(|| format!("failed to download {} {}", name, version))?
+            .into_reader()
+            .read_to_end(&mut body)?;
+        Ok(Some(body))
+    }
+}
+
+/// Indexes for `index-url`, each opened when first needed
+#[derive(Default)]
+pub struct IndexUrls(std::collections::HashMap<String, CratesIoIndex>);
+
+impl IndexUrls {
+    pub fn open(&mut self, url: &str) -> &mut CratesIoIndex {
+        self.0
+            .entry(url.to_owned())
+            .or_insert_with(|| CratesIoIndex::open_url(url))
+    }
+}
+
+fn remove_cache(path: &Path) -> CargoResult<()> {
+    match std::fs::remove_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to remove crates.io index at {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ops::cargo::PublishIndex as _;
+
+    fn corrupted() -> assert_fs::TempDir {
+        use assert_fs::prelude::*;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.child(".git").write_str("gitdir: nowhere").unwrap();
+        temp.child("config.json")
+            .write_str("{ half-finished")
+            .unwrap();
+        temp
+    }
+
+    #[test]
+    fn corrupted_is_tolerated_when_optional() {
+        let temp = corrupted();
+        let mut index = CratesIoIndex::open_at(
+            temp.path(),
+            crates_index::INDEX_GIT_URL,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert!(!index.is_available());
+        assert!(index.crate_("serde").is_none());
+        assert!(!index.is_published("serde", "1.0.0"));
+        assert!(index.update().is_err());
+    }
+
+    #[test]
+    fn corrupted_is_error_when_required() {
+        let temp = corrupted();
+        let err =
+            CratesIoIndex::open_at(temp.path(), crates_index::INDEX_GIT_URL, false, true, false)
+                .err()
+                .unwrap()
+                .to_string();
+        assert!(err.contains("--repair-index"), "{}", err);
+        assert!(err.contains(&temp.path().display().to_string()), "{}", err);
+    }
+
+    #[test]
+    fn offline_never_repairs() {
+        let temp = corrupted();
+        let err =
+            CratesIoIndex::open_at(temp.path(), crates_index::INDEX_GIT_URL, true, true, true)
+                .err()
+                .unwrap()
+                .to_string();
+        assert!(err.contains("CARGO_NET_OFFLINE"), "{}", err);
+        // Nothing was removed to re-clone
+        assert!(temp.path().join("config.json").exists());
+    }
+
+    #[test]
+    fn protocol_from_env() {
+        assert_eq!(parse_protocol(None), IndexProtocol::Git);
+        assert_eq!(parse_protocol(Some("git")), IndexProtocol::Git);
+        assert_eq!(parse_protocol(Some("sparse")), IndexProtocol::Sparse);
+    }
+
+    #[test]
+    fn offline_skips_update() {
+        let temp = corrupted();
+        let mut index =
+            CratesIoIndex::open_at(temp.path(), crates_index::INDEX_GIT_URL, false, false, true)
+                .unwrap();
+        index.update().unwrap();
+    }
+}
@@ -1,6 +1,12 @@
 pub mod cargo;
 pub mod cmd;
 pub mod git;
+pub mod github;
+pub mod index;
+pub mod overlay;
+pub mod path;
 pub mod replace;
+pub mod reporter;
 pub mod shell;
+pub mod timings;
 pub mod version;
@@ -0,0 +1,77 @@
+//! Pending file edits from a dry-run
+//!
+//! Dry-runs don't write, so edits are staged in an [`Overlay`] instead and reads through it see
+//! them, keeping the simulated release consistent with what `--execute` would do.  Each run owns
+//! its overlay, so nothing staged outlives the run that staged it.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Default)]
+pub struct Overlay {
+    pending: BTreeMap<PathBuf, String>,
+}
+
+impl Overlay {
+    /// Read `path`, preferring a pending edit
+    pub fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+        if let Some(content) = self.pending.get(&key(path)) {
+            return Ok(content.clone());
+        }
+        std::fs::read_to_string(path)
+    }
+
+    /// Record what a dry-run would have written to `path`
+    pub fn stage(&mut self, path: &Path, content: String) {
+        self.pending.insert(key(path), content);
+    }
+}
+
+/// `path`, resolved so `./Cargo.toml` and its absolute form share an edit
+fn key(path: &Path) -> PathBuf {
+    // Joining keeps absolute paths as-is while giving a bare file name a directory to resolve
+    crate::ops::path::resolve(&Path::new(".").join(path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn staged_shadows_disk() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("Cargo.toml");
+        file.write_str("on disk").unwrap();
+
+        let mut overlay = Overlay::default();
+        assert_eq!(overlay.read_to_string(file.path()).unwrap(), "on disk");
+        overlay.stage(file.path(), "pending".to_owned());
+        assert_eq!(overlay.read_to_string(file.path()).unwrap(), "pending");
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "on disk");
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn equivalent_paths_share_edit() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("pkg/Cargo.toml");
+        file.write_str("on disk").unwrap();
+
+        let mut overlay = Overlay::default();
+        overlay.stage(&temp.path().join("pkg/./Cargo.toml"), "pending".to_owned());
+        let absolute = dunce::canonicalize(file.path()).unwrap();
+        assert_eq!(overlay.read_to_string(&absolute).unwrap(), "pending");
+        assert_eq!(
+            overlay
+                .read_to_string(&temp.path().join("pkg/../pkg/Cargo.toml"))
+                .unwrap(),
+            "pending"
+        );
+
+        temp.close().unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Whether `left` and `right` refer to the same location
+///
+/// Paths are resolved when they exist and Windows verbatim (`\\?\`) prefixes are ignored, so a UNC
+/// path from `canonicalize` still matches the plain path `cargo metadata` reports.
+pub fn same_path(left: &Path, right: &Path) -> bool {
+    let left = dunce::canonicalize(left).unwrap_or_else(|_| left.to_owned());
+    let right = dunce::canonicalize(right).unwrap_or_else(|_| right.to_owned());
+    strip_verbatim(&left) == strip_verbatim(&right)
+}
+
+/// `path` with its directory resolved but its file name kept
+///
+/// Directories are resolved so paths compare equal to those under git's (resolved) work tree.  The
+/// file itself is not, so a symlinked `Cargo.toml` stays with the package linking it rather than
+/// moving to wherever the link points.
+pub fn resolve(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            match dunce::canonicalize(parent) {
+                Ok(parent) => parent.join(name),
+                Err(_) => path.to_owned(),
+            }
+        }
+        _ => path.to_owned(),
+    }
+}
+
+fn strip_verbatim(path: &Path) -> Cow<'_, Path> {
+    let raw = match path.to_str() {
+        Some(raw) => raw,
+        None => return Cow::Borrowed(path),
+    };
+    if let Some(rest) = raw.strip_prefix(r"\\?\UNC\") {
+        Cow::Owned(PathBuf::from(format!(r"\\{}", rest)))
+    } else if let Some(rest) = raw.strip_prefix(r"\\?\") {
+        let bytes = rest.as_bytes();
+        let is_disk = 2 <= bytes.len() && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+        if is_disk {
+            Cow::Owned(PathBuf::from(rest))
+        } else {
+            // Devices and volume GUIDs have no non-verbatim form
+            Cow::Borrowed(path)
+        }
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Write `data` to `path`, even when `path` is longer than Windows' `MAX_PATH`
+pub fn write(path: &Path, data: &str) -> std::io::Result<()> {
+    std::fs::write(long_path(path), data).map_err(|err| annotate(err, path))
+}
+
+/// Rename `from` to `to`, even when either is longer than Windows' `MAX_PATH`
+pub fn rename(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::rename(long_path(from), long_path(to)).map_err(|err| annotate(err, to))
+}
+
+fn annotate(err: std::io::Error, path: &Path) -> std::io::Error {
+    std::io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+/// Opt `path` into long-path-capable Windows APIs
+///
+/// `std::fs::canonicalize` returns verbatim (`\\?\`) paths on Windows, which are not limited to
+/// `MAX_PATH`.
+#[cfg(windows)]
+fn long_path(path: &Path) -> Cow<'_, Path> {
+    const MAX_PATH: usize = 260;
+
+    let is_verbatim = path
+        .to_str()
+        .map(|raw| raw.starts_with(r"\\?\"))
+        .unwrap_or(false);
+    if path.as_os_str().len() < MAX_PATH || is_verbatim {
+        return Cow::Borrowed(path);
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => match std::fs::canonicalize(parent) {
+            Ok(parent) => Cow::Owned(parent.join(name)),
+            Err(_) => Cow::Borrowed(path),
+        },
+        _ => Cow::Borrowed(path),
+    }
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn resolve_keeps_symlinked_file() {
+        use assert_fs::prelude::*;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.child("template/member.toml").touch().unwrap();
+        temp.child("member").create_dir_all().unwrap();
+        std::os::unix::fs::symlink("../template/member.toml", temp.child("member/Cargo.toml"))
+            .unwrap();
+        std::os::unix::fs::symlink("member", temp.child("alias")).unwrap();
+        let root = dunce::canonicalize(temp.path()).unwrap();
+
+        assert_eq!(
+            resolve(&temp.path().join("member/Cargo.toml")),
+            root.join("member/Cargo.toml")
+        );
+        assert_eq!(
+            resolve(&temp.path().join("alias/Cargo.toml")),
+            root.join("member/Cargo.toml")
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn same_path_ignores_verbatim_disk() {
+        assert!(same_path(
+            Path::new(r"\\?\C:\Users\me\project\crate"),
+            Path::new(r"C:\Users\me\project\crate"),
+        ));
+        assert!(same_path(
+            Path::new(r"C:\Users\me\project\crate"),
+            Path::new(r"\\?\C:\Users\me\project\crate"),
+        ));
+    }
+
+    #[test]
+    fn same_path_ignores_verbatim_unc() {
+        assert!(same_path(
+            Path::new(r"\\?\UNC\server\share\project\crate"),
+            Path::new(r"\\server\share\project\crate"),
+        ));
+    }
+
+    #[test]
+    fn same_path_distinguishes_paths() {
+        assert!(!same_path(
+            Path::new(r"\\?\C:\Users\me\project\crate"),
+            Path::new(r"C:\Users\me\project\other"),
+        ));
+        assert!(!same_path(
+            Path::new(r"\\?\UNC\server\share\crate"),
+            Path::new(r"C:\server\share\crate"),
+        ));
+        assert!(!same_path(
+            Path::new(r"\\?\Volume{0}\crate"),
+            Path::new(r"Volume{0}\crate"),
+        ));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn same_path_canonicalized() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let verbatim = std::fs::canonicalize(temp.path()).unwrap();
+        assert!(verbatim.to_str().unwrap().starts_with(r"\\?\"));
+        assert!(same_path(&verbatim, temp.path()));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn write_long_path() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let mut dir = temp.path().to_owned();
+        while dir.as_os_str().len() < 300 {
+            dir.push("a-deeply-nested-directory");
+        }
+        std::fs::create_dir_all(long_path(&dir)).unwrap();
+        let path = dir.join("Cargo.toml");
+
+        write(&path, "[package]").unwrap();
+        rename(&path, &dir.join("Cargo.toml.bak")).unwrap();
+
+        let actual = std::fs::read_to_string(long_path(&dir.join("Cargo.toml.bak"))).unwrap();
+        assert_eq!(actual, "[package]");
+    }
+}
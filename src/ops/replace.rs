@@ -1,8 +1,11 @@
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::path::Path;
+use std::path::PathBuf;
 
 use crate::config::Replace;
 use crate::error::CargoResult;
+use crate::ops::overlay::Overlay;
 
 pub static NOW: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| {
     time::OffsetDateTime::now_utc()
@@ -10,6 +13,21 @@ pub static NOW: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| {
         .unwrap()
 });
 
+/// Placeholders `cargo-release` fills in itself, which `template-vars` can't redefine
+pub const BUILTIN_VARS: &[&str] = &[
+    "prev_version",
+    "prev_metadata",
+    "version",
+    "metadata",
+    "crate_name",
+    "date",
+    "dependency_updates",
+    "prefix",
+    "tag_name",
+    "prev_tag_name",
+    "changes",
+];
+
 #[derive(Clone, Default, Debug)]
 pub struct Template<'a> {
     pub prev_version: Option<&'a str>,
@@ -18,66 +36,215 @@ pub struct Template<'a> {
     pub metadata: Option<&'a str>,
     pub crate_name: Option<&'a str>,
     pub date: Option<&'a str>,
+    pub dependency_updates: Option<&'a str>,
 
     pub prefix: Option<&'a str>,
     pub tag_name: Option<&'a str>,
+    /// The package's tag from its prior release
+    pub prev_tag_name: Option<&'a str>,
+    /// Conventional commits since the prior tag, grouped into sections
+    pub changes: Option<&'a str>,
+
+    /// User-defined placeholders, from `template-vars`
+    pub vars: Option<&'a BTreeMap<String, String>>,
+    /// Versions of dependencies released alongside, for `{{dep.<crate>.<field>}}`
+    pub dependencies: Option<DependencyLookup<'a>>,
+}
+
+/// Look up `field` (`version` or `prev_version`) of the dependency named `crate`
+#[derive(Clone, Copy)]
+pub struct DependencyLookup<'a>(pub &'a dyn Fn(&str, &str) -> Option<&'a str>);
+
+impl std::fmt::Debug for DependencyLookup<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DependencyLookup").finish()
+    }
 }
 
 impl<'a> Template<'a> {
     pub fn render(&self, input: &str) -> String {
+        self.render_with(input, verbatim)
+    }
+
+    /// Render `input` as a shell command line, quoting each value as a single shell word
+    pub fn render_shell(&self, input: &str) -> String {
+        self.render_with(input, crate::ops::cmd::shell_escape)
+    }
+
+    fn render_with(&self, input: &str, escape: impl Fn(&str) -> Cow<'_, str>) -> String {
         let mut s = input.to_string();
         const PREV_VERSION: &str = "{{prev_version}}";
-        s = render_var(s, PREV_VERSION, self.prev_version);
+        s = render_var(s, PREV_VERSION, self.prev_version, &escape);
         const PREV_METADATA: &str = "{{prev_metadata}}";
-        s = render_var(s, PREV_METADATA, self.prev_metadata);
+        s = render_var(s, PREV_METADATA, self.prev_metadata, &escape);
         const VERSION: &str = "{{version}}";
-        s = render_var(s, VERSION, self.version);
+        s = render_var(s, VERSION, self.version, &escape);
         const METADATA: &str = "{{metadata}}";
-        s = render_var(s, METADATA, self.metadata);
+        s = render_var(s, METADATA, self.metadata, &escape);
         const CRATE_NAME: &str = "{{crate_name}}";
-        s = render_var(s, CRATE_NAME, self.crate_name);
+        s = render_var(s, CRATE_NAME, self.crate_name, &escape);
         const DATE: &str = "{{date}}";
-        s = render_var(s, DATE, self.date);
+        s = render_var(s, DATE, self.date, &escape);
+        const DEPENDENCY_UPDATES: &str = "{{dependency_updates}}";
+        s = render_var(s, DEPENDENCY_UPDATES, self.dependency_updates, &escape);
 
         const PREFIX: &str = "{{prefix}}";
-        s = render_var(s, PREFIX, self.prefix);
+        s = render_var(s, PREFIX, self.prefix, &escape);
         const TAG_NAME: &str = "{{tag_name}}";
-        s = render_var(s, TAG_NAME, self.tag_name);
+        s = render_var(s, TAG_NAME, self.tag_name, &escape);
+        const PREV_TAG_NAME: &str = "{{prev_tag_name}}";
+        s = render_var(s, PREV_TAG_NAME, self.prev_tag_name, &escape);
+        const CHANGES: &str = "{{changes}}";
+        s = render_var(s, CHANGES, self.changes, &escape);
+
+        for (name, value) in self.vars.into_iter().flatten() {
+            if BUILTIN_VARS.contains(&name.as_str()) {
+                continue;
+            }
+            s = render_var(s, &format!("{{{{{}}}}}", name), Some(value), &escape);
+        }
+        s = self.render_dependencies(s, &escape);
+        for name in unrendered_vars(&s) {
+            if !BUILTIN_VARS.contains(&name) {
+                log::debug!("Unrendered {{{{{}}}}} present in template {:?}", name, s);
+            }
+        }
+        s
+    }
+
+    /// Render `{{dep.<crate>.<field>}}`, through `dependencies`
+    fn render_dependencies(&self, mut s: String, escape: impl Fn(&str) -> Cow<'_, str>) -> String {
+        const DEP_PREFIX: &str = "{{dep.";
+        let names: indexmap::IndexSet<String> = s
+            .split(DEP_PREFIX)
+            .skip(1)
+            .filter_map(|rest| Some(rest.split_once("}}")?.0.to_owned()))
+            .collect();
+        for name in names {
+            let var_name = format!("{}{}}}}}", DEP_PREFIX, name);
+            let value = name
+                .rsplit_once('.')
+                .zip(self.dependencies)
+                .and_then(|((dep, field), lookup)| (lookup.0)(dep, field));
+            s = render_var(s, &var_name, value, &escape);
+        }
         s
     }
 }
 
-fn render_var(mut template: String, var_name: &str, var_value: Option<&str>) -> String {
+/// Names of the `{{name}}` placeholders left in `template`
+fn unrendered_vars(template: &str) -> impl Iterator<Item = &str> {
+    template.split("{{").skip(1).filter_map(|rest| {
+        let (name, _) = rest.split_once("}}")?;
+        let is_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        is_name.then_some(name)
+    })
+}
+
+fn verbatim(value: &str) -> Cow<'_, str> {
+    Cow::Borrowed(value)
+}
+
+fn render_var(
+    mut template: String,
+    var_name: &str,
+    var_value: Option<&str>,
+    escape: impl Fn(&str) -> Cow<'_, str>,
+) -> String {
     if let Some(var_value) = var_value {
-        template = template.replace(var_name, var_value);
+        template = template.replace(var_name, &escape(var_value));
     } else if template.contains(var_name) {
         log::debug!("Unrendered {} present in template {:?}", var_name, template);
     }
     template
 }
 
+/// Apply `replace_config`, returning the files changed
 pub fn do_file_replacements(
     replace_config: &[Replace],
     template: &Template<'_>,
     cwd: &Path,
     prerelease: bool,
     noisy: bool,
+    overlay: &mut Overlay,
     dry_run: bool,
-) -> CargoResult<bool> {
-    // Since we don't have a convenient insert-order map, let's do sorted, rather than random.
-    let mut by_file = BTreeMap::new();
-    for replace in replace_config {
-        let file = replace.file.clone();
-        by_file.entry(file).or_insert_with(Vec::new).push(replace);
-    }
+) -> CargoResult<Vec<PathBuf>> {
+    apply_file_replacements(
+        replace_config,
+        template,
+        cwd,
+        prerelease,
+        noisy,
+        overlay,
+        dry_run,
+        None,
+    )
+}
+
+/// Dry-run `replace_config`, collecting every problem rather than stopping at the first
+///
+/// Edits are staged into `overlay` like any dry-run, so checking several packages in turn sees a
+/// shared file as the earlier packages would leave it.
+pub fn check_file_replacements(
+    replace_config: &[Replace],
+    template: &Template<'_>,
+    cwd: &Path,
+    prerelease: bool,
+    overlay: &mut Overlay,
+    problems: &mut Vec<String>,
+) -> CargoResult<()> {
+    let noisy = false;
+    let dry_run = true;
+    apply_file_replacements(
+        replace_config,
+        template,
+        cwd,
+        prerelease,
+        noisy,
+        overlay,
+        dry_run,
+        Some(problems),
+    )?;
+    Ok(())
+}
 
-    for (path, replaces) in by_file.into_iter() {
+fn apply_file_replacements(
+    replace_config: &[Replace],
+    template: &Template<'_>,
+    cwd: &Path,
+    prerelease: bool,
+    noisy: bool,
+    overlay: &mut Overlay,
+    dry_run: bool,
+    mut problems: Option<&mut Vec<String>>,
+) -> CargoResult<Vec<PathBuf>> {
+    let checking = problems.is_some();
+    // Checking collects the problem and moves on; applying stops at it
+    let mut report = |problem: String| -> CargoResult<()> {
+        match problems.as_deref_mut() {
+            Some(problems) => {
+                problems.push(problem);
+                Ok(())
+            }
+            None => Err(anyhow::Error::msg(problem)),
+        }
+    };
+
+    let mut changed = Vec::new();
+    for (path, replaces) in by_file(replace_config) {
         let file = cwd.join(&path);
         log::debug!("processing replacements for file {}", file.display());
         if !file.exists() {
-            anyhow::bail!("unable to find file {} to perform replace", file.display());
+            report(format!(
+                "unable to find file {} to perform replace",
+                file.display()
+            ))?;
+            continue;
         }
-        let data = std::fs::read_to_string(&file)?;
+        let data = overlay.read_to_string(&file)?;
         let mut replaced = data.clone();
 
         for replace in replaces {
@@ -87,51 +254,60 @@ pub fn do_file_replacements(
             }
 
             let pattern = replace.search.as_str();
-            let r = regex::RegexBuilder::new(pattern).multi_line(true).build()?;
-
-            let min = replace.min.or(replace.exactly).unwrap_or(1);
-            let max = replace.max.or(replace.exactly).unwrap_or(std::usize::MAX);
-            let actual = r.find_iter(&replaced).count();
-            if actual < min {
-                anyhow::bail!(
-                    "for `{}` in '{}', at least {} replacements expected, found {}",
-                    pattern,
-                    path.display(),
-                    min,
-                    actual
-                );
-            } else if max < actual {
-                anyhow::bail!(
-                    "for `{}` in '{}', at most {} replacements expected, found {}",
-                    pattern,
-                    path.display(),
-                    max,
-                    actual
-                );
-            }
+            let r = match regex::RegexBuilder::new(pattern).multi_line(true).build() {
+                Ok(r) => r,
+                Err(err) if checking => {
+                    report(format!(
+                        "for `{}` in '{}', {}",
+                        pattern,
+                        path.display(),
+                        err
+                    ))?;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
 
             let to_replace = replace.replace.as_str();
             let replacer = template.render(to_replace);
 
+            if replace.idempotent && is_applied(&r, &replaced, &replacer) {
+                if !checking {
+                    let _ = crate::ops::reporter::status(
+                        "Skipping",
+                        format!("`{}` in {}, already replaced", pattern, path.display()),
+                    );
+                }
+                continue;
+            }
+
+            if let Err(problem) = check_count(replace, &r, &replaced, &path) {
+                report(problem)?;
+                continue;
+            }
+
             replaced = r.replace_all(&replaced, replacer.as_str()).into_owned();
         }
 
         if data != replaced {
             if dry_run {
-                let display_path = path.display().to_string();
-                let data_lines: Vec<_> = data.lines().map(|s| format!("{}\n", s)).collect();
-                let replaced_lines: Vec<_> = replaced.lines().map(|s| format!("{}\n", s)).collect();
-                let diff = difflib::unified_diff(
-                    &data_lines,
-                    &replaced_lines,
-                    display_path.as_str(),
-                    display_path.as_str(),
-                    "original",
-                    "replaced",
-                    0,
-                );
-                if noisy {
-                    let _ = crate::ops::shell::status(
+                if checking {
+                    // Checks only need the staged content
+                } else if noisy {
+                    let display_path = path.display().to_string();
+                    let data_lines: Vec<_> = data.lines().map(|s| format!("{}\n", s)).collect();
+                    let replaced_lines: Vec<_> =
+                        replaced.lines().map(|s| format!("{}\n", s)).collect();
+                    let diff = difflib::unified_diff(
+                        &data_lines,
+                        &replaced_lines,
+                        display_path.as_str(),
+                        display_path.as_str(),
+                        "original",
+                        "replaced",
+                        0,
+                    );
+                    let _ = crate::ops::reporter::status(
                         "Replacing",
                         format!(
                             "in {}\n{}",
@@ -141,14 +317,240 @@ pub fn do_file_replacements(
                     );
                 } else {
                     let _ =
-                        crate::ops::shell::status("Replacing", format!("in {}", path.display()));
+                        crate::ops::reporter::status("Replacing", format!("in {}", path.display()));
                 }
+                overlay.stage(&file, replaced);
             } else {
-                std::fs::write(&file, replaced)?;
+                crate::ops::path::write(&file, &replaced)?;
             }
+            changed.push(file);
         } else {
             log::trace!("{} is unchanged", file.display());
         }
     }
-    Ok(true)
+    Ok(changed)
+}
+
+/// Group `replace_config` by file, so each file is read and written once
+fn by_file(replace_config: &[Replace]) -> BTreeMap<PathBuf, Vec<&Replace>> {
+    // Since we don't have a convenient insert-order map, let's do sorted, rather than random.
+    let mut by_file = BTreeMap::new();
+    for replace in replace_config {
+        let file = replace.file.clone();
+        by_file.entry(file).or_insert_with(Vec::new).push(replace);
+    }
+    by_file
+}
+
+/// Whether `r` matches `data` as many times as `replace` allows
+fn check_count(replace: &Replace, r: &regex::Regex, data: &str, path: &Path) -> Result<(), String> {
+    let pattern = replace.search.as_str();
+    let min = replace.min.or(replace.exactly).unwrap_or(1);
+    let max = replace.max.or(replace.exactly).unwrap_or(std::usize::MAX);
+    let actual = r.find_iter(data).count();
+    if actual < min {
+        Err(format!(
+            "for `{}` in '{}', at least {} replacements expected, found {}",
+            pattern,
+            path.display(),
+            min,
+            actual
+        ))
+    } else if max < actual {
+        Err(format!(
+            "for `{}` in '{}', at most {} replacements expected, found {}",
+            pattern,
+            path.display(),
+            max,
+            actual
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether replacing `r` with `replacer` in `data` would only re-apply what's already there
+fn is_applied(r: &regex::Regex, data: &str, replacer: &str) -> bool {
+    let mut matched = false;
+    for caps in r.captures_iter(data) {
+        matched = true;
+        let mut expanded = String::new();
+        caps.expand(replacer, &mut expanded);
+        let start = caps.get(0).unwrap().start();
+        if !data[start..].starts_with(&expanded) {
+            return false;
+        }
+    }
+    // Replacements that consume their match, like a placeholder date, leave nothing to match
+    matched || data.contains(replacer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use assert_fs::prelude::*;
+
+    const CHANGELOG: &str = "# Changelog\n\n## Unreleased\n\n- Fixed a bug\n";
+
+    fn heading(idempotent: bool) -> Replace {
+        Replace {
+            file: std::path::PathBuf::from("CHANGELOG.md"),
+            search: "^## Unreleased".to_owned(),
+            replace: "## Unreleased\n\n## {{version}}".to_owned(),
+            min: None,
+            max: None,
+            exactly: Some(1),
+            prerelease: false,
+            idempotent,
+        }
+    }
+
+    fn replace_twice(replace: Replace) -> (CargoResult<Vec<PathBuf>>, String) {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let changelog = temp.child("CHANGELOG.md");
+        changelog.write_str(CHANGELOG).unwrap();
+        let template = Template {
+            version: Some("1.0.0"),
+            ..Default::default()
+        };
+        let replaces = [replace];
+
+        let mut overlay = Overlay::default();
+        do_file_replacements(
+            &replaces,
+            &template,
+            temp.path(),
+            false,
+            false,
+            &mut overlay,
+            false,
+        )
+        .unwrap();
+        let second = do_file_replacements(
+            &replaces,
+            &template,
+            temp.path(),
+            false,
+            false,
+            &mut overlay,
+            false,
+        );
+        let actual = std::fs::read_to_string(changelog.path()).unwrap();
+
+        temp.close().unwrap();
+        (second, actual)
+    }
+
+    #[test]
+    fn reapplied_without_idempotent() {
+        let (result, actual) = replace_twice(heading(false));
+        result.unwrap();
+        assert_eq!(
+            actual,
+            "# Changelog\n\n## Unreleased\n\n## 1.0.0\n\n## 1.0.0\n\n- Fixed a bug\n"
+        );
+    }
+
+    #[test]
+    fn idempotent_skips_applied() {
+        let (result, actual) = replace_twice(heading(true));
+        assert_eq!(result.unwrap(), Vec::<PathBuf>::new());
+        assert_eq!(
+            actual,
+            "# Changelog\n\n## Unreleased\n\n## 1.0.0\n\n- Fixed a bug\n"
+        );
+    }
+
+    #[test]
+    fn idempotent_skip_satisfies_min() {
+        let version = Replace {
+            file: std::path::PathBuf::from("CHANGELOG.md"),
+            search: "Unreleased".to_owned(),
+            replace: "{{version}}".to_owned(),
+            min: None,
+            max: None,
+            exactly: Some(1),
+            prerelease: false,
+            idempotent: false,
+        };
+        let (result, _) = replace_twice(version.clone());
+        assert!(result.is_err());
+
+        let (result, actual) = replace_twice(Replace {
+            idempotent: true,
+            ..version
+        });
+        result.unwrap();
+        assert_eq!(actual, "# Changelog\n\n## 1.0.0\n\n- Fixed a bug\n");
+    }
+
+    #[test]
+    fn render_custom_vars() {
+        let vars = maplit::btreemap! {
+            "sprint".to_owned() => "Sprint 42".to_owned(),
+            "version".to_owned() => "ignored".to_owned(),
+        };
+        let template = Template {
+            version: Some("1.0.0"),
+            vars: Some(&vars),
+            ..Default::default()
+        };
+        assert_eq!(
+            template.render("{{version}} ({{sprint}}, {{ticket}})"),
+            "1.0.0 (Sprint 42, {{ticket}})"
+        );
+    }
+
+    #[test]
+    fn render_dependencies() {
+        let lookup = |name: &str, field: &str| match (name, field) {
+            ("core", "version") => Some("0.2.0"),
+            ("core", "prev_version") => Some("0.1.0"),
+            _ => None,
+        };
+        let template = Template {
+            dependencies: Some(DependencyLookup(&lookup)),
+            ..Default::default()
+        };
+        assert_eq!(
+            template.render("Updated core from {{dep.core.prev_version}} to {{dep.core.version}}"),
+            "Updated core from 0.1.0 to 0.2.0"
+        );
+        assert_eq!(
+            template.render("{{dep.other.version}} {{dep.core.metadata}}"),
+            "{{dep.other.version}} {{dep.core.metadata}}"
+        );
+        assert_eq!(
+            Template::default().render("{{dep.core.version}}"),
+            "{{dep.core.version}}"
+        );
+    }
+
+    #[test]
+    fn unrendered() {
+        assert_eq!(
+            unrendered_vars("{{ticket}} {{ not a var }} {{date}}").collect::<Vec<_>>(),
+            ["ticket", "date"]
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn render_shell_quotes_values() {
+        let template = Template {
+            version: Some("1.0.0-ünï.1"),
+            metadata: Some("it's a build"),
+            crate_name: Some("foo"),
+            ..Default::default()
+        };
+        assert_eq!(
+            template.render_shell("echo {{crate_name}} {{version}}+{{metadata}}"),
+            r#"echo foo '1.0.0-ünï.1'+'it'\''s a build'"#
+        );
+        assert_eq!(
+            template.render("{{crate_name}} {{version}}+{{metadata}}"),
+            "foo 1.0.0-ünï.1+it's a build"
+        );
+    }
 }
@@ -0,0 +1,192 @@
+//! Structured progress events, for tools embedding cargo-release
+//!
+//! Steps report through the installed [`Reporter`], which defaults to printing via
+//! [`crate::ops::shell`].
+
+use std::sync::RwLock;
+
+use crate::error::CargoResult;
+use crate::ops::shell;
+
+pub trait Reporter: Send + Sync {
+    /// A release step, like `"publish"`, is starting
+    fn on_phase_start(&self, phase: &str) -> CargoResult<()> {
+        log::debug!("starting {}", phase);
+        Ok(())
+    }
+
+    /// Progress on `package` within a phase, like `("Publishing", "foo", "")`
+    fn on_package_phase(&self, action: &str, package: &str, message: &str) -> CargoResult<()> {
+        if message.is_empty() {
+            shell::status(action, package)
+        } else {
+            shell::status(action, format!("{} {}", package, message))
+        }
+    }
+
+    /// Progress not tied to a single package
+    fn on_status(&self, action: &str, message: &str) -> CargoResult<()> {
+        shell::status(action, message)
+    }
+
+    fn on_warning(&self, message: &str) -> CargoResult<()> {
+        shell::warn(message)
+    }
+
+    fn on_error(&self, message: &str) -> CargoResult<()> {
+        shell::error(message)
+    }
+
+    fn on_note(&self, message: &str) -> CargoResult<()> {
+        shell::note(message)
+    }
+
+    /// The release plan, reported once before finishing
+    fn on_summary(&self, summary: &str) -> CargoResult<()> {
+        shell::summary(summary)
+    }
+
+    /// An external command is about to run, or would have under dry-run
+    fn on_command(&self, _command: &[String], _dry_run: bool) -> CargoResult<()> {
+        Ok(())
+    }
+}
+
+/// Prints to stderr, like the `cargo-release` binary
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ShellReporter;
+
+impl Reporter for ShellReporter {}
+
+static REPORTER: RwLock<Option<Box<dyn Reporter>>> = RwLock::new(None);
+
+#[cfg(test)]
+thread_local! {
+    /// Used in place of [`REPORTER`], so tests running in parallel don't record each other's
+    /// events
+    static TEST_REPORTER: std::cell::RefCell<Option<Box<dyn Reporter>>> =
+        std::cell::RefCell::new(None);
+}
+
+/// Send all further events to `reporter`
+pub fn set_reporter(reporter: impl Reporter + 'static) {
+    *REPORTER.write().unwrap() = Some(Box::new(reporter));
+}
+
+/// Go back to printing events, like before any [`set_reporter`]
+pub fn reset_reporter() {
+    *REPORTER.write().unwrap() = None;
+}
+
+/// Send the current thread's events to `reporter` for the rest of a test, restoring the default
+/// once the guard drops
+#[cfg(test)]
+pub(crate) fn set_test_reporter(reporter: impl Reporter + 'static) -> TestReporterGuard {
+    TEST_REPORTER.with(|r| *r.borrow_mut() = Some(Box::new(reporter)));
+    TestReporterGuard(())
+}
+
+#[cfg(test)]
+pub(crate) struct TestReporterGuard(());
+
+#[cfg(test)]
+impl Drop for TestReporterGuard {
+    fn drop(&mut self) {
+        TEST_REPORTER.with(|r| *r.borrow_mut() = None);
+    }
+}
+
+/// Run `f` with the current test's reporter, if it installed one
+#[cfg(test)]
+fn with_test_reporter<R, F: FnOnce(&dyn Reporter) -> R>(f: F) -> Result<R, F> {
+    TEST_REPORTER.with(|r| match r.borrow().as_deref() {
+        Some(reporter) => Ok(f(reporter)),
+        None => Err(f),
+    })
+}
+
+thread_local! {
+    /// Warnings and errors reported within [`count_problems`]
+    static PROBLEMS: std::cell::Cell<Option<usize>> = std::cell::Cell::new(None);
+}
+
+/// Run `f`, also returning how many warnings and errors it reported
+///
+/// Only reports from the current thread are counted.
+pub fn count_problems<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let outer = PROBLEMS.with(|p| p.replace(Some(0)));
+    let result = f();
+    let count = PROBLEMS.with(|p| p.replace(outer)).unwrap_or(0);
+    PROBLEMS.with(|p| p.set(outer.map(|outer| outer + count)));
+    (result, count)
+}
+
+fn note_problem() {
+    PROBLEMS.with(|p| p.set(p.get().map(|count| count + 1)));
+}
+
+fn with<R>(f: impl FnOnce(&dyn Reporter) -> R) -> R {
+    #[cfg(test)]
+    let f = match with_test_reporter(f) {
+        Ok(result) => return result,
+        Err(f) => f,
+    };
+    let reporter = REPORTER.read().unwrap();
+    match reporter.as_deref() {
+        Some(reporter) => f(reporter),
+        None => f(&ShellReporter),
+    }
+}
+
+pub fn phase_start(phase: &str) -> CargoResult<()> {
+    with(|r| r.on_phase_start(phase))
+}
+
+pub fn package(action: &str, package: &str, message: impl std::fmt::Display) -> CargoResult<()> {
+    let message = message.to_string();
+    with(|r| r.on_package_phase(action, package, &message))
+}
+
+pub fn status(action: &str, message: impl std::fmt::Display) -> CargoResult<()> {
+    let message = message.to_string();
+    with(|r| r.on_status(action, &message))
+}
+
+pub fn warn(message: impl std::fmt::Display) -> CargoResult<()> {
+    note_problem();
+    let message = message.to_string();
+    with(|r| r.on_warning(&message))
+}
+
+pub fn error(message: impl std::fmt::Display) -> CargoResult<()> {
+    note_problem();
+    let message = message.to_string();
+    with(|r| r.on_error(&message))
+}
+
+pub fn note(message: impl std::fmt::Display) -> CargoResult<()> {
+    let message = message.to_string();
+    with(|r| r.on_note(&message))
+}
+
+pub fn summary(summary: impl std::fmt::Display) -> CargoResult<()> {
+    let summary = summary.to_string();
+    with(|r| r.on_summary(&summary))
+}
+
+/// Report `message` at `level`, like [`shell::log`]
+pub fn log(level: log::Level, message: impl std::fmt::Display) -> CargoResult<()> {
+    match level {
+        log::Level::Error => error(message),
+        log::Level::Warn => warn(message),
+        log::Level::Info => note(message),
+        _ => {
+            log::log!(level, "{}", message);
+            Ok(())
+        }
+    }
+}
+
+pub fn command(command: &[String], dry_run: bool) -> CargoResult<()> {
+    with(|r| r.on_command(command, dry_run))
+}
@@ -1,7 +1,8 @@
-use std::io::{stdin, stdout, Write};
+use std::io::{stdin, stdout, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::Context as _;
-use termcolor::{ColorChoice, StandardStream, WriteColor};
+use termcolor::{Buffer, BufferWriter, ColorChoice, StandardStream, WriteColor};
 
 pub use termcolor::Color;
 pub use termcolor::ColorSpec;
@@ -9,14 +10,19 @@ pub use termcolor::ColorSpec;
 use crate::error::CargoResult;
 
 pub fn confirm(prompt: &str) -> bool {
-    let mut input = String::new();
+    ask(prompt, "y/N", &mut stdin().lock()).to_lowercase() == "y"
+}
+
+/// Print `prompt` with its `choices` and read the response from `input`
+pub fn ask(prompt: &str, choices: &str, input: &mut dyn BufRead) -> String {
+    let mut response = String::new();
 
-    console_println(&format!("{} [y/N] ", prompt), None, true);
+    console_println(&format!("{} [{}] ", prompt, choices), None, true);
 
     stdout().flush().unwrap();
-    stdin().read_line(&mut input).expect("y/n required");
+    input.read_line(&mut response).expect("y/n required");
 
-    input.trim().to_lowercase() == "y"
+    response.trim().to_owned()
 }
 
 fn console_println(text: &str, color: Option<Color>, bold: bool) {
@@ -30,6 +36,29 @@ fn console_println(text: &str, color: Option<Color>, bold: bool) {
     stdout.reset().unwrap();
 }
 
+/// Which shell messages to print, independent of the `log` level
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Print everything
+    All,
+    /// Only print warnings, errors, and the release summary
+    Summary,
+}
+
+static SUMMARY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_filter(filter: Filter) {
+    SUMMARY.store(filter == Filter::Summary, Ordering::Relaxed);
+}
+
+pub fn filter() -> Filter {
+    if SUMMARY.load(Ordering::Relaxed) {
+        Filter::Summary
+    } else {
+        Filter::All
+    }
+}
+
 /// Whether to color logged output
 fn colorize_stderr() -> ColorChoice {
     if concolor_control::get(concolor_control::Stream::Stderr).color() {
@@ -39,6 +68,49 @@ fn colorize_stderr() -> ColorChoice {
     }
 }
 
+/// A line being composed for stderr from several fragments
+///
+/// Fragments are buffered and written with a single write when the guard is dropped, so messages
+/// from other threads can't land in the middle of the line.
+pub struct StderrLock {
+    writer: BufferWriter,
+    buffer: Buffer,
+}
+
+pub fn lock_stderr() -> StderrLock {
+    let writer = BufferWriter::stderr(colorize_stderr());
+    let buffer = writer.buffer();
+    StderrLock { writer, buffer }
+}
+
+impl StderrLock {
+    /// Append `fragment` with formatting
+    pub fn write(&mut self, fragment: impl std::fmt::Display, spec: &ColorSpec) -> CargoResult<()> {
+        self.buffer.set_color(spec)?;
+        write!(self.buffer, "{}", fragment)?;
+        self.buffer.reset()?;
+        Ok(())
+    }
+}
+
+impl Drop for StderrLock {
+    fn drop(&mut self) {
+        let _ = emit(&self.writer, &self.buffer);
+    }
+}
+
+#[cfg(test)]
+static CAPTURE: std::sync::Mutex<Option<Vec<u8>>> = std::sync::Mutex::new(None);
+
+fn emit(writer: &BufferWriter, buffer: &Buffer) -> std::io::Result<()> {
+    #[cfg(test)]
+    if let Some(capture) = CAPTURE.lock().unwrap().as_mut() {
+        capture.extend_from_slice(buffer.as_slice());
+        return Ok(());
+    }
+    writer.print(buffer)
+}
+
 /// Print a message with a colored title in the style of Cargo shell messages.
 pub fn print(
     status: &str,
@@ -46,26 +118,28 @@ pub fn print(
     color: Color,
     justified: bool,
 ) -> CargoResult<()> {
-    let color_choice = colorize_stderr();
-    let mut output = StandardStream::stderr(color_choice);
+    let mut output = lock_stderr();
 
-    output.set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
     if justified {
-        write!(output, "{status:>12}")?;
+        output.write(
+            format_args!("{status:>12}"),
+            ColorSpec::new().set_fg(Some(color)).set_bold(true),
+        )?;
     } else {
-        write!(output, "{}", status)?;
-        output.set_color(ColorSpec::new().set_bold(true))?;
-        write!(output, ":")?;
+        output.write(status, ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+        output.write(":", ColorSpec::new().set_bold(true))?;
     }
-    output.reset()?;
 
-    writeln!(output, " {message:#}").with_context(|| "Failed to write message")?;
+    writeln!(output.buffer, " {message:#}").with_context(|| "Failed to write message")?;
 
     Ok(())
 }
 
 /// Print a styled action message.
 pub fn status(action: &str, message: impl std::fmt::Display) -> CargoResult<()> {
+    if filter() == Filter::Summary {
+        return Ok(());
+    }
     print(action, message, Color::Green, true)
 }
 
@@ -79,11 +153,19 @@ pub fn warn(message: impl std::fmt::Display) -> CargoResult<()> {
     print("warning", message, Color::Yellow, false)
 }
 
-/// Print a styled warning message.
+/// Print a styled note message.
 pub fn note(message: impl std::fmt::Display) -> CargoResult<()> {
+    if filter() == Filter::Summary {
+        return Ok(());
+    }
     print("note", message, Color::Cyan, false)
 }
 
+/// Print the release summary, which is shown under every [`Filter`]
+pub fn summary(message: impl std::fmt::Display) -> CargoResult<()> {
+    print("summary", message, Color::Cyan, false)
+}
+
 pub fn log(level: log::Level, message: impl std::fmt::Display) -> CargoResult<()> {
     match level {
         log::Level::Error => error(message),
@@ -97,12 +179,74 @@ pub fn log(level: log::Level, message: impl std::fmt::Display) -> CargoResult<()
 }
 
 /// Print a part of a line with formatting
+///
+/// To keep a line made of several parts together, use [`lock_stderr`].
 pub fn write_stderr(fragment: impl std::fmt::Display, spec: &ColorSpec) -> CargoResult<()> {
-    let color_choice = colorize_stderr();
-    let mut output = StandardStream::stderr(color_choice);
+    lock_stderr().write(fragment, spec)
+}
 
-    output.set_color(spec)?;
-    write!(output, "{}", fragment)?;
-    output.reset()?;
-    Ok(())
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn concurrent_lines_stay_whole() {
+        *CAPTURE.lock().unwrap() = Some(Vec::new());
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                scope.spawn(move || {
+                    for line in 0..100 {
+                        status("Stressing", format_args!("thread {} line {}", thread, line))
+                            .unwrap();
+
+                        let mut stderr = lock_stderr();
+                        stderr.write("    fragments", &ColorSpec::new()).unwrap();
+                        stderr
+                            .write(
+                                format_args!(" thread {}", thread),
+                                ColorSpec::new().set_fg(Some(Color::Yellow)),
+                            )
+                            .unwrap();
+                        stderr
+                            .write(format_args!(" line {}\n", line), &ColorSpec::new())
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        let captured = CAPTURE.lock().unwrap().take().unwrap();
+        let captured = String::from_utf8(captured).unwrap();
+
+        let mut count = 0;
+        for line in captured
+            .lines()
+            .filter(|l| l.contains("Stressing") || l.contains("fragments"))
+        {
+            count += 1;
+            assert_eq!(line.matches("thread").count(), 1, "{:?}", line);
+            assert_eq!(line.matches("line").count(), 1, "{:?}", line);
+            let (_, rest) = line.split_once("thread ").unwrap();
+            let words: Vec<_> = strip_ansi(rest).split(' ').map(str::to_owned).collect();
+            assert_eq!(words.len(), 3, "{:?}", line);
+            assert!(words[0].parse::<usize>().is_ok(), "{:?}", line);
+            assert_eq!(words[1], "line", "{:?}", line);
+            assert!(words[2].parse::<usize>().is_ok(), "{:?}", line);
+        }
+        assert_eq!(count, 8 * 100 * 2);
+    }
+
+    fn strip_ansi(s: &str) -> String {
+        let mut stripped = String::new();
+        let mut in_escape = false;
+        for c in s.chars() {
+            if in_escape {
+                in_escape = c != 'm';
+            } else if c == '\x1b' {
+                in_escape = true;
+            } else {
+                stripped.push(c);
+            }
+        }
+        stripped
+    }
 }
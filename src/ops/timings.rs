@@ -0,0 +1,127 @@
+//! Opt-in wall-clock timings for finding slow release phases
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::CargoResult;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECORDED: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum TimingsFormat {
+    /// Summary table on stderr
+    Text,
+    /// JSON on stdout
+    Json,
+}
+
+/// Start recording timings
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop recording timings, discarding any not yet [`take`]n
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+    RECORDED.lock().unwrap().clear();
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Time `phase` until the returned guard is dropped
+///
+/// This is a no-op unless timings were [`enable`]d.
+pub fn start(phase: impl std::fmt::Display) -> Timer {
+    let phase = is_enabled().then(|| (phase.to_string(), Instant::now()));
+    Timer { phase }
+}
+
+#[must_use = "the phase is timed until the guard is dropped"]
+pub struct Timer {
+    phase: Option<(String, Instant)>,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some((phase, start)) = self.phase.take() {
+            let elapsed = start.elapsed();
+            RECORDED.lock().unwrap().push((phase, elapsed));
+        }
+    }
+}
+
+/// Remove all recorded timings, slowest first
+pub fn take() -> Vec<(String, Duration)> {
+    let mut recorded = std::mem::take(&mut *RECORDED.lock().unwrap());
+    recorded.sort_by_key(|(_, elapsed)| std::cmp::Reverse(*elapsed));
+    recorded
+}
+
+pub fn report(format: TimingsFormat) -> CargoResult<()> {
+    let recorded = take();
+    match format {
+        TimingsFormat::Text => {
+            for (phase, elapsed) in recorded {
+                crate::ops::shell::status(
+                    "Timing",
+                    format!("{:>8.2}s  {}", elapsed.as_secs_f64(), phase),
+                )?;
+            }
+        }
+        TimingsFormat::Json => {
+            let recorded = recorded
+                .into_iter()
+                .map(|(phase, elapsed)| {
+                    serde_json::json!({
+                        "phase": phase,
+                        "seconds": elapsed.as_secs_f64(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let output = serde_json::to_string_pretty(&recorded)?;
+            let mut stdout = std::io::stdout().lock();
+            writeln!(stdout, "{}", output)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Enabled;
+
+    impl Enabled {
+        fn new() -> Self {
+            enable();
+            Self
+        }
+    }
+
+    impl Drop for Enabled {
+        fn drop(&mut self) {
+            disable();
+        }
+    }
+
+    #[test]
+    fn records_phases() {
+        let _enabled = Enabled::new();
+        {
+            let _timer = start("outer");
+            let _timer = start(format_args!("inner {}", 1));
+        }
+        let recorded = take();
+        let phases = recorded.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>();
+        assert!(phases.contains(&"outer"), "{:?}", phases);
+        assert!(phases.contains(&"inner 1"), "{:?}", phases);
+        assert!(recorded.windows(2).all(|w| w[1].1 <= w[0].1));
+    }
+}
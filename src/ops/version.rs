@@ -148,6 +148,10 @@ fn prerelease_id_version(version: &semver::Version) -> CargoResult<Option<(Strin
 }
 
 /// Upgrade an existing requirement to a new version
+///
+/// Each comparator is edited in-place so the original spacing and comma placement of
+/// multi-comparator requirements (like `>=0.4, <0.6`) are preserved.  Range bounds are only
+/// changed when they no longer match `version`.
 pub fn upgrade_requirement(req: &str, version: &semver::Version) -> CargoResult<Option<String>> {
     let req_text = req.to_string();
     let raw_req = semver::VersionReq::parse(&req_text)
@@ -156,20 +160,28 @@ pub fn upgrade_requirement(req: &str, version: &semver::Version) -> CargoResult<
         // Empty matches everything, no-change.
         Ok(None)
     } else {
-        let comparators: Result<Vec<_>, _> = raw_req
-            .comparators
-            .into_iter()
-            .map(|p| set_comparator(p, version))
-            .collect();
-        let comparators = comparators?;
-        let new_req = semver::VersionReq { comparators };
-        let mut new_req_text = new_req.to_string();
-        if new_req_text.starts_with('^') && !req.starts_with('^') {
-            new_req_text.remove(0);
+        let mut new_req_text = String::new();
+        for (i, segment) in req_text.split(',').enumerate() {
+            if i != 0 {
+                new_req_text.push(',');
+            }
+            let existing = segment.trim();
+            let pred = semver::Comparator::from_str(existing)?;
+            let new_pred = set_comparator(pred.clone(), version)?;
+            if new_pred == pred {
+                new_req_text.push_str(segment);
+            } else {
+                let mut new_pred_text = new_pred.to_string();
+                if new_pred_text.starts_with('^') && !existing.starts_with('^') {
+                    new_pred_text.remove(0);
+                }
+                new_req_text.push_str(&segment.replacen(existing, &new_pred_text, 1));
+            }
         }
         // Validate contract
         #[cfg(debug_assert)]
         {
+            let new_req = semver::VersionReq::parse(&new_req_text).unwrap();
             assert!(
                 new_req.matches(version),
                 "Invalid req created: {}",
@@ -200,9 +212,18 @@ fn set_comparator(
             Ok(pred)
         }
         semver::Op::Exact => Ok(assign_partial_req(version, pred)),
-        semver::Op::Greater | semver::Op::GreaterEq | semver::Op::Less | semver::Op::LessEq => Err(
-            anyhow::format_err!("support for modifying {} is currently unsupported", pred),
-        ),
+        semver::Op::Greater | semver::Op::Less | semver::Op::LessEq | semver::Op::GreaterEq
+            if pred.matches(version) =>
+        {
+            // Bounds are kept for as long as they still apply
+            Ok(pred)
+        }
+        semver::Op::GreaterEq | semver::Op::LessEq => Ok(assign_partial_req(version, pred)),
+        semver::Op::Less => Ok(assign_upper_bound(version, pred)),
+        semver::Op::Greater => Err(anyhow::format_err!(
+            "support for modifying {} is currently unsupported",
+            pred
+        )),
         semver::Op::Tilde => Ok(assign_partial_req(version, pred)),
         semver::Op::Caret => Ok(assign_partial_req(version, pred)),
         _ => {
@@ -215,6 +236,28 @@ fn set_comparator(
     }
 }
 
+/// Move an exclusive upper bound just past `version`, keeping the bound's precision
+fn assign_upper_bound(
+    version: &semver::Version,
+    mut pred: semver::Comparator,
+) -> semver::Comparator {
+    pred.major = version.major;
+    match (pred.minor, pred.patch) {
+        (None, _) => {
+            pred.major += 1;
+        }
+        (Some(_), None) => {
+            pred.minor = Some(version.minor + 1);
+        }
+        (Some(_), Some(_)) => {
+            pred.minor = Some(version.minor);
+            pred.patch = Some(version.patch + 1);
+        }
+    }
+    pred.pre = semver::Prerelease::EMPTY;
+    pred
+}
+
 fn assign_partial_req(
     version: &semver::Version,
     mut pred: semver::Comparator,
@@ -418,5 +461,39 @@ mod test {
             assert_req_bump("1.1.1", "=1.0.0", "=1.1.1");
             assert_req_bump("2.0.0", "=1.0.0", "=2.0.0");
         }
+
+        #[test]
+        fn range_matching() {
+            assert_req_bump("0.4.0", ">=0.4, <0.6", None);
+            assert_req_bump("0.5.3", ">=0.4, <0.6", None);
+            assert_req_bump("1.2.0", ">1.0.0, <=1.2", None);
+        }
+
+        #[test]
+        fn range_upper_bound() {
+            assert_req_bump("0.6.0", ">=0.4, <0.6", ">=0.4, <0.7");
+            assert_req_bump("0.9.1", ">=0.4, <0.6", ">=0.4, <0.10");
+            assert_req_bump("2.0.0", ">=1, <2", ">=1, <3");
+            assert_req_bump("1.2.3", ">=1.2.0, <1.2.3", ">=1.2.0, <1.2.4");
+            assert_req_bump("1.3.0", ">=1.0, <=1.2", ">=1.0, <=1.3");
+        }
+
+        #[test]
+        fn range_lower_bound() {
+            assert_req_bump("1.0.0", ">=1.1, <2", ">=1.0, <2");
+        }
+
+        #[test]
+        fn range_preserves_style() {
+            assert_req_bump("0.6.0", ">=0.4,<0.6", ">=0.4,<0.7");
+            assert_req_bump("0.6.0", ">=0.4 ,  <0.6", ">=0.4 ,  <0.7");
+            assert_req_bump("0.6.0", "0.4, <0.6", "0.6, <0.7");
+        }
+
+        #[test]
+        fn range_unsupported() {
+            let version = semver::Version::parse("1.0.0").unwrap();
+            assert!(upgrade_requirement(">1.0.0, <2", &version).is_err());
+        }
     }
 }
@@ -1,3 +1,5 @@
+use std::io::Write;
+
 use crate::error::CargoResult;
 use crate::error::CliError;
 use crate::ops::git;
@@ -12,21 +14,42 @@ pub struct ChangesStep {
     #[command(flatten)]
     manifest: clap_cargo::Manifest,
 
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
+
+    /// Check whether the branch is behind the push remote, which requires a `git fetch`
+    #[arg(long)]
+    check_remote: bool,
+
+    /// For packages without changes of their own, list the workspace dependencies being released
+    #[arg(long)]
+    include_unreleased_deps: bool,
+
+    /// How to report changes
+    #[arg(long, value_name = "FMT", value_enum, default_value_t)]
+    format: ChangesFormat,
+
+    #[command(flatten)]
+    dry_run: super::DryRunArgs,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum ChangesFormat {
+    /// Summary on stderr
+    #[default]
+    Text,
+    /// JSON on stdout
+    Json,
 }
 
 impl ChangesStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.dry_run.check();
         git::git_version()?;
 
         let ws_meta = self
@@ -40,14 +63,11 @@ impl ChangesStep {
         let pkgs = plan::load(&config, &ws_meta)?;
         let pkgs = plan::plan(pkgs)?;
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            log::info!("No packages selected.");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
         let dry_run = false;
         let mut failed = false;
@@ -55,6 +75,7 @@ impl ChangesStep {
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Warn,
         )?;
@@ -62,26 +83,38 @@ impl ChangesStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Warn,
         )?;
 
-        failed |= !super::verify_if_behind(
+        failed |= !super::verify_if_behind_when_asked(
+            self.check_remote,
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
             dry_run,
-            log::Level::Warn,
         )?;
 
-        changes(&ws_meta, &selected_pkgs)?;
+        match self.format {
+            ChangesFormat::Text => {
+                changes(&ws_meta, &selected_pkgs, self.include_unreleased_deps)?;
+            }
+            ChangesFormat::Json => {
+                let entries = changes_entries(&ws_meta, &selected_pkgs, &excluded_pkgs)?;
+                let output = serde_json::to_string_pretty(&entries)?;
+                let mut stdout = std::io::stdout().lock();
+                writeln!(stdout, "{}", output)?;
+            }
+        }
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
             ..Default::default()
         }
@@ -91,78 +124,17 @@ impl ChangesStep {
 pub fn changes(
     ws_meta: &cargo_metadata::Metadata,
     selected_pkgs: &[plan::PackageRelease],
+    include_unreleased_deps: bool,
 ) -> CargoResult<()> {
-    for pkg in selected_pkgs {
+    let workspace_root = ws_meta.workspace_root.as_std_path();
+    let pkg_commits = all_package_commits(workspace_root, selected_pkgs)?;
+
+    for (pkg, commits) in selected_pkgs.iter().zip(&pkg_commits) {
         let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
         let crate_name = pkg.meta.name.as_str();
-        if let Some(prior_tag_name) = &pkg.prior_tag {
-            let workspace_root = ws_meta.workspace_root.as_std_path();
-            let repo = git2::Repository::discover(workspace_root)?;
-
-            let mut tag_id = None;
-            let fq_prior_tag_name = format!("refs/tags/{}", prior_tag_name);
-            repo.tag_foreach(|id, name| {
-                if name == fq_prior_tag_name.as_bytes() {
-                    tag_id = Some(id);
-                    false
-                } else {
-                    true
-                }
-            })?;
-            let tag_id = tag_id
-                .ok_or_else(|| anyhow::format_err!("could not find tag {}", prior_tag_name))?;
-
-            let head_id = repo.head()?.peel_to_commit()?.id();
-
-            let mut revwalk = repo.revwalk()?;
-            revwalk.push_range(&format!("{tag_id}..{head_id}"))?;
-
-            let mut commits = Vec::new();
-            for commit_id in revwalk {
-                let commit_id = commit_id?;
-                let commit = repo.find_commit(commit_id)?;
-                if 1 < commit.parent_count() {
-                    // Assuming merge commits can be ignored
-                    continue;
-                }
-                let parent_tree = commit.parent(0).ok().map(|c| c.tree()).transpose()?;
-                let tree = commit.tree()?;
-                let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
-
-                let mut changed_paths = std::collections::BTreeSet::new();
-                for delta in diff.deltas() {
-                    let old_path = delta.old_file().path();
-                    let new_path = delta.new_file().path();
-                    for entry_relpath in [old_path, new_path].into_iter().flatten() {
-                        for path in pkg
-                            .package_content
-                            .iter()
-                            .filter_map(|p| p.strip_prefix(workspace_root).ok())
-                        {
-                            if path == entry_relpath {
-                                changed_paths.insert(path.to_owned());
-                            }
-                        }
-                    }
-                }
-
-                if !changed_paths.is_empty() {
-                    let short_id =
-                        String::from_utf8_lossy(&repo.find_object(commit_id, None)?.short_id()?)
-                            .into_owned();
-                    commits.push(PackageCommit {
-                        id: commit_id,
-                        short_id,
-                        summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or(b""))
-                            .into_owned(),
-                        message: String::from_utf8_lossy(commit.message_bytes()).into_owned(),
-                        paths: changed_paths,
-                    });
-                }
-            }
-
+        if let (Some(prior_tag_name), Some(commits)) = (&pkg.prior_tag, commits) {
             if !commits.is_empty() {
-                crate::ops::shell::status(
+                crate::ops::reporter::status(
                     "Changes",
                     format!(
                         "for {} from {} to {}",
@@ -171,19 +143,20 @@ pub fn changes(
                 )?;
                 let prefix = format!("{:>13}", " ");
                 let mut max_status = None;
-                for commit in &commits {
-                    #[allow(clippy::needless_borrow)] // False positive
-                    let _ = crate::ops::shell::write_stderr(&prefix, &ColorSpec::new());
-                    let _ = crate::ops::shell::write_stderr(
-                        &commit.short_id,
-                        ColorSpec::new().set_fg(Some(Color::Yellow)),
-                    );
-                    let _ = crate::ops::shell::write_stderr(" ", &ColorSpec::new());
-                    let _ = crate::ops::shell::write_stderr(&commit.summary, &ColorSpec::new());
-
-                    let current_status = commit.status();
-                    write_status(current_status);
-                    let _ = crate::ops::shell::write_stderr("\n", &ColorSpec::new());
+                for commit in commits {
+                    let current_status = commit.status(pkg.config.commit_types());
+                    {
+                        let mut stderr = crate::ops::shell::lock_stderr();
+                        let _ = stderr.write(&prefix, &ColorSpec::new());
+                        let _ = stderr.write(
+                            &commit.short_id,
+                            ColorSpec::new().set_fg(Some(Color::Yellow)),
+                        );
+                        let _ = stderr.write(" ", &ColorSpec::new());
+                        let _ = stderr.write(&commit.summary, &ColorSpec::new());
+                        write_status(&mut stderr, current_status);
+                        let _ = stderr.write("\n", &ColorSpec::new());
+                    }
                     match (current_status, max_status) {
                         (Some(cur), Some(max)) => {
                             max_status = Some(cur.max(max));
@@ -239,11 +212,23 @@ pub fn changes(
                         CommitStatus::Ignore => None,
                     };
                     if let Some(suggested) = suggested {
-                        let _ = crate::ops::shell::note(format!("to update the version, run `cargo release version -p {crate_name} {suggested}`"));
+                        let _ = crate::ops::reporter::note(format!("to update the version, run `cargo release version -p {crate_name} {suggested}`"));
                     } else if unbumped {
-                        let _ = crate::ops::shell::note(format!("to update the version, run `cargo release version -p {crate_name} <LEVEL|VERSION>`"));
+                        let _ = crate::ops::reporter::note(format!("to update the version, run `cargo release version -p {crate_name} <LEVEL|VERSION>`"));
                     }
                 }
+            } else if include_unreleased_deps {
+                let released = released_dependencies(pkg, selected_pkgs, &pkg_commits);
+                if !released.is_empty() {
+                    crate::ops::reporter::status(
+                        "Changes",
+                        format!(
+                            "for {} from {} to {}",
+                            crate_name, prior_tag_name, version.full_version_string
+                        ),
+                    )?;
+                    write_released_dependencies(&released);
+                }
             }
         } else {
             log::debug!(
@@ -256,7 +241,285 @@ pub fn changes(
     Ok(())
 }
 
-fn write_status(status: Option<CommitStatus>) {
+/// Each package's commits since its prior tag, if it has one
+fn all_package_commits(
+    workspace_root: &std::path::Path,
+    pkgs: &[plan::PackageRelease],
+) -> CargoResult<Vec<Option<Vec<PackageCommit>>>> {
+    let repo = git2::Repository::discover(workspace_root)?;
+    pkgs.iter()
+        .map(|pkg| match pkg.prior_tag.as_deref() {
+            Some(prior_tag_name) => {
+                package_commits(&repo, workspace_root, pkg, prior_tag_name, None).map(Some)
+            }
+            None => Ok(None),
+        })
+        .collect()
+}
+
+/// Each package's changes, for `--format json`
+///
+/// Skipped packages follow, with their `skip_reason` and no commits.
+pub fn changes_entries(
+    ws_meta: &cargo_metadata::Metadata,
+    selected_pkgs: &[plan::PackageRelease],
+    excluded_pkgs: &[plan::PackageRelease],
+) -> CargoResult<Vec<ChangesEntry>> {
+    let workspace_root = ws_meta.workspace_root.as_std_path();
+    let pkg_commits = all_package_commits(workspace_root, selected_pkgs)?;
+    let entries = selected_pkgs
+        .iter()
+        .zip(pkg_commits)
+        .map(|(pkg, commits)| {
+            let commits = commits
+                .unwrap_or_default()
+                .into_iter()
+                .map(|commit| CommitEntry {
+                    id: commit.id.to_string(),
+                    status: commit.status(pkg.config.commit_types()),
+                    short_id: commit.short_id,
+                    summary: commit.summary,
+                    paths: commit.paths,
+                })
+                .collect();
+            ChangesEntry {
+                plan: pkg.plan_entry(),
+                commits,
+            }
+        })
+        .chain(excluded_pkgs.iter().map(|pkg| ChangesEntry {
+            plan: pkg.plan_entry(),
+            commits: Vec::new(),
+        }))
+        .collect();
+    Ok(entries)
+}
+
+/// The summary of a package's changes since its prior tag, for `tag-message-source = "changes"`
+///
+/// `None` when there is no prior tag to diff against.
+pub fn changes_summary(pkg: &plan::PackageRelease) -> CargoResult<Option<String>> {
+    let commits = match released_commits(pkg)? {
+        Some(commits) => commits,
+        None => return Ok(None),
+    };
+
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    Ok(Some(render_summary(
+        &pkg.meta.name,
+        &version.full_version_string,
+        &commits,
+        pkg.config.commit_types(),
+    )))
+}
+
+/// A package's commits since its prior tag, up to the commit being tagged
+///
+/// `None` when there is no prior tag to diff against.  Merge commits are left out.
+pub fn released_commits(pkg: &plan::PackageRelease) -> CargoResult<Option<Vec<PackageCommit>>> {
+    let prior_tag_name = match pkg.prior_tag.as_deref() {
+        Some(prior_tag_name) => prior_tag_name,
+        None => return Ok(None),
+    };
+    let repo = git2::Repository::discover(&pkg.package_root)?;
+    let root = repo
+        .workdir()
+        .ok_or_else(|| anyhow::format_err!("cannot list changes in a bare repository"))?
+        .to_owned();
+    package_commits(&repo, &root, pkg, prior_tag_name, pkg.tag_target.as_deref()).map(Some)
+}
+
+/// Breaking changes, features, and fixes as bulleted sections, for `{{changes}}`
+///
+/// Empty when none of the commits are conventional ones of those kinds.
+pub fn render_conventional(
+    commits: &[PackageCommit],
+    types: &crate::config::CommitTypes,
+) -> String {
+    render_sections(
+        commits,
+        types,
+        &[
+            ("Breaking Changes", Some(CommitStatus::Breaking)),
+            ("Features", Some(CommitStatus::Feature)),
+            ("Fixes", Some(CommitStatus::Fix)),
+        ],
+    )
+    .trim()
+    .to_owned()
+}
+
+/// A heading with the version, then the commits grouped by status
+fn render_summary(
+    crate_name: &str,
+    version: &str,
+    commits: &[PackageCommit],
+    types: &crate::config::CommitTypes,
+) -> String {
+    let mut summary = format!("{} {}\n", crate_name, version);
+    summary.push_str(&render_sections(
+        commits,
+        types,
+        &[
+            ("Breaking Changes", Some(CommitStatus::Breaking)),
+            ("Features", Some(CommitStatus::Feature)),
+            ("Fixes", Some(CommitStatus::Fix)),
+            ("Other", None),
+        ],
+    ));
+    summary
+}
+
+/// Each non-empty section, preceded by a blank line
+///
+/// Headings aren't prefixed with `#` as `git tag` strips those lines as comments.
+fn render_sections(
+    commits: &[PackageCommit],
+    types: &crate::config::CommitTypes,
+    sections: &[(&str, Option<CommitStatus>)],
+) -> String {
+    let mut rendered = String::new();
+    for (heading, status) in sections {
+        let mut bullets = commits
+            .iter()
+            .filter(|c| c.status(types) == *status)
+            .peekable();
+        if bullets.peek().is_none() {
+            continue;
+        }
+        rendered.push_str(&format!("\n{}:\n", heading));
+        for commit in bullets {
+            rendered.push_str(&format!(
+                "- {} ({})\n",
+                commit.description(),
+                commit.short_id
+            ));
+        }
+    }
+    rendered
+}
+
+/// A package's commits since `prior_tag_name` up to `head`, defaulting to `HEAD`, newest first
+fn package_commits(
+    repo: &git2::Repository,
+    workspace_root: &std::path::Path,
+    pkg: &plan::PackageRelease,
+    prior_tag_name: &str,
+    head: Option<&str>,
+) -> CargoResult<Vec<PackageCommit>> {
+    let mut tag_id = None;
+    let fq_prior_tag_name = format!("refs/tags/{}", prior_tag_name);
+    repo.tag_foreach(|id, name| {
+        if name == fq_prior_tag_name.as_bytes() {
+            tag_id = Some(id);
+            false
+        } else {
+            true
+        }
+    })?;
+    let tag_id =
+        tag_id.ok_or_else(|| anyhow::format_err!("could not find tag {}", prior_tag_name))?;
+
+    let head_id = match head {
+        Some(head) => repo.revparse_single(head)?.peel_to_commit()?.id(),
+        None => repo.head()?.peel_to_commit()?.id(),
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_range(&format!("{tag_id}..{head_id}"))?;
+
+    let mut commits = Vec::new();
+    for commit_id in revwalk {
+        let commit_id = commit_id?;
+        let commit = repo.find_commit(commit_id)?;
+        if 1 < commit.parent_count() {
+            // Assuming merge commits can be ignored
+            continue;
+        }
+        let parent_tree = commit.parent(0).ok().map(|c| c.tree()).transpose()?;
+        let tree = commit.tree()?;
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+        let mut changed_paths = std::collections::BTreeSet::new();
+        for delta in diff.deltas() {
+            let old_path = delta.old_file().path();
+            let new_path = delta.new_file().path();
+            for entry_relpath in [old_path, new_path].into_iter().flatten() {
+                if pkg.owns_path(&workspace_root.join(entry_relpath)) {
+                    changed_paths.insert(entry_relpath.to_owned());
+                }
+            }
+        }
+
+        if !changed_paths.is_empty() {
+            let short_id = String::from_utf8_lossy(&repo.find_object(commit_id, None)?.short_id()?)
+                .into_owned();
+            commits.push(PackageCommit {
+                id: commit_id,
+                short_id,
+                summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or(b""))
+                    .into_owned(),
+                message: String::from_utf8_lossy(commit.message_bytes()).into_owned(),
+                paths: changed_paths,
+            });
+        }
+    }
+
+    Ok(commits)
+}
+
+/// Workspace dependencies of `pkg` being released, with their commits
+fn released_dependencies<'p>(
+    pkg: &plan::PackageRelease,
+    selected_pkgs: &'p [plan::PackageRelease],
+    pkg_commits: &'p [Option<Vec<PackageCommit>>],
+) -> Vec<(&'p plan::PackageRelease, &'p [PackageCommit])> {
+    selected_pkgs
+        .iter()
+        .zip(pkg_commits)
+        .filter(|(dep, _)| dep.dependents.iter().any(|d| d.pkg.id == pkg.meta.id))
+        .filter_map(|(dep, commits)| {
+            let commits = commits.as_deref().unwrap_or_default();
+            (!commits.is_empty() || dep.planned_version.is_some()).then_some((dep, commits))
+        })
+        .collect()
+}
+
+fn write_released_dependencies(released: &[(&plan::PackageRelease, &[PackageCommit])]) {
+    // Enough to hint at what changed without repeating each dependency's own listing
+    const MAX_COMMITS: usize = 3;
+
+    let prefix = format!("{:>13}", " ");
+    let mut stderr = crate::ops::shell::lock_stderr();
+    let _ = stderr.write(&prefix, &ColorSpec::new());
+    let _ = stderr.write("released due to dependency updates:\n", &ColorSpec::new());
+    for (dep, commits) in released {
+        let from = dep
+            .prior_version
+            .as_ref()
+            .map(|v| v.full_version_string.as_str())
+            .or(dep.prior_tag.as_deref())
+            .unwrap_or("unreleased");
+        let to = dep.planned_version.as_ref().unwrap_or(&dep.initial_version);
+        let _ = stderr.write(
+            &format!(
+                "{}  {} {} to {}\n",
+                prefix, dep.meta.name, from, to.full_version_string
+            ),
+            &ColorSpec::new(),
+        );
+        for commit in commits.iter().take(MAX_COMMITS) {
+            let _ = stderr.write(&format!("{}    ", prefix), &ColorSpec::new());
+            let _ = stderr.write(
+                &commit.short_id,
+                ColorSpec::new().set_fg(Some(Color::Yellow)),
+            );
+            let _ = stderr.write(&format!(" {}\n", commit.summary), &ColorSpec::new());
+        }
+    }
+}
+
+fn write_status(stderr: &mut crate::ops::shell::StderrLock, status: Option<CommitStatus>) {
     if let Some(status) = status {
         let suffix;
         let mut color = ColorSpec::new();
@@ -277,7 +540,7 @@ fn write_status(status: Option<CommitStatus>) {
                 suffix = "";
             }
         }
-        let _ = crate::ops::shell::write_stderr(suffix, &color);
+        let _ = stderr.write(suffix, &color);
     }
 }
 
@@ -291,50 +554,290 @@ pub struct PackageCommit {
 }
 
 impl PackageCommit {
-    pub fn status(&self) -> Option<CommitStatus> {
-        if let Some(status) = self.conventional_status() {
+    pub fn status(&self, types: &crate::config::CommitTypes) -> Option<CommitStatus> {
+        if let Some(status) = self.conventional_status(types) {
             return status;
         }
 
         None
     }
 
-    fn conventional_status(&self) -> Option<Option<CommitStatus>> {
+    /// The summary without any conventional commit prefix, like `feat: `
+    pub fn description(&self) -> &str {
+        match git_conventional::Commit::parse(&self.message) {
+            Ok(parts) => parts.description(),
+            Err(_) => self.summary.as_str(),
+        }
+    }
+
+    fn conventional_status(
+        &self,
+        types: &crate::config::CommitTypes,
+    ) -> Option<Option<CommitStatus>> {
         let parts = git_conventional::Commit::parse(&self.message).ok()?;
         if parts.breaking() {
             return Some(Some(CommitStatus::Breaking));
         }
 
-        if [
-            git_conventional::Type::CHORE,
-            git_conventional::Type::TEST,
-            git_conventional::Type::STYLE,
-            git_conventional::Type::REFACTOR,
-            git_conventional::Type::REVERT,
-        ]
-        .contains(&parts.type_())
-        {
-            Some(Some(CommitStatus::Ignore))
-        } else if [
-            git_conventional::Type::DOCS,
-            git_conventional::Type::PERF,
-            git_conventional::Type::FIX,
-        ]
-        .contains(&parts.type_())
-        {
-            Some(Some(CommitStatus::Fix))
-        } else if [git_conventional::Type::FEAT].contains(&parts.type_()) {
-            Some(Some(CommitStatus::Feature))
-        } else {
-            Some(None)
-        }
+        let status = types.kind(parts.type_().as_str()).map(|kind| match kind {
+            crate::config::CommitKind::Ignore => CommitStatus::Ignore,
+            crate::config::CommitKind::Fix => CommitStatus::Fix,
+            crate::config::CommitKind::Feature => CommitStatus::Feature,
+        });
+        Some(status)
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// A package's changes, for scripts consuming `cargo release changes --format json`
+///
+/// Field names are a stable contract.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChangesEntry {
+    #[serde(flatten)]
+    pub plan: plan::PlanEntry,
+    /// Newest first
+    pub commits: Vec<CommitEntry>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CommitEntry {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    /// `null` for commits that aren't conventional or have an unknown type
+    pub status: Option<CommitStatus>,
+    /// Relative to the workspace root
+    pub paths: std::collections::BTreeSet<std::path::PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum CommitStatus {
     Ignore,
     Fix,
     Feature,
     Breaking,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::git::fixture::{self, git};
+
+    #[test]
+    fn released_due_to_dependency() {
+        use assert_fs::prelude::*;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        fixture::init(temp.path());
+        git(temp.path(), &["add", "."]);
+        git(temp.path(), &["commit", "--quiet", "-m", "initial"]);
+        for tag in ["a-v0.1.0", "b-v0.1.0", "c-v0.1.0"] {
+            git(temp.path(), &["tag", tag]);
+        }
+        temp.child("a/src/lib.rs")
+            .write_str("pub fn repaired() {}\n")
+            .unwrap();
+        git(temp.path(), &["commit", "--quiet", "-am", "fix: Repair a"]);
+
+        let (ws_meta, pkgs) = plan::fixture::load(temp.path(), &Default::default());
+        let pkgs: Vec<_> = pkgs.into_values().collect();
+        let workspace_root = ws_meta.workspace_root.as_std_path();
+        let repo = git2::Repository::discover(workspace_root).unwrap();
+        let pkg_commits: Vec<_> = pkgs
+            .iter()
+            .map(|pkg| {
+                let prior_tag_name = pkg.prior_tag.as_deref().unwrap();
+                Some(package_commits(&repo, workspace_root, pkg, prior_tag_name, None).unwrap())
+            })
+            .collect();
+        let find = |name: &str| pkgs.iter().find(|p| p.meta.name == name).unwrap();
+
+        let released = released_dependencies(find("b"), &pkgs, &pkg_commits);
+        assert_eq!(released.len(), 1);
+        let (dep, commits) = released[0];
+        assert_eq!(dep.meta.name, "a");
+        assert_eq!(dep.prior_tag.as_deref(), Some("a-v0.1.0"));
+        let summaries: Vec<_> = commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["fix: Repair a"]);
+        // Only released dependencies count
+        assert!(released_dependencies(find("c"), &pkgs, &pkg_commits).is_empty());
+        assert!(released_dependencies(find("a"), &pkgs, &pkg_commits).is_empty());
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn json_entries() {
+        use assert_fs::prelude::*;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        fixture::init(temp.path());
+        git(temp.path(), &["add", "."]);
+        git(temp.path(), &["commit", "--quiet", "-m", "initial"]);
+        for tag in ["a-v0.1.0", "b-v0.1.0", "c-v0.1.0"] {
+            git(temp.path(), &["tag", tag]);
+        }
+        temp.child("a/src/lib.rs")
+            .write_str("pub fn repaired() {}\n")
+            .unwrap();
+        git(temp.path(), &["commit", "--quiet", "-am", "fix: Repair a"]);
+
+        let (ws_meta, pkgs) = plan::fixture::load(temp.path(), &Default::default());
+        let (pkgs, mut excluded): (Vec<_>, Vec<_>) = pkgs
+            .into_values()
+            .partition(|pkg| pkg.meta.name == "a" || pkg.meta.name == "c");
+        excluded[0].skip(plan::SkipReason::Excluded);
+        let repo = git2::Repository::discover(temp.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id();
+        let short_id = repo.find_object(head, None).unwrap().short_id().unwrap();
+
+        let entries = changes_entries(&ws_meta, &pkgs, &excluded).unwrap();
+        let actual = serde_json::to_value(entries).unwrap();
+        assert_eq!(
+            actual,
+            serde_json::json!([
+                {
+                    "name": "a",
+                    "version": "0.1.0",
+                    "planned_version": null,
+                    "planned_tag": null,
+                    "prior_tag": "a-v0.1.0",
+                    "changed_file_count": null,
+                    "change_detection": "initial-tag",
+                    "skip_reason": null,
+                    "publish": true,
+                    "tag": false,
+                    "push": true,
+                    "commits": [
+                        {
+                            "id": head.to_string(),
+                            "short_id": short_id.as_str().unwrap(),
+                            "summary": "fix: Repair a",
+                            "status": "fix",
+                            "paths": ["a/src/lib.rs"],
+                        }
+                    ],
+                },
+                {
+                    "name": "c",
+                    "version": "0.1.0",
+                    "planned_version": null,
+                    "planned_tag": null,
+                    "prior_tag": "c-v0.1.0",
+                    "changed_file_count": null,
+                    "change_detection": "initial-tag",
+                    "skip_reason": null,
+                    "publish": true,
+                    "tag": false,
+                    "push": true,
+                    "commits": [],
+                },
+                {
+                    "name": "b",
+                    "version": "0.1.0",
+                    "planned_version": null,
+                    "planned_tag": null,
+                    "prior_tag": "b-v0.1.0",
+                    "changed_file_count": null,
+                    "change_detection": "initial-tag",
+                    "skip_reason": "excluded",
+                    "publish": false,
+                    "tag": false,
+                    "push": false,
+                    "commits": [],
+                },
+            ])
+        );
+
+        temp.close().unwrap();
+    }
+
+    /// Statuses of every commit in a history using assorted types, oldest first
+    fn statuses(types: &crate::config::CommitTypes) -> Vec<Option<CommitStatus>> {
+        let temp = assert_fs::TempDir::new().unwrap();
+        fixture::init(temp.path());
+        for message in [
+            "feat: Add widgets",
+            "perf: Speed up widgets",
+            "deps: Update serde",
+            "ci: Test on nightly",
+            "docs: Explain widgets",
+            "fix!: Remove broken widgets",
+            "Not conventional",
+        ] {
+            git(
+                temp.path(),
+                &["commit", "--quiet", "--allow-empty", "-m", message],
+            );
+        }
+
+        let repo = git2::Repository::discover(temp.path()).unwrap();
+        let mut revwalk = repo.revwalk().unwrap();
+        revwalk.push_head().unwrap();
+        revwalk.set_sorting(git2::Sort::REVERSE).unwrap();
+        let statuses = revwalk
+            .map(|id| {
+                let commit = repo.find_commit(id.unwrap()).unwrap();
+                let commit = PackageCommit {
+                    id: commit.id(),
+                    short_id: String::new(),
+                    summary: commit.summary().unwrap().to_owned(),
+                    message: commit.message().unwrap().to_owned(),
+                    paths: Default::default(),
+                };
+                commit.status(types)
+            })
+            .collect();
+
+        temp.close().unwrap();
+        statuses
+    }
+
+    #[test]
+    fn default_types() {
+        let config = crate::config::Config::from_defaults();
+        let actual = statuses(config.commit_types());
+        assert_eq!(
+            actual,
+            [
+                Some(CommitStatus::Feature),
+                Some(CommitStatus::Fix),
+                None,
+                None,
+                Some(CommitStatus::Fix),
+                Some(CommitStatus::Breaking),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_types() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[commit-types]
+feature = ["feat", "perf"]
+fix = ["fix", "docs"]
+ignore = ["chore", "ci", "deps"]
+"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let actual = statuses(config.commit_types());
+        assert_eq!(
+            actual,
+            [
+                Some(CommitStatus::Feature),
+                Some(CommitStatus::Feature),
+                Some(CommitStatus::Ignore),
+                Some(CommitStatus::Ignore),
+                Some(CommitStatus::Fix),
+                Some(CommitStatus::Breaking),
+                None,
+            ]
+        );
+    }
+}
@@ -1,7 +1,10 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
 use crate::config;
 use crate::error::CliError;
 use crate::ops::git;
-use crate::ops::replace::{Template, NOW};
+use crate::ops::replace::{DependencyLookup, Template, NOW};
 use crate::steps::plan;
 
 /// Commit the specified packages
@@ -12,24 +15,15 @@ pub struct CommitStep {
     #[command(flatten)]
     manifest: clap_cargo::Manifest,
 
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
-
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
@@ -41,13 +35,9 @@ pub struct CommitStep {
 
 impl CommitStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
 
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
-
         let ws_meta = self
             .manifest
             .metadata()
@@ -64,18 +54,20 @@ impl CommitStep {
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if crate::ops::git::is_dirty(ws_meta.workspace_root.as_std_path())?.is_none() {
-            let _ = crate::ops::shell::error("nothing to commit");
+        let changed = release_paths(&ws_meta, &ws_config, &selected_pkgs)?;
+        if changed.is_empty() {
+            let _ = crate::ops::reporter::error("nothing to commit");
             return Err(2.into());
         }
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Warn,
         )?;
@@ -87,31 +79,39 @@ impl CommitStep {
             log::Level::Warn,
         )?;
 
+        failed |= !verify_changed_paths(
+            &ws_meta,
+            &selected_pkgs,
+            &changed,
+            dry_run,
+            log::Level::Warn,
+        )?;
+
         // STEP 1: Release Confirmation
         super::confirm("Commit", &selected_pkgs, self.no_confirm, dry_run)?;
 
         if ws_config.is_workspace {
             let consolidate_commits = super::consolidate_commits(&selected_pkgs, &excluded_pkgs)?;
-            if !consolidate_commits {
-                let _ = crate::shell::warn(
-                    "ignoring `consolidate-commits=false`; `cargo release commit` can effectively only do one commit",
-                );
+            if consolidate_commits {
+                workspace_commit(&ws_meta, &ws_config, &selected_pkgs, &changed, dry_run)?;
+            } else {
+                per_package_commits(&selected_pkgs, &changed, dry_run)?;
             }
-            super::commit::workspace_commit(&ws_meta, &ws_config, &selected_pkgs, dry_run)?;
         } else if !selected_pkgs.is_empty() {
             let selected_pkg = selected_pkgs
                 .first()
                 .expect("non-workspace can have at most 1 package");
-            super::commit::pkg_commit(selected_pkg, dry_run)?;
+            super::commit::pkg_commit(selected_pkg, &changed, dry_run)?;
         }
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
             commit: self.commit.clone(),
             ..Default::default()
@@ -119,14 +119,118 @@ impl CommitStep {
     }
 }
 
-pub fn pkg_commit(pkg: &plan::PackageRelease, dry_run: bool) -> Result<(), CliError> {
+/// The changed files a release commit takes
+///
+/// Changes matching `allow-dirty`, or outside `clean-check-path` and not belonging to `pkgs`, were
+/// let through by the clean-tree check and so are left uncommitted.
+pub fn release_paths(
+    ws_meta: &cargo_metadata::Metadata,
+    ws_config: &config::Config,
+    pkgs: &[plan::PackageRelease],
+) -> Result<Vec<PathBuf>, CliError> {
+    let ws_root = dunce::canonicalize(ws_meta.workspace_root.as_std_path())?;
+    let scope = ws_config
+        .clean_check_path()
+        .map(|subtree| ws_root.join(subtree));
+    let shared = [ws_root.join("Cargo.toml"), ws_root.join("Cargo.lock")];
+    let allowed = super::allowed_dirty(ws_config)?;
+    let paths = git::changed_paths(&ws_root)?
+        .into_iter()
+        .filter(|path| {
+            !path
+                .strip_prefix(&ws_root)
+                .map(|relative| allowed.is_match(relative))
+                .unwrap_or(false)
+        })
+        .filter(|path| {
+            scope
+                .as_ref()
+                .map(|scope| path.starts_with(scope))
+                .unwrap_or(true)
+                || shared.contains(path)
+                || pkgs.iter().any(|pkg| pkg.owns_path(path))
+        })
+        .collect();
+    Ok(paths)
+}
+
+pub fn pkg_commit(
+    pkg: &plan::PackageRelease,
+    paths: &[PathBuf],
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("commit");
     let cwd = &pkg.package_root;
+    let commit_msg = pkg_commit_message(pkg);
+    let sign = pkg.config.sign_commit();
+    let no_verify = pkg.config.no_verify_git_hooks();
+    if !git::commit_paths(cwd, paths, &commit_msg, sign, no_verify, dry_run)? {
+        // commit failed, abort release
+        return Err(101.into());
+    }
+
+    Ok(())
+}
+
+/// Commit each package's own changes separately, with anything shared going into the last commit
+fn per_package_commits(
+    pkgs: &[plan::PackageRelease],
+    changed: &[PathBuf],
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let changed_pkgs: Vec<_> = pkgs
+        .iter()
+        .filter(|pkg| changed.iter().any(|path| pkg.owns_path(path)))
+        .collect();
+    let (last, rest) = match changed_pkgs.split_last() {
+        Some(split) => split,
+        None => {
+            // Only shared files changed, so there is no telling which package they are for
+            let _ = crate::ops::reporter::error(
+                "no package has changes of its own to commit; set `consolidate-commits = true`",
+            );
+            return Err(101.into());
+        }
+    };
+
+    let mut committed = HashSet::new();
+    for pkg in rest {
+        let _ = crate::ops::reporter::phase_start("commit");
+        let paths: Vec<_> = changed
+            .iter()
+            .filter(|path| pkg.owns_path(path) && committed.insert(*path))
+            .cloned()
+            .collect();
+        if !git::commit_paths(
+            &pkg.package_root,
+            &paths,
+            &pkg_commit_message(pkg),
+            pkg.config.sign_commit(),
+            pkg.config.no_verify_git_hooks(),
+            dry_run,
+        )? {
+            // commit failed, abort release
+            return Err(101.into());
+        }
+    }
+    let remaining: Vec<_> = changed
+        .iter()
+        .filter(|path| !committed.contains(path))
+        .cloned()
+        .collect();
+    pkg_commit(last, &remaining, dry_run)
+}
+
+fn pkg_commit_message(pkg: &plan::PackageRelease) -> String {
     let crate_name = pkg.meta.name.as_str();
     let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
     let prev_version_var = pkg.initial_version.bare_version_string.as_str();
     let prev_metadata_var = pkg.initial_version.full_version.build.as_str();
     let version_var = version.bare_version_string.as_str();
     let metadata_var = version.full_version.build.as_str();
+    let dependency_updates_var =
+        crate::ops::cargo::render_dependency_updates(&pkg.dependency_updates);
+    let dependency_var = |name: &str, field: &str| pkg.dependency_var(name, field);
     let template = Template {
         prev_version: Some(prev_version_var),
         prev_metadata: Some(prev_metadata_var),
@@ -134,24 +238,99 @@ pub fn pkg_commit(pkg: &plan::PackageRelease, dry_run: bool) -> Result<(), CliEr
         metadata: Some(metadata_var),
         crate_name: Some(crate_name),
         date: Some(NOW.as_str()),
+        dependency_updates: Some(dependency_updates_var.as_str()),
+        prev_tag_name: Some(pkg.prev_tag_name()),
+        vars: pkg.config.template_vars(),
+        dependencies: Some(DependencyLookup(&dependency_var)),
         ..Default::default()
     };
-    let commit_msg = template.render(pkg.config.pre_release_commit_message());
-    let sign = pkg.config.sign_commit();
-    if !git::commit_all(cwd, &commit_msg, sign, dry_run)? {
-        // commit failed, abort release
-        return Err(101.into());
+    template.render(pkg.config.pre_release_commit_message())
+}
+
+/// Check the changes about to be committed are the selected packages' or the workspace's own
+fn verify_changed_paths(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+    changed: &[PathBuf],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, CliError> {
+    let mut success = true;
+
+    let ws_root = ws_meta.workspace_root.as_std_path();
+    let shared = [ws_root.join("Cargo.toml"), ws_root.join("Cargo.lock")];
+    // Like a workspace-root changelog, replacements can edit files outside of their package
+    let replaced: Vec<_> = pkgs
+        .iter()
+        .flat_map(|pkg| {
+            pkg.config
+                .pre_release_replacements()
+                .iter()
+                .filter_map(|replace| {
+                    dunce::canonicalize(pkg.package_root.join(&replace.file)).ok()
+                })
+        })
+        .collect();
+    let unexpected: Vec<_> = changed
+        .iter()
+        .filter(|path| {
+            !shared.contains(path)
+                && !replaced.contains(path)
+                && !pkgs.iter().any(|pkg| pkg.owns_path(path))
+        })
+        .map(|path| {
+            path.strip_prefix(ws_root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect();
+    if !unexpected.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "changes outside of the released packages will be committed:\n  {}",
+                unexpected.join("\n  ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
     }
 
-    Ok(())
+    Ok(success)
+}
+
+/// The start every release commit made from `message` has in common, for recognizing them
+///
+/// Version-specific placeholders differ between releases, so only the first line up to the first
+/// unrendered placeholder is kept.  `None` when that leaves nothing to match on.
+pub fn release_message_prefix(message: &str, crate_name: Option<&str>) -> Option<String> {
+    let template = Template {
+        crate_name,
+        ..Default::default()
+    };
+    let rendered = template.render(message);
+    let first_line = rendered.lines().next().unwrap_or_default();
+    let prefix = first_line.split("{{").next().unwrap_or_default().trim_end();
+    if prefix.is_empty() {
+        None
+    } else {
+        Some(prefix.to_owned())
+    }
 }
 
 pub fn workspace_commit(
     ws_meta: &cargo_metadata::Metadata,
     ws_config: &config::Config,
     pkgs: &[plan::PackageRelease],
+    paths: &[PathBuf],
     dry_run: bool,
 ) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("commit");
     let shared_version = super::find_shared_versions(pkgs)?;
 
     let shared_commit_msg = {
@@ -161,18 +340,25 @@ pub fn workspace_commit(
         let metadata_var = shared_version
             .as_ref()
             .map(|v| v.full_version.build.as_str());
+        let dependency_updates_var = crate::ops::cargo::render_dependency_updates(
+            pkgs.iter().flat_map(|p| &p.dependency_updates),
+        );
         let template = Template {
             version: version_var,
             metadata: metadata_var,
             date: Some(NOW.as_str()),
+            dependency_updates: Some(dependency_updates_var.as_str()),
+            vars: ws_config.template_vars(),
             ..Default::default()
         };
         template.render(ws_config.pre_release_commit_message())
     };
-    if !git::commit_all(
+    if !git::commit_paths(
         ws_meta.workspace_root.as_std_path(),
+        paths,
         &shared_commit_msg,
         ws_config.sign_commit(),
+        ws_config.no_verify_git_hooks(),
         dry_run,
     )? {
         // commit failed, abort release
@@ -181,3 +367,161 @@ pub fn workspace_commit(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ops::git::fixture::{self, git};
+    use assert_fs::prelude::*;
+
+    #[derive(Debug, clap::Parser)]
+    struct Cli {
+        #[command(flatten)]
+        step: CommitStep,
+    }
+
+    /// `pure_ws`, committed to a fresh repo under `temp`
+    fn repo(temp: &assert_fs::TempDir) -> assert_fs::fixture::ChildPath {
+        let ws = temp.child("ws");
+        ws.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        fixture::init(ws.path());
+        git(ws.path(), &["add", "."]);
+        git(ws.path(), &["commit", "--quiet", "-m", "initial"]);
+        ws
+    }
+
+    fn run(ws: &assert_fs::fixture::ChildPath, args: &[&str]) -> i32 {
+        let manifest_path = ws.child("Cargo.toml");
+        let cli = <Cli as clap::Parser>::parse_from(
+            [
+                "cargo-release",
+                "--manifest-path",
+                manifest_path.path().to_str().unwrap(),
+                "--isolated",
+                "--execute",
+                "--no-confirm",
+            ]
+            .iter()
+            .chain(args),
+        );
+        crate::error::report(cli.step.run())
+    }
+
+    /// Subject and files of each commit since `initial`, oldest first
+    fn commits(ws: &assert_fs::fixture::ChildPath) -> Vec<(String, Vec<String>)> {
+        let log = git(ws.path(), &["log", "--reverse", "--format=%H %s"]);
+        log.lines()
+            .skip(1)
+            .map(|line| {
+                let (sha, subject) = line.split_once(' ').unwrap();
+                let files = git(ws.path(), &["show", "--name-only", "--format=", sha]);
+                (
+                    subject.to_owned(),
+                    files.lines().map(|f| f.to_owned()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nothing_to_commit() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let ws = repo(&temp);
+
+        assert_eq!(run(&ws, &[]), 2);
+        assert_eq!(commits(&ws), []);
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn per_package() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let ws = repo(&temp);
+        let config = temp.child("release.toml");
+        config.write_str("consolidate-commits = false\n").unwrap();
+        ws.child("a/src/lib.rs").write_str("// 1\n").unwrap();
+        ws.child("b/src/lib.rs").write_str("// 2\n").unwrap();
+        ws.child("Cargo.toml")
+            .write_str("[workspace]\nmembers = [\"a\", \"b\", \"c\"]\nresolver = \"2\"\n")
+            .unwrap();
+
+        assert_eq!(run(&ws, &["--config", config.path().to_str().unwrap()]), 0);
+        assert_eq!(
+            commits(&ws),
+            [
+                (
+                    "chore: Release a version 0.1.0".to_owned(),
+                    vec!["a/src/lib.rs".to_owned()]
+                ),
+                (
+                    "chore: Release b version 0.1.0".to_owned(),
+                    vec!["Cargo.toml".to_owned(), "b/src/lib.rs".to_owned()]
+                ),
+            ]
+        );
+        assert_eq!(git(ws.path(), &["status", "--porcelain"]), "");
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn commits_unrelated_changes() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let ws = repo(&temp);
+        ws.child("README.md").write_str("ws\n").unwrap();
+        git(ws.path(), &["add", "README.md"]);
+        git(ws.path(), &["commit", "--quiet", "-m", "readme"]);
+        ws.child("a/src/lib.rs").write_str("// 1\n").unwrap();
+        ws.child("README.md").write_str("ws, changed\n").unwrap();
+
+        // A workspace-root file, like one edited by replacements, goes along with a warning
+        assert_eq!(run(&ws, &[]), 0);
+        assert_eq!(
+            commits(&ws)[1],
+            (
+                "chore: Release".to_owned(),
+                vec!["README.md".to_owned(), "a/src/lib.rs".to_owned()]
+            )
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn leaves_allowed_dirty() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let ws = repo(&temp);
+        let config = temp.child("release.toml");
+        config.write_str("allow-dirty = [\"c/**\"]\n").unwrap();
+        ws.child("a/src/lib.rs").write_str("// 1\n").unwrap();
+        ws.child("c/src/lib.rs").write_str("// 2\n").unwrap();
+
+        assert_eq!(run(&ws, &["--config", config.path().to_str().unwrap()]), 0);
+        assert_eq!(
+            commits(&ws),
+            [("chore: Release".to_owned(), vec!["a/src/lib.rs".to_owned()])]
+        );
+        assert_eq!(git(ws.path(), &["status", "--porcelain"]), "M c/src/lib.rs");
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn release_message_prefix_stops_at_placeholder() {
+        assert_eq!(
+            release_message_prefix(
+                "chore: Release {{crate_name}} version {{version}}",
+                Some("foo")
+            )
+            .as_deref(),
+            Some("chore: Release foo version")
+        );
+        assert_eq!(
+            release_message_prefix("chore: Release\n\n{{dependency_updates}}", None).as_deref(),
+            Some("chore: Release")
+        );
+        assert_eq!(release_message_prefix("{{version}}", None), None);
+    }
+}
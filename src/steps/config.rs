@@ -4,6 +4,7 @@ use crate::config::load_package_config;
 use crate::config::load_workspace_config;
 use crate::config::Config;
 use crate::config::ConfigArgs;
+use crate::error::CargoResult;
 use crate::error::CliError;
 
 /// Dump workspace configuration
@@ -13,15 +14,27 @@ pub struct ConfigStep {
     #[arg(short, long, default_value = "-")]
     output: std::path::PathBuf,
 
+    /// Report questionable configuration rather than dumping it
+    #[arg(long)]
+    check: bool,
+
+    /// Dump the configuration of this package, rather than the root package or workspace
+    #[arg(short, long, value_name = "SPEC")]
+    package: Option<String>,
+
     #[command(flatten)]
     manifest: clap_cargo::Manifest,
 
     #[command(flatten)]
     config: ConfigArgs,
+
+    #[command(flatten)]
+    dry_run: super::DryRunArgs,
 }
 
 impl ConfigStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.dry_run.check();
         log::trace!("initializing");
         let ws_meta = self
             .manifest
@@ -30,23 +43,11 @@ impl ConfigStep {
             .features(cargo_metadata::CargoOpt::AllFeatures)
             .exec()?;
 
-        let release_config =
-            if let Some(root_id) = ws_meta.resolve.as_ref().and_then(|r| r.root.as_ref()) {
-                let pkg = ws_meta
-                    .packages
-                    .iter()
-                    .find(|p| p.id == *root_id)
-                    .expect("root should always be present");
-
-                let mut release_config = Config::from_defaults();
-                release_config.update(&load_package_config(&self.config, &ws_meta, pkg)?);
-                release_config
-            } else {
-                let mut release_config = Config::from_defaults();
-                release_config.update(&load_workspace_config(&self.config, &ws_meta)?);
-                release_config
-            };
+        if self.check {
+            return self.run_check(&ws_meta);
+        }
 
+        let release_config = resolve(&self.config, &ws_meta, self.package.as_deref())?;
         let output = toml::to_string_pretty(&release_config)?;
 
         if self.output == std::path::Path::new("-") {
@@ -57,4 +58,145 @@ impl ConfigStep {
 
         Ok(())
     }
+
+    fn run_check(&self, ws_meta: &cargo_metadata::Metadata) -> Result<(), CliError> {
+        let mut flagged = false;
+        for pkg in ws_meta
+            .packages
+            .iter()
+            .filter(|p| ws_meta.workspace_members.contains(&p.id))
+        {
+            let pkg_config = load_package_config(&self.config, ws_meta, pkg)?;
+            if !pkg_config.release() {
+                continue;
+            }
+            if pkg_config.tag()
+                && pkg_config.tag_unpublished()
+                && !crate::steps::plan::is_publishable(&pkg_config, pkg)
+            {
+                let _ = crate::ops::reporter::warn(format!(
+                    "{} is never published but releases still tag it; set `tag-unpublished = false` or `tag = false`",
+                    pkg.name
+                ));
+                flagged = true;
+            }
+        }
+
+        if flagged {
+            Err(101.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The configuration for `package`, defaulting to the root package or else the workspace
+fn resolve(
+    args: &ConfigArgs,
+    ws_meta: &cargo_metadata::Metadata,
+    package: Option<&str>,
+) -> CargoResult<Config> {
+    let pkg = match package {
+        Some(package) => Some(
+            ws_meta
+                .packages
+                .iter()
+                .filter(|p| ws_meta.workspace_members.contains(&p.id))
+                .find(|p| p.name == package)
+                .ok_or_else(|| {
+                    anyhow::format_err!("package `{}` is not a workspace member", package)
+                })?,
+        ),
+        None => ws_meta
+            .resolve
+            .as_ref()
+            .and_then(|r| r.root.as_ref())
+            .map(|root_id| {
+                ws_meta
+                    .packages
+                    .iter()
+                    .find(|p| p.id == *root_id)
+                    .expect("root should always be present")
+            }),
+    };
+
+    let mut release_config = Config::from_defaults();
+    match pkg {
+        Some(pkg) => release_config.update(&load_package_config(args, ws_meta, pkg)?),
+        None => release_config.update(&load_workspace_config(args, ws_meta)?),
+    }
+    Ok(release_config)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use assert_fs::prelude::*;
+
+    /// `fixture` copied under `temp`, with `publish = false` set in the manifests of `unpublished`
+    fn metadata(
+        temp: &assert_fs::TempDir,
+        fixture: &str,
+        unpublished: &[&str],
+    ) -> cargo_metadata::Metadata {
+        temp.copy_from(format!("tests/fixtures/{}", fixture), &["**"])
+            .unwrap();
+        for manifest in unpublished {
+            let manifest = temp.child(manifest);
+            let contents = std::fs::read_to_string(manifest.path()).unwrap();
+            manifest
+                .write_str(&contents.replacen("[package]\n", "[package]\npublish = false\n", 1))
+                .unwrap();
+        }
+        cargo_metadata::MetadataCommand::new()
+            .manifest_path(temp.child("Cargo.toml").path())
+            .features(cargo_metadata::CargoOpt::AllFeatures)
+            .exec()
+            .unwrap()
+    }
+
+    /// The dumped configuration, with `push = false` set for the whole workspace
+    fn dump(ws_meta: &cargo_metadata::Metadata, package: Option<&str>) -> toml::Table {
+        let args = ConfigArgs {
+            isolated: true,
+            custom_config: vec![crate::config::parse_config_arg("push=false").unwrap()],
+            ..Default::default()
+        };
+        let output = toml::to_string_pretty(&resolve(&args, ws_meta, package).unwrap()).unwrap();
+        toml::from_str(&output).unwrap()
+    }
+
+    #[test]
+    fn single_crate() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let ws_meta = metadata(&temp, "simple", &["Cargo.toml"]);
+
+        for package in [None, Some("simple")] {
+            let simple = dump(&ws_meta, package);
+            assert_eq!(simple["push"].as_bool(), Some(false));
+            assert_eq!(simple["publish"].as_bool(), Some(false));
+        }
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn workspace_root() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let ws_meta = metadata(&temp, "pure_ws", &["b/Cargo.toml"]);
+
+        let workspace = dump(&ws_meta, None);
+        assert_eq!(workspace["push"].as_bool(), Some(false));
+        assert_eq!(workspace["publish"].as_bool(), Some(true));
+
+        let b = dump(&ws_meta, Some("b"));
+        assert_eq!(b["push"].as_bool(), Some(false));
+        assert_eq!(b["publish"].as_bool(), Some(false));
+
+        let args = ConfigArgs::default();
+        assert!(resolve(&args, &ws_meta, Some("missing")).is_err());
+
+        temp.close().unwrap();
+    }
 }
@@ -0,0 +1,215 @@
+use std::io::Write;
+
+use crate::error::CargoResult;
+use crate::error::CliError;
+use crate::ops::cargo::PublishIndex;
+use crate::steps::plan;
+
+/// Print the packages `--unpublished` would release, as JSON for CI job matrices
+///
+/// Only metadata is read, so this works on a dirty tree or in a shallow clone
+#[derive(Debug, Clone, clap::Args)]
+pub struct GenerateMatrixStep {
+    #[command(flatten)]
+    manifest: clap_cargo::Manifest,
+
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
+
+    /// Re-clone the local crates.io index if it is corrupted
+    #[arg(long)]
+    repair_index: bool,
+
+    /// How to reach the crates.io index, defaulting to `CARGO_REGISTRIES_CRATES_IO_PROTOCOL`
+    #[arg(long = "index", value_name = "PROTOCOL", value_enum)]
+    index_protocol: Option<crate::ops::index::IndexProtocol>,
+
+    #[command(flatten)]
+    dry_run: super::DryRunArgs,
+}
+
+impl GenerateMatrixStep {
+    pub fn run(&self) -> Result<(), CliError> {
+        self.dry_run.check();
+        let ws_meta = self
+            .manifest
+            .metadata()
+            // When evaluating dependency ordering, we need to consider optional dependencies
+            .features(cargo_metadata::CargoOpt::AllFeatures)
+            .exec()?;
+        // Without the index, every package would look unpublished
+        let index = crate::ops::index::CratesIoIndex::open(
+            self.index_protocol
+                .unwrap_or_else(crate::ops::index::IndexProtocol::from_env),
+            self.repair_index,
+            true,
+        )?;
+        let config = self.to_config();
+        let pkgs = plan::load(&config, &ws_meta)?;
+
+        let entries = matrix(&ws_meta, pkgs.values(), &index);
+        write(&entries)?;
+
+        Ok(())
+    }
+
+    fn to_config(&self) -> crate::config::ConfigArgs {
+        crate::config::ConfigArgs {
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
+            metadata_only: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// A package to release, as one CI job
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct MatrixEntry {
+    #[serde(flatten)]
+    pub plan: plan::PlanEntry,
+    /// The package's directory, relative to the workspace root
+    pub path: String,
+    pub registry: String,
+}
+
+/// The packages whose manifest version isn't in `index` yet
+pub fn matrix<'p>(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: impl IntoIterator<Item = &'p plan::PackageRelease>,
+    index: &impl PublishIndex,
+) -> Vec<MatrixEntry> {
+    let ws_root = ws_meta.workspace_root.as_std_path();
+    pkgs.into_iter()
+        .filter(|pkg| pkg.is_unpublished(index))
+        .map(|pkg| {
+            let path = pkg
+                .package_root
+                .strip_prefix(ws_root)
+                .unwrap_or(&pkg.package_root);
+            let path = path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            MatrixEntry {
+                plan: pkg.plan_entry(),
+                path: if path.is_empty() {
+                    ".".to_owned()
+                } else {
+                    path
+                },
+                registry: pkg.config.registry().unwrap_or("crates-io").to_owned(),
+            }
+        })
+        .collect()
+}
+
+fn write(entries: &[MatrixEntry]) -> CargoResult<()> {
+    let output = serde_json::to_string_pretty(entries)?;
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Knows only the listed `(name, version)`s
+    struct MockIndex(&'static [(&'static str, &'static str)]);
+
+    impl PublishIndex for MockIndex {
+        fn update(&mut self) -> CargoResult<()> {
+            Ok(())
+        }
+
+        fn is_published(&self, name: &str, version: &str) -> bool {
+            self.0.iter().any(|(n, v)| *n == name && *v == version)
+        }
+    }
+
+    fn load() -> (
+        cargo_metadata::Metadata,
+        indexmap::IndexMap<cargo_metadata::PackageId, plan::PackageRelease>,
+    ) {
+        load_from(std::path::Path::new("tests/fixtures/unpublished_ws"))
+    }
+
+    fn load_from(
+        root: &std::path::Path,
+    ) -> (
+        cargo_metadata::Metadata,
+        indexmap::IndexMap<cargo_metadata::PackageId, plan::PackageRelease>,
+    ) {
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            metadata_only: true,
+            ..Default::default()
+        };
+        plan::fixture::load(root, &args)
+    }
+
+    #[test]
+    fn unpublished() {
+        let (ws_meta, pkgs) = load();
+
+        let actual = matrix(&ws_meta, pkgs.values(), &MockIndex(&[]));
+        let actual = serde_json::to_value(actual).unwrap();
+        assert_eq!(
+            actual,
+            serde_json::json!([{
+                "name": "b",
+                "version": "0.1.0",
+                "planned_version": null,
+                "planned_tag": null,
+                "prior_tag": null,
+                "changed_file_count": null,
+                "change_detection": "none",
+                "skip_reason": null,
+                "publish": true,
+                "tag": false,
+                "push": true,
+                "path": "b",
+                "registry": "crates-io",
+            }])
+        );
+    }
+
+    #[test]
+    fn outside_a_repository() {
+        use assert_fs::prelude::*;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.copy_from("tests/fixtures/unpublished_ws", &["**"])
+            .unwrap();
+        let (ws_meta, pkgs) = load_from(temp.path());
+
+        let actual = matrix(&ws_meta, pkgs.values(), &MockIndex(&[]));
+        assert_eq!(actual.len(), 1, "{:?}", actual);
+        assert_eq!(actual[0].plan.prior_tag, None);
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn published() {
+        let (ws_meta, pkgs) = load();
+
+        let actual = matrix(&ws_meta, pkgs.values(), &MockIndex(&[("b", "0.1.0")]));
+        assert!(actual.is_empty(), "{:?}", actual);
+        let actual = matrix(&ws_meta, pkgs.values(), &MockIndex(&[("b", "0.0.1")]));
+        assert_eq!(actual.len(), 1);
+    }
+
+    #[test]
+    fn release_disabled() {
+        let (ws_meta, mut pkgs) = load();
+        for pkg in pkgs.values_mut() {
+            pkg.config.release = Some(false);
+        }
+
+        let actual = matrix(&ws_meta, pkgs.values(), &MockIndex(&[]));
+        assert!(actual.is_empty(), "{:?}", actual);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use crate::error::CargoResult;
+use crate::error::CliError;
+use crate::ops::github::CreateRelease;
+use crate::ops::github::Created;
+use crate::ops::github::Repo;
+use crate::ops::replace::DependencyLookup;
+use crate::ops::replace::Template;
+use crate::steps::plan;
+
+/// Where the API token comes from
+const TOKEN_VAR: &str = "GITHUB_TOKEN";
+
+/// The repository to create releases in: `repo`, if given, or whatever `push-remote` points to
+pub fn resolve_repo(
+    ws_config: &crate::config::Config,
+    ws_meta: &cargo_metadata::Metadata,
+    repo: Option<&Repo>,
+) -> CargoResult<Repo> {
+    if let Some(repo) = repo {
+        return Ok(repo.clone());
+    }
+    let remote = ws_config.push_remote();
+    let url = crate::ops::git::remote_url(ws_meta.workspace_root.as_std_path(), remote)?;
+    match url.as_deref().and_then(Repo::from_remote_url) {
+        Some(repo) => Ok(repo),
+        None => anyhow::bail!(
+            "can't tell the GitHub repository from remote `{}` ({}); pass `--github-repo OWNER/NAME`",
+            remote,
+            url.as_deref()
+                .map(crate::ops::git::redact_url)
+                .unwrap_or_else(|| "no URL".into())
+        ),
+    }
+}
+
+/// Packages with `github-release` whose tag gets pushed
+fn releasing<'p>(
+    ws_config: &crate::config::Config,
+    pkgs: &'p [plan::PackageRelease],
+) -> impl Iterator<Item = (&'p plan::PackageRelease, &'p str)> {
+    let push = ws_config.push();
+    pkgs.iter().filter_map(move |pkg| {
+        let tag_name = pkg.planned_tag.as_deref()?;
+        (push && pkg.config.push() && pkg.config.github_release()).then_some((pkg, tag_name))
+    })
+}
+
+/// Check that GitHub releases can be created, before anything is released
+pub fn verify_github_release(
+    ws_config: &crate::config::Config,
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+    repo: Option<&Repo>,
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, CliError> {
+    let _timer = crate::ops::timings::start("verify_github_release");
+    let mut success = true;
+
+    if releasing(ws_config, pkgs).next().is_none() {
+        return Ok(success);
+    }
+    if let Err(err) = resolve_repo(ws_config, ws_meta, repo) {
+        let _ = crate::ops::reporter::log(level, format!("{:#}", err));
+        success = false;
+    }
+    if std::env::var_os(TOKEN_VAR)
+        .map(|t| t.is_empty())
+        .unwrap_or(true)
+    {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!("`github-release` needs a token in `{}`", TOKEN_VAR),
+        );
+        success = false;
+    }
+
+    if !success && level == log::Level::Error && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
+}
+
+/// Create a GitHub release for each pushed tag of a package with `github-release`
+pub fn github_release(
+    ws_config: &crate::config::Config,
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+    repo: Option<&Repo>,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("github-release");
+    let _timer = crate::ops::timings::start("github_release");
+
+    let mut releasing = releasing(ws_config, pkgs).peekable();
+    if releasing.peek().is_none() {
+        return Ok(());
+    }
+    let repo = resolve_repo(ws_config, ws_meta, repo)?;
+    let token = std::env::var(TOKEN_VAR).unwrap_or_default();
+    if token.is_empty() && !dry_run {
+        let _ = crate::ops::reporter::error(format!(
+            "`github-release` needs a token in `{}`",
+            TOKEN_VAR
+        ));
+        return Err(101.into());
+    }
+
+    let mut seen_tags = HashSet::new();
+    for (pkg, tag_name) in releasing {
+        // Packages sharing a tag share its release
+        if !seen_tags.insert(tag_name) {
+            continue;
+        }
+        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+        let notes = render_notes(pkg, tag_name)?;
+        let request = CreateRelease::new(&repo, tag_name, &notes, version.is_prerelease());
+        let _ =
+            crate::ops::reporter::status("Releasing", format!("{} on GitHub ({})", tag_name, repo));
+        if dry_run {
+            log::debug!(
+                "would post to {}:\n{}",
+                request.url,
+                serde_json::to_string_pretty(&request.body).unwrap_or_default()
+            );
+            continue;
+        }
+        match request.send(&token)? {
+            Created::New => {}
+            Created::AlreadyExists => {
+                let _ = crate::ops::reporter::note(format!(
+                    "GitHub release for {} already exists, leaving it as is",
+                    tag_name
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `release-notes` for `pkg`
+fn render_notes(pkg: &plan::PackageRelease, tag_name: &str) -> CargoResult<String> {
+    let notes = pkg.config.release_notes();
+    let changes_var = if notes.contains("{{changes}}") {
+        match crate::steps::changes::released_commits(pkg)? {
+            Some(commits) => {
+                crate::steps::changes::render_conventional(&commits, pkg.config.commit_types())
+            }
+            None => String::new(),
+        }
+    } else {
+        String::new()
+    };
+    let dependency_var = |name: &str, field: &str| pkg.dependency_var(name, field);
+    let template = Template {
+        tag_name: Some(tag_name),
+        changes: Some(changes_var.as_str()),
+        dependencies: Some(DependencyLookup(&dependency_var)),
+        ..pkg.template()
+    };
+    Ok(template.render(notes))
+}
@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::path::Path;
 
+use crate::config::HookScope;
 use crate::error::CliError;
 use crate::ops::cmd;
 use crate::ops::git;
@@ -20,39 +22,35 @@ pub struct HookStep {
     #[arg(long)]
     unpublished: bool,
 
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
-
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
     no_confirm: bool,
+
+    /// Check whether the branch is behind the push remote, which requires a `git fetch`
+    #[arg(long)]
+    check_remote: bool,
 }
 
 impl HookStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
-        let index = crates_index::Index::new_cargo_default()?;
-
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
+        let index = crate::ops::index::CratesIoIndex::open(
+            crate::ops::index::IndexProtocol::from_env(),
+            false,
+            false,
+        )?;
+        index.warn_unpublished(self.unpublished);
 
         let ws_meta = self
             .manifest
@@ -88,7 +86,7 @@ impl HookStep {
                 let version = &pkg.initial_version;
                 if !crate::ops::cargo::is_published(
                     &index,
-                    crate_name,
+                    pkg.published_name(),
                     &version.full_version_string,
                 ) {
                     log::debug!(
@@ -101,26 +99,24 @@ impl HookStep {
             }
 
             pkg.config.pre_release_replacements = Some(vec![]);
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
         }
 
         let pkgs = plan::plan(pkgs)?;
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Warn,
         )?;
@@ -130,15 +126,16 @@ impl HookStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Warn,
         )?;
 
-        failed |= !super::verify_if_behind(
+        failed |= !super::verify_if_behind_when_asked(
+            self.check_remote,
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
             dry_run,
-            log::Level::Warn,
         )?;
 
         // STEP 1: Release Confirmation
@@ -149,13 +146,14 @@ impl HookStep {
             hook(&ws_meta, pkg, dry_run)?;
         }
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
             ..Default::default()
         }
@@ -167,46 +165,18 @@ pub fn hook(
     pkg: &plan::PackageRelease,
     dry_run: bool,
 ) -> Result<(), CliError> {
+    let _timer = crate::ops::timings::start(format_args!("hook {}", pkg.meta.name));
     if let Some(pre_rel_hook) = pkg.config.pre_release_hook() {
         let cwd = &pkg.package_root;
-        let crate_name = pkg.meta.name.as_str();
-        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
-        let prev_version_var = pkg.initial_version.bare_version_string.as_str();
-        let prev_metadata_var = pkg.initial_version.full_version.build.as_str();
-        let version_var = version.bare_version_string.as_str();
-        let metadata_var = version.full_version.build.as_str();
-        let template = Template {
-            prev_version: Some(prev_version_var),
-            prev_metadata: Some(prev_metadata_var),
-            version: Some(version_var),
-            metadata: Some(metadata_var),
-            crate_name: Some(crate_name),
-            date: Some(NOW.as_str()),
-            tag_name: pkg.planned_tag.as_deref(),
-            ..Default::default()
-        };
-        let pre_rel_hook = pre_rel_hook
-            .args()
-            .into_iter()
-            .map(|arg| template.render(arg))
-            .collect::<Vec<_>>();
+        let pre_rel_hook = pre_rel_hook.render(&pkg.template());
         log::debug!("calling pre-release hook: {:?}", pre_rel_hook);
-        let envs = maplit::btreemap! {
-            OsStr::new("PREV_VERSION") => prev_version_var.as_ref(),
-            OsStr::new("PREV_METADATA") => prev_metadata_var.as_ref(),
-            OsStr::new("NEW_VERSION") => version_var.as_ref(),
-            OsStr::new("NEW_METADATA") => metadata_var.as_ref(),
-            OsStr::new("DRY_RUN") => OsStr::new(if dry_run { "true" } else { "false" }),
-            OsStr::new("CRATE_NAME") => OsStr::new(crate_name),
-            OsStr::new("WORKSPACE_ROOT") => ws_meta.workspace_root.as_os_str(),
-            OsStr::new("CRATE_ROOT") => pkg.manifest_path.parent().unwrap_or_else(|| Path::new(".")).as_os_str(),
-        };
+        let envs = package_envs(ws_meta, pkg, dry_run);
         // we use dry_run environmental variable to run the script
         // so here we set dry_run=false and always execute the command.
         if !cmd::call_with_env(pre_rel_hook, envs, cwd, false)? {
-            let _ = crate::ops::shell::error(format!(
+            let _ = crate::ops::reporter::error(format!(
                 "release of {} aborted by non-zero return of prerelease hook.",
-                crate_name
+                pkg.meta.name
             ));
             return Err(101.into());
         }
@@ -214,3 +184,423 @@ pub fn hook(
 
     Ok(())
 }
+
+/// The environment a hook run for `pkg` gets
+fn package_envs<'a>(
+    ws_meta: &'a cargo_metadata::Metadata,
+    pkg: &'a plan::PackageRelease,
+    dry_run: bool,
+) -> BTreeMap<&'a OsStr, &'a OsStr> {
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    maplit::btreemap! {
+        OsStr::new("PREV_VERSION") => OsStr::new(pkg.initial_version.bare_version_string.as_str()),
+        OsStr::new("PREV_METADATA") => OsStr::new(pkg.initial_version.full_version.build.as_str()),
+        OsStr::new("NEW_VERSION") => OsStr::new(version.bare_version_string.as_str()),
+        OsStr::new("NEW_METADATA") => OsStr::new(version.full_version.build.as_str()),
+        OsStr::new("DRY_RUN") => OsStr::new(if dry_run { "true" } else { "false" }),
+        OsStr::new("CRATE_NAME") => OsStr::new(pkg.meta.name.as_str()),
+        OsStr::new("WORKSPACE_ROOT") => ws_meta.workspace_root.as_os_str(),
+        OsStr::new("CRATE_ROOT") => pkg.manifest_path.parent().unwrap_or_else(|| Path::new(".")).as_os_str(),
+    }
+}
+
+/// Run `post-publish-hook` for the packages just published
+///
+/// This runs before tagging so a failed smoke test doesn't leave an official-looking tag behind.
+pub fn post_publish_hook(
+    ws_meta: &cargo_metadata::Metadata,
+    ws_config: &crate::config::Config,
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let published: Vec<_> = pkgs.iter().filter(|p| p.config.publish()).collect();
+    if published.is_empty() {
+        return Ok(());
+    }
+    let _timer = crate::ops::timings::start("post-publish hook");
+
+    match ws_config.post_publish_hook_scope() {
+        HookScope::Package => {
+            for pkg in &published {
+                let post_pub_hook = match pkg.config.post_publish_hook() {
+                    Some(post_pub_hook) => post_pub_hook,
+                    None => continue,
+                };
+                let cwd = &pkg.package_root;
+                let registry_var = pkg.config.registry().unwrap_or("crates-io");
+                let post_pub_hook = post_pub_hook.render(&pkg.template());
+                log::debug!("calling post-publish hook: {:?}", post_pub_hook);
+                let mut envs = package_envs(ws_meta, pkg, dry_run);
+                envs.insert(OsStr::new("REGISTRY"), OsStr::new(registry_var));
+                if !cmd::call_with_env(post_pub_hook, envs, cwd, dry_run)? {
+                    report_post_publish_failure(pkg.meta.name.as_str(), &published);
+                    return Err(101.into());
+                }
+            }
+        }
+        HookScope::Run => {
+            let post_pub_hook = match ws_config.post_publish_hook() {
+                Some(post_pub_hook) => post_pub_hook,
+                None => return Ok(()),
+            };
+            let cwd = ws_meta.workspace_root.as_std_path();
+            let shared_version = shared_version(published.iter().copied());
+            let version_var = shared_version.map(|v| v.bare_version_string.as_str());
+            let metadata_var = shared_version.map(|v| v.full_version.build.as_str());
+            let registry_var = ws_config.registry().unwrap_or("crates-io");
+            let template = Template {
+                version: version_var,
+                metadata: metadata_var,
+                date: Some(NOW.as_str()),
+                vars: ws_config.template_vars(),
+                ..Default::default()
+            };
+            let post_pub_hook = post_pub_hook.render(&template);
+            log::debug!("calling post-publish hook: {:?}", post_pub_hook);
+            let mut envs = maplit::btreemap! {
+                OsStr::new("REGISTRY") => OsStr::new(registry_var),
+                OsStr::new("DRY_RUN") => OsStr::new(if dry_run { "true" } else { "false" }),
+                OsStr::new("WORKSPACE_ROOT") => ws_meta.workspace_root.as_os_str(),
+            };
+            if let (Some(version_var), Some(metadata_var)) = (version_var, metadata_var) {
+                envs.insert(OsStr::new("NEW_VERSION"), OsStr::new(version_var));
+                envs.insert(OsStr::new("NEW_METADATA"), OsStr::new(metadata_var));
+            }
+            if !cmd::call_with_env(post_pub_hook, envs, cwd, dry_run)? {
+                report_post_publish_failure("the release", &published);
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Exit code for a failed `post-release-hook`
+///
+/// Everything was already released, so this is kept apart from a failed release.
+pub const POST_RELEASE_HOOK_FAILED: i32 = 102;
+
+/// Run `post-release-hook` once the release is published, tagged, and pushed
+///
+/// The workspace's hook runs once for the whole release, from the workspace root; packages setting
+/// a hook of their own run it for themselves, from the crate root.
+pub fn post_release_hook(
+    ws_meta: &cargo_metadata::Metadata,
+    ws_config: &crate::config::Config,
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let release_hook = ws_config.post_release_hook();
+    let mut released = Vec::new();
+    for pkg in pkgs {
+        let post_rel_hook = match pkg.config.post_release_hook() {
+            Some(post_rel_hook) => post_rel_hook,
+            None => continue,
+        };
+        if Some(post_rel_hook) == release_hook {
+            released.push(pkg);
+            continue;
+        }
+        let _timer =
+            crate::ops::timings::start(format_args!("post-release hook {}", pkg.meta.name));
+        let cwd = &pkg.package_root;
+        let post_rel_hook = post_rel_hook.render(&pkg.template());
+        log::debug!("calling post-release hook: {:?}", post_rel_hook);
+        let mut envs = package_envs(ws_meta, pkg, dry_run);
+        if let Some(tag_name) = pkg.planned_tag.as_deref() {
+            envs.insert(OsStr::new("TAG_NAME"), OsStr::new(tag_name));
+        }
+        // Unlike `pre-release-hook`, nothing was released in a dry-run for this to act on
+        if !cmd::call_with_env(post_rel_hook, envs, cwd, dry_run)? {
+            let _ = crate::ops::reporter::error(format!(
+                "post-release hook failed for {}; the release itself is complete",
+                pkg.meta.name
+            ));
+            return Err(POST_RELEASE_HOOK_FAILED.into());
+        }
+    }
+
+    let post_rel_hook = match release_hook {
+        Some(post_rel_hook) if !released.is_empty() => post_rel_hook,
+        _ => return Ok(()),
+    };
+    let _timer = crate::ops::timings::start("post-release hook");
+    let cwd = ws_meta.workspace_root.as_std_path();
+    let shared_version = shared_version(released.iter().copied());
+    let version_var = shared_version.map(|v| v.bare_version_string.as_str());
+    let metadata_var = shared_version.map(|v| v.full_version.build.as_str());
+    let tags: indexmap::IndexSet<_> = released
+        .iter()
+        .filter_map(|p| p.planned_tag.as_deref())
+        .collect();
+    let tag_var = match tags.len() {
+        1 => tags.first().copied(),
+        _ => None,
+    };
+    let template = Template {
+        version: version_var,
+        metadata: metadata_var,
+        tag_name: tag_var,
+        date: Some(NOW.as_str()),
+        vars: ws_config.template_vars(),
+        ..Default::default()
+    };
+    let post_rel_hook = post_rel_hook.render(&template);
+    log::debug!("calling post-release hook: {:?}", post_rel_hook);
+    let mut envs = maplit::btreemap! {
+        OsStr::new("DRY_RUN") => OsStr::new(if dry_run { "true" } else { "false" }),
+        OsStr::new("WORKSPACE_ROOT") => ws_meta.workspace_root.as_os_str(),
+    };
+    if let (Some(version_var), Some(metadata_var)) = (version_var, metadata_var) {
+        envs.insert(OsStr::new("NEW_VERSION"), OsStr::new(version_var));
+        envs.insert(OsStr::new("NEW_METADATA"), OsStr::new(metadata_var));
+    }
+    if let Some(tag_var) = tag_var {
+        envs.insert(OsStr::new("TAG_NAME"), OsStr::new(tag_var));
+    }
+    if !cmd::call_with_env(post_rel_hook, envs, cwd, dry_run)? {
+        let _ =
+            crate::ops::reporter::error("post-release hook failed; the release itself is complete");
+        return Err(POST_RELEASE_HOOK_FAILED.into());
+    }
+
+    Ok(())
+}
+
+/// The version `pkgs` are all released at, if they agree on one
+fn shared_version<'p>(
+    pkgs: impl IntoIterator<Item = &'p plan::PackageRelease>,
+) -> Option<&'p plan::Version> {
+    let mut versions = pkgs
+        .into_iter()
+        .map(|p| p.planned_version.as_ref().unwrap_or(&p.initial_version));
+    let first = versions.next()?;
+    versions
+        .all(|v| v.full_version_string == first.full_version_string)
+        .then_some(first)
+}
+
+fn report_post_publish_failure(failed: &str, published: &[&plan::PackageRelease]) {
+    let versions = published
+        .iter()
+        .map(|p| {
+            let version = p.planned_version.as_ref().unwrap_or(&p.initial_version);
+            (p.published_name(), version.full_version_string.as_str())
+        })
+        .collect::<Vec<_>>();
+    let _ = crate::ops::reporter::error(format!(
+        "post-publish hook failed for {}; {} published but not tagged",
+        failed,
+        itertools::join(
+            versions
+                .iter()
+                .map(|(name, version)| format!("{} {}", name, version)),
+            ", "
+        ),
+    ));
+    let yank = itertools::join(
+        versions
+            .iter()
+            .map(|(name, version)| format!("`cargo yank --version {} {}`", version, name)),
+        ", ",
+    );
+    let tag = itertools::join(published.iter().map(|p| format!("-p {}", p.meta.name)), " ");
+    let _ = crate::ops::reporter::note(format!(
+        "if the published release is broken, yank it with {}\nonce fixed, finish the release with `cargo release tag --execute {}` and `cargo release push --execute`",
+        yank, tag
+    ));
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod test {
+    use super::*;
+
+    use assert_fs::prelude::*;
+
+    use crate::config::Command;
+    use crate::ops::git::fixture::{self, git};
+
+    /// `pure_ws`, committed to a fresh repo, with `hook` to run after publishing
+    fn load(
+        temp: &assert_fs::TempDir,
+        hook: &str,
+        scope: HookScope,
+    ) -> (cargo_metadata::Metadata, Vec<plan::PackageRelease>) {
+        let ws = temp.child("ws");
+        ws.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        fixture::init(ws.path());
+        git(ws.path(), &["add", "."]);
+        git(ws.path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let (ws_meta, pkgs) = plan::fixture::load(ws.path(), &args);
+        let mut pkgs: Vec<_> = pkgs.into_values().collect();
+        for pkg in &mut pkgs {
+            pkg.config.publish = Some(true);
+            pkg.config.post_publish_hook = Some(Command::Line(hook.to_owned()));
+            pkg.config.post_publish_hook_scope = Some(scope);
+            pkg.planned_tag = Some(format!("{}-v0.1.0", pkg.meta.name));
+        }
+        (ws_meta, pkgs)
+    }
+
+    fn runs(log: &assert_fs::fixture::ChildPath) -> Vec<String> {
+        std::fs::read_to_string(log.path())
+            .unwrap_or_default()
+            .lines()
+            .map(|l| l.to_owned())
+            .collect()
+    }
+
+    /// Like `cargo release`, only tag once the hook passes
+    fn publish_then_tag(
+        ws_meta: &cargo_metadata::Metadata,
+        pkgs: &[plan::PackageRelease],
+        dry_run: bool,
+    ) -> Result<(), CliError> {
+        let ws_config = pkgs[0].config.clone();
+        post_publish_hook(ws_meta, &ws_config, pkgs, dry_run)?;
+        crate::steps::tag::tag(pkgs, dry_run)
+    }
+
+    #[test]
+    fn per_package() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!("echo \"$CRATE_NAME $REGISTRY\" >> {}", log.path().display());
+        let (ws_meta, pkgs) = load(&temp, &hook, HookScope::Package);
+
+        publish_then_tag(&ws_meta, &pkgs, false).unwrap();
+        assert_eq!(runs(&log), ["a crates-io", "b crates-io", "c crates-io"]);
+        assert_eq!(
+            git(temp.child("ws").path(), &["tag", "--list"]),
+            "a-v0.1.0\nb-v0.1.0\nc-v0.1.0"
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn per_run() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!("echo \"$NEW_VERSION\" >> {}", log.path().display());
+        let (ws_meta, pkgs) = load(&temp, &hook, HookScope::Run);
+
+        publish_then_tag(&ws_meta, &pkgs, false).unwrap();
+        assert_eq!(runs(&log), ["0.1.0"]);
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn dry_run_skips() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!("echo run >> {}", log.path().display());
+        let (ws_meta, pkgs) = load(&temp, &hook, HookScope::Package);
+
+        publish_then_tag(&ws_meta, &pkgs, true).unwrap();
+        assert!(runs(&log).is_empty());
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn failure_aborts_before_tagging() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!("echo \"$CRATE_NAME\" >> {} && false", log.path().display());
+        let (ws_meta, pkgs) = load(&temp, &hook, HookScope::Package);
+
+        assert!(publish_then_tag(&ws_meta, &pkgs, false).is_err());
+        assert_eq!(runs(&log), ["a"]);
+        assert_eq!(git(temp.child("ws").path(), &["tag", "--list"]), "");
+
+        temp.close().unwrap();
+    }
+
+    fn with_post_release_hook(pkgs: &mut [plan::PackageRelease], hook: &str) {
+        for pkg in pkgs {
+            pkg.config.post_publish_hook = None;
+            pkg.config.post_release_hook = Some(Command::Line(hook.to_owned()));
+        }
+    }
+
+    #[test]
+    fn post_release() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!(
+            "echo \"$CRATE_NAME $PREV_VERSION $NEW_VERSION $TAG_NAME\" >> {}",
+            log.path().display()
+        );
+        let (ws_meta, mut pkgs) = load(&temp, "", HookScope::Package);
+        with_post_release_hook(&mut pkgs, &hook);
+
+        post_release_hook(&ws_meta, &Default::default(), &pkgs, false).unwrap();
+        assert_eq!(
+            runs(&log),
+            [
+                "a 0.1.0 0.1.0 a-v0.1.0",
+                "b 0.1.0 0.1.0 b-v0.1.0",
+                "c 0.1.0 0.1.0 c-v0.1.0"
+            ]
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn post_release_once() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!(
+            "echo \"${{CRATE_NAME:-release}} $NEW_VERSION ${{TAG_NAME:-untagged}}\" >> {}",
+            log.path().display()
+        );
+        let (ws_meta, mut pkgs) = load(&temp, "", HookScope::Package);
+        with_post_release_hook(&mut pkgs, &hook);
+        let ws_config = pkgs[0].config.clone();
+        pkgs[2].config.post_release_hook = Some(Command::Line(format!("{} # c", hook)));
+
+        post_release_hook(&ws_meta, &ws_config, &pkgs, false).unwrap();
+        assert_eq!(runs(&log), ["c 0.1.0 c-v0.1.0", "release 0.1.0 untagged"]);
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn post_release_dry_run_skips() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!("echo run >> {}", log.path().display());
+        let (ws_meta, mut pkgs) = load(&temp, "", HookScope::Package);
+        with_post_release_hook(&mut pkgs, &hook);
+
+        post_release_hook(&ws_meta, &Default::default(), &pkgs, true).unwrap();
+        assert!(runs(&log).is_empty());
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn post_release_failure_code() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let log = temp.child("runs.log");
+        let hook = format!("echo \"$CRATE_NAME\" >> {} && false", log.path().display());
+        let (ws_meta, mut pkgs) = load(&temp, "", HookScope::Package);
+        with_post_release_hook(&mut pkgs, &hook);
+
+        let result = post_release_hook(&ws_meta, &Default::default(), &pkgs, false);
+        assert_eq!(crate::error::report(result), POST_RELEASE_HOOK_FAILED);
+        assert_eq!(runs(&log), ["a"]);
+
+        temp.close().unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::error::CargoResult;
+use crate::error::CliError;
+
+/// Write a starter `release.toml`
+#[derive(Debug, Clone, clap::Args)]
+pub struct InitStep {
+    #[command(flatten)]
+    manifest: clap_cargo::Manifest,
+
+    /// Write the starter configuration without asking any questions
+    #[arg(long)]
+    defaults: bool,
+
+    /// Overwrite an existing `release.toml`
+    #[arg(long)]
+    force: bool,
+
+    #[command(flatten)]
+    dry_run: super::DryRunArgs,
+}
+
+impl InitStep {
+    pub fn run(&self) -> Result<(), CliError> {
+        self.dry_run.check();
+        let ws_meta = self.manifest.metadata().no_deps().exec()?;
+        let path = ws_meta.workspace_root.as_std_path().join("release.toml");
+        if path.exists() && !self.force {
+            let _ = crate::ops::reporter::error(format!(
+                "{} already exists; pass `--force` to overwrite it",
+                path.display()
+            ));
+            return Err(101.into());
+        }
+
+        let mut starter = Starter::detect(&ws_meta);
+        if !self.defaults {
+            if starter.is_workspace {
+                starter.shared_version =
+                    crate::ops::shell::confirm("Release all packages with one shared version?");
+            }
+            if !crate::ops::shell::confirm(&format!("Write {}?", path.display())) {
+                return Err(0.into());
+            }
+        }
+
+        let content = starter.render()?;
+        crate::ops::path::write(&path, &content)?;
+        let _ = crate::ops::reporter::status("Created", path.display());
+
+        Ok(())
+    }
+}
+
+/// Whether any `cargo-release` configuration exists for the workspace
+///
+/// User-level files are not checked as they aren't specific to this workspace.
+pub fn has_config(ws_meta: &cargo_metadata::Metadata) -> bool {
+    let ws_root = ws_meta.workspace_root.as_std_path();
+    let ws_files = [
+        ws_root.join("release.toml"),
+        ws_root.join(".config/cargo-release/release.toml"),
+    ];
+    if ws_files.iter().any(|p| p.exists()) || !ws_meta.workspace_metadata["release"].is_null() {
+        return true;
+    }
+
+    ws_meta
+        .packages
+        .iter()
+        .filter(|p| ws_meta.workspace_members.contains(&p.id))
+        .any(|p| {
+            let crate_root = p.manifest_path.parent().unwrap().as_std_path();
+            crate_root.join("release.toml").exists()
+                || crate_root.join(".config/release.toml").exists()
+                || !p.metadata["release"].is_null()
+        })
+}
+
+/// What a starter `release.toml` is tailored to
+#[derive(Clone, Debug, Default)]
+pub struct Starter {
+    pub is_workspace: bool,
+    /// Changelogs, relative to the workspace root
+    pub changelogs: Vec<PathBuf>,
+    pub shared_version: bool,
+}
+
+impl Starter {
+    pub fn detect(ws_meta: &cargo_metadata::Metadata) -> Self {
+        let ws_root = ws_meta.workspace_root.as_std_path();
+        let changelogs = ws_meta
+            .packages
+            .iter()
+            .filter(|p| ws_meta.workspace_members.contains(&p.id))
+            .map(|p| {
+                p.manifest_path
+                    .parent()
+                    .unwrap()
+                    .as_std_path()
+                    .join("CHANGELOG.md")
+            })
+            .filter(|p| p.exists())
+            .map(|p| p.strip_prefix(ws_root).map(Path::to_owned).unwrap_or(p))
+            .collect();
+        Self {
+            is_workspace: 1 < ws_meta.workspace_members.len(),
+            changelogs,
+            shared_version: false,
+        }
+    }
+
+    pub fn render(&self) -> CargoResult<String> {
+        let mut out = String::new();
+        out.push_str("# Configuration for `cargo release`\n");
+        out.push_str("#\n");
+        out.push_str("# Uncomment a setting to change it from the default shown.\n");
+        out.push_str(
+            "# See https://github.com/crate-ci/cargo-release/blob/master/docs/reference.md\n",
+        );
+        out.push('\n');
+
+        if self.shared_version {
+            out.push_str("shared-version = true\n");
+            out.push('\n');
+        }
+
+        if self.is_workspace {
+            out.push_str("# This is a workspace:\n");
+            if !self.shared_version {
+                out.push_str(
+                    "# - Set `shared-version = true` to release every package with one version\n",
+                );
+            }
+            out.push_str(
+                "# - Packages are tagged `<name>-v<version>` by default; set `tag-prefix = \"\"` for\n",
+            );
+            out.push_str(
+                "#   a single `v<version>` tag, which only makes sense with a shared version\n",
+            );
+            out.push('\n');
+        }
+
+        let defaults = defaults(self.is_workspace)?;
+        for line in defaults.lines() {
+            if self.shared_version && line.starts_with("shared-version") {
+                continue;
+            }
+            if line.is_empty() {
+                out.push('\n');
+            } else {
+                out.push_str("# ");
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        if !self.changelogs.is_empty() {
+            out.push('\n');
+            out.push_str("# Changelogs found:\n");
+            for changelog in &self.changelogs {
+                out.push_str(&format!("# - {}\n", changelog.display()));
+            }
+            out.push_str(
+                "# Replacements run relative to each package, so for a heading per release:\n",
+            );
+            out.push_str("# [[pre-release-replacements]]\n");
+            out.push_str("# file = \"CHANGELOG.md\"\n");
+            out.push_str("# search = \"Unreleased\"\n");
+            out.push_str("# replace = \"{{version}}\"\n");
+            out.push_str("#\n");
+            out.push_str("# [[pre-release-replacements]]\n");
+            out.push_str("# file = \"CHANGELOG.md\"\n");
+            out.push_str("# search = \"ReleaseDate\"\n");
+            out.push_str("# replace = \"{{date}}\"\n");
+        }
+
+        Ok(out)
+    }
+}
+
+fn defaults(is_workspace: bool) -> CargoResult<String> {
+    let mut config = Config::from_defaults();
+    // `from_defaults` doesn't know whether it's describing a workspace
+    config.consolidate_commits = Some(is_workspace);
+    Ok(toml::to_string_pretty(&config)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(content: &str) -> Config {
+        let config: Config = toml::from_str(content).unwrap();
+        config.validate().unwrap();
+        config
+    }
+
+    #[test]
+    fn defaults_round_trip() {
+        let config = parse(&defaults(true).unwrap());
+        assert_eq!(
+            config.tag_name(),
+            Config::from_defaults().tag_name(),
+            "{:?}",
+            config
+        );
+    }
+
+    #[test]
+    fn package() {
+        let content = Starter::default().render().unwrap();
+        let config = parse(&content);
+        assert_eq!(config.shared_version(), None);
+        assert!(!content.contains("This is a workspace"), "{}", content);
+    }
+
+    #[test]
+    fn workspace() {
+        let starter = Starter {
+            is_workspace: true,
+            changelogs: vec![PathBuf::from("crates/a/CHANGELOG.md")],
+            shared_version: false,
+        };
+        let content = starter.render().unwrap();
+        parse(&content);
+        assert!(content.contains("`shared-version = true`"), "{}", content);
+        assert!(content.contains("`tag-prefix"), "{}", content);
+        assert!(content.contains("crates/a/CHANGELOG.md"), "{}", content);
+        assert!(
+            content.contains("# [[pre-release-replacements]]"),
+            "{}",
+            content
+        );
+    }
+
+    #[test]
+    fn shared_version() {
+        let starter = Starter {
+            is_workspace: true,
+            changelogs: Vec::new(),
+            shared_version: true,
+        };
+        let config = parse(&starter.render().unwrap());
+        assert_eq!(config.shared_version(), Some("default"));
+    }
+}
@@ -3,7 +3,10 @@ use std::str::FromStr;
 pub mod changes;
 pub mod commit;
 pub mod config;
+pub mod generate_matrix;
+pub mod github;
 pub mod hook;
+pub mod init;
 pub mod owner;
 pub mod plan;
 pub mod publish;
@@ -11,23 +14,88 @@ pub mod push;
 pub mod release;
 pub mod replace;
 pub mod tag;
+pub mod verify;
 pub mod version;
+pub mod wait;
+
+use anyhow::Context as _;
 
 use crate::error::CargoResult;
 use crate::ops::version::VersionExt as _;
 
+/// `--execute`, for steps that change anything
+#[derive(Copy, Clone, Debug, Default, clap::Args)]
+pub struct ExecuteArgs {
+    /// Actually perform a release. Dry-run mode is the default
+    #[arg(short = 'x', long)]
+    execute: bool,
+
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+}
+
+impl ExecuteArgs {
+    /// Reject `--dry-run` with `--execute`, warning about `--dry-run` otherwise
+    ///
+    /// Call before doing anything else.
+    pub fn check(&self) -> Result<(), crate::error::CliError> {
+        if self.execute && self.dry_run.dry_run {
+            let _ = crate::ops::reporter::error(
+                "`--dry-run` and `--execute` contradict each other; dry-run is the default, so drop `--dry-run`",
+            );
+            return Err(2.into());
+        }
+        self.dry_run.check();
+        Ok(())
+    }
+
+    pub fn dry_run(&self) -> bool {
+        !self.execute
+    }
+}
+
+/// `--dry-run`, accepted by every step from before dry-run became the default
+#[derive(Copy, Clone, Debug, Default, clap::Args)]
+pub struct DryRunArgs {
+    #[arg(short = 'n', long, hide = true)]
+    dry_run: bool,
+}
+
+impl DryRunArgs {
+    /// Warn that `--dry-run` is deprecated
+    pub fn check(&self) {
+        if self.dry_run {
+            let _ = crate::ops::reporter::warn(
+                "`--dry-run` is deprecated and has no effect, dry-run is the default",
+            );
+        }
+    }
+}
+
 pub fn verify_git_is_clean(
     path: &std::path::Path,
+    ws_config: &crate::config::Config,
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    use itertools::Itertools;
+
+    let _timer = crate::ops::timings::start("verify_git_is_clean");
     let mut success = true;
-    if let Some(dirty) = crate::ops::git::is_dirty(path)? {
-        let _ = crate::ops::shell::log(
+    let dirty = match ws_config.clean_check_path() {
+        Some(subtree) => crate::ops::git::is_dirty_under(&path.join(subtree))?,
+        None => crate::ops::git::is_dirty(path)?,
+    };
+    let dirty = match dirty {
+        Some(dirty) => disallowed_dirty(path, ws_config, dirty)?,
+        None => Vec::new(),
+    };
+    if !dirty.is_empty() {
+        let _ = crate::ops::reporter::log(
             level,
             format!(
                 "uncommitted changes detected, please resolve before release:\n  {}",
-                dirty.join("\n  ")
+                dirty.iter().join("\n  ")
             ),
         );
         if level == log::Level::Error {
@@ -40,11 +108,52 @@ pub fn verify_git_is_clean(
     Ok(success)
 }
 
+/// Drop uncommitted files matching `allow-dirty`
+fn disallowed_dirty(
+    ws_root: &std::path::Path,
+    ws_config: &crate::config::Config,
+    dirty: Vec<crate::ops::git::Dirty>,
+) -> CargoResult<Vec<crate::ops::git::Dirty>> {
+    if ws_config.allow_dirty().is_empty() {
+        return Ok(dirty);
+    }
+
+    let allowed = allowed_dirty(ws_config)?;
+    let repo_root = dunce::canonicalize(crate::ops::git::top_level(ws_root)?)?;
+    let ws_root = dunce::canonicalize(ws_root)?;
+    let remaining = dirty
+        .into_iter()
+        .filter(|entry| match entry {
+            crate::ops::git::Dirty::Path { path, .. } => !repo_root
+                .join(path)
+                .strip_prefix(&ws_root)
+                .map(|relative| allowed.is_match(relative))
+                .unwrap_or(false),
+            crate::ops::git::Dirty::State(_) => true,
+        })
+        .collect();
+    Ok(remaining)
+}
+
+/// Workspace-relative globs for uncommitted files the clean-tree check lets through
+pub fn allowed_dirty(ws_config: &crate::config::Config) -> CargoResult<globset::GlobSet> {
+    let mut allowed = globset::GlobSetBuilder::new();
+    for pattern in ws_config.allow_dirty() {
+        allowed.add(
+            globset::GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()?,
+        );
+    }
+    Ok(allowed.build()?)
+}
+
 pub fn verify_tags_missing(
     pkgs: &[plan::PackageRelease],
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_tags_missing");
     let mut success = true;
 
     let mut tag_exists = false;
@@ -53,12 +162,17 @@ pub fn verify_tags_missing(
         if let Some(tag_name) = pkg.planned_tag.as_ref() {
             if seen_tags.insert(tag_name) {
                 let cwd = &pkg.package_root;
-                if crate::ops::git::tag_exists(cwd, tag_name)? {
+                if let Some(info) = crate::ops::git::inspect_tag(cwd, tag_name)? {
                     let crate_name = pkg.meta.name.as_str();
-                    let _ = crate::ops::shell::log(
-                        level,
-                        format!("tag `{}` already exists (for `{}`)", tag_name, crate_name),
-                    );
+                    let mut message =
+                        format!("tag `{}` already exists (for `{}`)", tag_name, crate_name);
+                    if let Some(mismatch) = tag_mismatch(&pkg.config, &info) {
+                        message.push_str(&format!(
+                            " as {}; it may have been created by hand",
+                            mismatch
+                        ));
+                    }
+                    let _ = crate::ops::reporter::log(level, message);
                     tag_exists = true;
                 }
             }
@@ -74,11 +188,148 @@ pub fn verify_tags_missing(
     Ok(success)
 }
 
+/// How `info` falls short of the tags `config` creates
+fn tag_mismatch(config: &crate::config::Config, info: &crate::ops::git::TagInfo) -> Option<String> {
+    if !config.annotated_tag() {
+        None
+    } else if !info.annotated {
+        let expected = if config.sign_tag() {
+            "a signed"
+        } else {
+            "an annotated"
+        };
+        Some(format!(
+            "a lightweight tag where {} one is expected",
+            expected
+        ))
+    } else if config.sign_tag() && !info.signed {
+        Some("an unsigned tag where a signed one is expected".to_owned())
+    } else {
+        None
+    }
+}
+
+/// Check that tags about to be pushed are annotated and signed, per `verify-tag-annotation`
+pub fn verify_tag_annotation(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_tag_annotation");
+    let mut success = true;
+
+    let mut unsigned = Vec::new();
+    let mut seen_tags = std::collections::HashSet::new();
+    for pkg in pkgs {
+        if !pkg.config.verify_tag_annotation() || !pkg.config.sign_tag() || !pkg.config.push() {
+            continue;
+        }
+        if let Some(tag_name) = pkg.planned_tag.as_ref() {
+            if seen_tags.insert(tag_name) {
+                // Missing tags are reported by `verify_tags_exist`
+                if let Some(info) = crate::ops::git::inspect_tag(&pkg.package_root, tag_name)? {
+                    if !info.signed {
+                        unsigned.push(format!("{} ({})", tag_name, info.kind()));
+                    }
+                }
+            }
+        }
+    }
+    if !unsigned.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "tags to push must be annotated and signed: {}",
+                unsigned.join(", ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+/// Packages sharing a tag would otherwise have all but the first silently go untagged
+pub fn verify_unique_tags(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_unique_tags");
+    let mut success = true;
+
+    let collisions = tag_collisions(pkgs);
+    for collision in &collisions {
+        let _ = crate::ops::reporter::log(level, collision);
+    }
+    if !collisions.is_empty() && level == log::Level::Error {
+        success = false;
+        if !dry_run {
+            return Err(101.into());
+        }
+    }
+
+    Ok(success)
+}
+
+fn tag_collisions(pkgs: &[plan::PackageRelease]) -> Vec<String> {
+    let mut by_tag: indexmap::IndexMap<&str, Vec<&plan::PackageRelease>> = Default::default();
+    for pkg in pkgs {
+        if let Some(tag_name) = pkg.planned_tag.as_deref() {
+            by_tag.entry(tag_name).or_default().push(pkg);
+        }
+    }
+
+    let mut collisions = Vec::new();
+    for (tag_name, tagged) in by_tag {
+        let first = tagged[0];
+        let version = |p: &plan::PackageRelease| {
+            p.planned_version
+                .as_ref()
+                .unwrap_or(&p.initial_version)
+                .full_version
+                .clone()
+        };
+        // A shared version is released together, so it can share a tag
+        let is_shared = first.config.shared_version().is_some()
+            && tagged.iter().all(|p| {
+                p.config.shared_version() == first.config.shared_version()
+                    && version(p) == version(first)
+            });
+        if tagged.len() < 2 || is_shared {
+            continue;
+        }
+        let sources: Vec<_> = tagged
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} (`tag-prefix = {:?}`, `tag-name = {:?}`)",
+                    p.meta.name,
+                    p.config.tag_prefix(p.is_root),
+                    p.config.tag_name()
+                )
+            })
+            .collect();
+        collisions.push(format!(
+            "tag `{}` is planned for multiple packages, give each a distinct `tag-prefix` or `tag-name`:\n  {}",
+            tag_name,
+            sources.join("\n  ")
+        ));
+    }
+    collisions
+}
+
 pub fn verify_tags_exist(
     pkgs: &[plan::PackageRelease],
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_tags_exist");
     let mut success = true;
 
     let mut tag_missing = false;
@@ -89,7 +340,7 @@ pub fn verify_tags_exist(
                 let cwd = &pkg.package_root;
                 if !crate::ops::git::tag_exists(cwd, tag_name)? {
                     let crate_name = pkg.meta.name.as_str();
-                    let _ = crate::ops::shell::log(
+                    let _ = crate::ops::reporter::log(
                         level,
                         format!("tag `{}` doesn't exist (for `{}`)", tag_name, crate_name),
                     );
@@ -108,33 +359,171 @@ pub fn verify_tags_exist(
     Ok(success)
 }
 
+/// Check the current branch against each package's `allow-branch`
+///
+/// Without packages, the workspace's `allow-branch` is checked.
 pub fn verify_git_branch(
     path: &std::path::Path,
     ws_config: &crate::config::Config,
+    pkgs: &[plan::PackageRelease],
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_git_branch");
     use itertools::Itertools;
 
     let mut success = true;
 
     let branch = crate::ops::git::current_branch(path)?;
+    let mut disallowed: std::collections::BTreeMap<String, Vec<&str>> = Default::default();
+    if pkgs.is_empty() {
+        if !is_allowed_branch(&branch, ws_config)? {
+            disallowed.insert(ws_config.allow_branch().join(", "), Vec::new());
+        }
+    } else {
+        for pkg in pkgs {
+            if !is_allowed_branch(&branch, &pkg.config)? {
+                disallowed
+                    .entry(pkg.config.allow_branch().join(", "))
+                    .or_default()
+                    .push(pkg.meta.name.as_str());
+            }
+        }
+    }
+    let everything =
+        disallowed.len() == 1 && disallowed.values().all(|names| names.len() == pkgs.len());
+    for (allowed, names) in &disallowed {
+        let message = if everything {
+            format!(
+                "cannot release from branch {:?}, instead switch to {:?}",
+                branch, allowed
+            )
+        } else {
+            format!(
+                "cannot release {} from branch {:?}, instead switch to {:?}",
+                names.join(", "),
+                branch,
+                allowed
+            )
+        };
+        let _ = crate::ops::reporter::log(level, message);
+    }
+    if !disallowed.is_empty() && level == log::Level::Error {
+        success = false;
+        if !dry_run {
+            return Err(101.into());
+        }
+    }
+
+    Ok(success)
+}
+
+/// Move packages whose `allow-branch` rules out the current branch to `excluded_pkgs`
+pub fn exclude_disallowed_branch(
+    path: &std::path::Path,
+    selected_pkgs: &mut Vec<plan::PackageRelease>,
+    excluded_pkgs: &mut Vec<plan::PackageRelease>,
+) -> Result<(), crate::error::CliError> {
+    use itertools::Itertools;
+
+    let branch = crate::ops::git::current_branch(path)?;
+    let mut allowed = Vec::new();
+    for mut pkg in std::mem::take(selected_pkgs) {
+        if is_allowed_branch(&branch, &pkg.config)? {
+            allowed.push(pkg);
+            continue;
+        }
+        let _ = crate::ops::reporter::note(format!(
+            "skipping {} which is released from {:?}, not {:?}; pass `--strict-branch` to fail instead",
+            pkg.meta.name,
+            pkg.config.allow_branch().join(", "),
+            branch
+        ));
+        pkg.planned_version = None;
+        pkg.planned_tag = None;
+        pkg.skip(plan::SkipReason::Branch);
+        excluded_pkgs.push(pkg);
+    }
+    *selected_pkgs = allowed;
+
+    Ok(())
+}
+
+fn is_allowed_branch(
+    branch: &str,
+    config: &crate::config::Config,
+) -> Result<bool, crate::error::CliError> {
     let mut good_branches = ignore::gitignore::GitignoreBuilder::new(".");
-    for pattern in ws_config.allow_branch() {
+    for pattern in config.allow_branch() {
         good_branches.add_line(None, pattern)?;
     }
     let good_branches = good_branches.build()?;
-    let good_branch_match = good_branches.matched_path_or_any_parents(&branch, false);
-    if !good_branch_match.is_ignore() {
-        let _ = crate::ops::shell::log(
+    let good_branch_match = good_branches.matched_path_or_any_parents(branch, false);
+    log::trace!("{} matched {:?}", branch, good_branch_match);
+    Ok(good_branch_match.is_ignore())
+}
+
+/// [`verify_if_behind`] as a warning, for steps that don't push
+///
+/// Nothing gets pushed by these steps, so only pay for a `git fetch` when asked.
+pub fn verify_if_behind_when_asked(
+    check_remote: bool,
+    path: &std::path::Path,
+    ws_config: &crate::config::Config,
+    dry_run: bool,
+) -> Result<bool, crate::error::CliError> {
+    if check_remote {
+        verify_if_behind(path, ws_config, dry_run, log::Level::Warn)
+    } else {
+        Ok(true)
+    }
+}
+
+pub fn verify_if_behind(
+    path: &std::path::Path,
+    ws_config: &crate::config::Config,
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    verify_if_behind_with(
+        path,
+        ws_config,
+        crate::ops::cargo::is_offline(),
+        dry_run,
+        level,
+    )
+}
+
+fn verify_if_behind_with(
+    path: &std::path::Path,
+    ws_config: &crate::config::Config,
+    offline: bool,
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_if_behind");
+    let mut success = true;
+
+    // If we are not pushing, we are not behind our push target.
+    if !ws_config.push() {
+        return Ok(success);
+    }
+
+    let git_remote = ws_config.push_remote();
+    let branch = crate::ops::git::current_branch(path)?;
+    if offline {
+        let _ = crate::ops::reporter::note(format!(
+            "offline, skipping check whether {} is behind {}/{}",
+            branch, git_remote, branch
+        ));
+        return Ok(success);
+    }
+    crate::ops::git::fetch(path, git_remote, &branch, false, false)?;
+    if crate::ops::git::is_behind_remote(path, git_remote, &branch)? {
+        let _ = crate::ops::reporter::log(
             level,
-            format!(
-                "cannot release from branch {:?}, instead switch to {:?}",
-                branch,
-                ws_config.allow_branch().join(", ")
-            ),
+            format!("{} is behind {}/{}", branch, git_remote, branch),
         );
-        log::trace!("due to {:?}", good_branch_match);
         if level == log::Level::Error {
             success = false;
             if !dry_run {
@@ -146,26 +535,121 @@ pub fn verify_git_branch(
     Ok(success)
 }
 
-pub fn verify_if_behind(
+pub fn verify_release_pushed(
+    path: &std::path::Path,
+    ws_config: &crate::config::Config,
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    verify_release_pushed_with(
+        path,
+        ws_config,
+        pkgs,
+        crate::ops::cargo::is_offline(),
+        dry_run,
+        level,
+    )
+}
+
+fn verify_release_pushed_with(
     path: &std::path::Path,
     ws_config: &crate::config::Config,
+    pkgs: &[plan::PackageRelease],
+    offline: bool,
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_release_pushed");
     let mut success = true;
 
-    // If we are not pushing, we are not behind our push target.
+    // If we are not pushing, nothing is expected to be on the remote.
     if !ws_config.push() {
         return Ok(success);
     }
 
     let git_remote = ws_config.push_remote();
     let branch = crate::ops::git::current_branch(path)?;
-    crate::ops::git::fetch(path, git_remote, &branch)?;
-    if crate::ops::git::is_behind_remote(path, git_remote, &branch)? {
-        let _ = crate::ops::shell::log(
+    // Offline, the last fetched state of the remote is still worth comparing against
+    if !offline {
+        crate::ops::git::fetch(path, git_remote, &branch, false, false)?;
+    }
+    let unpushed = match crate::ops::git::unpushed_commits(path, git_remote, &branch)? {
+        Some(unpushed) => unpushed,
+        None => return Ok(success),
+    };
+
+    let prefixes: Vec<_> = std::iter::once(commit::release_message_prefix(
+        ws_config.pre_release_commit_message(),
+        None,
+    ))
+    .chain(pkgs.iter().map(|pkg| {
+        commit::release_message_prefix(
+            pkg.config.pre_release_commit_message(),
+            Some(pkg.meta.name.as_str()),
+        )
+    }))
+    .flatten()
+    .collect();
+    let releases: Vec<_> = unpushed
+        .iter()
+        .filter(|c| prefixes.iter().any(|p| c.summary.starts_with(p.as_str())))
+        .collect();
+    if !releases.is_empty() {
+        let tags: Vec<_> = releases
+            .iter()
+            .flat_map(|c| c.tags.iter().map(|t| t.as_str()))
+            .collect();
+        let tags = if tags.is_empty() {
+            "no tags".to_owned()
+        } else {
+            format!("tags {}", tags.join(", "))
+        };
+        let _ = crate::ops::reporter::log(
             level,
-            format!("{} is behind {}/{}", branch, git_remote, branch),
+            format!(
+                "previous release appears unpushed ({} commit{}, {}); run `cargo release push` first",
+                releases.len(),
+                if releases.len() == 1 { "" } else { "s" },
+                tags
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+pub fn verify_online_publish(
+    pkgs: &[plan::PackageRelease],
+    offline: bool,
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_online_publish");
+    let mut success = true;
+
+    if !offline {
+        return Ok(success);
+    }
+
+    let publishing: Vec<_> = pkgs
+        .iter()
+        .filter(|p| p.config.publish())
+        .map(|p| p.meta.name.as_str())
+        .collect();
+    if !publishing.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "cannot publish {} while offline (`CARGO_NET_OFFLINE` is set); pass `--no-publish` to release without publishing",
+                publishing.join(", ")
+            ),
         );
         if level == log::Level::Error {
             success = false;
@@ -183,18 +667,22 @@ pub fn verify_monotonically_increasing(
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_monotonically_increasing");
     let mut success = true;
 
     let mut downgrades_present = false;
     for pkg in pkgs {
         if let Some(version) = pkg.planned_version.as_ref() {
-            if version.full_version < pkg.initial_version.full_version {
+            // The manifest may be ahead of what this branch released, so trust the prior release
+            let prior = pkg.prior_version.as_ref().unwrap_or(&pkg.initial_version);
+            // Build metadata has no precedence, so changing only it is never a downgrade
+            if version.bare_version < prior.bare_version {
                 let crate_name = pkg.meta.name.as_str();
-                let _ = crate::ops::shell::log(
+                let _ = crate::ops::reporter::log(
                     level,
                     format!(
                         "cannot downgrade {} from {} to {}",
-                        crate_name, version.full_version, pkg.initial_version.full_version
+                        crate_name, version.full_version, prior.full_version
                     ),
                 );
                 downgrades_present = true;
@@ -211,23 +699,98 @@ pub fn verify_monotonically_increasing(
     Ok(success)
 }
 
+/// The index `pkg` gets published to, unless it's a registry that isn't checked
+pub fn publish_index<'i>(
+    pkg: &plan::PackageRelease,
+    index: &'i mut crate::ops::index::CratesIoIndex,
+    index_urls: &'i mut crate::ops::index::IndexUrls,
+) -> Option<&'i mut crate::ops::index::CratesIoIndex> {
+    match (pkg.config.index_url(), pkg.config.registry()) {
+        (Some(index_url), _) => Some(index_urls.open(index_url)),
+        (None, None) => Some(index),
+        (None, Some(_)) => None,
+    }
+}
+
+/// Compare `pkg`'s `.crate` against the one `index` has for its version, for `publish-skip-identical`
+///
+/// A planned version is packaged from the manifest the version step wrote, so this is only called
+/// for one after that step.  A dry-run never writes it, leaving that `Unknown`.
+pub fn published_content(
+    ws_meta: &cargo_metadata::Metadata,
+    pkg: &plan::PackageRelease,
+    index: &impl crate::ops::cargo::PublishIndex,
+    dry_run: bool,
+) -> Result<crate::ops::cargo::PublishedContent, crate::error::CliError> {
+    if pkg.planned_version.is_some() && dry_run {
+        return Ok(crate::ops::cargo::PublishedContent::Unknown);
+    }
+    let version = &pkg
+        .planned_version
+        .as_ref()
+        .unwrap_or(&pkg.initial_version)
+        .full_version_string;
+    let archive = pkg.package_archive(ws_meta, version)?;
+    let content =
+        crate::ops::cargo::published_content(index, pkg.published_name(), version, &archive.path)?;
+    Ok(content)
+}
+
+/// Report how `pkg`'s already published version compares, returning whether it counts as
+/// released
+pub fn report_published_content(
+    pkg: &plan::PackageRelease,
+    content: &crate::ops::cargo::PublishedContent,
+) -> bool {
+    let crate_name = pkg.meta.name.as_str();
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    match content {
+        crate::ops::cargo::PublishedContent::Identical => {
+            let _ = crate::ops::reporter::note(format!(
+                "{} {} already published with identical content, skipping",
+                crate_name, version.full_version_string
+            ));
+            true
+        }
+        crate::ops::cargo::PublishedContent::Different { local, published } => {
+            let _ = crate::ops::reporter::error(format!(
+                "{} {} is already published with different content (sha256 {} locally, {} published)",
+                crate_name, version.full_version_string, local, published
+            ));
+            false
+        }
+        crate::ops::cargo::PublishedContent::Unknown => {
+            let _ = crate::ops::reporter::error(format!(
+                "{} {} is already published",
+                crate_name, version.full_version_string
+            ));
+            false
+        }
+    }
+}
+
 pub fn verify_rate_limit(
     pkgs: &[plan::PackageRelease],
-    index: &crates_index::Index,
+    index: &crate::ops::index::CratesIoIndex,
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_rate_limit");
     let mut success = true;
 
+    if !index.is_available() {
+        log::debug!("skipping rate limit check without a crates.io index");
+        return Ok(success);
+    }
+
     // "It's not particularly secret, we just don't publish it other than in the code because
     // it's subject to change. The responses from the rate limited requests on when to try
     // again contain the most accurate information."
     let mut new = 0;
     let mut existing = 0;
     for pkg in pkgs {
-        if pkg.config.registry().is_none() && pkg.config.publish() {
-            let crate_name = pkg.meta.name.as_str();
-            if index.crate_(crate_name).is_some() {
+        if pkg.config.is_crates_io() && pkg.config.publish() {
+            if index.crate_(pkg.published_name()).is_some() {
                 existing += 1;
             } else {
                 new += 1;
@@ -238,7 +801,7 @@ pub fn verify_rate_limit(
     if 5 < new {
         // "The rate limit for creating new crates is 1 crate every 10 minutes, with a burst of 5 crates."
         success = false;
-        let _ = crate::ops::shell::log(
+        let _ = crate::ops::reporter::log(
             level,
             format!(
                 "attempting to publish {} new crates which is above the crates.io rate limit",
@@ -250,7 +813,7 @@ pub fn verify_rate_limit(
     if 30 < existing {
         // "The rate limit for new versions of existing crates is 1 per minute, with a burst of 30 crates, so when releasing new versions of these crates, you shouldn't hit the limit."
         success = false;
-        let _ = crate::ops::shell::log(
+        let _ = crate::ops::reporter::log(
             level,
             format!(
                 "attempting to publish {} existing crates which is above the crates.io rate limit",
@@ -266,21 +829,384 @@ pub fn verify_rate_limit(
     Ok(success)
 }
 
-pub fn verify_metadata(
+pub fn verify_registries(
+    path: &std::path::Path,
     pkgs: &[plan::PackageRelease],
     dry_run: bool,
     level: log::Level,
 ) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_registries");
     let mut success = true;
 
-    for pkg in pkgs {
-        if !pkg.config.publish() {
-            continue;
+    let registries: Vec<_> = pkgs
+        .iter()
+        .filter(|p| p.config.publish())
+        .filter_map(|p| p.config.registry().map(|r| (p.meta.name.as_str(), r)))
+        .collect();
+    if registries.is_empty() {
+        return Ok(success);
+    }
+
+    let known = crate::ops::cargo::known_registries(path)?;
+    for (crate_name, registry) in registries {
+        if !crate::ops::cargo::is_known_registry(&known, registry) {
+            let _ = crate::ops::reporter::log(
+                level,
+                format!(
+                    "registry `{}` for `{}` is not defined in cargo config; known registries: {}",
+                    registry,
+                    crate_name,
+                    itertools::join(&known, ", ")
+                ),
+            );
+            if level == log::Level::Error {
+                success = false;
+            }
         }
-        let mut missing = Vec::new();
+    }
 
-        // General cargo rules
-        if pkg
+    if !success && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
+}
+
+pub fn verify_credentials(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_credentials");
+    let mut success = true;
+
+    let mut registries = std::collections::BTreeMap::<_, Vec<_>>::new();
+    for pkg in pkgs.iter().filter(|p| {
+        // Without a registry name, there is no cargo config to look credentials up in
+        p.config.publish()
+            && p.config.index_url().is_none()
+            && plan::is_publishable(&p.config, &p.meta)
+    }) {
+        registries
+            .entry(pkg.config.registry())
+            .or_default()
+            .push(pkg.meta.name.as_str());
+    }
+
+    for (registry, crate_names) in registries {
+        if crate::ops::cargo::has_credentials(registry)? {
+            continue;
+        }
+        let login = match registry {
+            Some(registry) => format!("cargo login --registry {}", registry),
+            None => "cargo login".to_owned(),
+        };
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "no token found for publishing {} to {}; run `{}` or set `{}`",
+                crate_names.join(", "),
+                registry.unwrap_or("crates.io"),
+                login,
+                crate::ops::cargo::registry_token_env(registry),
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+        }
+    }
+
+    if !success && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
+}
+
+/// Fail on path dependencies left behind by a package removed from the workspace
+///
+/// Path dependencies outside of the workspace root or in `workspace.exclude` are left alone as they
+/// were never members.
+pub fn verify_path_dependencies(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_path_dependencies");
+    let mut success = true;
+
+    let ws_root = ws_meta.workspace_root.as_std_path();
+    let members: Vec<_> = ws_meta
+        .packages
+        .iter()
+        .filter(|p| ws_meta.workspace_members.contains(&p.id))
+        .filter_map(|p| p.manifest_path.parent())
+        .map(|p| p.as_std_path())
+        .collect();
+    let excluded = workspace_excludes(ws_root)?;
+    for pkg in pkgs {
+        for dep in &pkg.meta.dependencies {
+            let path = match dep.path.as_ref() {
+                Some(path) => path.as_std_path(),
+                None => continue,
+            };
+            let reason = if !path.join("Cargo.toml").exists() {
+                "does not exist"
+            } else if path.starts_with(ws_root)
+                && !members.contains(&path)
+                && !excluded.iter().any(|e| path.starts_with(e))
+            {
+                "is not a workspace member"
+            } else {
+                continue;
+            };
+            let _ = crate::ops::reporter::log(
+                level,
+                format!(
+                    "{} depends on {} at {}, which {}; remove the dependency or restore the package",
+                    pkg.meta.name,
+                    dep.name,
+                    path.display(),
+                    reason
+                ),
+            );
+            if level == log::Level::Error {
+                success = false;
+            }
+        }
+    }
+
+    if !success && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
+}
+
+/// Absolute paths listed in the root manifest's `workspace.exclude`
+fn workspace_excludes(ws_root: &std::path::Path) -> CargoResult<Vec<std::path::PathBuf>> {
+    let manifest_path = ws_root.join("Cargo.toml");
+    let manifest: toml::Table = toml::from_str(&std::fs::read_to_string(&manifest_path)?)
+        .with_context(|| format!("failed to parse {}", manifest_path.display()))?;
+    let excluded = manifest
+        .get("workspace")
+        .and_then(|w| w.get("exclude"))
+        .and_then(|e| e.as_array())
+        .map(|e| {
+            e.iter()
+                .filter_map(|p| p.as_str())
+                .map(|p| ws_root.join(p))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(excluded)
+}
+
+/// Check every `pre-release-replacements` entry up front, so a stale pattern fails the release
+/// before any file is modified
+pub fn verify_replacements(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_replacements");
+    let mut success = true;
+
+    let problems = replace::replacement_problems(pkgs)?;
+    if !problems.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "pre-release replacements would fail:\n  {}",
+                problems.join("\n  ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+pub fn verify_placeholder_versions(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_placeholder_versions");
+    let mut success = true;
+
+    let placeholders: Vec<_> = pkgs
+        .iter()
+        .filter(|p| !p.config.allow_placeholder())
+        .filter(|p| {
+            p.config
+                .is_placeholder_version(&p.initial_version.full_version_string)
+        })
+        .map(|p| format!("{} {}", p.meta.name, p.initial_version.full_version_string))
+        .collect();
+    if !placeholders.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "refusing to release placeholder versions:\n  {}\nexclude them with `--exclude <PKG>` or set a real initial version; to release anyway, pass `--allow-placeholder-versions` or set `allow-placeholder = true`",
+                placeholders.join("\n  ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+/// Renaming on publish would leave dependents requiring a crate that doesn't exist
+pub fn verify_publish_as(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_publish_as");
+    let mut success = true;
+
+    for pkg in pkgs {
+        let publish_as = match pkg.config.publish_as() {
+            Some(publish_as) if pkg.config.publish() => publish_as,
+            _ => continue,
+        };
+        if pkg.dependents.is_empty() {
+            continue;
+        }
+        let dependents: Vec<_> = pkg.dependents.iter().map(|d| d.pkg.name.as_str()).collect();
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "{} is published as {} but workspace members depend on it, which is unsupported: {}",
+                pkg.meta.name,
+                publish_as,
+                dependents.join(", ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+/// crates.io only rejects oversized packages after the upload, late in a release
+pub fn verify_package_size(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+    verify_size: bool,
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_package_size");
+    let mut success = true;
+
+    let pkgs: Vec<_> = pkgs
+        .iter()
+        .filter(|p| p.config.publish())
+        .filter_map(|p| p.config.max_package_size().map(|max| (p, max)))
+        .collect();
+    if pkgs.is_empty() {
+        return Ok(success);
+    }
+    if dry_run && !verify_size {
+        log::debug!("skipping `max-package-size` in a dry-run without `--verify-size`");
+        return Ok(success);
+    }
+
+    for (pkg, max) in pkgs {
+        let crate_name = pkg.meta.name.as_str();
+        let archive = pkg.planned_archive(ws_meta)?;
+        if max < archive.size {
+            let largest = crate::ops::cargo::largest_files(&archive.path, 5)?;
+            let largest: Vec<_> = largest
+                .iter()
+                .map(|(path, size)| format!("{} ({} bytes)", path.display(), size))
+                .collect();
+            let _ = crate::ops::reporter::log(
+                level,
+                format!(
+                    "{} packages to {} bytes, over its `max-package-size` of {} bytes; largest files:\n  {}",
+                    crate_name,
+                    archive.size,
+                    max,
+                    largest.join("\n  ")
+                ),
+            );
+            if level == log::Level::Error {
+                success = false;
+                if !dry_run {
+                    return Err(101.into());
+                }
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+/// Fail on every package cargo can't list, rather than on the first part way through publishing
+pub fn verify_package_content(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_package_content");
+    let mut success = true;
+
+    for pkg in pkgs {
+        if !pkg.config.publish() {
+            continue;
+        }
+        if let Err(err) = pkg.package_content() {
+            let _ = crate::ops::reporter::log(
+                level,
+                format!("{} can't be packaged: {:#}", pkg.meta.name, err),
+            );
+            success = false;
+        }
+    }
+
+    if !success && level == log::Level::Error && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
+}
+
+pub fn verify_metadata(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_metadata");
+    let mut success = true;
+
+    for pkg in pkgs {
+        if !pkg.config.publish() {
+            continue;
+        }
+        let mut missing = Vec::new();
+
+        // General cargo rules
+        if pkg
             .meta
             .description
             .as_deref()
@@ -312,7 +1238,7 @@ pub fn verify_metadata(
         }
 
         if !missing.is_empty() {
-            let _ = crate::ops::shell::log(
+            let _ = crate::ops::reporter::log(
                 level,
                 format!(
                     "{} is missing the following fields:\n  {}",
@@ -331,69 +1257,274 @@ pub fn verify_metadata(
     Ok(success)
 }
 
-pub fn warn_changed(
-    ws_meta: &cargo_metadata::Metadata,
+/// Check the active cargo can read the manifests of packages inheriting from the workspace
+///
+/// Older cargo only fails on `workspace = true` once it gets to `cargo publish`.
+pub fn verify_manifest_inheritance(
     pkgs: &[plan::PackageRelease],
-) -> Result<(), crate::error::CliError> {
-    let mut changed_pkgs = std::collections::HashSet::new();
-    for pkg in pkgs {
-        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
-        let crate_name = pkg.meta.name.as_str();
-        if let Some(prior_tag_name) = &pkg.prior_tag {
-            if let Some(changed) =
-                crate::steps::version::changed_since(ws_meta, pkg, prior_tag_name)
-            {
-                if !changed.is_empty() {
-                    log::debug!(
-                        "Files changed in {} since {}: {:#?}",
-                        crate_name,
-                        prior_tag_name,
-                        changed
-                    );
-                    changed_pkgs.insert(&pkg.meta.id);
-                    if changed.len() == 1 && changed[0].ends_with("Cargo.lock") {
-                        // Lock file changes don't invalidate dependencies
-                    } else {
-                        changed_pkgs.extend(pkg.dependents.iter().map(|d| &d.pkg.id));
-                    }
-                } else if changed_pkgs.contains(&pkg.meta.id) {
-                    log::debug!(
-                        "Dependency changed for {} since {}",
-                        crate_name,
-                        prior_tag_name,
-                    );
-                    changed_pkgs.insert(&pkg.meta.id);
-                    changed_pkgs.extend(pkg.dependents.iter().map(|d| &d.pkg.id));
-                } else {
-                    let _ = crate::ops::shell::warn(format!(
-                        "updating {} to {} despite no changes made since tag {}",
-                        crate_name, version.full_version_string, prior_tag_name
-                    ));
-                }
-            } else {
-                log::debug!(
-                        "cannot detect changes for {} because tag {} is missing. Try setting `--prev-tag-name <TAG>`.",
-                        crate_name,
-                        prior_tag_name
-                    );
-            }
-        } else {
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_manifest_inheritance");
+    let mut success = true;
+
+    let cargo_version = match crate::ops::cargo::cargo_version() {
+        Some(cargo_version) => cargo_version,
+        None => {
             log::debug!(
-                    "cannot detect changes for {} because no tag was found. Try setting `--prev-tag-name <TAG>`.",
-                    crate_name,
-                );
+                "could not determine the cargo version, assuming it supports workspace inheritance"
+            );
+            return Ok(success);
         }
+    };
+    for problem in inheritance_problems(pkgs, cargo_version)? {
+        let _ = crate::ops::reporter::log(level, problem);
+        success = false;
     }
 
-    Ok(())
+    if !success && level == log::Level::Error && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
 }
 
-pub fn find_shared_versions(
+/// Each published package inheriting fields `cargo_version` can't
+fn inheritance_problems(
     pkgs: &[plan::PackageRelease],
-) -> Result<Option<plan::Version>, crate::error::CliError> {
-    let mut is_shared = true;
-    let mut shared_versions: std::collections::HashMap<&str, &plan::Version> = Default::default();
-    for pkg in pkgs {
+    cargo_version: &semver::Version,
+) -> Result<Vec<String>, crate::error::CliError> {
+    let required = crate::ops::cargo::MANIFEST_INHERITANCE;
+    if required <= *cargo_version {
+        return Ok(Vec::new());
+    }
+
+    let mut problems = Vec::new();
+    for pkg in pkgs {
+        if !pkg.config.publish() {
+            continue;
+        }
+        let keys = crate::config::inherited_keys(&pkg.manifest_path)?;
+        if !keys.is_empty() {
+            problems.push(format!(
+                "{} inherits `{}` from the workspace, which needs cargo {} but cargo {} is active",
+                pkg.meta.name,
+                keys.join("`, `"),
+                required,
+                cargo_version
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+/// Check the active toolchain is new enough to build each published package
+///
+/// `cargo publish` builds the package to verify it, which fails on a `rust-version` newer than
+/// the toolchain.
+pub fn verify_rust_version(
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_rust_version");
+    let mut success = true;
+
+    let cargo_version = match crate::ops::cargo::cargo_version() {
+        Some(cargo_version) => cargo_version,
+        None => {
+            log::debug!("could not determine the cargo version, assuming it meets `rust-version`");
+            return Ok(success);
+        }
+    };
+    let problems = rust_version_problems(pkgs, cargo_version);
+    if !problems.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "the active toolchain is older than `rust-version`:\n  {}",
+                problems.join("\n  ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+/// Each published and verified package whose `rust-version` is newer than `cargo_version`
+fn rust_version_problems(
+    pkgs: &[plan::PackageRelease],
+    cargo_version: &semver::Version,
+) -> Vec<String> {
+    pkgs.iter()
+        .filter(|pkg| pkg.config.publish() && pkg.config.verify())
+        .filter_map(|pkg| {
+            let rust_version = pkg.meta.rust_version.as_ref()?;
+            (cargo_version < rust_version).then(|| {
+                format!(
+                    "{} needs {} but {} is active",
+                    pkg.meta.name, rust_version, cargo_version
+                )
+            })
+        })
+        .collect()
+}
+
+/// Check packages changed since their last release updated their changelog
+///
+/// A package's changelog is whichever `pre-release-replacements` file is named like
+/// `CHANGELOG.md`.
+pub fn verify_changelog(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, crate::error::CliError> {
+    let _timer = crate::ops::timings::start("verify_changelog");
+    let mut success = true;
+
+    let problems = changelog_problems(ws_meta, pkgs)?;
+    if !problems.is_empty() {
+        let _ = crate::ops::reporter::log(
+            level,
+            format!(
+                "changes aren't in the changelog:\n  {}",
+                problems.join("\n  ")
+            ),
+        );
+        if level == log::Level::Error {
+            success = false;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+    }
+
+    Ok(success)
+}
+
+/// Each package with changes since its prior tag but none to its changelog
+fn changelog_problems(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+) -> Result<Vec<String>, crate::error::CliError> {
+    let mut problems = Vec::new();
+    for pkg in pkgs {
+        let prior_tag = match pkg.prior_tag.as_deref() {
+            Some(prior_tag) => prior_tag,
+            None => continue,
+        };
+        let changelogs: std::collections::BTreeSet<_> = pkg
+            .config
+            .pre_release_replacements()
+            .iter()
+            .filter(|replace| {
+                replace
+                    .file
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| name.to_ascii_lowercase().starts_with("changelog"))
+                    .unwrap_or(false)
+            })
+            // Missing files are reported by `verify_replacements`
+            .filter_map(|replace| dunce::canonicalize(pkg.package_root.join(&replace.file)).ok())
+            .collect();
+        if changelogs.is_empty() {
+            continue;
+        }
+        let pkg_changed = version::changed_since(ws_meta, pkg, prior_tag)
+            .map(|changed| !changed.is_empty())
+            .unwrap_or(false);
+        if !pkg_changed {
+            continue;
+        }
+        // The changelog may live outside of the package
+        let changed =
+            crate::ops::git::changed_files(ws_meta.workspace_root.as_std_path(), prior_tag)?
+                .unwrap_or_default();
+        for changelog in changelogs {
+            if !changed.contains(&changelog) {
+                problems.push(format!(
+                    "{} changed since {} but {} didn't",
+                    pkg.meta.name,
+                    prior_tag,
+                    changelog.display()
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+pub fn warn_changed(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &[plan::PackageRelease],
+) -> Result<(), crate::error::CliError> {
+    let mut changed_pkgs = indexmap::IndexSet::new();
+    for pkg in pkgs {
+        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+        let crate_name = pkg.meta.name.as_str();
+        if let Some(prior_tag_name) = &pkg.prior_tag {
+            if let Some(changed) =
+                crate::steps::version::changed_since(ws_meta, pkg, prior_tag_name)
+            {
+                if !changed.is_empty() {
+                    log::debug!(
+                        "Files changed in {} since {}: {:#?}",
+                        crate_name,
+                        prior_tag_name,
+                        changed
+                    );
+                    changed_pkgs.insert(&pkg.meta.id);
+                    if changed.len() == 1 && changed[0].ends_with("Cargo.lock") {
+                        // Lock file changes don't invalidate dependencies
+                    } else {
+                        changed_pkgs.extend(pkg.dependents.iter().map(|d| &d.pkg.id));
+                    }
+                } else if changed_pkgs.contains(&pkg.meta.id) {
+                    log::debug!(
+                        "Dependency changed for {} since {}",
+                        crate_name,
+                        prior_tag_name,
+                    );
+                    changed_pkgs.insert(&pkg.meta.id);
+                    changed_pkgs.extend(pkg.dependents.iter().map(|d| &d.pkg.id));
+                } else {
+                    let _ = crate::ops::reporter::warn(format!(
+                        "updating {} to {} despite no changes made since tag {}",
+                        crate_name, version.full_version_string, prior_tag_name
+                    ));
+                }
+            } else {
+                log::debug!(
+                        "cannot detect changes for {} because tag {} is missing. Try setting `--prev-tag-name <TAG>`.",
+                        crate_name,
+                        prior_tag_name
+                    );
+            }
+        } else {
+            log::debug!(
+                    "cannot detect changes for {} because no tag was found. Try setting `--prev-tag-name <TAG>`.",
+                    crate_name,
+                );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn find_shared_versions(
+    pkgs: &[plan::PackageRelease],
+) -> Result<Option<plan::Version>, crate::error::CliError> {
+    let mut is_shared = true;
+    let mut shared_versions: indexmap::IndexMap<&str, &plan::Version> = Default::default();
+    for pkg in pkgs {
         let group_name = if let Some(group_name) = pkg.config.shared_version() {
             group_name
         } else {
@@ -401,10 +1532,10 @@ pub fn find_shared_versions(
         };
         let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
         match shared_versions.entry(group_name) {
-            std::collections::hash_map::Entry::Occupied(existing) => {
+            indexmap::map::Entry::Occupied(existing) => {
                 if version.bare_version != existing.get().bare_version {
                     is_shared = false;
-                    let _ = crate::ops::shell::error(format!(
+                    let _ = crate::ops::reporter::error(format!(
                         "{} has version {}, should be {}",
                         pkg.meta.name,
                         version.bare_version_string,
@@ -412,13 +1543,15 @@ pub fn find_shared_versions(
                     ));
                 }
             }
-            std::collections::hash_map::Entry::Vacant(vacant) => {
+            indexmap::map::Entry::Vacant(vacant) => {
                 vacant.insert(version);
             }
         }
     }
     if !is_shared {
-        let _ = crate::ops::shell::error("crate versions deviated, aborting");
+        let _ = crate::ops::reporter::error(
+            "crate versions deviated, aborting\nif an earlier release was interrupted, bring the group back to one version with `cargo release version <VERSION> --package <PKG>`",
+        );
         return Err(101.into());
     }
 
@@ -439,7 +1572,7 @@ pub fn consolidate_commits(
         if consolidate_commits.is_none() {
             consolidate_commits = current;
         } else if consolidate_commits != current {
-            let _ = crate::ops::shell::error("inconsistent `consolidate-commits` setting");
+            let _ = crate::ops::reporter::error("inconsistent `consolidate-commits` setting");
             return Err(101.into());
         }
     }
@@ -453,48 +1586,312 @@ pub fn confirm(
     dry_run: bool,
 ) -> Result<(), crate::error::CliError> {
     if !dry_run && !no_confirm {
-        let prompt = if pkgs.len() == 1 {
-            let pkg = &pkgs[0];
+        let prompt = confirm_prompt(step, pkgs);
+        let confirmed = crate::ops::shell::confirm(&prompt);
+        if !confirmed {
+            return Err(0.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Like [`confirm`] but, when interactive, `!foo,bar` drops packages from the release
+///
+/// Dropped packages are treated like `--exclude`d ones, with the plan recomputed before asking
+/// again.
+pub fn confirm_with_drops(
+    step: &str,
+    selected_pkgs: Vec<plan::PackageRelease>,
+    excluded_pkgs: Vec<plan::PackageRelease>,
+    no_confirm: bool,
+    dry_run: bool,
+) -> Result<(Vec<plan::PackageRelease>, Vec<plan::PackageRelease>), crate::error::CliError> {
+    use std::io::IsTerminal as _;
+
+    if dry_run || no_confirm || !std::io::stdin().is_terminal() {
+        confirm(step, &selected_pkgs, no_confirm, dry_run)?;
+        return Ok((selected_pkgs, excluded_pkgs));
+    }
+    refine(
+        step,
+        selected_pkgs,
+        excluded_pkgs,
+        &mut std::io::stdin().lock(),
+    )
+}
+
+fn refine(
+    step: &str,
+    selected_pkgs: Vec<plan::PackageRelease>,
+    excluded_pkgs: Vec<plan::PackageRelease>,
+    input: &mut dyn std::io::BufRead,
+) -> Result<(Vec<plan::PackageRelease>, Vec<plan::PackageRelease>), crate::error::CliError> {
+    let prompt = confirm_prompt(step, &selected_pkgs);
+    let response = crate::ops::shell::ask(&prompt, "y/N/!PKG,...", input);
+    let dropped: Vec<_> = if let Some(dropped) = response.strip_prefix('!') {
+        dropped
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect()
+    } else if response.to_lowercase() == "y" {
+        return Ok((selected_pkgs, excluded_pkgs));
+    } else {
+        return Err(0.into());
+    };
+
+    let unknown: Vec<_> = dropped
+        .iter()
+        .filter(|name| !selected_pkgs.iter().any(|p| p.meta.name == **name))
+        .collect();
+    if !unknown.is_empty() || dropped.is_empty() {
+        let _ = crate::ops::reporter::error(format!(
+            "can only drop packages being released, got `{}`",
+            response
+        ));
+        return Err(2.into());
+    }
+
+    let mut pkgs: indexmap::IndexMap<_, _> = selected_pkgs
+        .into_iter()
+        .chain(excluded_pkgs)
+        .map(|pkg| (pkg.meta.id.clone(), pkg))
+        .collect();
+    for pkg in pkgs.values_mut() {
+        if dropped.contains(&pkg.meta.name.as_str()) {
+            log::debug!("dropped by user, skipping {}", pkg.meta.name);
+            pkg.planned_version = None;
+            pkg.planned_tag = None;
+            pkg.skip(plan::SkipReason::Dropped);
+        }
+    }
+    let pkgs = plan::plan(pkgs)?;
+
+    let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        .into_iter()
+        .map(|(_, pkg)| pkg)
+        .partition(|p| p.config.release());
+    require_selected(&selected_pkgs, &excluded_pkgs)?;
+
+    let prompt = confirm_prompt(step, &selected_pkgs);
+    if crate::ops::shell::ask(&prompt, "y/N", input).to_lowercase() == "y" {
+        Ok((selected_pkgs, excluded_pkgs))
+    } else {
+        Err(0.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum SummaryFormat {
+    /// Only the packages and versions being confirmed
+    #[default]
+    List,
+    /// An aligned table of each package's versions, tag, publishing, and changes
+    Table,
+}
+
+/// Print [`summary_table`] to stderr, for `--summary table`
+pub fn print_summary_table(pkgs: &[plan::PackageRelease]) {
+    use crate::ops::shell::Color;
+    use crate::ops::shell::ColorSpec;
+
+    for (i, row) in summary_table(pkgs).iter().enumerate() {
+        let mut stderr = crate::ops::shell::lock_stderr();
+        for (column, cell) in row.iter().enumerate() {
+            let mut color = ColorSpec::new();
+            if i == 0 {
+                color.set_bold(true);
+            } else {
+                match (column, cell.trim_end()) {
+                    (_, "-") => {
+                        color.set_dimmed(true);
+                    }
+                    (2, _) => {
+                        color.set_fg(Some(Color::Green));
+                    }
+                    (5, "yes") => {
+                        color.set_fg(Some(Color::Yellow));
+                    }
+                    _ => {}
+                }
+            }
+            if 0 < column {
+                let _ = stderr.write("  ", &ColorSpec::new());
+            }
+            let _ = stderr.write(cell, &color);
+        }
+        let _ = stderr.write("\n", &ColorSpec::new());
+    }
+}
+
+/// A header and a row per package, with every column but the last padded to its widest cell
+///
+/// `-` stands in for a missing planned version or tag, and for changes that weren't checked.
+fn summary_table(pkgs: &[plan::PackageRelease]) -> Vec<Vec<String>> {
+    let yes_no = |b: bool| if b { "yes" } else { "no" }.to_owned();
+    let mut rows = vec![["crate", "version", "planned", "tag", "publish", "changed"]
+        .map(str::to_owned)
+        .to_vec()];
+    for pkg in pkgs {
+        rows.push(vec![
+            pkg.meta.name.clone(),
+            pkg.initial_version.full_version_string.clone(),
+            pkg.planned_version
+                .as_ref()
+                .map(|v| v.full_version_string.clone())
+                .unwrap_or_else(|| "-".to_owned()),
+            pkg.planned_tag.clone().unwrap_or_else(|| "-".to_owned()),
+            yes_no(pkg.config.publish()),
+            pkg.changed.map(yes_no).unwrap_or_else(|| "-".to_owned()),
+        ]);
+    }
+
+    let columns = rows[0].len();
+    for column in 0..columns - 1 {
+        let width = rows.iter().map(|row| row[column].len()).max().unwrap_or(0);
+        for row in &mut rows {
+            row[column] = format!("{:<width$}", row[column]);
+        }
+    }
+    rows
+}
+
+fn confirm_prompt(step: &str, pkgs: &[plan::PackageRelease]) -> String {
+    if pkgs.len() == 1 {
+        let pkg = &pkgs[0];
+        let crate_name = pkg.meta.name.as_str();
+        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+        format!(
+            "{} {} {}{}?",
+            step,
+            crate_name,
+            version.full_version_string,
+            confirm_note(pkg)
+        )
+    } else {
+        use std::io::Write;
+
+        let mut buffer: Vec<u8> = vec![];
+        writeln!(&mut buffer, "{}", step).unwrap();
+        for pkg in pkgs {
             let crate_name = pkg.meta.name.as_str();
             let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
-            format!("{} {} {}?", step, crate_name, version.full_version_string)
-        } else {
-            use std::io::Write;
+            writeln!(
+                &mut buffer,
+                "  {} {}{}",
+                crate_name,
+                version.full_version_string,
+                confirm_note(pkg)
+            )
+            .unwrap();
+        }
+        write!(&mut buffer, "?").unwrap();
+        String::from_utf8(buffer).expect("Only valid UTF-8 has been written")
+    }
+}
 
-            let mut buffer: Vec<u8> = vec![];
-            writeln!(&mut buffer, "{}", step).unwrap();
-            for pkg in pkgs {
-                let crate_name = pkg.meta.name.as_str();
-                let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
-                writeln!(
-                    &mut buffer,
-                    "  {} {}",
-                    crate_name, version.full_version_string
-                )
-                .unwrap();
-            }
-            write!(&mut buffer, "?").unwrap();
-            String::from_utf8(buffer).expect("Only valid UTF-8 has been written")
-        };
+fn confirm_note(pkg: &plan::PackageRelease) -> &'static str {
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    if pkg.config.tag() && pkg.planned_tag.is_none() && version.is_prerelease() {
+        " (no tag: prerelease)"
+    } else {
+        ""
+    }
+}
 
-        let confirmed = crate::ops::shell::confirm(&prompt);
-        if !confirmed {
-            return Err(0.into());
+/// Report the plan for `pkgs` as one compact block, under `--quiet-summary`
+pub fn summarize(pkgs: &[plan::PackageRelease]) {
+    if crate::ops::shell::filter() != crate::ops::shell::Filter::Summary {
+        return;
+    }
+
+    let _ = crate::ops::reporter::summary(summary(pkgs));
+}
+
+fn summary(pkgs: &[plan::PackageRelease]) -> String {
+    let name_width = pkgs
+        .iter()
+        .map(|pkg| pkg.meta.name.len())
+        .max()
+        .unwrap_or(0);
+    let mut block = format!(
+        "{} {}",
+        pkgs.len(),
+        if pkgs.len() == 1 {
+            "package"
+        } else {
+            "packages"
+        }
+    );
+    for pkg in pkgs {
+        let mut line = format!(
+            "\n  {:<name_width$}  {}",
+            pkg.meta.name, pkg.initial_version.full_version_string
+        );
+        if let Some(version) = pkg.planned_version.as_ref() {
+            line.push_str(&format!(" -> {}", version.full_version_string));
+        }
+        if let Some(tag_name) = pkg.planned_tag.as_ref() {
+            line.push_str(&format!(", tag {}", tag_name));
         }
+        block.push_str(&line);
     }
+    block
+}
 
+/// List the packages not being released and why, so a missing package can be accounted for
+/// Fail when nothing is left to process, saying why each package was skipped
+pub fn require_selected(
+    selected_pkgs: &[plan::PackageRelease],
+    excluded_pkgs: &[plan::PackageRelease],
+) -> Result<(), crate::error::CliError> {
+    if selected_pkgs.is_empty() {
+        report_skipped(excluded_pkgs);
+        let _ = crate::ops::reporter::error("no packages selected");
+        return Err(2.into());
+    }
     Ok(())
 }
 
+pub fn report_skipped(pkgs: &[plan::PackageRelease]) {
+    if let Some(block) = skipped(pkgs) {
+        let _ = crate::ops::reporter::note(block);
+    }
+}
+
+fn skipped(pkgs: &[plan::PackageRelease]) -> Option<String> {
+    let skipped: Vec<_> = pkgs
+        .iter()
+        .filter_map(|pkg| Some((pkg.meta.name.as_str(), pkg.skip_reason?)))
+        .collect();
+    if skipped.is_empty() {
+        return None;
+    }
+    let name_width = skipped
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut block = "skipped packages:".to_owned();
+    for (name, reason) in skipped {
+        block.push_str(&format!("\n  {:<name_width$}  {}", name, reason));
+    }
+    Some(block)
+}
+
 pub fn finish(failed: bool, dry_run: bool) -> Result<(), crate::error::CliError> {
     if dry_run {
         if failed {
-            let _ =
-                crate::ops::shell::error("dry-run failed, resolve the above errors and try again.");
+            let _ = crate::ops::reporter::error(
+                "dry-run failed, resolve the above errors and try again.",
+            );
             Err(101.into())
         } else {
-            let _ =
-                crate::ops::shell::warn("aborting release due to dry run; re-run with `--execute`");
+            let _ = crate::ops::reporter::warn(
+                "aborting release due to dry run; re-run with `--execute`",
+            );
             Ok(())
         }
     } else {
@@ -536,7 +1933,8 @@ impl TargetVersion {
                     }
                 }
                 let version = plan::Version::from(full_version);
-                if version.bare_version != plan::Version::from(current.clone()).bare_version {
+                // Metadata-only changes are still released, see `PackageRelease::is_metadata_only`
+                if version.full_version != *current {
                     Ok(Some(version))
                 } else {
                     Ok(None)
@@ -617,33 +2015,116 @@ impl clap::builder::TypedValueParser for TargetVersionParser {
     }
 }
 
-#[derive(Debug, Clone, Copy, clap::ValueEnum)]
-#[value(rename_all = "kebab-case")]
-pub enum BumpLevel {
-    /// Increase the major version (x.0.0)
-    Major,
-    /// Increase the minor version (x.y.0)
-    Minor,
-    /// Increase the patch version (x.y.z)
-    Patch,
-    /// Remove the pre-version (x.y.z)
-    Release,
-    /// Increase the rc pre-version (x.y.z-rc.M)
-    Rc,
-    /// Increase the beta pre-version (x.y.z-beta.M)
-    Beta,
-    /// Increase the alpha pre-version (x.y.z-alpha.M)
-    Alpha,
+/// A `--package` value, optionally overriding `LEVEL|VERSION` for that package with
+/// `NAME@LEVEL|VERSION`
+#[derive(Clone, Debug)]
+pub struct PackageVersionSpec {
+    pub name: String,
+    pub target: Option<TargetVersion>,
 }
 
-impl std::fmt::Display for BumpLevel {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use clap::ValueEnum;
+impl std::str::FromStr for PackageVersionSpec {
+    type Err = String;
 
-        self.to_possible_value()
-            .expect("no values are skipped")
-            .get_name()
-            .fmt(f)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('@') {
+            Some((name, target)) if !name.is_empty() => Ok(Self {
+                name: name.to_owned(),
+                target: Some(
+                    TargetVersion::from_str(target)
+                        .map_err(|e| format!("invalid version for `{}`: {}", name, e))?,
+                ),
+            }),
+            Some(_) => Err(format!("expected `NAME@LEVEL|VERSION`, got `{}`", s)),
+            None => Ok(Self {
+                name: s.to_owned(),
+                target: None,
+            }),
+        }
+    }
+}
+
+/// Validates `--package` as a [`PackageVersionSpec`] while keeping the raw value, as
+/// `clap_cargo::Workspace` stores it
+#[derive(Copy, Clone)]
+pub struct PackageVersionSpecParser;
+
+impl clap::builder::TypedValueParser for PackageVersionSpecParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let inner_parser = |s: &str| PackageVersionSpec::from_str(s).map(|_| s.to_owned());
+        inner_parser.parse_ref(cmd, arg, value)
+    }
+}
+
+/// Split `NAME@LEVEL|VERSION` overrides out of `--package`, leaving plain names for selecting
+/// packages
+pub fn split_package_targets(
+    workspace: &clap_cargo::Workspace,
+) -> Result<
+    (
+        clap_cargo::Workspace,
+        indexmap::IndexMap<String, TargetVersion>,
+    ),
+    String,
+> {
+    let mut names = Vec::new();
+    let mut targets = indexmap::IndexMap::new();
+    for spec in &workspace.package {
+        let spec = PackageVersionSpec::from_str(spec)?;
+        let is_duplicate = names.contains(&spec.name);
+        if is_duplicate && (spec.target.is_some() || targets.contains_key(&spec.name)) {
+            return Err(format!(
+                "`{}` was passed to `--package` more than once",
+                spec.name
+            ));
+        }
+        if let Some(target) = spec.target {
+            targets.insert(spec.name.clone(), target);
+        }
+        if !is_duplicate {
+            names.push(spec.name);
+        }
+    }
+
+    let mut workspace = workspace.clone();
+    workspace.package = names;
+    Ok((workspace, targets))
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum BumpLevel {
+    /// Increase the major version (x.0.0)
+    Major,
+    /// Increase the minor version (x.y.0)
+    Minor,
+    /// Increase the patch version (x.y.z)
+    Patch,
+    /// Remove the pre-version (x.y.z)
+    Release,
+    /// Increase the rc pre-version (x.y.z-rc.M)
+    Rc,
+    /// Increase the beta pre-version (x.y.z-beta.M)
+    Beta,
+    /// Increase the alpha pre-version (x.y.z-alpha.M)
+    Alpha,
+}
+
+impl std::fmt::Display for BumpLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use clap::ValueEnum;
+
+        self.to_possible_value()
+            .expect("no values are skipped")
+            .get_name()
+            .fmt(f)
     }
 }
 
@@ -705,3 +2186,963 @@ impl BumpLevel {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod release_pushed {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+        use assert_fs::prelude::*;
+
+        /// A clone whose last commit, a release, was never pushed
+        pub(super) fn unpushed_release() -> assert_fs::TempDir {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let remote = temp.child("remote.git");
+            let local = temp.child("local");
+            git(temp.path(), &["init", "--quiet", "--bare", "remote.git"]);
+            local.create_dir_all().unwrap();
+            fixture::init(local.path());
+            git(
+                local.path(),
+                &["remote", "add", "origin", remote.path().to_str().unwrap()],
+            );
+            local.child("README.md").write_str("release").unwrap();
+            git(local.path(), &["add", "README.md"]);
+            git(local.path(), &["commit", "--quiet", "-m", "initial"]);
+            git(local.path(), &["push", "--quiet", "origin", "HEAD"]);
+
+            local.child("README.md").write_str("1.0.0").unwrap();
+            git(
+                local.path(),
+                &[
+                    "commit",
+                    "--quiet",
+                    "-am",
+                    "chore: Release foo version 1.0.0",
+                ],
+            );
+            git(local.path(), &["tag", "-a", "v1.0.0", "-m", "v1.0.0"]);
+            temp
+        }
+
+        #[test]
+        fn unpushed() {
+            let temp = unpushed_release();
+            let local = temp.path().join("local");
+            let config = crate::config::Config::from_defaults();
+
+            assert!(verify_release_pushed(&local, &config, &[], true, log::Level::Warn).unwrap());
+            assert!(!verify_release_pushed(&local, &config, &[], true, log::Level::Error).unwrap());
+            assert!(verify_release_pushed(&local, &config, &[], false, log::Level::Error).is_err());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn pushed() {
+            let temp = unpushed_release();
+            let local = temp.path().join("local");
+            git(&local, &["push", "--quiet", "origin", "HEAD"]);
+            let config = crate::config::Config::from_defaults();
+
+            assert!(verify_release_pushed(&local, &config, &[], false, log::Level::Error).unwrap());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn other_commits() {
+            let temp = unpushed_release();
+            let local = temp.path().join("local");
+            let config = crate::config::Config {
+                pre_release_commit_message: Some(crate::config::MessageTemplate::Inline(
+                    "release: {{version}}".to_owned(),
+                )),
+                ..crate::config::Config::from_defaults()
+            };
+
+            assert!(verify_release_pushed(&local, &config, &[], false, log::Level::Error).unwrap());
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod branch_policy {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+
+        /// `pure_ws` where `c` is released from `experimental` and the rest from `main`
+        fn workspace() -> (assert_fs::TempDir, Vec<plan::PackageRelease>) {
+            use assert_fs::prelude::*;
+
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+            fixture::init(temp.path());
+            for args in [
+                &["add", "."][..],
+                &["commit", "--quiet", "-m", "initial"],
+                &["checkout", "--quiet", "-B", "main"],
+            ] {
+                git(temp.path(), args);
+            }
+            let (_, pkgs) = plan::fixture::load(temp.path(), &Default::default());
+            let mut pkgs: Vec<_> = pkgs.into_values().collect();
+            for pkg in &mut pkgs {
+                let allowed = if pkg.meta.name == "c" {
+                    "experimental"
+                } else {
+                    "main"
+                };
+                pkg.config.allow_branch = Some(vec![allowed.to_owned()]);
+            }
+            (temp, pkgs)
+        }
+
+        fn names(pkgs: &[plan::PackageRelease]) -> Vec<&str> {
+            let mut names: Vec<_> = pkgs.iter().map(|p| p.meta.name.as_str()).collect();
+            names.sort_unstable();
+            names
+        }
+
+        #[test]
+        fn from_main() {
+            let (temp, mut selected) = workspace();
+            let mut excluded = Vec::new();
+
+            exclude_disallowed_branch(temp.path(), &mut selected, &mut excluded).unwrap();
+            assert_eq!(names(&selected), ["a", "b"]);
+            assert_eq!(names(&excluded), ["c"]);
+            assert!(excluded.iter().all(|p| !p.config.release()));
+            assert_eq!(excluded[0].skip_reason, Some(plan::SkipReason::Branch));
+            let config = crate::config::Config::from_defaults();
+            assert!(
+                verify_git_branch(temp.path(), &config, &selected, false, log::Level::Error)
+                    .unwrap()
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn from_experimental() {
+            let (temp, mut selected) = workspace();
+            git(temp.path(), &["checkout", "--quiet", "-b", "experimental"]);
+            let mut excluded = Vec::new();
+
+            exclude_disallowed_branch(temp.path(), &mut selected, &mut excluded).unwrap();
+            assert_eq!(names(&selected), ["c"]);
+            assert_eq!(names(&excluded), ["a", "b"]);
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn strict() {
+            let (temp, pkgs) = workspace();
+            let config = crate::config::Config::from_defaults();
+
+            assert!(
+                !verify_git_branch(temp.path(), &config, &pkgs, true, log::Level::Error).unwrap()
+            );
+            assert!(
+                verify_git_branch(temp.path(), &config, &pkgs, false, log::Level::Error).is_err()
+            );
+            // Only the workspace policy applies without packages
+            assert!(
+                verify_git_branch(temp.path(), &config, &[], false, log::Level::Error).unwrap()
+            );
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod offline {
+        use super::*;
+
+        use std::sync::{Arc, Mutex};
+
+        use crate::error::CargoResult;
+        use crate::ops::reporter::Reporter;
+
+        #[derive(Clone, Default)]
+        pub(super) struct Commands(pub(super) Arc<Mutex<Vec<Vec<String>>>>);
+
+        impl Reporter for Commands {
+            fn on_command(&self, command: &[String], _dry_run: bool) -> CargoResult<()> {
+                self.0.lock().unwrap().push(command.to_owned());
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn skips_fetch() {
+            let commands = Commands::default();
+            let _guard = crate::ops::reporter::set_test_reporter(commands.clone());
+            let temp = super::release_pushed::unpushed_release();
+            let local = temp.path().join("local");
+            let remote = temp.path().join("remote.git");
+            std::process::Command::new("git")
+                .args(["remote", "add", "upstream", remote.to_str().unwrap()])
+                .current_dir(&local)
+                .status()
+                .unwrap();
+            let config = crate::config::Config {
+                push_remote: Some("upstream".to_owned()),
+                ..crate::config::Config::from_defaults()
+            };
+            let fetched = || {
+                commands
+                    .0
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .any(|c| c.starts_with(&["git".to_owned(), "fetch".to_owned()]))
+            };
+
+            let behind = verify_if_behind_with(&local, &config, true, true, log::Level::Error);
+            let pushed =
+                verify_release_pushed_with(&local, &config, &[], true, true, log::Level::Error);
+            assert!(behind.unwrap());
+            assert!(pushed.unwrap());
+            assert!(!fetched());
+
+            verify_if_behind_with(&local, &config, false, true, log::Level::Error).unwrap();
+            assert!(fetched());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn refuses_publish() {
+            let (_, pkgs) = plan::fixture::load("tests/fixtures/simple", &Default::default());
+            let pkgs: Vec<_> = pkgs.into_values().collect();
+
+            assert!(verify_online_publish(&pkgs, false, false, log::Level::Error).unwrap());
+            assert!(!verify_online_publish(&pkgs, true, true, log::Level::Error).unwrap());
+            assert!(verify_online_publish(&pkgs, true, false, log::Level::Error).is_err());
+        }
+    }
+
+    mod fetch_cache {
+        use super::*;
+
+        use super::offline::Commands;
+
+        fn fetches(commands: &Commands) -> Vec<Vec<String>> {
+            commands
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|c| c.starts_with(&["git".to_owned(), "fetch".to_owned()]))
+                .cloned()
+                .collect()
+        }
+
+        #[test]
+        fn once_per_branch() {
+            let commands = Commands::default();
+            let _guard = crate::ops::reporter::set_test_reporter(commands.clone());
+            let temp = super::release_pushed::unpushed_release();
+            let local = temp.path().join("local");
+            let config = crate::config::Config::from_defaults();
+
+            verify_if_behind(&local, &config, true, log::Level::Warn).unwrap();
+            verify_release_pushed(&local, &config, &[], true, log::Level::Warn).unwrap();
+            // From a subdirectory, it's still the same repository
+            let sub = local.join("sub");
+            std::fs::create_dir(&sub).unwrap();
+            verify_if_behind(&sub, &config, true, log::Level::Warn).unwrap();
+            let branch = crate::ops::git::current_branch(&local).unwrap();
+            assert_eq!(
+                fetches(&commands),
+                [vec![
+                    "git".to_owned(),
+                    "fetch".to_owned(),
+                    "--no-tags".to_owned(),
+                    "origin".to_owned(),
+                    branch.clone(),
+                ]]
+            );
+
+            // Tags weren't fetched before
+            crate::ops::git::fetch(&local, "origin", &branch, true, false).unwrap();
+            crate::ops::git::fetch(&local, "origin", &branch, false, false).unwrap();
+            assert_eq!(fetches(&commands).len(), 2);
+
+            crate::ops::git::fetch(&local, "origin", &branch, false, true).unwrap();
+            assert_eq!(fetches(&commands).len(), 3);
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod path_dependencies {
+        use super::*;
+
+        fn load(root: &std::path::Path) -> (cargo_metadata::Metadata, Vec<plan::PackageRelease>) {
+            let (ws_meta, pkgs) = plan::fixture::load(root, &Default::default());
+            (ws_meta, pkgs.into_values().collect())
+        }
+
+        #[test]
+        fn excluded_package() {
+            let (ws_meta, pkgs) = load(std::path::Path::new("tests/fixtures/dangling_path_ws"));
+
+            assert!(verify_path_dependencies(&ws_meta, &pkgs, false, log::Level::Error).unwrap());
+        }
+
+        #[test]
+        fn deleted_package() {
+            use assert_fs::prelude::*;
+
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/dangling_path_ws", &["**"])
+                .unwrap();
+            assert!(std::process::Command::new("git")
+                .args(["init", "--quiet"])
+                .current_dir(temp.path())
+                .status()
+                .unwrap()
+                .success());
+            let (ws_meta, pkgs) = load(temp.path());
+            std::fs::remove_dir_all(temp.child("removed").path()).unwrap();
+
+            assert!(!verify_path_dependencies(&ws_meta, &pkgs, true, log::Level::Error).unwrap());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn members() {
+            let (ws_meta, pkgs) = load(std::path::Path::new("tests/fixtures/pure_ws"));
+
+            assert!(verify_path_dependencies(&ws_meta, &pkgs, false, log::Level::Error).unwrap());
+        }
+    }
+
+    mod tag_collisions {
+        use super::*;
+
+        fn load(configure: impl Fn(&mut plan::PackageRelease)) -> Vec<plan::PackageRelease> {
+            let (_, pkgs) =
+                plan::fixture::planned("tests/fixtures/pure_ws", &Default::default(), |pkg| {
+                    if pkg.meta.name != "c" {
+                        configure(pkg);
+                    }
+                });
+            pkgs
+        }
+
+        #[test]
+        fn distinct_by_default() {
+            let pkgs = load(|_| {});
+            assert_eq!(tag_collisions(&pkgs), Vec::<String>::new());
+        }
+
+        #[test]
+        fn colliding_prefixes() {
+            let pkgs = load(|pkg| pkg.config.tag_prefix = Some(String::new()));
+            let collisions = tag_collisions(&pkgs);
+            assert_eq!(collisions.len(), 1, "{:?}", collisions);
+            let collision = &collisions[0];
+            assert!(collision.contains("tag `v0.2.0`"), "{}", collision);
+            assert!(collision.contains("\n  a (`tag-prefix"), "{}", collision);
+            assert!(collision.contains("\n  b (`tag-prefix"), "{}", collision);
+            assert!(!collision.contains("\n  c "), "{}", collision);
+            assert!(collision.contains("`tag-name = "), "{}", collision);
+        }
+
+        #[test]
+        fn shared_version_shares_tag() {
+            let pkgs = load(|pkg| {
+                pkg.config.tag_prefix = Some(String::new());
+                pkg.config.shared_version =
+                    Some(crate::config::SharedVersion::Name("default".to_owned()));
+            });
+            assert_eq!(tag_collisions(&pkgs), Vec::<String>::new());
+        }
+    }
+
+    mod tag_kinds {
+        use super::*;
+        use crate::ops::git::TagInfo;
+
+        fn tag(annotated: bool, signed: bool) -> TagInfo {
+            TagInfo {
+                annotated,
+                signed,
+                target: String::new(),
+            }
+        }
+
+        #[test]
+        fn annotated_by_default() {
+            let config = crate::config::Config::from_defaults();
+            let mismatch = tag_mismatch(&config, &tag(false, false)).unwrap();
+            assert!(mismatch.contains("an annotated one"), "{}", mismatch);
+            assert_eq!(tag_mismatch(&config, &tag(true, false)), None);
+        }
+
+        #[test]
+        fn lightweight_without_message() {
+            let config = crate::config::Config {
+                tag_message: Some(crate::config::MessageTemplate::Inline("".into())),
+                ..Default::default()
+            };
+            assert_eq!(tag_mismatch(&config, &tag(false, false)), None);
+        }
+
+        #[test]
+        fn expects_annotated() {
+            let config = crate::config::Config {
+                tag_message: Some(crate::config::MessageTemplate::Inline("{{version}}".into())),
+                ..Default::default()
+            };
+            let mismatch = tag_mismatch(&config, &tag(false, false)).unwrap();
+            assert!(mismatch.contains("lightweight"), "{}", mismatch);
+            assert!(mismatch.contains("an annotated one"), "{}", mismatch);
+            assert_eq!(tag_mismatch(&config, &tag(true, false)), None);
+        }
+
+        #[test]
+        fn expects_signed() {
+            let config = crate::config::Config {
+                tag_message: Some(crate::config::MessageTemplate::Inline("{{version}}".into())),
+                sign_tag: Some(true),
+                ..Default::default()
+            };
+            let mismatch = tag_mismatch(&config, &tag(false, false)).unwrap();
+            assert!(mismatch.contains("a signed one"), "{}", mismatch);
+            let mismatch = tag_mismatch(&config, &tag(true, false)).unwrap();
+            assert!(mismatch.contains("unsigned"), "{}", mismatch);
+            assert_eq!(tag_mismatch(&config, &tag(true, true)), None);
+        }
+    }
+
+    mod package_version_spec {
+        use super::*;
+
+        fn workspace(package: &[&str]) -> clap_cargo::Workspace {
+            let mut workspace = clap_cargo::Workspace::default();
+            workspace.package = package.iter().map(|p| (*p).to_owned()).collect();
+            workspace
+        }
+
+        #[test]
+        fn parse() {
+            let spec: PackageVersionSpec = "foo".parse().unwrap();
+            assert_eq!(spec.name, "foo");
+            assert!(spec.target.is_none());
+
+            let spec: PackageVersionSpec = "foo@1.2.0".parse().unwrap();
+            assert_eq!(spec.name, "foo");
+            assert_eq!(spec.target.unwrap().to_string(), "1.2.0");
+
+            let spec: PackageVersionSpec = "bar@minor".parse().unwrap();
+            assert_eq!(spec.target.unwrap().to_string(), "minor");
+
+            assert!("@1.2.0".parse::<PackageVersionSpec>().is_err());
+            assert!("foo@latest".parse::<PackageVersionSpec>().is_err());
+        }
+
+        #[test]
+        fn usage_error() {
+            #[derive(Debug, clap::Parser)]
+            struct Cli {
+                #[command(flatten)]
+                step: crate::steps::version::VersionStep,
+            }
+
+            let err = <Cli as clap::Parser>::try_parse_from(["cargo-release", "-p", "foo@latest"])
+                .unwrap_err();
+            assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+
+            assert!(
+                <Cli as clap::Parser>::try_parse_from(["cargo-release", "-p", "foo@minor"]).is_ok()
+            );
+        }
+
+        #[test]
+        fn split() {
+            let (workspace, targets) =
+                split_package_targets(&workspace(&["foo@1.2.0", "bar@minor", "baz"])).unwrap();
+            assert_eq!(workspace.package, ["foo", "bar", "baz"]);
+            let targets: Vec<_> = targets
+                .iter()
+                .map(|(name, target)| format!("{}@{}", name, target))
+                .collect();
+            assert_eq!(targets, ["foo@1.2.0", "bar@minor"]);
+        }
+
+        #[test]
+        fn duplicates() {
+            assert!(split_package_targets(&workspace(&["foo@1.2.0", "foo@minor"])).is_err());
+            assert!(split_package_targets(&workspace(&["foo", "foo@minor"])).is_err());
+            assert!(split_package_targets(&workspace(&["foo@minor", "foo"])).is_err());
+
+            let (workspace, targets) = split_package_targets(&workspace(&["foo", "foo"])).unwrap();
+            assert_eq!(workspace.package, ["foo"]);
+            assert!(targets.is_empty());
+        }
+    }
+
+    mod manifest_inheritance {
+        use super::*;
+
+        fn load() -> Vec<plan::PackageRelease> {
+            let (_, pkgs) =
+                plan::fixture::load("tests/fixtures/inherited_fields", &Default::default());
+            pkgs.into_values().collect()
+        }
+
+        #[test]
+        fn old_cargo() {
+            let pkgs = load();
+
+            let problems = inheritance_problems(&pkgs, &semver::Version::new(1, 63, 0)).unwrap();
+            // `b` isn't published
+            assert_eq!(
+                problems,
+                ["a inherits `package.publish`, `package.edition`, `package.license`, `package.rust-version` from the workspace, which needs cargo 1.64.0 but cargo 1.63.0 is active"]
+            );
+        }
+
+        #[test]
+        fn new_cargo() {
+            let pkgs = load();
+
+            let problems = inheritance_problems(&pkgs, &semver::Version::new(1, 64, 0)).unwrap();
+            assert!(problems.is_empty(), "{:?}", problems);
+        }
+    }
+
+    mod rust_version {
+        use super::*;
+
+        fn load() -> Vec<plan::PackageRelease> {
+            let (_, pkgs) = plan::fixture::load("tests/fixtures/pure_ws", &Default::default());
+            let mut pkgs: Vec<_> = pkgs.into_values().collect();
+            for pkg in &mut pkgs {
+                pkg.meta.rust_version = Some(semver::Version::new(1, 70, 0));
+            }
+            pkgs[1].config.publish = Some(false);
+            pkgs
+        }
+
+        #[test]
+        fn old_toolchain() {
+            let pkgs = load();
+
+            let problems = rust_version_problems(&pkgs, &semver::Version::new(1, 69, 0));
+            // `b` isn't published
+            assert_eq!(
+                problems,
+                [
+                    "a needs 1.70.0 but 1.69.0 is active",
+                    "c needs 1.70.0 but 1.69.0 is active"
+                ]
+            );
+        }
+
+        #[test]
+        fn new_toolchain() {
+            let pkgs = load();
+
+            let problems = rust_version_problems(&pkgs, &semver::Version::new(1, 70, 0));
+            assert!(problems.is_empty(), "{:?}", problems);
+        }
+    }
+
+    mod changelog {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+        use assert_fs::prelude::*;
+
+        /// `pure_ws` with `a` released at `0.1.0` and changed since
+        fn repo() -> assert_fs::TempDir {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+            temp.child("a/CHANGELOG.md")
+                .write_str("## Unreleased\n")
+                .unwrap();
+            fixture::init(temp.path());
+            git(temp.path(), &["add", "."]);
+            git(temp.path(), &["commit", "--quiet", "-m", "Release"]);
+            git(temp.path(), &["tag", "-a", "a-v0.1.0", "-m", "a-v0.1.0"]);
+            temp.child("a/src/lib.rs")
+                .write_str("pub fn changed() {}\n")
+                .unwrap();
+            git(temp.path(), &["commit", "--quiet", "-am", "Change a"]);
+            temp
+        }
+
+        fn problems(temp: &assert_fs::TempDir) -> Vec<String> {
+            let args = crate::config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let (ws_meta, pkgs) = plan::fixture::load(temp.path(), &args);
+            let mut pkgs: Vec<_> = pkgs.into_values().collect();
+            for pkg in &mut pkgs {
+                pkg.config.pre_release_replacements = Some(vec![crate::config::Replace {
+                    file: std::path::PathBuf::from("CHANGELOG.md"),
+                    search: "## Unreleased".to_owned(),
+                    replace: "## {{version}}".to_owned(),
+                    min: None,
+                    max: None,
+                    exactly: Some(1),
+                    prerelease: false,
+                    idempotent: false,
+                }]);
+            }
+            changelog_problems(&ws_meta, &pkgs).unwrap()
+        }
+
+        #[test]
+        fn unchanged_changelog() {
+            let temp = repo();
+
+            let changelog = dunce::canonicalize(temp.path())
+                .unwrap()
+                .join("a/CHANGELOG.md");
+            // `b` and `c` have no changelog
+            assert_eq!(
+                problems(&temp),
+                [format!(
+                    "a changed since a-v0.1.0 but {} didn't",
+                    changelog.display()
+                )]
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn updated_changelog() {
+            let temp = repo();
+            temp.child("a/CHANGELOG.md")
+                .write_str("## Unreleased\n\n- Changed a\n")
+                .unwrap();
+            git(
+                temp.path(),
+                &["commit", "--quiet", "-am", "Note the change"],
+            );
+
+            let problems = problems(&temp);
+            assert!(problems.is_empty(), "{:?}", problems);
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod summary {
+        use super::*;
+
+        #[test]
+        fn aligns_columns() {
+            let (_, pkgs) = plan::fixture::load("tests/fixtures/pure_ws", &Default::default());
+            let mut pkgs: Vec<_> = pkgs.into_values().collect();
+            pkgs[0]
+                .bump(&TargetVersion::Relative(BumpLevel::Minor), None)
+                .unwrap();
+            pkgs[0].planned_tag = Some("a-v0.2.0".to_owned());
+            pkgs[0].changed = Some(true);
+            pkgs[1].config.publish = Some(false);
+            pkgs[1].changed = Some(false);
+
+            let lines: Vec<_> = summary_table(&pkgs)
+                .iter()
+                .map(|row| row.join("  "))
+                .collect();
+            assert_eq!(
+                lines,
+                [
+                    "crate  version  planned  tag       publish  changed",
+                    "a      0.1.0    0.2.0    a-v0.2.0  yes      yes",
+                    "b      0.1.0    -        -         no       no",
+                    "c      0.1.0    -        -         yes      -",
+                ]
+            );
+        }
+    }
+
+    mod refine {
+        use super::*;
+
+        fn load() -> (Vec<plan::PackageRelease>, Vec<plan::PackageRelease>) {
+            let (_, pkgs) =
+                plan::fixture::planned("tests/fixtures/pure_ws", &Default::default(), |_| {});
+            pkgs.into_iter().partition(|p| p.config.release())
+        }
+
+        fn names(pkgs: &[plan::PackageRelease]) -> Vec<&str> {
+            pkgs.iter().map(|p| p.meta.name.as_str()).collect()
+        }
+
+        #[test]
+        fn drop_then_confirm() {
+            let (selected, excluded) = load();
+            assert_eq!(names(&selected), ["a", "b", "c"]);
+
+            let (selected, excluded) =
+                refine("Release", selected, excluded, &mut "!b\ny\n".as_bytes()).unwrap();
+            assert_eq!(names(&selected), ["a", "c"]);
+            assert_eq!(names(&excluded), ["b"]);
+            assert!(excluded[0].planned_version.is_none());
+            assert!(excluded[0].planned_tag.is_none());
+            assert_eq!(excluded[0].skip_reason, Some(plan::SkipReason::Dropped));
+
+            assert_eq!(
+                skipped(&excluded).as_deref(),
+                Some("skipped packages:\n  b  dropped at confirmation")
+            );
+            assert_eq!(skipped(&selected), None);
+        }
+
+        #[test]
+        fn drop_everything() {
+            let (selected, excluded) = load();
+
+            let result = refine(
+                "Release",
+                selected,
+                excluded,
+                &mut "!a, b, c\ny\n".as_bytes(),
+            );
+            assert_eq!(crate::error::report(result.map(|_| ())), 2);
+        }
+
+        #[test]
+        fn skipped_reasons() {
+            let (mut pkgs, _) = load();
+            let reasons = [
+                plan::SkipReason::Published,
+                plan::SkipReason::TagExists,
+                plan::SkipReason::NotPublished,
+            ];
+            for (pkg, reason) in pkgs.iter_mut().zip(reasons) {
+                pkg.skip(reason);
+            }
+
+            assert_eq!(
+                skipped(&pkgs).as_deref(),
+                Some(
+                    "skipped packages:\n  a  already published\n  b  tag already exists\n  c  not published"
+                )
+            );
+        }
+
+        #[test]
+        fn drop_then_abort() {
+            let (selected, excluded) = load();
+
+            let err = refine("Release", selected, excluded, &mut "!a, c\nn\n".as_bytes());
+            assert!(err.is_err());
+        }
+
+        #[test]
+        fn drop_unknown() {
+            let (selected, excluded) = load();
+
+            let err = refine("Release", selected, excluded, &mut "!d\ny\n".as_bytes());
+            assert!(err.is_err());
+        }
+    }
+
+    mod nested_workspace {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+
+        /// `simple` generated into `generated/`, below the git root, with changes outside of it
+        fn repo(release_toml: &str) -> (assert_fs::TempDir, cargo_metadata::Metadata) {
+            use assert_fs::prelude::*;
+
+            let temp = assert_fs::TempDir::new().unwrap();
+            let generated = temp.child("generated");
+            generated
+                .copy_from("tests/fixtures/simple", &["**"])
+                .unwrap();
+            generated
+                .child("release.toml")
+                .write_str(release_toml)
+                .unwrap();
+            fixture::init(temp.path());
+            git(temp.path(), &["add", "."]);
+            git(temp.path(), &["commit", "--quiet", "-m", "initial"]);
+            temp.child("notes.txt").write_str("unrelated").unwrap();
+            let ws_meta = plan::fixture::metadata(generated.path());
+            (temp, ws_meta)
+        }
+
+        fn tag_prefix(ws_meta: &cargo_metadata::Metadata) -> String {
+            let pkgs = plan::load(&Default::default(), ws_meta).unwrap();
+            let (_, pkg) = pkgs.into_iter().next().unwrap();
+            pkg.config.tag_prefix(pkg.is_root).to_owned()
+        }
+
+        #[test]
+        fn prefixed_by_default() {
+            let (temp, ws_meta) = repo("");
+            assert_eq!(tag_prefix(&ws_meta), "{{crate_name}}-");
+
+            let ws_config =
+                crate::config::load_workspace_config(&Default::default(), &ws_meta).unwrap();
+            assert!(!verify_git_is_clean(
+                ws_meta.workspace_root.as_std_path(),
+                &ws_config,
+                true,
+                log::Level::Error
+            )
+            .unwrap());
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn is_root() {
+            let (temp, ws_meta) = repo("is-root = true\n");
+            assert_eq!(tag_prefix(&ws_meta), "");
+
+            temp.close().unwrap();
+        }
+
+        fn remaining_paths(
+            ws_root: &std::path::Path,
+            ws_config: &crate::config::Config,
+        ) -> Vec<std::path::PathBuf> {
+            let dirty = crate::ops::git::is_dirty(ws_root).unwrap().unwrap();
+            disallowed_dirty(ws_root, ws_config, dirty)
+                .unwrap()
+                .into_iter()
+                .map(|d| match d {
+                    crate::ops::git::Dirty::Path { path, .. } => path,
+                    crate::ops::git::Dirty::State(state) => panic!("unexpected state {}", state),
+                })
+                .collect()
+        }
+
+        #[test]
+        fn allow_dirty() {
+            let (temp, ws_meta) = repo("allow-dirty = [\".build-info\", \"target-metadata/**\"]\n");
+            let generated = temp.child("generated");
+            generated.child(".build-info").write_str("42").unwrap();
+            generated
+                .child("target-metadata/nested/info.json")
+                .write_str("{}")
+                .unwrap();
+            let ws_root = ws_meta.workspace_root.as_std_path();
+            let ws_config =
+                crate::config::load_workspace_config(&Default::default(), &ws_meta).unwrap();
+
+            // Outside of the workspace root, so not covered by its globs
+            assert_eq!(
+                remaining_paths(ws_root, &ws_config),
+                [std::path::Path::new("notes.txt")]
+            );
+
+            std::fs::remove_file(temp.child("notes.txt").path()).unwrap();
+            assert!(verify_git_is_clean(ws_root, &ws_config, false, log::Level::Error).unwrap());
+
+            generated.child("src/main.rs").write_str("dirty").unwrap();
+            assert_eq!(
+                remaining_paths(ws_root, &ws_config),
+                [std::path::Path::new("generated/src/main.rs")]
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn clean_check_path() {
+            let (temp, ws_meta) = repo("clean-check-path = \".\"\n");
+
+            let ws_config =
+                crate::config::load_workspace_config(&Default::default(), &ws_meta).unwrap();
+            assert!(verify_git_is_clean(
+                ws_meta.workspace_root.as_std_path(),
+                &ws_config,
+                false,
+                log::Level::Error
+            )
+            .unwrap());
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod ordering {
+        use super::*;
+
+        use assert_fs::prelude::*;
+
+        /// Plan a minor release of `pure_ws` as one shared-version group
+        fn plan_summary() -> String {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+            git2::Repository::init(temp.path()).unwrap();
+            let (_, pkgs) = plan::fixture::planned(temp.path(), &Default::default(), |pkg| {
+                pkg.config.shared_version =
+                    Some(crate::config::SharedVersion::Name("group".to_owned()));
+            });
+            let summary = summary(&pkgs);
+
+            temp.close().unwrap();
+            summary
+        }
+
+        #[test]
+        fn repeatable() {
+            let first = plan_summary();
+            assert_eq!(first, plan_summary());
+
+            let names: Vec<_> = first
+                .lines()
+                .skip(1)
+                .map(|line| line.split_whitespace().next().unwrap())
+                .collect();
+            // Dependencies first
+            assert_eq!(names, ["a", "b", "c"]);
+        }
+    }
+
+    mod replacements {
+        use super::*;
+
+        const FIXTURE: &str = "tests/fixtures/stale_changelog";
+
+        fn load() -> Vec<plan::PackageRelease> {
+            let (_, pkgs) = plan::fixture::planned(FIXTURE, &Default::default(), |_| {});
+            pkgs
+        }
+
+        #[test]
+        fn reports_every_problem() {
+            let pkgs = load();
+
+            let problems = replace::replacement_problems(&pkgs[..1]).unwrap();
+            assert_eq!(problems.len(), 2, "{:?}", problems);
+            assert!(problems[0].contains("at least 1 replacements expected, found 0"));
+            assert!(problems[1].contains("unable to find file"));
+        }
+
+        #[test]
+        fn fails_before_modifying() {
+            let pkgs = load();
+            let changelog = std::path::Path::new(FIXTURE).join("CHANGELOG.md");
+            let before = std::fs::read_to_string(&changelog).unwrap();
+
+            assert!(!verify_replacements(&pkgs, true, log::Level::Error).unwrap());
+            assert!(verify_replacements(&pkgs, false, log::Level::Error).is_err());
+            assert!(verify_replacements(&pkgs, false, log::Level::Warn).unwrap());
+
+            assert_eq!(std::fs::read_to_string(&changelog).unwrap(), before);
+        }
+    }
+}
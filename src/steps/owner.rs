@@ -11,24 +11,15 @@ pub struct OwnerStep {
     #[command(flatten)]
     workspace: clap_cargo::Workspace,
 
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
-
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
@@ -37,13 +28,9 @@ pub struct OwnerStep {
 
 impl OwnerStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
 
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
-
         let ws_meta = self
             .manifest
             .metadata()
@@ -68,7 +55,7 @@ impl OwnerStep {
 
             pkg.config.publish = Some(false);
             pkg.config.owners = Some(vec![]);
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
 
             let crate_name = pkg.meta.name.as_str();
             log::debug!("disabled by user, skipping {}", crate_name,);
@@ -76,35 +63,50 @@ impl OwnerStep {
 
         let mut pkgs = plan::plan(pkgs)?;
 
+        let index = crate::ops::index::CratesIoIndex::open(
+            crate::ops::index::IndexProtocol::from_env(),
+            false,
+            false,
+        )?;
         for pkg in pkgs.values_mut() {
-            if pkg.config.owners().is_empty() {
+            // Alternate registries aren't indexed locally, so `cargo owner` will report those
+            let is_unpublished = pkg.config.is_crates_io()
+                && index.is_available()
+                && index.crate_(pkg.published_name()).is_none();
+            if is_unpublished && pkg.config.release() {
+                let _ = crate::ops::reporter::note(format!(
+                    "skipping {} which isn't published yet",
+                    pkg.meta.name
+                ));
+                pkg.config.publish = Some(false);
+                pkg.config.owners = Some(vec![]);
+                pkg.skip(plan::SkipReason::Unpublished);
+            } else if pkg.config.owners().is_empty() {
                 log::debug!("disabled due to no owners, skipping {}", pkg.meta.name);
                 pkg.config.publish = Some(false);
                 pkg.config.owners = Some(vec![]);
-                pkg.config.release = Some(false);
+                pkg.skip(plan::SkipReason::NoOwners);
             } else if !pkg.config.publish() {
                 log::debug!("disabled due to publish=false, skipping {}", pkg.meta.name);
                 pkg.config.publish = Some(false);
                 pkg.config.owners = Some(vec![]);
-                pkg.config.release = Some(false);
+                pkg.skip(plan::SkipReason::NotPublished);
             }
         }
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Error,
         )?;
@@ -112,6 +114,7 @@ impl OwnerStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Error,
         )?;
@@ -126,35 +129,287 @@ impl OwnerStep {
         // STEP 1: Release Confirmation
         super::confirm("Owner", &selected_pkgs, self.no_confirm, dry_run)?;
 
-        ensure_owners(&selected_pkgs, dry_run)?;
+        ensure_owners(&selected_pkgs, true, dry_run)?;
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
             ..Default::default()
         }
     }
 }
 
-pub fn ensure_owners(pkgs: &[plan::PackageRelease], dry_run: bool) -> Result<(), CliError> {
+pub fn ensure_owners(
+    pkgs: &[plan::PackageRelease],
+    remove_extra: bool,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("owner");
     for pkg in pkgs {
         if !pkg.config.publish() || !pkg.ensure_owners {
             continue;
         }
 
-        let crate_name = pkg.meta.name.as_str();
         crate::ops::cargo::ensure_owners(
-            crate_name,
+            pkg.published_name(),
             pkg.config.owners(),
             pkg.config.registry(),
+            pkg.config.index_url(),
+            remove_extra,
             dry_run,
         )?;
     }
 
     Ok(())
 }
+
+/// Fail when a configured owner no longer owns an already-published package, per `verify-owners`
+///
+/// Packages for `index-url` are looked up there, and the rest on crates.io or their `registry`.
+pub fn verify_owners(
+    pkgs: &[plan::PackageRelease],
+    index: &impl crate::ops::cargo::PublishIndex,
+    index_urls: &mut crate::ops::index::IndexUrls,
+    owners: &dyn crate::ops::cargo::OwnerRegistry,
+    dry_run: bool,
+    level: log::Level,
+) -> Result<bool, CliError> {
+    use crate::ops::cargo::PublishIndex as _;
+
+    let _timer = crate::ops::timings::start("verify_owners");
+    let mut success = true;
+
+    for pkg in pkgs {
+        if !pkg.config.verify_owners() || !pkg.config.publish() || pkg.config.owners().is_empty() {
+            continue;
+        }
+        let name = pkg.published_name();
+        // Owners are only set on publish, so there is nothing to check yet
+        let unpublished = match (pkg.config.index_url(), pkg.config.registry()) {
+            (Some(index_url), _) => index_urls.open(index_url).versions(name).is_empty(),
+            (None, None) => index.versions(name).is_empty(),
+            // Alternate registries aren't indexed locally, leaving it to the lookup
+            (None, Some(_)) => false,
+        };
+        if unpublished {
+            log::debug!("skipping owners check of unpublished {}", name);
+            continue;
+        }
+
+        let current = match owners.owners(name, pkg.config.registry(), pkg.config.index_url()) {
+            Ok(current) => current,
+            Err(err) => {
+                let _ = crate::ops::reporter::log(
+                    level,
+                    format!("failed to look up the owners of {}: {:#}", name, err),
+                );
+                success = false;
+                continue;
+            }
+        };
+        let missing = missing_owners(pkg.config.owners(), &current);
+        if !missing.is_empty() {
+            let _ = crate::ops::reporter::log(
+                level,
+                format!(
+                    "{} is missing configured owners: {}\n  add them with `cargo owner --add` or update `owners`",
+                    name,
+                    missing.join(", ")
+                ),
+            );
+            success = false;
+        }
+    }
+
+    if !success && level == log::Level::Error && !dry_run {
+        return Err(101.into());
+    }
+
+    Ok(success)
+}
+
+/// Logins among `expected` that aren't in `current`
+fn missing_owners<'e>(expected: &'e [String], current: &[String]) -> Vec<&'e str> {
+    expected
+        .iter()
+        .filter(|login| !current.contains(login))
+        .map(|login| login.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::error::CargoResult;
+
+    /// Knows only the listed crates
+    struct MockIndex(&'static [&'static str]);
+
+    impl crate::ops::cargo::PublishIndex for MockIndex {
+        fn update(&mut self) -> CargoResult<()> {
+            Ok(())
+        }
+
+        fn is_published(&self, name: &str, _version: &str) -> bool {
+            self.0.contains(&name)
+        }
+
+        fn versions(&self, name: &str) -> Vec<String> {
+            if self.0.contains(&name) {
+                vec!["0.1.0".to_owned()]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    /// Every crate is owned by the same logins
+    struct MockOwners(&'static [&'static str]);
+
+    impl crate::ops::cargo::OwnerRegistry for MockOwners {
+        fn owners(
+            &self,
+            _name: &str,
+            _registry: Option<&str>,
+            _index_url: Option<&str>,
+        ) -> CargoResult<Vec<String>> {
+            Ok(self.0.iter().map(|s| (*s).to_owned()).collect())
+        }
+    }
+
+    /// Every lookup fails, like for a crate the registry doesn't have
+    struct FailingOwners;
+
+    impl crate::ops::cargo::OwnerRegistry for FailingOwners {
+        fn owners(
+            &self,
+            _name: &str,
+            _registry: Option<&str>,
+            _index_url: Option<&str>,
+        ) -> CargoResult<Vec<String>> {
+            anyhow::bail!("crate not found")
+        }
+    }
+
+    /// `pure_ws` with `verify-owners` and `owners` set
+    fn pkgs(owners: &[&str]) -> Vec<plan::PackageRelease> {
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let (_, pkgs) = plan::fixture::load("tests/fixtures/pure_ws", &args);
+        let mut pkgs: Vec<_> = pkgs.into_values().collect();
+        for pkg in &mut pkgs {
+            pkg.config.verify_owners = Some(true);
+            pkg.config.owners = Some(owners.iter().map(|s| (*s).to_owned()).collect());
+        }
+        pkgs
+    }
+
+    #[test]
+    fn all_present() {
+        let pkgs = pkgs(&["github:org:team"]);
+        let owners = MockOwners(&["github:org:team", "maintainer"]);
+        assert!(verify_owners(
+            &pkgs,
+            &MockIndex(&["a", "b"]),
+            &mut Default::default(),
+            &owners,
+            false,
+            log::Level::Error
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn missing() {
+        let pkgs = pkgs(&["github:org:team", "maintainer"]);
+        let owners = MockOwners(&["maintainer"]);
+        assert!(!verify_owners(
+            &pkgs,
+            &MockIndex(&["a"]),
+            &mut Default::default(),
+            &owners,
+            true,
+            log::Level::Error
+        )
+        .unwrap());
+        assert!(verify_owners(
+            &pkgs,
+            &MockIndex(&["a"]),
+            &mut Default::default(),
+            &owners,
+            false,
+            log::Level::Error
+        )
+        .is_err());
+        assert_eq!(
+            missing_owners(pkgs[0].config.owners(), &["maintainer".to_owned()]),
+            ["github:org:team"]
+        );
+    }
+
+    #[test]
+    fn unpublished_skipped() {
+        let pkgs = pkgs(&["github:org:team"]);
+        let owners = MockOwners(&[]);
+        assert!(verify_owners(
+            &pkgs,
+            &MockIndex(&[]),
+            &mut Default::default(),
+            &owners,
+            false,
+            log::Level::Error
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn disabled() {
+        let mut pkgs = pkgs(&["github:org:team"]);
+        for pkg in &mut pkgs {
+            pkg.config.verify_owners = None;
+        }
+        let owners = MockOwners(&[]);
+        assert!(verify_owners(
+            &pkgs,
+            &MockIndex(&["a", "b", "c"]),
+            &mut Default::default(),
+            &owners,
+            false,
+            log::Level::Error
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn lookup_failure() {
+        let pkgs = pkgs(&["github:org:team"]);
+        let index = MockIndex(&["a"]);
+        assert!(!verify_owners(
+            &pkgs,
+            &index,
+            &mut Default::default(),
+            &FailingOwners,
+            true,
+            log::Level::Error
+        )
+        .unwrap());
+        assert!(verify_owners(
+            &pkgs,
+            &index,
+            &mut Default::default(),
+            &FailingOwners,
+            false,
+            log::Level::Error
+        )
+        .is_err());
+    }
+}
@@ -8,41 +8,252 @@ use crate::ops::git;
 use crate::ops::replace::Template;
 use crate::ops::version::VersionExt as _;
 
+/// Load every workspace member
+///
+/// Packages are in topological order, dependencies before their dependents and otherwise in
+/// `workspace_members` order, so publishing can follow it.  Steps process and report packages in
+/// this order; everything without an inherent order, like pushed refs, is sorted alphabetically so
+/// repeated runs print the same output.
 pub fn load(
     args: &config::ConfigArgs,
     ws_meta: &cargo_metadata::Metadata,
 ) -> CargoResult<indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>> {
-    let root = git::top_level(ws_meta.workspace_root.as_std_path())?;
+    let (pkgs, warnings) = load_quietly(args, ws_meta)?;
+    for warning in warnings {
+        config::warn_once(warning);
+    }
+    Ok(pkgs)
+}
+
+/// [`load`], returning the warnings on the packages rather than reporting them
+#[allow(clippy::type_complexity)]
+fn load_quietly(
+    args: &config::ConfigArgs,
+    ws_meta: &cargo_metadata::Metadata,
+) -> CargoResult<(
+    indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
+    Vec<String>,
+)> {
+    let root = match git::top_level(ws_meta.workspace_root.as_std_path()) {
+        Ok(root) => root,
+        // Nothing past the manifests is read, so a repository isn't needed
+        Err(_) if args.metadata_only => ws_meta.workspace_root.as_std_path().to_owned(),
+        Err(err) => return Err(err),
+    };
 
     let member_ids = cargo::sort_workspace(ws_meta);
-    member_ids
+    let mut pkgs = indexmap::IndexMap::new();
+    let mut warnings = Vec::new();
+    // Report every broken package at once, rather than making the user fix them one at a time
+    let mut broken = Vec::new();
+    for member_id in member_ids {
+        let pkg_meta = &ws_meta[member_id];
+        let _timer = crate::ops::timings::start(format_args!("load {}", pkg_meta.name));
+        match PackageRelease::load_quietly(args, &root, ws_meta, pkg_meta) {
+            Ok((pkg, pkg_warnings)) => {
+                pkgs.insert(pkg.meta.id.clone(), pkg);
+                warnings.extend(pkg_warnings);
+            }
+            Err(err) if args.skip_broken => {
+                warnings.push(format!(
+                    "skipping {} which failed to load: {:#}",
+                    pkg_meta.name, err
+                ));
+            }
+            Err(err) => {
+                broken.push(format!("{:#}", err));
+            }
+        }
+    }
+    if !broken.is_empty() {
+        anyhow::bail!(
+            "failed to load {} package(s):\n{}\n\nto release the remaining packages, pass `--skip-broken`",
+            broken.len(),
+            broken.join("\n")
+        );
+    }
+    verify_distinct_roots(pkgs.values())?;
+
+    Ok((pkgs, warnings))
+}
+
+/// Fail when packages share a directory
+///
+/// Changes, dirty files, and commits are attributed to packages by directory, so packages sharing
+/// one would each claim the other's files.
+fn verify_distinct_roots<'p>(pkgs: impl Iterator<Item = &'p PackageRelease>) -> CargoResult<()> {
+    let mut roots: indexmap::IndexMap<&Path, Vec<&str>> = indexmap::IndexMap::new();
+    for pkg in pkgs {
+        roots
+            .entry(pkg.package_root.as_path())
+            .or_default()
+            .push(pkg.meta.name.as_str());
+    }
+    let shared: Vec<_> = roots
         .iter()
-        .map(|p| PackageRelease::load(args, &root, ws_meta, &ws_meta[p]))
-        .map(|p| p.map(|p| (p.meta.id.clone(), p)))
-        .collect()
+        .filter(|(_, names)| 1 < names.len())
+        .map(|(root, names)| format!("{} share {}", names.join(", "), root.display()))
+        .collect();
+    if !shared.is_empty() {
+        anyhow::bail!(
+            "packages must each have their own directory, as files are attributed to packages by directory:\n  {}",
+            shared.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// Skip packages without changes since their prior tag, for `--changed-only`
+///
+/// Packages without a prior tag count as changed.  An unchanged package is still released when a
+/// dependency being released would rewrite its requirement, per the dependency's
+/// `dependent-version`.
+pub fn skip_unchanged(
+    ws_meta: &cargo_metadata::Metadata,
+    pkgs: &mut indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
+) {
+    for pkg in pkgs.values_mut() {
+        if pkg.changed.is_none() {
+            pkg.changed = Some(match pkg.prior_tag.as_deref() {
+                Some(prior_tag) => super::version::changed_since(ws_meta, pkg, prior_tag)
+                    .map(|changed| !changed.is_empty())
+                    .unwrap_or(true),
+                None => true,
+            });
+        }
+    }
+
+    // Dependencies come first, so a dependent sees whether they are being released
+    let ids: Vec<_> = pkgs.keys().cloned().collect();
+    for id in ids {
+        let pkg = &pkgs[&id];
+        if !pkg.config.release() || pkg.changed != Some(false) {
+            continue;
+        }
+        let updated_dependency = pkg.meta.dependencies.iter().find_map(|dep| {
+            let dep_pkg = pkgs
+                .values()
+                .find(|p| p.meta.name == dep.name && p.config.release())?;
+            let version = dep_pkg.planned_version.as_ref()?;
+            let rewritten = dep.req != semver::VersionReq::STAR
+                && match dep_pkg.config.dependent_version() {
+                    config::DependentVersion::Upgrade => true,
+                    config::DependentVersion::Fix => !dep.req.matches(&version.full_version),
+                };
+            rewritten.then(|| dep_pkg.meta.name.clone())
+        });
+
+        let pkg = pkgs.get_mut(&id).expect("ids come from pkgs");
+        match updated_dependency {
+            Some(dep_name) => {
+                log::debug!(
+                    "releasing unchanged {} for its updated dependency {}",
+                    pkg.meta.name,
+                    dep_name
+                );
+                pkg.changed = Some(true);
+            }
+            None => {
+                log::debug!(
+                    "skipping {} which is unchanged since {}",
+                    pkg.meta.name,
+                    pkg.prior_tag.as_deref().unwrap_or_default()
+                );
+                pkg.planned_version = None;
+                pkg.skip(SkipReason::Unchanged);
+            }
+        }
+    }
+}
+
+/// What a release of the workspace at `ws_meta` would do, without reporting anything
+///
+/// Packages are bumped to `level_or_version`, when given, like `cargo release <LEVEL|VERSION>`,
+/// and each entry lists the commits since the package's prior tag, for tools embedding
+/// cargo-release.  Skipped packages follow, with their `skip_reason`.
+pub fn compute(
+    ws_meta: &cargo_metadata::Metadata,
+    args: &config::ConfigArgs,
+    level_or_version: Option<&super::TargetVersion>,
+    metadata: Option<&str>,
+) -> CargoResult<Vec<super::changes::ChangesEntry>> {
+    let (mut pkgs, warnings) = load_quietly(args, ws_meta)?;
+    for warning in warnings {
+        log::debug!("{}", warning);
+    }
+    if let Some(level_or_version) = level_or_version {
+        for pkg in pkgs.values_mut() {
+            if pkg.config.release() {
+                pkg.bump(level_or_version, metadata)?;
+            }
+        }
+    }
+    let (pkgs, notes) = plan_quietly(pkgs)?;
+    for note in notes {
+        log::debug!("{}", note);
+    }
+    let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        .into_iter()
+        .map(|(_, pkg)| pkg)
+        .partition(|pkg| pkg.config.release());
+    super::changes::changes_entries(ws_meta, &selected_pkgs, &excluded_pkgs)
 }
 
 pub fn plan(
-    mut pkgs: indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
+    pkgs: indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
 ) -> CargoResult<indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>> {
-    let mut shared_versions: std::collections::HashMap<String, Version> = Default::default();
-    for pkg in pkgs.values() {
-        if !pkg.config.release() {
-            continue;
+    let (pkgs, notes) = plan_quietly(pkgs)?;
+    for note in notes {
+        let _ = crate::ops::reporter::note(note);
+    }
+    Ok(pkgs)
+}
+
+/// [`plan`], returning the notes on its decisions rather than reporting them
+#[allow(clippy::type_complexity)]
+fn plan_quietly(
+    mut pkgs: indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
+) -> CargoResult<(
+    indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
+    Vec<String>,
+)> {
+    for pkg in pkgs.values_mut() {
+        if pkg.config.release()
+            && pkg.config.shared_version_only_on_change()
+            && pkg.changed == Some(false)
+        {
+            log::debug!(
+                "skipping {} which is unchanged since {}",
+                pkg.meta.name,
+                pkg.prior_tag.as_deref().unwrap_or_default()
+            );
+            pkg.planned_version = None;
+            pkg.skip(SkipReason::Unchanged);
         }
+    }
+
+    let mut shared_versions: indexmap::IndexMap<String, Version> = Default::default();
+    for pkg in pkgs.values() {
         let group_name = if let Some(group_name) = pkg.config.shared_version() {
             group_name.to_owned()
         } else {
             continue;
         };
-        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+        let version = if pkg.config.release() {
+            pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version)
+        } else if pkg.config.shared_version_only_on_change() {
+            // Skipped for being unchanged but the group still shouldn't go backwards
+            &pkg.initial_version
+        } else {
+            continue;
+        };
         match shared_versions.entry(group_name) {
-            std::collections::hash_map::Entry::Occupied(mut existing) => {
+            indexmap::map::Entry::Occupied(mut existing) => {
                 if existing.get().full_version < version.full_version {
                     existing.insert(version.clone());
                 }
             }
-            std::collections::hash_map::Entry::Vacant(vacant) => {
+            indexmap::map::Entry::Vacant(vacant) => {
                 vacant.insert(version.clone());
             }
         }
@@ -66,11 +277,40 @@ pub fn plan(
         }
     }
 
+    let mut notes = Vec::new();
     for pkg in pkgs.values_mut() {
-        pkg.plan()?;
+        notes.extend(pkg.plan_quietly()?);
     }
 
-    Ok(pkgs)
+    let released: std::collections::HashMap<_, _> = pkgs
+        .values()
+        .filter(|p| p.config.release())
+        .filter_map(|p| {
+            let version = p.planned_version.as_ref()?;
+            Some((
+                p.meta.name.clone(),
+                (p.initial_version.clone(), version.clone()),
+            ))
+        })
+        .collect();
+    for pkg in pkgs.values_mut() {
+        let mut released_dependencies: Vec<ReleasedDependency> = Vec::new();
+        for dep in &pkg.meta.dependencies {
+            if released_dependencies.iter().any(|r| r.name == dep.name) {
+                continue;
+            }
+            if let Some((prev_version, version)) = released.get(&dep.name) {
+                released_dependencies.push(ReleasedDependency {
+                    name: dep.name.clone(),
+                    prev_version: prev_version.clone(),
+                    version: version.clone(),
+                });
+            }
+        }
+        pkg.released_dependencies = released_dependencies;
+    }
+
+    Ok((pkgs, notes))
 }
 
 #[derive(Debug)]
@@ -78,19 +318,38 @@ pub struct PackageRelease {
     pub meta: cargo_metadata::Package,
     pub manifest_path: PathBuf,
     pub package_root: PathBuf,
+    /// Other workspace members under `package_root`, whose files are their own
+    pub nested_roots: Vec<PathBuf>,
     pub is_root: bool,
     pub config: config::Config,
 
-    pub package_content: Vec<PathBuf>,
+    /// What `cargo package` includes, see [`PackageRelease::package_content`]
+    pub content: PackageContentCache,
     pub bin: bool,
     pub dependents: Vec<Dependency>,
     pub features: cargo::Features,
 
     pub initial_version: Version,
     pub prior_tag: Option<String>,
+    /// The version released at `prior_tag`, if it could be read
+    pub prior_version: Option<Version>,
+    /// How `prior_tag` was found
+    pub change_detection: ChangeDetection,
+    /// Whether the package changed since `prior_tag`, if checked
+    pub changed: Option<bool>,
+    /// How many of the package's files changed since `prior_tag`, if checked
+    pub changed_file_count: Option<usize>,
 
     pub planned_version: Option<Version>,
     pub planned_tag: Option<String>,
+    /// The commit to tag, rather than `HEAD`
+    pub tag_target: Option<String>,
+    /// Dependency requirements rewritten for `planned_version`
+    pub dependency_updates: Vec<cargo::DependencyUpdate>,
+    /// Workspace dependencies getting a new version in the same release
+    pub released_dependencies: Vec<ReleasedDependency>,
+    /// Why the package isn't being released, when it isn't
+    pub skip_reason: Option<SkipReason>,
 
     pub ensure_owners: bool,
 }
@@ -102,39 +361,79 @@ impl PackageRelease {
         ws_meta: &cargo_metadata::Metadata,
         pkg_meta: &cargo_metadata::Package,
     ) -> CargoResult<Self> {
+        let (pkg, warnings) = Self::load_quietly(args, git_root, ws_meta, pkg_meta)?;
+        for warning in warnings {
+            config::warn_once(warning);
+        }
+        Ok(pkg)
+    }
+
+    /// [`PackageRelease::load`], returning the warnings on the package rather than reporting them
+    fn load_quietly(
+        args: &config::ConfigArgs,
+        git_root: &Path,
+        ws_meta: &cargo_metadata::Metadata,
+        pkg_meta: &cargo_metadata::Package,
+    ) -> CargoResult<(Self, Vec<String>)> {
+        let mut warnings = Vec::new();
         let meta = pkg_meta.clone();
-        let manifest_path = pkg_meta.manifest_path.as_std_path().to_owned();
+        let manifest_path = crate::ops::path::resolve(pkg_meta.manifest_path.as_std_path());
         let package_root = manifest_path
             .parent()
             .unwrap_or_else(|| Path::new("."))
             .to_owned();
-        let config = config::load_package_config(args, ws_meta, pkg_meta)?;
+        let nested_roots = ws_meta
+            .packages
+            .iter()
+            .filter(|p| p.id != pkg_meta.id && ws_meta.workspace_members.contains(&p.id))
+            .filter_map(|p| {
+                let manifest_path = crate::ops::path::resolve(p.manifest_path.as_std_path());
+                manifest_path.parent().map(Path::to_owned)
+            })
+            .filter(|root| root != &package_root && root.starts_with(&package_root))
+            .collect();
+        let (config, config_warnings) =
+            config::load_package_config_quietly(args, ws_meta, pkg_meta)?;
+        warnings.extend(config_warnings);
         if !config.release() {
             log::trace!("disabled in config, skipping {}", manifest_path.display());
         }
+        if let Some(registries) = pkg_meta.publish.as_deref() {
+            if config.release()
+                && config.publish()
+                && config.is_crates_io()
+                && 1 < registries.len()
+                && !registries.iter().any(|r| r == "crates-io")
+            {
+                warnings.push(format!(
+                    "{} can be published to any of {}; set `registry` to pick one",
+                    pkg_meta.name,
+                    registries.join(", ")
+                ));
+            }
+        }
 
         let bin = pkg_meta
             .targets
             .iter()
             .flat_map(|t| t.kind.iter())
             .any(|k| k == "bin");
-        let mut package_content = cargo::package_content(&manifest_path)?;
-        if bin {
-            // When publishing bins, the lock file is listed as relative to the package root, so
-            // let's remap it to the workspace root
-            let lock_file = ws_meta.workspace_root.as_std_path().join("Cargo.lock");
-            if !package_content.contains(&lock_file) {
-                package_content.push(lock_file);
-            }
-        } else {
-            // Lock files are not relevant when publishing non-bins
-            package_content.retain(|p| !p.ends_with("Cargo.lock"));
+        // When publishing bins, the lock file is listed as relative to the package root, so
+        // let's remap it to the workspace root
+        let lock_file = bin.then(|| ws_meta.workspace_root.as_std_path().join("Cargo.lock"));
+        // Only needed for change detection, so don't block releasing the rest of the workspace
+        let fallback = !config.publish() || !is_publishable(&config, pkg_meta);
+        let content = PackageContentCache::new(
+            manifest_path.clone(),
+            package_root.clone(),
+            lock_file,
+            fallback,
+        );
+        if !fallback && !args.metadata_only {
+            // Surface broken manifests now rather than part way through releasing; later callers
+            // reuse this listing until the manifest changes
+            content.with(|_| ())?;
         }
-        package_content.retain(|p| {
-            !p.strip_prefix(&package_root)
-                .map(|p| p.starts_with("tests"))
-                .unwrap_or(false)
-        });
         let features = config.features();
         let dependents = find_dependents(ws_meta, pkg_meta)
             .map(|(pkg, dep)| Dependency {
@@ -143,7 +442,7 @@ impl PackageRelease {
             })
             .collect();
 
-        let is_root = git_root == package_root;
+        let is_root = config.is_root().unwrap_or(git_root == package_root);
         let initial_version = Version::from(pkg_meta.version.clone());
         let tag_name = config.tag_name();
         let tag_prefix = config.tag_prefix(is_root);
@@ -156,14 +455,20 @@ impl PackageRelease {
             &initial_version,
             &initial_version,
         );
-        let prior_tag = if crate::ops::git::tag_exists(&package_root, &initial_tag)? {
-            Some(initial_tag)
+        // Only consider this branch's release line, so maintenance branches can release alongside
+        // newer ones
+        let (prior_tag, change_detection) = if args.metadata_only {
+            (None, ChangeDetection::None)
+        } else if crate::ops::git::tag_exists(&package_root, &initial_tag)?
+            && crate::ops::git::tag_is_ancestor(&package_root, &initial_tag)?
+        {
+            (Some(initial_tag), ChangeDetection::InitialTag)
         } else {
             let tag_name = config.tag_name();
             let tag_prefix = config.tag_prefix(is_root);
             let name = meta.name.as_str();
             let tag_glob = render_tag_glob(tag_name, tag_prefix, name);
-            match globset::Glob::new(&tag_glob) {
+            let prior_tag = match globset::Glob::new(&tag_glob) {
                 Ok(tag_glob) => {
                     let tag_glob = tag_glob.compile_matcher();
                     crate::ops::git::find_last_tag(&package_root, &tag_glob)
@@ -172,37 +477,220 @@ impl PackageRelease {
                     log::debug!("failed to find tag with glob `{}`: {}", tag_glob, err);
                     None
                 }
-            }
+            };
+            let change_detection = if prior_tag.is_some() {
+                ChangeDetection::GlobFallback
+            } else {
+                ChangeDetection::None
+            };
+            (prior_tag, change_detection)
         };
+        let prior_version = prior_tag.as_deref().and_then(|prior_tag| {
+            crate::ops::git::package_version_at(&package_root, prior_tag, &manifest_path)
+                .map(Version::from)
+        });
 
         let planned_version = None;
         let planned_tag = None;
+        let skip_reason = (!config.release()).then_some(SkipReason::Config);
         let ensure_owners = config.publish() && !config.owners().is_empty();
 
-        let pkg = PackageRelease {
+        let mut pkg = PackageRelease {
             meta,
             manifest_path,
             package_root,
+            nested_roots,
             is_root,
             config,
 
-            package_content,
+            content,
             bin,
             dependents,
             features,
 
             initial_version,
             prior_tag,
+            prior_version,
+            change_detection,
+            changed: None,
+            changed_file_count: None,
 
             planned_version,
             planned_tag,
+            tag_target: None,
+            dependency_updates: Vec::new(),
+            released_dependencies: Vec::new(),
+            skip_reason,
             ensure_owners,
         };
-        Ok(pkg)
+        pkg.detect_changes(ws_meta);
+        Ok((pkg, warnings))
+    }
+
+    /// Stop releasing the package, keeping the first reason for the skipped packages report
+    pub fn skip(&mut self, reason: SkipReason) {
+        self.config.release = Some(false);
+        self.skip_reason.get_or_insert(reason);
+    }
+
+    /// `{{prev_tag_name}}`, empty when there is no prior tag
+    pub fn prev_tag_name(&self) -> &str {
+        self.prior_tag.as_deref().unwrap_or_default()
     }
 
     pub fn set_prior_tag(&mut self, prior_tag: String) {
+        self.prior_version = crate::ops::git::package_version_at(
+            &self.package_root,
+            &prior_tag,
+            &self.manifest_path,
+        )
+        .map(Version::from);
         self.prior_tag = Some(prior_tag);
+        self.change_detection = ChangeDetection::Explicit;
+    }
+
+    /// Check for changes since `prior_tag`, when the config needs to know
+    pub fn detect_changes(&mut self, ws_meta: &cargo_metadata::Metadata) {
+        if !self.config.shared_version_only_on_change() {
+            return;
+        }
+        self.changed_file_count = self.prior_tag.as_deref().and_then(|prior_tag| {
+            super::version::changed_since(ws_meta, self, prior_tag).map(|changed| changed.len())
+        });
+        self.changed = self.changed_file_count.map(|count| count != 0);
+    }
+
+    /// The placeholders every template rendered for this package's release has
+    pub fn template(&self) -> Template<'_> {
+        let version = self
+            .planned_version
+            .as_ref()
+            .unwrap_or(&self.initial_version);
+        Template {
+            prev_version: Some(self.initial_version.bare_version_string.as_str()),
+            prev_metadata: Some(self.initial_version.full_version.build.as_str()),
+            version: Some(version.bare_version_string.as_str()),
+            metadata: Some(version.full_version.build.as_str()),
+            crate_name: Some(self.meta.name.as_str()),
+            date: Some(crate::ops::replace::NOW.as_str()),
+            tag_name: self.planned_tag.as_deref(),
+            prev_tag_name: Some(self.prev_tag_name()),
+            vars: self.config.template_vars(),
+            ..Default::default()
+        }
+    }
+
+    /// `field` of the released dependency `name`, for `{{dep.<name>.<field>}}`
+    pub fn dependency_var(&self, name: &str, field: &str) -> Option<&str> {
+        let dep = self.released_dependencies.iter().find(|d| d.name == name)?;
+        match field {
+            "version" => Some(dep.version.bare_version_string.as_str()),
+            "prev_version" => Some(dep.prev_version.bare_version_string.as_str()),
+            _ => None,
+        }
+    }
+
+    /// The crate name in the registry, per `publish-as`
+    pub fn published_name(&self) -> &str {
+        self.config.publish_as().unwrap_or(self.meta.name.as_str())
+    }
+
+    /// The files `cargo package` includes, listed again once the manifest has changed
+    pub fn package_content(&self) -> CargoResult<Vec<PathBuf>> {
+        self.content.with(|files| files.to_vec())
+    }
+
+    /// The `.crate` for `version`, built on first use and again only once the files are relisted
+    pub fn package_archive(
+        &self,
+        ws_meta: &cargo_metadata::Metadata,
+        version: &str,
+    ) -> CargoResult<cargo::PackageArchive> {
+        let crate_name = self.meta.name.as_str();
+        let pkgid = self.pkgid(ws_meta);
+        self.content.archive(version, |manifest_path| {
+            cargo::package_archive(
+                manifest_path,
+                pkgid,
+                ws_meta.target_directory.as_std_path(),
+                crate_name,
+                version,
+            )
+        })
+    }
+
+    /// The `.crate` for `planned_version`, packaged from a staged copy before the version is bumped
+    pub fn planned_archive(
+        &self,
+        ws_meta: &cargo_metadata::Metadata,
+    ) -> CargoResult<cargo::PackageArchive> {
+        let crate_name = self.meta.name.as_str();
+        let pkgid = self.pkgid(ws_meta);
+        let version = &self.initial_version.full_version_string;
+        let planned_version = &self
+            .planned_version
+            .as_ref()
+            .unwrap_or(&self.initial_version)
+            .full_version_string;
+        self.content.archive(planned_version, |manifest_path| {
+            cargo::package_archive_as(
+                manifest_path,
+                pkgid,
+                ws_meta.target_directory.as_std_path(),
+                crate_name,
+                version,
+                planned_version,
+            )
+        })
+    }
+
+    fn pkgid(&self, ws_meta: &cargo_metadata::Metadata) -> Option<&str> {
+        if 1 < ws_meta.workspace_members.len() {
+            Some(self.meta.name.as_str())
+        } else {
+            None
+        }
+    }
+
+    /// Whether a change to `path` is a change to this package, per `change-paths`
+    pub fn owns_path(&self, path: &Path) -> bool {
+        let in_content = || {
+            self.content
+                .with(|files| files.iter().any(|p| p == path))
+                .unwrap_or_else(|err| {
+                    log::debug!("failed to list {}: {:#}", self.meta.name, err);
+                    false
+                })
+        };
+        match self.config.change_paths() {
+            config::ChangePaths::Package => in_content(),
+            config::ChangePaths::CrateDir => {
+                let in_dir = path.starts_with(&self.package_root)
+                    && !self.nested_roots.iter().any(|root| path.starts_with(root));
+                in_dir || in_content()
+            }
+        }
+    }
+
+    /// The machine-readable view of this package's plan
+    pub fn plan_entry(&self) -> PlanEntry {
+        let release = self.config.release();
+        PlanEntry {
+            name: self.meta.name.clone(),
+            version: self.initial_version.full_version_string.clone(),
+            planned_version: self
+                .planned_version
+                .as_ref()
+                .map(|v| v.full_version_string.clone()),
+            planned_tag: self.planned_tag.clone(),
+            prior_tag: self.prior_tag.clone(),
+            changed_file_count: self.changed_file_count,
+            change_detection: self.change_detection,
+            skip_reason: self.skip_reason,
+            publish: release && self.config.publish(),
+            tag: release && self.planned_tag.is_some(),
+            push: release && self.config.push(),
+        }
     }
 
     pub fn bump<'s>(
@@ -238,33 +726,276 @@ impl PackageRelease {
         Ok(())
     }
 
+    /// Whether the manifest's version is missing from `index`, for `--unpublished`
+    pub fn is_unpublished(&self, index: &impl cargo::PublishIndex) -> bool {
+        self.config.release()
+            && self.config.publish()
+            && !index.is_published(
+                self.published_name(),
+                &self.initial_version.full_version_string,
+            )
+    }
+
+    /// Whether the planned version only changes build metadata
+    ///
+    /// These are committed and tagged like any release but crates.io ignores build metadata, so
+    /// publishing would be rejected as a duplicate.
+    pub fn is_metadata_only(&self) -> bool {
+        self.planned_version
+            .as_ref()
+            .map(|planned| {
+                planned.bare_version == self.initial_version.bare_version
+                    && planned.full_version.build != self.initial_version.full_version.build
+            })
+            .unwrap_or(false)
+    }
+
     pub fn plan(&mut self) -> CargoResult<()> {
+        for note in self.plan_quietly()? {
+            let _ = crate::ops::reporter::note(note);
+        }
+        Ok(())
+    }
+
+    /// [`PackageRelease::plan`], returning the notes on its decisions rather than reporting them
+    fn plan_quietly(&mut self) -> CargoResult<Vec<String>> {
+        let mut notes = Vec::new();
         if !self.config.release() {
-            return Ok(());
+            return Ok(notes);
+        }
+
+        if self.config.publish() && self.is_metadata_only() {
+            notes.push(format!(
+                "not publishing {} {} as it only changes build metadata, which crates.io ignores for {}",
+                self.meta.name,
+                self.planned_version.as_ref().unwrap().full_version_string,
+                self.initial_version.bare_version_string,
+            ));
+            self.config.publish = Some(false);
+            self.ensure_owners = false;
         }
 
         let base = self
             .planned_version
             .as_ref()
             .unwrap_or(&self.initial_version);
-        let tag = if self.config.tag() {
+        let tag = if self.config.tag()
+            && !self.config.tag_unpublished()
+            && !is_publishable(&self.config, &self.meta)
+        {
+            notes.push(format!(
+                "not tagging {} which is never published (`tag-unpublished = false`)",
+                self.meta.name
+            ));
+            None
+        } else if self.config.tag() && (self.config.tag_prerelease() || !base.is_prerelease()) {
             let tag_name = self.config.tag_name();
             let tag_prefix = self.config.tag_prefix(self.is_root);
             let name = self.meta.name.as_str();
-            Some(render_tag(
+            let tag = render_tag(tag_name, tag_prefix, name, &self.initial_version, base);
+            let current = render_tag(
                 tag_name,
                 tag_prefix,
                 name,
                 &self.initial_version,
-                base,
-            ))
+                &self.initial_version,
+            );
+            if self.is_metadata_only() && tag == current {
+                anyhow::bail!(
+                    "{} {} only changes build metadata, which `tag-name` leaves out, so it would reuse tag {}; add `{{{{metadata}}}}` to `tag-name`",
+                    name,
+                    base.full_version_string,
+                    tag
+                );
+            }
+            Some(tag)
         } else {
             None
         };
 
         self.planned_tag = tag;
 
-        Ok(())
+        Ok(notes)
+    }
+}
+
+/// A package's `cargo package --list` and `.crate`, kept until its manifest changes
+///
+/// Both shell out to cargo, so they're done on first use and again only when the manifest's mtime
+/// moves or a step that rewrites files, like a version bump or replacements, calls
+/// [`PackageContentCache::invalidate`].
+#[derive(Debug)]
+pub struct PackageContentCache {
+    manifest_path: PathBuf,
+    package_root: PathBuf,
+    /// The workspace lock file, when the package includes it
+    lock_file: Option<PathBuf>,
+    /// List tracked files when cargo can't list the package
+    fallback: bool,
+    cached: std::sync::Mutex<Option<CachedContent>>,
+}
+
+#[derive(Debug)]
+struct CachedContent {
+    mtime: Option<std::time::SystemTime>,
+    stale: bool,
+    files: Vec<PathBuf>,
+    /// The version packaged and its `.crate`
+    archive: Option<(String, cargo::PackageArchive)>,
+}
+
+impl PackageContentCache {
+    pub fn new(
+        manifest_path: PathBuf,
+        package_root: PathBuf,
+        lock_file: Option<PathBuf>,
+        fallback: bool,
+    ) -> Self {
+        Self {
+            manifest_path,
+            package_root,
+            lock_file,
+            fallback,
+            cached: Default::default(),
+        }
+    }
+
+    /// A cache already holding `files`
+    pub fn with_files(manifest_path: PathBuf, package_root: PathBuf, files: Vec<PathBuf>) -> Self {
+        let cache = Self::new(manifest_path, package_root, None, true);
+        *cache.cached.lock().unwrap() = Some(CachedContent {
+            mtime: cache.mtime(),
+            stale: false,
+            files,
+            archive: None,
+        });
+        cache
+    }
+
+    /// Call `f` with the package's files, listing them if they aren't cached or are out of date
+    pub fn with<R>(&self, f: impl FnOnce(&[PathBuf]) -> R) -> CargoResult<R> {
+        let mut cached = self.cached.lock().unwrap();
+        let cached = self.refresh(&mut cached)?;
+        Ok(f(&cached.files))
+    }
+
+    /// Build the package's `.crate` for `version` with `package`, reusing the last one until the
+    /// files change
+    pub fn archive(
+        &self,
+        version: &str,
+        package: impl FnOnce(&Path) -> CargoResult<cargo::PackageArchive>,
+    ) -> CargoResult<cargo::PackageArchive> {
+        let mut cached = self.cached.lock().unwrap();
+        // A manifest cargo can't list can't be packaged either, and listing says why more clearly
+        let cached = self.refresh(&mut cached)?;
+        if let Some((packaged, archive)) = &cached.archive {
+            if packaged == version {
+                return Ok(archive.clone());
+            }
+        }
+        let archive = package(&self.manifest_path)?;
+        cached.archive = Some((version.to_owned(), archive.clone()));
+        Ok(archive)
+    }
+
+    fn refresh<'c>(
+        &self,
+        cached: &'c mut Option<CachedContent>,
+    ) -> CargoResult<&'c mut CachedContent> {
+        let mtime = self.mtime();
+        let fresh = cached
+            .as_ref()
+            .map(|c| !c.stale && c.mtime == mtime)
+            .unwrap_or(false);
+        if !fresh {
+            match self.list() {
+                Ok(files) => {
+                    *cached = Some(CachedContent {
+                        mtime,
+                        stale: false,
+                        files,
+                        archive: None,
+                    });
+                }
+                // A manifest rewritten mid-release is still the same package, so keep what we had
+                Err(err) if cached.is_some() => {
+                    log::debug!(
+                        "keeping prior file list for {}: {:#}",
+                        self.manifest_path.display(),
+                        err
+                    );
+                    if let Some(cached) = cached.as_mut() {
+                        cached.archive = None;
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(cached.as_mut().expect("listed above"))
+    }
+
+    /// List the files again on next use
+    pub fn invalidate(&self) {
+        if let Some(cached) = self.cached.lock().unwrap().as_mut() {
+            cached.stale = true;
+        }
+    }
+
+    fn mtime(&self) -> Option<std::time::SystemTime> {
+        std::fs::metadata(&self.manifest_path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    fn list(&self) -> CargoResult<Vec<PathBuf>> {
+        let _timer =
+            crate::ops::timings::start(format_args!("list {}", self.manifest_path.display()));
+        let mut package_content = match cargo::package_content(&self.manifest_path) {
+            Ok(package_content) => package_content,
+            Err(err) if self.fallback => {
+                log::debug!(
+                    "falling back to tracked files for unpublished {}: {:#}",
+                    self.manifest_path.display(),
+                    err
+                );
+                git::tracked_files(&self.package_root)?
+            }
+            Err(err) => return Err(err),
+        };
+        if let Some(lock_file) = &self.lock_file {
+            if !package_content.contains(lock_file) {
+                package_content.push(lock_file.clone());
+            }
+        } else {
+            // Lock files are not relevant when publishing non-bins
+            package_content.retain(|p| !p.ends_with("Cargo.lock"));
+        }
+        package_content.retain(|p| {
+            !p.strip_prefix(&self.package_root)
+                .map(|p| p.starts_with("tests"))
+                .unwrap_or(false)
+        });
+        // Editing what a symlink, like a templated `Cargo.toml`, points to changes the package too
+        let link_targets: Vec<_> = package_content
+            .iter()
+            .filter(|p| p.is_symlink())
+            .filter_map(|p| dunce::canonicalize(p).ok())
+            .filter(|p| !package_content.contains(p))
+            .collect();
+        package_content.extend(link_targets);
+        Ok(package_content)
+    }
+}
+
+/// Whether the manifest allows publishing `meta` to the configured registry
+pub fn is_publishable(config: &config::Config, meta: &cargo_metadata::Package) -> bool {
+    match meta.publish.as_deref() {
+        None => true,
+        Some(registries) => {
+            let target = config.registry().unwrap_or("crates-io");
+            registries.iter().any(|r| r == target)
+        }
     }
 }
 
@@ -334,6 +1065,93 @@ pub struct Dependency {
     pub req: semver::VersionReq,
 }
 
+#[derive(Clone, Debug)]
+pub struct ReleasedDependency {
+    pub name: String,
+    pub prev_version: Version,
+    pub version: Version,
+}
+
+/// How a package's prior tag was found
+///
+/// Serialized values are a stable contract for tools consuming the plan.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeDetection {
+    /// Passed in by the user, like with `--prev-tag-name`
+    Explicit,
+    /// The tag for the current version
+    InitialTag,
+    /// The latest tag matching the package's tag pattern
+    GlobFallback,
+    /// No prior tag was found
+    None,
+}
+
+/// A package's plan, for tools mirroring cargo-release's decisions
+///
+/// This is part of each entry in the JSON from `cargo release changes` and `generate-matrix`, and
+/// from [`compute`].
+/// Field names are a stable contract.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct PlanEntry {
+    pub name: String,
+    pub version: String,
+    pub planned_version: Option<String>,
+    pub planned_tag: Option<String>,
+    pub prior_tag: Option<String>,
+    pub changed_file_count: Option<usize>,
+    pub change_detection: ChangeDetection,
+    pub skip_reason: Option<SkipReason>,
+    pub publish: bool,
+    pub tag: bool,
+    pub push: bool,
+}
+
+/// Why a workspace member isn't being released
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    /// `release = false`
+    Config,
+    /// Left out by `--package`, `--exclude`, or the like
+    Excluded,
+    /// Dropped at the confirmation prompt
+    Dropped,
+    /// Unchanged since its prior tag, under `shared-version` `only-on-change` or `--changed-only`
+    Unchanged,
+    /// `allow-branch` rules out the current branch
+    Branch,
+    /// The version is already in the registry
+    Published,
+    /// The version isn't in the registry yet
+    Unpublished,
+    /// The tag already exists
+    TagExists,
+    /// No `owners` to set
+    NoOwners,
+    /// `publish = false`
+    NotPublished,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            Self::Config => "disabled in config",
+            Self::Excluded => "excluded on the command line",
+            Self::Dropped => "dropped at confirmation",
+            Self::Unchanged => "unchanged since its prior tag",
+            Self::Branch => "not released from this branch",
+            Self::Published => "already published",
+            Self::Unpublished => "not published yet",
+            Self::TagExists => "tag already exists",
+            Self::NoOwners => "no owners configured",
+            Self::NotPublished => "not published",
+        };
+        f.write_str(reason)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Version {
     pub full_version: semver::Version,
@@ -362,3 +1180,912 @@ impl From<semver::Version> for Version {
         }
     }
 }
+
+/// Workspaces loaded for tests
+#[cfg(test)]
+pub(crate) mod fixture {
+    use std::path::Path;
+
+    use super::PackageRelease;
+    use crate::config::ConfigArgs;
+    use crate::steps::BumpLevel;
+    use crate::steps::TargetVersion;
+
+    /// `cargo metadata` for the workspace at `root`
+    pub(crate) fn metadata(root: impl AsRef<Path>) -> cargo_metadata::Metadata {
+        cargo_metadata::MetadataCommand::new()
+            .manifest_path(root.as_ref().join("Cargo.toml"))
+            .exec()
+            .unwrap()
+    }
+
+    /// Every package of the workspace at `root`, at its current version
+    pub(crate) fn load(
+        root: impl AsRef<Path>,
+        args: &ConfigArgs,
+    ) -> (
+        cargo_metadata::Metadata,
+        indexmap::IndexMap<cargo_metadata::PackageId, PackageRelease>,
+    ) {
+        let ws_meta = metadata(root);
+        let pkgs = super::load(args, &ws_meta).unwrap();
+        (ws_meta, pkgs)
+    }
+
+    /// [`load`], with `configure` applied to each package before every released one is bumped to
+    /// its next minor version and the release planned
+    pub(crate) fn planned(
+        root: impl AsRef<Path>,
+        args: &ConfigArgs,
+        mut configure: impl FnMut(&mut PackageRelease),
+    ) -> (cargo_metadata::Metadata, Vec<PackageRelease>) {
+        let (ws_meta, mut pkgs) = load(root, args);
+        for pkg in pkgs.values_mut() {
+            configure(pkg);
+            if pkg.config.release() {
+                pkg.bump(&TargetVersion::Relative(BumpLevel::Minor), None)
+                    .unwrap();
+            }
+        }
+        let pkgs = super::plan(pkgs).unwrap().into_values().collect();
+        (ws_meta, pkgs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn simple_pkg(config: config::Config, version: &str) -> PackageRelease {
+        let ws_meta = crate::steps::plan::fixture::metadata("tests/fixtures/simple");
+        let meta = ws_meta.packages[0].clone();
+        let manifest_path = meta.manifest_path.as_std_path().to_owned();
+        let package_root = manifest_path.parent().unwrap().to_owned();
+        let features = config.features();
+        let content = PackageContentCache::with_files(
+            manifest_path.clone(),
+            package_root.clone(),
+            Vec::new(),
+        );
+        let mut pkg = PackageRelease {
+            meta,
+            manifest_path,
+            package_root,
+            nested_roots: Vec::new(),
+            is_root: true,
+            config,
+
+            content,
+            bin: true,
+            dependents: Vec::new(),
+            features,
+
+            initial_version: Version::from(semver::Version::parse("0.1.0").unwrap()),
+            prior_tag: None,
+            prior_version: None,
+            change_detection: ChangeDetection::None,
+            changed: None,
+            changed_file_count: None,
+
+            planned_version: None,
+            planned_tag: None,
+            tag_target: None,
+            dependency_updates: Vec::new(),
+            released_dependencies: Vec::new(),
+            skip_reason: None,
+
+            ensure_owners: false,
+        };
+        pkg.planned_version = Some(Version::from(semver::Version::parse(version).unwrap()));
+        pkg
+    }
+
+    mod load {
+        use super::*;
+
+        fn broken_ws() -> cargo_metadata::Metadata {
+            crate::steps::plan::fixture::metadata("tests/fixtures/broken_ws")
+        }
+
+        #[test]
+        fn unpublished_falls_back_to_tracked_files() {
+            let ws_meta = crate::steps::plan::fixture::metadata("tests/fixtures/unpublished_ws");
+            let args = config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let pkgs = load(&args, &ws_meta).unwrap();
+            let a = pkgs.values().find(|p| p.meta.name == "a").unwrap();
+            // `a`'s license file is missing, so cargo can't list it
+            assert!(cargo::package_content(&a.manifest_path).is_err());
+            let package_content = a.package_content().unwrap();
+            assert!(
+                package_content.iter().any(|p| p.ends_with("a/src/lib.rs")),
+                "{:?}",
+                package_content
+            );
+            // Only tracked files include what the manifest excludes from the package
+            assert!(
+                package_content
+                    .iter()
+                    .any(|p| p.ends_with("a/excluded.txt")),
+                "{:?}",
+                package_content
+            );
+        }
+
+        #[test]
+        fn reports_all_broken() {
+            let ws_meta = broken_ws();
+            let args = config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let err = load(&args, &ws_meta).unwrap_err().to_string();
+            assert!(err.contains("failed to load 2 package(s)"), "{}", err);
+            assert!(err.contains("broken_ws/a/Cargo.toml"), "{}", err);
+            assert!(err.contains("broken_ws/b/Cargo.toml"), "{}", err);
+            assert!(!err.contains("broken_ws/c/Cargo.toml"), "{}", err);
+            assert!(err.contains("MISSING-LICENSE"), "{}", err);
+            assert!(!err.contains("warning:"), "{}", err);
+        }
+
+        #[test]
+        fn skip_broken() {
+            let ws_meta = broken_ws();
+            let args = config::ConfigArgs {
+                isolated: true,
+                skip_broken: true,
+                ..Default::default()
+            };
+            let pkgs = load(&args, &ws_meta).unwrap();
+            let names = pkgs
+                .values()
+                .map(|p| p.meta.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["c"]);
+        }
+
+        #[test]
+        fn archive_reports_listing_error() {
+            let ws_meta = broken_ws();
+            let manifest_path =
+                crate::ops::path::resolve(Path::new("tests/fixtures/broken_ws/a/Cargo.toml"));
+            let package_root = manifest_path.parent().unwrap().to_owned();
+            let content = PackageContentCache::new(manifest_path, package_root, None, false);
+            let err = content
+                .archive("0.1.0", |manifest_path| {
+                    cargo::package_archive(
+                        manifest_path,
+                        Some("a"),
+                        ws_meta.target_directory.as_std_path(),
+                        "a",
+                        "0.1.0",
+                    )
+                })
+                .unwrap_err();
+            assert!(
+                format!("{:#}", err).contains("MISSING-LICENSE"),
+                "{:#}",
+                err
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    mod symlinked_manifests {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+        use assert_fs::prelude::*;
+
+        /// `symlinked_manifests`, with `b` and `c` linking their manifests from `template`, released
+        /// at `0.1.0`
+        fn repo() -> assert_fs::TempDir {
+            let repo = assert_fs::TempDir::new().unwrap();
+            repo.copy_from("tests/fixtures/symlinked_manifests", &["**"])
+                .unwrap();
+            for name in ["b", "c"] {
+                std::os::unix::fs::symlink(
+                    format!("../template/{}.toml", name),
+                    repo.child(name).child("Cargo.toml"),
+                )
+                .unwrap();
+            }
+            fixture::init(repo.path());
+            git(repo.path(), &["add", "."]);
+            git(repo.path(), &["commit", "--quiet", "-m", "Release"]);
+            for name in ["b", "c"] {
+                let tag = format!("{}-v0.1.0", name);
+                git(repo.path(), &["tag", "-a", &tag, "-m", &tag]);
+            }
+            repo
+        }
+
+        #[test]
+        fn distinct_roots() {
+            let repo = repo();
+            repo.child("template/b.toml")
+                .write_str("[package]\nname = \"b\"\nversion = \"0.1.0\"\ndescription = \"B\"\n")
+                .unwrap();
+            git(repo.path(), &["commit", "--quiet", "-am", "Describe b"]);
+
+            let ws_meta = crate::steps::plan::fixture::metadata(repo.path());
+            let args = config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let pkgs = load(&args, &ws_meta).unwrap();
+            let b = pkgs.values().find(|p| p.meta.name == "b").unwrap();
+            let c = pkgs.values().find(|p| p.meta.name == "c").unwrap();
+
+            // Packages stay where their manifest is linked from, not where it points
+            let root = dunce::canonicalize(repo.path()).unwrap();
+            assert_eq!(b.package_root, root.join("b"));
+            assert_eq!(c.package_root, root.join("c"));
+            assert_eq!(
+                b.prior_version
+                    .as_ref()
+                    .map(|v| v.full_version_string.as_str()),
+                Some("0.1.0")
+            );
+
+            let template = root.join("template/b.toml");
+            assert!(b.owns_path(&template));
+            assert!(!c.owns_path(&template));
+            let b_changed = crate::steps::version::changed_since(&ws_meta, b, "b-v0.1.0").unwrap();
+            assert_eq!(b_changed, [template]);
+            let c_changed = crate::steps::version::changed_since(&ws_meta, c, "c-v0.1.0").unwrap();
+            assert!(c_changed.is_empty(), "{:?}", c_changed);
+
+            repo.close().unwrap();
+        }
+    }
+
+    mod distinct_roots {
+        use super::*;
+
+        #[test]
+        fn shared_root_fails() {
+            let a = simple_pkg(config::Config::default(), "0.1.1");
+            let mut b = simple_pkg(config::Config::default(), "0.1.1");
+            b.meta.name = "b".to_owned();
+            let err = verify_distinct_roots([&a, &b].into_iter())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("simple, b share "), "{}", err);
+
+            let c = simple_pkg(config::Config::default(), "0.1.1");
+            verify_distinct_roots([&c].into_iter()).unwrap();
+        }
+    }
+
+    mod tag_unpublished {
+        use super::*;
+
+        fn planned_tag(
+            tag_unpublished: Option<bool>,
+            publish: Option<Vec<String>>,
+        ) -> Option<String> {
+            let config = config::Config {
+                tag_unpublished,
+                ..Default::default()
+            };
+            let mut pkg = simple_pkg(config, "0.2.0");
+            pkg.meta.publish = publish;
+            pkg.plan().unwrap();
+            pkg.planned_tag
+        }
+
+        #[test]
+        fn default_tags_unpublished() {
+            assert_eq!(planned_tag(None, Some(vec![])), Some("v0.2.0".to_owned()));
+        }
+
+        #[test]
+        fn skips_unpublished() {
+            assert_eq!(planned_tag(Some(false), Some(vec![])), None);
+            assert_eq!(planned_tag(Some(false), None), Some("v0.2.0".to_owned()));
+        }
+
+        #[test]
+        fn skips_other_registries() {
+            assert_eq!(
+                planned_tag(Some(false), Some(vec!["internal".to_owned()])),
+                None
+            );
+            assert_eq!(
+                planned_tag(Some(false), Some(vec!["crates-io".to_owned()])),
+                Some("v0.2.0".to_owned())
+            );
+        }
+    }
+
+    mod metadata_only {
+        use super::*;
+
+        fn pkg(initial: &str, planned: &str) -> PackageRelease {
+            let mut pkg = simple_pkg(config::Config::from_defaults(), planned);
+            pkg.initial_version = Version::from(semver::Version::parse(initial).unwrap());
+            pkg
+        }
+
+        #[test]
+        fn tags_without_publishing() {
+            let mut pkg = pkg("1.2.3+build.4", "1.2.3+build.5");
+            pkg.config.tag_name = Some("{{prefix}}v{{version}}+{{metadata}}".to_owned());
+            assert!(pkg.is_metadata_only());
+            pkg.plan().unwrap();
+            assert!(!pkg.config.publish());
+            assert_eq!(pkg.planned_tag.as_deref(), Some("v1.2.3+build.5"));
+        }
+
+        #[test]
+        fn rejects_reused_tag() {
+            let mut pkg = pkg("1.2.3+build.4", "1.2.3+build.5");
+            let err = pkg.plan().unwrap_err().to_string();
+            assert!(err.contains("would reuse tag v1.2.3"), "{}", err);
+        }
+
+        #[test]
+        fn adding_metadata() {
+            let mut pkg = pkg("1.2.3", "1.2.3+build.5");
+            pkg.config.tag_name = Some("{{prefix}}v{{version}}+{{metadata}}".to_owned());
+            assert!(pkg.is_metadata_only());
+            pkg.plan().unwrap();
+            assert!(!pkg.config.publish());
+            assert_eq!(pkg.planned_tag.as_deref(), Some("v1.2.3+build.5"));
+        }
+
+        #[test]
+        fn untagged_reuses_nothing() {
+            let mut pkg = pkg("1.2.3", "1.2.3+build.5");
+            pkg.config.tag = Some(false);
+            pkg.plan().unwrap();
+            assert_eq!(pkg.planned_tag, None);
+        }
+
+        #[test]
+        fn version_change_publishes() {
+            let mut pkg = pkg("1.2.3+build.4", "1.2.4+build.5");
+            assert!(!pkg.is_metadata_only());
+            pkg.plan().unwrap();
+            assert!(pkg.config.publish());
+        }
+
+        #[test]
+        fn not_a_downgrade() {
+            let metadata_only = pkg("1.2.3+build.5", "1.2.3+build.10");
+            assert!(crate::steps::verify_monotonically_increasing(
+                &[metadata_only],
+                true,
+                log::Level::Error
+            )
+            .unwrap());
+            let downgrade = pkg("1.2.3+build.5", "1.2.2+build.10");
+            assert!(!crate::steps::verify_monotonically_increasing(
+                &[downgrade],
+                true,
+                log::Level::Error
+            )
+            .unwrap());
+        }
+
+        #[test]
+        fn bump_sets_metadata() {
+            let current = semver::Version::parse("1.2.3+build.4").unwrap();
+            let absolute =
+                crate::steps::TargetVersion::Absolute(semver::Version::parse("1.2.3").unwrap());
+            let bumped = absolute.bump(&current, Some("build.5")).unwrap().unwrap();
+            assert_eq!(bumped.full_version_string, "1.2.3+build.5");
+            assert!(absolute.bump(&current, None).unwrap().is_none());
+
+            let relative = crate::steps::TargetVersion::Relative(crate::steps::BumpLevel::Release);
+            let bumped = relative.bump(&current, Some("build.5")).unwrap().unwrap();
+            assert_eq!(bumped.full_version_string, "1.2.3+build.5");
+        }
+    }
+
+    mod shared_version {
+        use super::*;
+
+        fn group(only_on_change: bool) -> config::Config {
+            config::Config {
+                shared_version: Some(config::SharedVersion::Group(config::SharedVersionGroup {
+                    name: "api".to_owned(),
+                    only_on_change,
+                })),
+                ..Default::default()
+            }
+        }
+
+        fn member(
+            name: &str,
+            config: config::Config,
+            version: &str,
+            changed: Option<bool>,
+        ) -> PackageRelease {
+            let mut pkg = simple_pkg(config, version);
+            pkg.meta.name = name.to_owned();
+            pkg.meta.id = cargo_metadata::PackageId {
+                repr: name.to_owned(),
+            };
+            pkg.prior_tag = Some(format!("{}-v0.1.0", name));
+            pkg.changed = changed;
+            pkg
+        }
+
+        fn planned(
+            members: Vec<PackageRelease>,
+        ) -> indexmap::IndexMap<String, (bool, Option<String>)> {
+            let pkgs = members
+                .into_iter()
+                .map(|p| (p.meta.id.clone(), p))
+                .collect();
+            plan(pkgs)
+                .unwrap()
+                .into_values()
+                .map(|p| {
+                    (
+                        p.meta.name.clone(),
+                        (
+                            p.config.release(),
+                            p.planned_version.map(|v| v.full_version_string),
+                        ),
+                    )
+                })
+                .collect()
+        }
+
+        #[test]
+        fn unchanged_members_follow_group() {
+            let actual = planned(vec![
+                member("a", group(false), "0.2.0", Some(true)),
+                member("b", group(false), "0.1.1", Some(false)),
+            ]);
+            assert_eq!(actual["a"], (true, Some("0.2.0".to_owned())));
+            assert_eq!(actual["b"], (true, Some("0.2.0".to_owned())));
+        }
+
+        #[test]
+        fn only_on_change_skips_unchanged_members() {
+            let actual = planned(vec![
+                member("a", group(true), "0.2.0", Some(true)),
+                member("b", group(true), "0.1.1", Some(false)),
+                member("c", group(true), "0.1.1", None),
+            ]);
+            assert_eq!(actual["a"], (true, Some("0.2.0".to_owned())));
+            assert_eq!(actual["b"], (false, None));
+            assert_eq!(actual["c"], (true, Some("0.2.0".to_owned())));
+        }
+
+        #[test]
+        fn only_on_change_records_skip_reason() {
+            let pkgs = vec![
+                member("a", group(true), "0.2.0", Some(true)),
+                member("b", group(true), "0.1.1", Some(false)),
+            ]
+            .into_iter()
+            .map(|p| (p.meta.id.clone(), p))
+            .collect();
+            let actual = plan(pkgs).unwrap();
+            let reasons: Vec<_> = actual.values().map(|p| p.skip_reason).collect();
+            assert_eq!(reasons, [None, Some(SkipReason::Unchanged)]);
+        }
+
+        #[test]
+        fn only_on_change_parses() {
+            let config: config::Config =
+                toml::from_str(r#"shared-version = { name = "api", only-on-change = true }"#)
+                    .unwrap();
+            assert_eq!(config.shared_version(), Some("api"));
+            assert!(config.shared_version_only_on_change());
+        }
+    }
+
+    mod tag_prerelease {
+        use super::*;
+
+        #[test]
+        fn stable_is_tagged() {
+            for tag_prerelease in [None, Some(true), Some(false)] {
+                let config = config::Config {
+                    tag_prerelease,
+                    ..Default::default()
+                };
+                let mut pkg = simple_pkg(config, "0.2.0");
+                pkg.plan().unwrap();
+                assert_eq!(pkg.planned_tag.as_deref(), Some("v0.2.0"));
+            }
+        }
+
+        #[test]
+        fn prerelease_is_tagged_by_default() {
+            for tag_prerelease in [None, Some(true)] {
+                let config = config::Config {
+                    tag_prerelease,
+                    ..Default::default()
+                };
+                let mut pkg = simple_pkg(config, "0.2.0-alpha.1");
+                pkg.plan().unwrap();
+                assert_eq!(pkg.planned_tag.as_deref(), Some("v0.2.0-alpha.1"));
+            }
+        }
+
+        #[test]
+        fn prerelease_is_not_tagged_when_disabled() {
+            let config = config::Config {
+                tag_prerelease: Some(false),
+                ..Default::default()
+            };
+            let mut pkg = simple_pkg(config, "0.2.0-alpha.1");
+            pkg.plan().unwrap();
+            assert_eq!(pkg.planned_tag, None);
+        }
+    }
+
+    mod plan_entry {
+        use super::*;
+
+        #[test]
+        fn field_names() {
+            let mut pkg = simple_pkg(config::Config::default(), "0.2.0");
+            pkg.set_prior_tag("v0.1.0".to_owned());
+            pkg.changed_file_count = Some(3);
+            pkg.planned_tag = Some("v0.2.0".to_owned());
+
+            let actual = serde_json::to_value(pkg.plan_entry()).unwrap();
+            assert_eq!(
+                actual,
+                serde_json::json!({
+                    "name": "simple",
+                    "version": "0.1.0",
+                    "planned_version": "0.2.0",
+                    "planned_tag": "v0.2.0",
+                    "prior_tag": "v0.1.0",
+                    "changed_file_count": 3,
+                    "change_detection": "explicit",
+                    "skip_reason": null,
+                    "publish": true,
+                    "tag": true,
+                    "push": true,
+                })
+            );
+        }
+
+        #[test]
+        fn change_detection_values() {
+            let actual: Vec<_> = [
+                ChangeDetection::Explicit,
+                ChangeDetection::InitialTag,
+                ChangeDetection::GlobFallback,
+                ChangeDetection::None,
+            ]
+            .iter()
+            .map(|c| serde_json::to_value(c).unwrap())
+            .collect();
+            assert_eq!(actual, ["explicit", "initial-tag", "glob-fallback", "none"]);
+        }
+
+        #[test]
+        fn skip_reason_values() {
+            let actual: Vec<_> = [
+                SkipReason::Config,
+                SkipReason::Excluded,
+                SkipReason::Dropped,
+                SkipReason::Unchanged,
+                SkipReason::Branch,
+                SkipReason::Published,
+                SkipReason::Unpublished,
+                SkipReason::TagExists,
+                SkipReason::NoOwners,
+                SkipReason::NotPublished,
+            ]
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap())
+            .collect();
+            assert_eq!(
+                actual,
+                [
+                    "config",
+                    "excluded",
+                    "dropped",
+                    "unchanged",
+                    "branch",
+                    "published",
+                    "unpublished",
+                    "tag-exists",
+                    "no-owners",
+                    "not-published"
+                ]
+            );
+        }
+
+        #[test]
+        fn disabled_in_config() {
+            let ws_meta = crate::steps::plan::fixture::metadata("tests/fixtures/simple");
+            let args = config::ConfigArgs {
+                isolated: true,
+                custom_config: vec![config::parse_config_arg("release=false").unwrap()],
+                ..Default::default()
+            };
+            let (_, pkg) = load(&args, &ws_meta).unwrap().into_iter().next().unwrap();
+
+            let actual = serde_json::to_value(pkg.plan_entry()).unwrap();
+            assert_eq!(actual["skip_reason"], "config");
+        }
+
+        #[test]
+        fn first_reason_sticks() {
+            let mut pkg = simple_pkg(config::Config::default(), "0.2.0");
+            pkg.skip(SkipReason::TagExists);
+            pkg.skip(SkipReason::Excluded);
+
+            assert_eq!(pkg.skip_reason, Some(SkipReason::TagExists));
+        }
+    }
+
+    mod compute {
+        use super::*;
+
+        use crate::steps::BumpLevel;
+        use crate::steps::TargetVersion;
+
+        fn args() -> config::ConfigArgs {
+            config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn pure_ws() {
+            let ws_meta = crate::steps::plan::fixture::metadata("tests/fixtures/pure_ws");
+
+            let planned = compute(&ws_meta, &args(), None, None).unwrap();
+            let names: Vec<_> = planned.iter().map(|p| p.plan.name.as_str()).collect();
+            assert_eq!(names, ["a", "b", "c"]);
+            let plan = &planned[0].plan;
+            assert_eq!(plan.version, "0.1.0");
+            assert_eq!(plan.planned_version, None);
+            assert_eq!(plan.planned_tag.as_deref(), Some("a-v0.1.0"));
+            assert!(plan.publish);
+            assert!(plan.tag);
+            assert!(plan.push);
+        }
+
+        #[test]
+        fn bumped() {
+            let ws_meta = crate::steps::plan::fixture::metadata("tests/fixtures/pure_ws");
+            let level = TargetVersion::Relative(BumpLevel::Minor);
+
+            let planned = compute(&ws_meta, &args(), Some(&level), None).unwrap();
+            let plan = &planned[0].plan;
+            assert_eq!(plan.planned_version.as_deref(), Some("0.2.0"));
+            assert_eq!(plan.planned_tag.as_deref(), Some("a-v0.2.0"));
+        }
+
+        #[test]
+        fn lists_commits() {
+            use crate::ops::git::fixture::{self, git};
+            use assert_fs::prelude::*;
+
+            let repo = assert_fs::TempDir::new().unwrap();
+            repo.copy_from("tests/fixtures/changed_ws", &["**"])
+                .unwrap();
+            fixture::init(repo.path());
+            git(repo.path(), &["add", "."]);
+            git(repo.path(), &["commit", "--quiet", "-m", "Release"]);
+            for name in ["core", "app", "util"] {
+                let tag = format!("{}-v0.1.0", name);
+                git(repo.path(), &["tag", "-a", &tag, "-m", &tag]);
+            }
+            repo.child("core/src/lib.rs")
+                .write_str("pub fn changed() {}\n")
+                .unwrap();
+            git(
+                repo.path(),
+                &["commit", "--quiet", "-am", "fix: Change core"],
+            );
+            let ws_meta = crate::steps::plan::fixture::metadata(repo.path());
+
+            let planned = compute(&ws_meta, &args(), None, None).unwrap();
+            let core = planned.iter().find(|p| p.plan.name == "core").unwrap();
+            let summaries: Vec<_> = core.commits.iter().map(|c| c.summary.as_str()).collect();
+            assert_eq!(summaries, ["fix: Change core"]);
+            let util = planned.iter().find(|p| p.plan.name == "util").unwrap();
+            assert!(util.commits.is_empty());
+
+            repo.close().unwrap();
+        }
+    }
+
+    mod skip_unchanged {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+        use assert_fs::prelude::*;
+
+        /// `changed_ws` released at `0.1.0`, then `core` changed
+        fn repo() -> assert_fs::TempDir {
+            let repo = assert_fs::TempDir::new().unwrap();
+            repo.copy_from("tests/fixtures/changed_ws", &["**"])
+                .unwrap();
+            fixture::init(repo.path());
+            git(repo.path(), &["add", "."]);
+            git(repo.path(), &["commit", "--quiet", "-m", "Release"]);
+            for name in ["core", "app", "util"] {
+                let tag = format!("{}-v0.1.0", name);
+                git(repo.path(), &["tag", "-a", &tag, "-m", &tag]);
+            }
+
+            repo.child("core/src/lib.rs")
+                .write_str("pub fn core() {}")
+                .unwrap();
+            git(repo.path(), &["commit", "--quiet", "-am", "Add core"]);
+            repo
+        }
+
+        fn plan_patch(
+            repo: &assert_fs::TempDir,
+            dependent_version: config::DependentVersion,
+        ) -> Vec<(String, Option<String>)> {
+            let args = config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let (ws_meta, mut pkgs) = crate::steps::plan::fixture::load(repo.path(), &args);
+            for pkg in pkgs.values_mut() {
+                pkg.config.dependent_version = Some(dependent_version);
+                pkg.bump(
+                    &crate::steps::TargetVersion::Relative(crate::steps::BumpLevel::Patch),
+                    None,
+                )
+                .unwrap();
+            }
+            skip_unchanged(&ws_meta, &mut pkgs);
+            let mut planned: Vec<_> = plan(pkgs)
+                .unwrap()
+                .into_values()
+                .map(|pkg| {
+                    (
+                        pkg.meta.name,
+                        pkg.planned_version.map(|v| v.full_version_string),
+                    )
+                })
+                .collect();
+            planned.sort();
+            planned
+        }
+
+        #[test]
+        fn bumps_changed_and_dependents() {
+            let repo = repo();
+
+            let planned = plan_patch(&repo, config::DependentVersion::Upgrade);
+            assert_eq!(
+                planned,
+                [
+                    ("app".to_owned(), Some("0.1.1".to_owned())),
+                    ("core".to_owned(), Some("0.1.1".to_owned())),
+                    ("util".to_owned(), None),
+                ]
+            );
+
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn dependents_only_when_requirement_breaks() {
+            let repo = repo();
+
+            let planned = plan_patch(&repo, config::DependentVersion::Fix);
+            assert_eq!(
+                planned,
+                [
+                    ("app".to_owned(), None),
+                    ("core".to_owned(), Some("0.1.1".to_owned())),
+                    ("util".to_owned(), None),
+                ]
+            );
+
+            repo.close().unwrap();
+        }
+    }
+
+    mod release_lines {
+        use super::*;
+
+        use assert_fs::prelude::*;
+        use git::fixture::git;
+
+        fn release(repo: &assert_fs::TempDir, version: &str, tag: bool) {
+            repo.child("Cargo.toml")
+                .write_str(&format!(
+                    "[package]\nname = \"foo\"\nversion = \"{}\"\n\n[lib]\npath = \"lib.rs\"\n",
+                    version
+                ))
+                .unwrap();
+            git(repo.path(), &["add", "Cargo.toml", "lib.rs"]);
+            git(repo.path(), &["commit", "--quiet", "-m", version]);
+            if tag {
+                let tag = format!("v{}", version);
+                git(repo.path(), &["tag", "-a", &tag, "-m", &tag]);
+            }
+        }
+
+        /// `1.x` maintained on `v1`, `2.x` on `v2`, both with unreleased changes
+        fn repo() -> assert_fs::TempDir {
+            let repo = assert_fs::TempDir::new().unwrap();
+            git::fixture::init(repo.path());
+            repo.child("lib.rs").write_str("").unwrap();
+
+            release(&repo, "1.0.0", true);
+            git(repo.path(), &["branch", "v1"]);
+            release(&repo, "2.0.0", true);
+            release(&repo, "2.1.0", false);
+            git(repo.path(), &["branch", "v2"]);
+            git(repo.path(), &["checkout", "--quiet", "v1"]);
+            release(&repo, "1.1.0", false);
+            repo
+        }
+
+        fn load(repo: &assert_fs::TempDir) -> PackageRelease {
+            let ws_meta = crate::steps::plan::fixture::metadata(repo.path());
+            let args = config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let root = git::top_level(repo.path()).unwrap();
+            PackageRelease::load(&args, &root, &ws_meta, &ws_meta.packages[0]).unwrap()
+        }
+
+        #[test]
+        fn maintenance_branch() {
+            let repo = repo();
+
+            let pkg = load(&repo);
+            assert_eq!(pkg.prior_tag.as_deref(), Some("v1.0.0"));
+            assert_eq!(pkg.change_detection, ChangeDetection::GlobFallback);
+            assert_eq!(
+                pkg.prior_version.map(|v| v.full_version_string).as_deref(),
+                Some("1.0.0")
+            );
+
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn latest_branch() {
+            let repo = repo();
+            git(repo.path(), &["checkout", "--quiet", "v2"]);
+
+            let pkg = load(&repo);
+            assert_eq!(pkg.prior_tag.as_deref(), Some("v2.0.0"));
+            assert_eq!(pkg.change_detection, ChangeDetection::GlobFallback);
+            assert_eq!(
+                pkg.prior_version.map(|v| v.full_version_string).as_deref(),
+                Some("2.0.0")
+            );
+
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn ignores_other_branch_tag() {
+            let repo = repo();
+            // `v2.0.0` exists, but not in `v1`'s history
+            repo.child("Cargo.toml")
+                .write_str(
+                    "[package]\nname = \"foo\"\nversion = \"2.0.0\"\n\n[lib]\npath = \"lib.rs\"\n",
+                )
+                .unwrap();
+
+            let pkg = load(&repo);
+            assert_eq!(pkg.prior_tag.as_deref(), Some("v1.0.0"));
+            assert_eq!(pkg.change_detection, ChangeDetection::GlobFallback);
+
+            repo.close().unwrap();
+        }
+    }
+}
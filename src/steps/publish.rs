@@ -13,42 +13,53 @@ pub struct PublishStep {
     #[command(flatten)]
     workspace: clap_cargo::Workspace,
 
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
+    /// Release packages whose version is a placeholder, like `0.0.0`
+    #[arg(long)]
+    allow_placeholder_versions: bool,
 
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
     no_confirm: bool,
 
+    /// Don't wait for published packages to appear in the index; see `cargo release wait`
+    #[arg(long)]
+    no_wait: bool,
+
+    /// Re-clone the local crates.io index if it is corrupted
+    #[arg(long)]
+    repair_index: bool,
+
+    /// How to reach the crates.io index, defaulting to `CARGO_REGISTRIES_CRATES_IO_PROTOCOL`
+    #[arg(long = "index", value_name = "PROTOCOL", value_enum)]
+    index_protocol: Option<crate::ops::index::IndexProtocol>,
+
+    /// Check `max-package-size` even in a dry-run
+    #[arg(long)]
+    verify_size: bool,
+
+    /// Index URL for the `--registry`, for registries not defined in cargo config
+    #[arg(long, value_name = "URL")]
+    registry_url: Option<String>,
+
     #[command(flatten)]
     publish: crate::config::PublishArgs,
 }
 
 impl PublishStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
 
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
-
         let ws_meta = self
             .manifest
             .metadata()
@@ -72,7 +83,7 @@ impl PublishStep {
             }
 
             pkg.config.publish = Some(false);
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
 
             let crate_name = pkg.meta.name.as_str();
             log::debug!("disabled by user, skipping {}", crate_name,);
@@ -80,38 +91,67 @@ impl PublishStep {
 
         let mut pkgs = plan::plan(pkgs)?;
 
-        let mut index = crates_index::Index::new_cargo_default()?;
+        let mut index = crate::ops::index::CratesIoIndex::open(
+            self.index_protocol
+                .unwrap_or_else(crate::ops::index::IndexProtocol::from_env),
+            self.repair_index,
+            !self.execute.dry_run(),
+        )?;
+        let dry_run = self.execute.dry_run();
+        let mut double_publish = false;
+        let mut index_urls = crate::ops::index::IndexUrls::default();
         for pkg in pkgs.values_mut() {
-            if pkg.config.registry().is_none() && pkg.config.release() {
+            if self.no_wait {
+                pkg.config.publish_wait = Some(false);
+            }
+
+            if !pkg.config.release() {
+                continue;
+            }
+            if let Some(index) = super::publish_index(pkg, &mut index, &mut index_urls) {
                 let crate_name = pkg.meta.name.as_str();
                 let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
-                if crate::ops::cargo::is_published(&index, crate_name, &version.full_version_string)
-                {
-                    let _ = crate::ops::shell::warn(format!(
+                if crate::ops::cargo::is_published(
+                    index,
+                    pkg.published_name(),
+                    &version.full_version_string,
+                ) {
+                    if pkg.config.publish_skip_identical() {
+                        let content = super::published_content(&ws_meta, pkg, &*index, dry_run)?;
+                        if !super::report_published_content(pkg, &content) {
+                            double_publish = true;
+                        }
+                        pkg.config.publish = Some(false);
+                        pkg.skip(plan::SkipReason::Published);
+                        continue;
+                    }
+                    let _ = crate::ops::reporter::warn(format!(
                         "disabled due to previous publish ({}), skipping {}",
                         version.full_version_string, crate_name
                     ));
                     pkg.config.publish = Some(false);
-                    pkg.config.release = Some(false);
+                    pkg.skip(plan::SkipReason::Published);
                 }
             }
         }
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
-
-        let dry_run = !self.execute;
         let mut failed = false;
+        if double_publish {
+            failed = true;
+            if !dry_run {
+                return Err(101.into());
+            }
+        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Error,
         )?;
@@ -119,6 +159,7 @@ impl PublishStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Error,
         )?;
@@ -130,23 +171,87 @@ impl PublishStep {
             log::Level::Warn,
         )?;
 
+        failed |= !super::verify_online_publish(
+            &selected_pkgs,
+            crate::ops::cargo::is_offline(),
+            dry_run,
+            log::Level::Error,
+        )?;
+
+        if self.registry_url.is_some() {
+            if let Some(pkg) = selected_pkgs
+                .iter()
+                .find(|p| p.config.publish() && p.config.is_crates_io())
+            {
+                let _ = crate::ops::reporter::error(format!(
+                    "`--registry-url` requires a registry name for `{}`, pass `--registry <NAME>`",
+                    pkg.meta.name
+                ));
+                return Err(2.into());
+            }
+        } else {
+            failed |= !super::verify_registries(
+                ws_meta.workspace_root.as_std_path(),
+                &selected_pkgs,
+                dry_run,
+                log::Level::Error,
+            )?;
+        }
+        // Dry-runs don't publish, so missing credentials shouldn't fail them
+        failed |= !super::verify_credentials(
+            &selected_pkgs,
+            dry_run,
+            if dry_run {
+                log::Level::Warn
+            } else {
+                log::Level::Error
+            },
+        )?;
+        failed |= !super::verify_package_content(&selected_pkgs, dry_run, log::Level::Error)?;
         failed |= !super::verify_metadata(&selected_pkgs, dry_run, log::Level::Error)?;
+        failed |= !super::verify_manifest_inheritance(&selected_pkgs, dry_run, log::Level::Error)?;
+        failed |= !super::verify_placeholder_versions(&selected_pkgs, dry_run, log::Level::Error)?;
+
+        failed |= !super::verify_publish_as(&selected_pkgs, dry_run, log::Level::Error)?;
+        failed |= !super::verify_package_size(
+            &ws_meta,
+            &selected_pkgs,
+            self.verify_size,
+            dry_run,
+            log::Level::Error,
+        )?;
         failed |= !super::verify_rate_limit(&selected_pkgs, &index, dry_run, log::Level::Error)?;
+        failed |= !super::owner::verify_owners(
+            &selected_pkgs,
+            &index,
+            &mut index_urls,
+            &crate::ops::cargo::CargoOwners,
+            dry_run,
+            log::Level::Error,
+        )?;
 
         // STEP 1: Release Confirmation
         super::confirm("Publish", &selected_pkgs, self.no_confirm, dry_run)?;
 
         // STEP 3: cargo publish
-        publish(&ws_meta, &selected_pkgs, &mut index, dry_run)?;
+        publish(
+            &ws_meta,
+            &selected_pkgs,
+            &mut index,
+            self.registry_url.as_deref(),
+            dry_run,
+        )?;
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
+            allow_placeholder_versions: self.allow_placeholder_versions,
             publish: self.publish.clone(),
             ..Default::default()
         }
@@ -156,19 +261,82 @@ impl PublishStep {
 pub fn publish(
     ws_meta: &cargo_metadata::Metadata,
     pkgs: &[plan::PackageRelease],
-    index: &mut crates_index::Index,
+    index: &mut crate::ops::index::CratesIoIndex,
+    registry_url: Option<&str>,
     dry_run: bool,
 ) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("publish");
+    let mut index_urls = crate::ops::index::IndexUrls::default();
+    let mut deferred = DeferredWaits::default();
+    // What the index showed before each upload, so a newer version already there isn't taken for
+    // someone else's release
+    let mut known = std::collections::BTreeMap::new();
     for pkg in pkgs {
         if !pkg.config.publish() {
             continue;
         }
 
+        for dep in deferred.take_dependencies_of(pkg) {
+            if let Some(index) = super::publish_index(dep, index, &mut index_urls) {
+                log::debug!(
+                    "checking {} is in the index before publishing {}",
+                    dep.meta.name,
+                    pkg.meta.name
+                );
+                let dep_known = known.get(&dep.meta.id).map(Vec::as_slice);
+                wait_for(dep, index, dep_known.unwrap_or_default(), dry_run)?;
+            }
+        }
+
         let crate_name = pkg.meta.name.as_str();
-        let _ = crate::ops::shell::status("Publishing", crate_name);
+        let published_name = pkg.published_name();
+        if pkg.config.publish_skip_identical() && pkg.planned_version.is_some() {
+            // Left by the release's pre-flight checks until the version step set the version
+            if let Some(index) = super::publish_index(pkg, index, &mut index_urls) {
+                let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+                if crate::ops::cargo::is_published(
+                    index,
+                    published_name,
+                    &version.full_version_string,
+                ) {
+                    let content = super::published_content(ws_meta, pkg, &*index, dry_run)?;
+                    if dry_run && content == crate::ops::cargo::PublishedContent::Unknown {
+                        let _ = crate::ops::reporter::note(format!(
+                            "{} {} is already published, its content is compared once the version is set",
+                            crate_name, version.full_version_string
+                        ));
+                        continue;
+                    }
+                    if !super::report_published_content(pkg, &content) {
+                        return Err(101.into());
+                    }
+                    continue;
+                }
+            }
+        }
+        if published_name == crate_name {
+            let _ = crate::ops::reporter::package("Publishing", crate_name, "");
+        } else {
+            let _ = crate::ops::reporter::package(
+                "Publishing",
+                crate_name,
+                format!("as {}", published_name),
+            );
+        }
 
+        let pkgid = if 1 < ws_meta.workspace_members.len() {
+            // Override `workspace.default-members`
+            Some(crate_name)
+        } else {
+            // `-p` is not recommended outside of a workspace
+            None
+        };
         let verify = if !pkg.config.verify() {
             false
+        } else if !pkg.config.verify_feature_sets().is_empty() {
+            verify_feature_sets(pkg, pkgid, dry_run)?;
+            // Already verified, and all of the features together might not build
+            false
         } else if dry_run && pkgs.len() != 1 {
             log::debug!("skipping verification to avoid unpublished dependencies from dry-run");
             false
@@ -177,54 +345,353 @@ pub fn publish(
         };
         // feature list to release
         let features = &pkg.features;
-        let pkgid = if 1 < ws_meta.workspace_members.len() {
-            // Override `workspace.default-members`
-            Some(crate_name)
-        } else {
-            // `-p` is not recommended outside of a workspace
-            None
+        let log = pkg.config.publish_log_dir().map(|dir| {
+            let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+            ws_meta.workspace_root.as_std_path().join(dir).join(format!(
+                "{}-{}.log",
+                crate_name, version.full_version_string
+            ))
+        });
+        if !dry_run && pkg.config.publish_wait() {
+            if let Some(index) = super::publish_index(pkg, index, &mut index_urls) {
+                use crate::ops::cargo::PublishIndex as _;
+                if let Err(err) = index.update_crate(published_name) {
+                    log::debug!("crate index update failed with {}", err);
+                }
+                known.insert(&pkg.meta.id, index.versions(published_name));
+            }
+        }
+        let published = {
+            let _timer = crate::ops::timings::start(format_args!("publish {}", crate_name));
+            // `cargo publish --dry-run` runs against the real name, which is all it can check
+            let renamed = if published_name != crate_name && !dry_run {
+                let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+                Some(crate::ops::cargo::RenamedPackage::stage(
+                    &pkg.manifest_path,
+                    pkgid,
+                    ws_meta.target_directory.as_std_path(),
+                    crate_name,
+                    &version.full_version_string,
+                    published_name,
+                )?)
+            } else {
+                None
+            };
+            let (manifest_path, pkgid) = match &renamed {
+                // A workspace of its own
+                Some(renamed) => (renamed.manifest_path(), None),
+                None => (pkg.manifest_path.clone(), pkgid),
+            };
+            let retries = pkg.config.publish_retries();
+            let mut attempt = 0;
+            loop {
+                let (published, output) = crate::ops::cargo::publish(
+                    dry_run,
+                    verify,
+                    &manifest_path,
+                    pkgid,
+                    features,
+                    pkg.config.registry(),
+                    registry_url,
+                    pkg.config.index_url(),
+                    pkg.config.target.as_ref().map(AsRef::as_ref),
+                    // The staged copy is outside of version control
+                    renamed.is_some(),
+                    log.as_deref(),
+                    0 < retries,
+                )?;
+                let output = match output {
+                    Some(output) if !published => output,
+                    _ => break published,
+                };
+                if 0 < attempt && crate::ops::cargo::is_already_uploaded(&output) {
+                    let _ = crate::ops::reporter::note(format!(
+                        "{} was published by an earlier attempt",
+                        crate_name
+                    ));
+                    break true;
+                }
+                if retries <= attempt || !crate::ops::cargo::is_transient_publish_failure(&output) {
+                    break false;
+                }
+                attempt += 1;
+                let delay = crate::ops::cargo::publish_retry_delay(attempt);
+                let _ = crate::ops::reporter::warn(format!(
+                    "publishing {} failed, retrying in {}s ({} of {})",
+                    crate_name,
+                    delay.as_secs(),
+                    attempt,
+                    retries
+                ));
+                std::thread::sleep(delay);
+                // The upload may have gone through despite the error
+                let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+                let published = super::publish_index(pkg, index, &mut index_urls)
+                    .map(|index| is_in_index(index, published_name, &version.full_version_string))
+                    .unwrap_or(false);
+                if published {
+                    let _ = crate::ops::reporter::note(format!(
+                        "{} was published despite the error, not retrying",
+                        crate_name
+                    ));
+                    break true;
+                }
+            }
         };
-        if !crate::ops::cargo::publish(
+        if !published {
+            if let Some(log) = log {
+                let _ = crate::ops::reporter::error(format!(
+                    "failed to publish {}, see {}",
+                    crate_name,
+                    log.display()
+                ));
+            }
+            return Err(101.into());
+        }
+
+        let pkg_known = known.get(&pkg.meta.id).map(Vec::as_slice);
+        let (pkg_index, pkg_index_urls) = (&mut *index, &mut index_urls);
+        wait_after_publish(
+            pkg,
+            move || super::publish_index(pkg, pkg_index, pkg_index_urls),
+            pkg_known.unwrap_or_default(),
+            &mut deferred,
             dry_run,
-            verify,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Wait for the just published `pkg` to appear in the index, unless `publish-wait` is off or the
+/// wait is deferred until a dependent publishes
+///
+/// `index` is only opened when there's a wait.
+fn wait_after_publish<'p, 'i, I: crate::ops::cargo::PublishIndex + 'i>(
+    pkg: &'p plan::PackageRelease,
+    index: impl FnOnce() -> Option<&'i mut I>,
+    known: &[String],
+    deferred: &mut DeferredWaits<'p>,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let crate_name = pkg.meta.name.as_str();
+    if !pkg.config.publish_wait() {
+        log::debug!("not waiting for {} to propagate to the index", crate_name);
+    } else if pkg.config.publish_wait_deferred() {
+        log::debug!(
+            "not waiting for {} to propagate to the index until a dependent publishes",
+            crate_name
+        );
+        deferred.defer(pkg);
+    } else if let Some(index) = index() {
+        wait_for(pkg, index, known, dry_run)?;
+        // HACK: Even once the index is updated, there seems to be another step before the publish is fully ready.
+        // We don't have a way yet to check for that, so waiting for now in hopes everything is ready
+        if !dry_run {
+            let publish_grace_sleep = pkg.config.publish_grace_sleep();
+            if !publish_grace_sleep.is_zero() {
+                log::debug!(
+                    "waiting an additional {} seconds for crates.io to update its indices...",
+                    publish_grace_sleep.as_secs()
+                );
+                std::thread::sleep(publish_grace_sleep);
+            }
+        }
+    } else {
+        log::debug!("not waiting for publish because the registry is not crates.io and doesn't get updated automatically");
+    }
+
+    Ok(())
+}
+
+/// Published crates not waited on, with `publish-timeout = 0`
+#[derive(Default)]
+struct DeferredWaits<'p>(Vec<&'p plan::PackageRelease>);
+
+impl<'p> DeferredWaits<'p> {
+    fn defer(&mut self, pkg: &'p plan::PackageRelease) {
+        self.0.push(pkg);
+    }
+
+    /// The deferred crates `pkg` depends on, which need to be in the index before it publishes
+    fn take_dependencies_of(
+        &mut self,
+        pkg: &plan::PackageRelease,
+    ) -> Vec<&'p plan::PackageRelease> {
+        let (dependencies, rest) = self
+            .0
+            .drain(..)
+            .partition(|dep| dep.dependents.iter().any(|d| d.pkg.id == pkg.meta.id));
+        self.0 = rest;
+        dependencies
+    }
+}
+
+/// Wait for `pkg`'s planned version to appear in `index`, past the `known` versions
+fn wait_for(
+    pkg: &plan::PackageRelease,
+    index: &mut impl crate::ops::cargo::PublishIndex,
+    known: &[String],
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let _timer = crate::ops::timings::start(format_args!("wait for {}", pkg.meta.name));
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    crate::ops::cargo::wait_for_publish(
+        index,
+        pkg.published_name(),
+        &version.full_version_string,
+        known,
+        pkg.config.publish_timeout(),
+        pkg.config.publish_poll_interval(),
+        dry_run,
+    )?;
+    Ok(())
+}
+
+/// Whether `name` `version` is in the crates.io index, after refreshing it
+fn is_in_index(index: &mut crate::ops::index::CratesIoIndex, name: &str, version: &str) -> bool {
+    use crate::ops::cargo::PublishIndex as _;
+
+    if let Err(err) = index.update() {
+        log::debug!("crate index update failed with {}", err);
+    }
+    index.is_published(name, version)
+}
+
+/// Verify each of `verify-feature-sets` with its own build
+fn verify_feature_sets(
+    pkg: &plan::PackageRelease,
+    pkgid: Option<&str>,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let crate_name = pkg.meta.name.as_str();
+    for features in pkg.config.verify_feature_sets() {
+        let features_desc = if features.is_empty() {
+            "default features".to_owned()
+        } else {
+            format!("features `{}`", features.join(","))
+        };
+        let _ = crate::ops::reporter::package(
+            "Verifying",
+            crate_name,
+            format!("with {}", features_desc),
+        );
+        if dry_run {
+            // Dependencies may not be published yet, so only report the matrix
+            continue;
+        }
+        let _timer = crate::ops::timings::start(format_args!(
+            "verify {} with {}",
+            crate_name, features_desc
+        ));
+        let prefix = if features.is_empty() {
+            format!("[{}]", crate_name)
+        } else {
+            format!("[{} {}]", crate_name, features.join(","))
+        };
+        if !crate::ops::cargo::verify_build(
             &pkg.manifest_path,
             pkgid,
             features,
-            pkg.config.registry(),
-            pkg.config.target.as_ref().map(AsRef::as_ref),
+            pkg.config.target.as_deref(),
+            &prefix,
+            dry_run,
         )? {
+            let _ = crate::ops::reporter::error(format!(
+                "verification of {} with {} failed",
+                crate_name, features_desc
+            ));
             return Err(101.into());
         }
+    }
 
-        if pkg.config.registry().is_none() {
-            let timeout = std::time::Duration::from_secs(300);
-            let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
-            crate::ops::cargo::wait_for_publish(
-                index,
-                crate_name,
-                &version.full_version_string,
-                timeout,
-                dry_run,
-            )?;
-            // HACK: Even once the index is updated, there seems to be another step before the publish is fully ready.
-            // We don't have a way yet to check for that, so waiting for now in hopes everything is ready
-            if !dry_run {
-                let publish_grace_sleep = std::env::var("PUBLISH_GRACE_SLEEP")
-                    .unwrap_or_else(|_| Default::default())
-                    .parse()
-                    .unwrap_or(0);
-                if 0 < publish_grace_sleep {
-                    log::debug!(
-                        "waiting an additional {} seconds for crates.io to update its indices...",
-                        publish_grace_sleep
-                    );
-                    std::thread::sleep(std::time::Duration::from_secs(publish_grace_sleep));
-                }
-            }
-        } else {
-            log::debug!("not waiting for publish because the registry is not crates.io and doesn't get updated automatically");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::error::CargoResult;
+    use crate::ops::cargo::fixture::FlipIndex;
+
+    /// Knows only the listed crates, at any version
+    struct MockIndex(&'static [&'static str]);
+
+    impl crate::ops::cargo::PublishIndex for MockIndex {
+        fn update(&mut self) -> CargoResult<()> {
+            Ok(())
+        }
+
+        fn is_published(&self, name: &str, _version: &str) -> bool {
+            self.0.contains(&name)
         }
     }
 
-    Ok(())
+    /// `pure_ws`, a chain where `c` depends on `b` depending on `a`, with no deferred wait for
+    /// more than a second
+    fn chain() -> Vec<plan::PackageRelease> {
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let (_, pkgs) = plan::fixture::load("tests/fixtures/pure_ws", &args);
+        let mut pkgs: Vec<_> = plan::plan(pkgs).unwrap().into_values().collect();
+        for pkg in &mut pkgs {
+            pkg.config.publish_timeout = Some(1);
+            pkg.config.publish_poll_interval = Some(0);
+        }
+        pkgs
+    }
+
+    fn names(pkgs: &[&plan::PackageRelease]) -> Vec<String> {
+        pkgs.iter().map(|p| p.meta.name.clone()).collect()
+    }
+
+    #[test]
+    fn no_wait_skips_waiting() {
+        let mut pkgs = chain();
+        // As `--no-wait` leaves it
+        pkgs[0].config.publish_wait = Some(false);
+        let mut index = FlipIndex {
+            polls: 0,
+            published_after: usize::MAX,
+        };
+        let mut deferred = DeferredWaits::default();
+
+        let flip = &mut index;
+        wait_after_publish(&pkgs[0], move || Some(flip), &[], &mut deferred, false).unwrap();
+        assert_eq!(index.polls, 0);
+        assert!(deferred.take_dependencies_of(&pkgs[1]).is_empty());
+    }
+
+    #[test]
+    fn deferred_until_dependent() {
+        let pkgs = chain();
+        let [a, b, c] = [0, 1, 2].map(|i| &pkgs[i]);
+        assert_eq!(names(&[a, b, c]), ["a", "b", "c"]);
+
+        let mut deferred = DeferredWaits::default();
+        deferred.defer(a);
+        assert!(deferred.take_dependencies_of(c).is_empty());
+        let due = deferred.take_dependencies_of(b);
+        assert_eq!(names(&due), ["a"]);
+        assert!(deferred.take_dependencies_of(b).is_empty());
+
+        wait_for(due[0], &mut MockIndex(&["a"]), &[], false).unwrap();
+    }
+
+    #[test]
+    fn deferred_still_missing() {
+        let pkgs = chain();
+        let (b, c) = (&pkgs[1], &pkgs[2]);
+
+        let mut deferred = DeferredWaits::default();
+        deferred.defer(b);
+        let due = deferred.take_dependencies_of(c);
+        assert_eq!(names(&due), ["b"]);
+
+        let err = wait_for(due[0], &mut MockIndex(&["a"]), &[], false).unwrap_err();
+        assert!(err.to_string().contains("timeout"), "{}", err);
+    }
 }
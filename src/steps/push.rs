@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 use crate::error::CliError;
 use crate::ops::git;
 use crate::steps::plan;
@@ -13,24 +11,15 @@ pub struct PushStep {
     #[command(flatten)]
     workspace: clap_cargo::Workspace,
 
-    /// Custom config file
-    #[arg(short, long = "config", value_name = "PATH")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
-
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
@@ -45,13 +34,9 @@ pub struct PushStep {
 
 impl PushStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
 
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
-
         let ws_meta = self
             .manifest
             .metadata()
@@ -75,7 +60,7 @@ impl PushStep {
             }
 
             pkg.config.push = Some(false);
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
 
             let crate_name = pkg.meta.name.as_str();
             log::debug!("disabled by user, skipping {}", crate_name,);
@@ -83,30 +68,30 @@ impl PushStep {
 
         let pkgs = plan::plan(pkgs)?;
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Error,
         )?;
 
         failed |= !super::verify_tags_exist(&selected_pkgs, dry_run, log::Level::Error)?;
+        failed |= !super::verify_tag_annotation(&selected_pkgs, dry_run, log::Level::Error)?;
 
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Error,
         )?;
@@ -124,13 +109,14 @@ impl PushStep {
         // STEP 6: git push
         push(&ws_config, &ws_meta, &selected_pkgs, dry_run)?;
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
             tag: self.tag.clone(),
             push: self.push.clone(),
@@ -145,11 +131,13 @@ pub fn push(
     pkgs: &[plan::PackageRelease],
     dry_run: bool,
 ) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("push");
+    let _timer = crate::ops::timings::start("push");
     if ws_config.push() {
         let git_remote = ws_config.push_remote();
         let branch = crate::ops::git::current_branch(ws_meta.workspace_root.as_std_path())?;
 
-        let mut shared_refs = HashSet::new();
+        let mut shared_refs = indexmap::IndexSet::new();
         for pkg in pkgs {
             if !pkg.config.push() {
                 continue;
@@ -167,16 +155,26 @@ pub fn push(
                 shared_refs.insert(tag_name);
             }
         }
+        if ws_config.push_notes() && !shared_refs.is_empty() {
+            shared_refs.insert(ws_config.notes_ref());
+        }
         if !shared_refs.is_empty() {
+            // Alphabetical, rather than by package, as refs are shared between packages
             let mut shared_refs = shared_refs.into_iter().collect::<Vec<_>>();
             shared_refs.sort_unstable();
-            let _ = crate::ops::shell::status(
+            // Fetching still goes through `git_remote`, as the push URL may not be readable
+            let push_target = ws_config.push_url().unwrap_or(git_remote);
+            let _ = crate::ops::reporter::status(
                 "Pushing",
-                format!("Pushing {} to {}", shared_refs.join(", "), git_remote),
+                format!(
+                    "Pushing {} to {}",
+                    shared_refs.join(", "),
+                    git::redact_url(push_target)
+                ),
             );
             if !git::push(
                 ws_meta.workspace_root.as_std_path(),
-                git_remote,
+                push_target,
                 shared_refs,
                 ws_config.push_options(),
                 dry_run,
@@ -188,3 +186,51 @@ pub fn push(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ops::git::fixture::{self, git};
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn push_url() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let origin = temp.child("origin.git");
+        let deploy = temp.child("deploy.git");
+        let local = temp.child("local");
+        git(temp.path(), &["init", "--quiet", "--bare", "origin.git"]);
+        git(temp.path(), &["init", "--quiet", "--bare", "deploy.git"]);
+        local.copy_from("tests/fixtures/simple", &["**"]).unwrap();
+        fixture::init(local.path());
+        for args in [
+            &["remote", "add", "origin", origin.path().to_str().unwrap()][..],
+            &["add", "."],
+            &["commit", "--quiet", "-m", "initial"],
+            &["push", "--quiet", "origin", "HEAD"],
+            &["tag", "-a", "v0.1.0", "-m", "v0.1.0"],
+        ] {
+            git(local.path(), args);
+        }
+
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let (ws_meta, pkgs) = plan::fixture::load(local.path(), &args);
+        let mut pkgs: Vec<_> = pkgs.into_values().collect();
+        pkgs[0].planned_tag = Some("v0.1.0".to_owned());
+        let ws_config = crate::config::Config {
+            push_url: Some(deploy.path().to_str().unwrap().to_owned()),
+            ..crate::config::Config::from_defaults()
+        };
+
+        push(&ws_config, &ws_meta, &pkgs, false).unwrap();
+
+        assert_eq!(git(deploy.path(), &["tag", "--list"]), "v0.1.0");
+        assert_eq!(git(origin.path(), &["tag", "--list"]), "");
+
+        temp.close().unwrap();
+    }
+}
@@ -5,6 +5,7 @@ use crate::ops::git;
 use crate::steps::plan;
 
 #[derive(Debug, Clone, clap::Args)]
+#[command(mut_arg("package", |arg| arg.value_parser(super::PackageVersionSpecParser)))]
 pub struct ReleaseStep {
     #[command(flatten)]
     manifest: clap_cargo::Manifest,
@@ -24,42 +25,104 @@ pub struct ReleaseStep {
     #[arg(short, long, requires = "level_or_version")]
     metadata: Option<String>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
-
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
     no_confirm: bool,
 
+    /// How to list the packages being released
+    #[arg(long, value_name = "FMT", value_enum, default_value_t)]
+    summary: super::SummaryFormat,
+
     /// The name of tag for the previous release.
     #[arg(long, value_name = "NAME")]
     prev_tag_name: Option<String>,
 
+    /// Report how long each phase took
+    #[arg(
+        long,
+        value_name = "FMT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text",
+        value_enum
+    )]
+    timings: Option<crate::ops::timings::TimingsFormat>,
+
+    /// Re-clone the local crates.io index if it is corrupted
+    #[arg(long)]
+    repair_index: bool,
+
+    /// How to reach the crates.io index, defaulting to `CARGO_REGISTRIES_CRATES_IO_PROTOCOL`
+    #[arg(long = "index", value_name = "PROTOCOL", value_enum)]
+    index_protocol: Option<crate::ops::index::IndexProtocol>,
+
+    /// Check `max-package-size` even in a dry-run
+    #[arg(long)]
+    verify_size: bool,
+
+    /// Error when a previous release's commits haven't been pushed
+    #[arg(long)]
+    strict_unpushed: bool,
+
+    /// Fail on packages whose `allow-branch` excludes this branch
+    #[arg(long)]
+    strict_branch: bool,
+
+    /// GitHub repository for `github-release`, when the push remote isn't on github.com
+    #[arg(long, value_name = "OWNER/NAME")]
+    github_repo: Option<crate::ops::github::Repo>,
+
     #[command(flatten)]
     config: crate::config::ConfigArgs,
 }
 
 impl ReleaseStep {
     pub fn run(&self) -> Result<(), CliError> {
-        git::git_version()?;
-        let mut index = crates_index::Index::new_cargo_default()?;
-
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
+        self.execute.check()?;
+        if self.timings.is_some() {
+            crate::ops::timings::enable();
+        }
+        let result = self.release();
+        if let Some(format) = self.timings {
+            crate::ops::timings::report(format)?;
         }
+        result
+    }
 
-        let ws_meta = self
-            .manifest
-            .metadata()
-            // When evaluating dependency ordering, we need to consider optional dependencies
-            .features(cargo_metadata::CargoOpt::AllFeatures)
-            .exec()?;
+    fn release(&self) -> Result<(), CliError> {
+        git::git_version()?;
+        let mut index = crate::ops::index::CratesIoIndex::open(
+            self.index_protocol
+                .unwrap_or_else(crate::ops::index::IndexProtocol::from_env),
+            self.repair_index,
+            !self.execute.dry_run(),
+        )?;
+        index.warn_unpublished(self.unpublished);
+
+        let ws_meta = {
+            let _timer = crate::ops::timings::start("metadata");
+            self.manifest
+                .metadata()
+                // When evaluating dependency ordering, we need to consider optional dependencies
+                .features(cargo_metadata::CargoOpt::AllFeatures)
+                .exec()?
+        };
+        if !self.config.isolated && !crate::steps::init::has_config(&ws_meta) {
+            let _ = crate::ops::reporter::note(
+                "no `release.toml` found, releasing with defaults; `cargo release init` writes a starter one",
+            );
+        }
         let ws_config = config::load_workspace_config(&self.config, &ws_meta)?;
+        let (workspace, package_targets) = match super::split_package_targets(&self.workspace) {
+            Ok(split) => split,
+            Err(err) => {
+                let _ = crate::ops::reporter::error(err);
+                return Err(2.into());
+            }
+        };
         let mut pkgs = plan::load(&self.config, &ws_meta)?;
 
         for pkg in pkgs.values_mut() {
@@ -67,19 +130,27 @@ impl ReleaseStep {
                 // Trust the user that the tag passed in is the latest tag for the workspace and that
                 // they don't care about any changes from before this tag.
                 pkg.set_prior_tag(prev_tag.to_owned());
+                pkg.detect_changes(&ws_meta);
+            }
+            if workspace.package.contains(&pkg.meta.name) {
+                // Explicitly selected packages are released even when unchanged
+                pkg.changed = Some(true);
             }
             if pkg.config.release() {
-                if let Some(level_or_version) = &self.level_or_version {
+                let level_or_version = package_targets
+                    .get(&pkg.meta.name)
+                    .or(self.level_or_version.as_ref());
+                if let Some(level_or_version) = level_or_version {
                     pkg.bump(level_or_version, self.metadata.as_deref())?;
                 }
             }
-            if index.crate_(&pkg.meta.name).is_some() {
+            if index.crate_(pkg.published_name()).is_some() {
                 // Already published, skip it.  Use `cargo release owner` for one-time updates
                 pkg.ensure_owners = false;
             }
         }
 
-        let (_selected_pkgs, excluded_pkgs) = self.workspace.partition_packages(&ws_meta);
+        let (_selected_pkgs, excluded_pkgs) = workspace.partition_packages(&ws_meta);
         for excluded_pkg in &excluded_pkgs {
             let pkg = if let Some(pkg) = pkgs.get_mut(&excluded_pkg.id) {
                 pkg
@@ -92,34 +163,27 @@ impl ReleaseStep {
             }
 
             let crate_name = pkg.meta.name.as_str();
-            let explicitly_excluded = self.workspace.exclude.contains(&excluded_pkg.name);
+            let explicitly_excluded = workspace.exclude.contains(&excluded_pkg.name);
             // 1. Don't show this message if already not releasing in config
             // 2. Still respect `--exclude`
-            if pkg.config.release()
-                && pkg.config.publish()
-                && self.unpublished
-                && !explicitly_excluded
-            {
-                let version = &pkg.initial_version;
-                if !cargo::is_published(&index, crate_name, &version.full_version_string) {
-                    log::debug!(
-                        "enabled {}, v{} is unpublished",
-                        crate_name,
-                        version.full_version_string
-                    );
-                    continue;
-                }
+            if self.unpublished && !explicitly_excluded && pkg.is_unpublished(&index) {
+                log::debug!(
+                    "enabled {}, v{} is unpublished",
+                    crate_name,
+                    pkg.initial_version.full_version_string
+                );
+                continue;
             }
 
             pkg.planned_version = None;
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
 
             if let Some(prior_tag_name) = &pkg.prior_tag {
                 if let Some(changed) =
                     crate::steps::version::changed_since(&ws_meta, pkg, prior_tag_name)
                 {
                     if !changed.is_empty() {
-                        let _ = crate::ops::shell::warn(format!(
+                        let _ = crate::ops::reporter::warn(format!(
                             "disabled by user, skipping {} which has files changed since {}: {:#?}",
                             crate_name, prior_tag_name, changed
                         ));
@@ -152,11 +216,12 @@ impl ReleaseStep {
                 continue;
             };
 
-            if pkg.config.publish() && pkg.config.registry().is_none() {
+            if pkg.config.publish() && pkg.config.is_crates_io() {
                 let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
                 let crate_name = pkg.meta.name.as_str();
-                if !cargo::is_published(&index, crate_name, &version.full_version_string) {
-                    let _ = crate::ops::shell::warn(format!(
+                if !cargo::is_published(&index, pkg.published_name(), &version.full_version_string)
+                {
+                    let _ = crate::ops::reporter::warn(format!(
                         "disabled by user, skipping {} v{} despite being unpublished",
                         crate_name, version.full_version_string,
                     ));
@@ -164,16 +229,20 @@ impl ReleaseStep {
             }
         }
 
-        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (mut selected_pkgs, mut excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
+        if !self.strict_branch {
+            super::exclude_disallowed_branch(
+                ws_meta.workspace_root.as_std_path(),
+                &mut selected_pkgs,
+                &mut excluded_pkgs,
+            )?;
         }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         let consolidate_commits = super::consolidate_commits(&selected_pkgs, &excluded_pkgs)?;
@@ -181,28 +250,66 @@ impl ReleaseStep {
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Error,
         )?;
 
         failed |= !super::verify_tags_missing(&selected_pkgs, dry_run, log::Level::Error)?;
 
+        failed |= !super::verify_unique_tags(&selected_pkgs, dry_run, log::Level::Error)?;
+
         failed |=
             !super::verify_monotonically_increasing(&selected_pkgs, dry_run, log::Level::Error)?;
 
+        failed |= !super::verify_placeholder_versions(&selected_pkgs, dry_run, log::Level::Error)?;
+
+        failed |= !super::verify_replacements(&selected_pkgs, dry_run, log::Level::Error)?;
+
+        failed |=
+            !super::verify_path_dependencies(&ws_meta, &selected_pkgs, dry_run, log::Level::Error)?;
+
+        failed |= !super::verify_publish_as(&selected_pkgs, dry_run, log::Level::Error)?;
+        failed |= !super::verify_package_size(
+            &ws_meta,
+            &selected_pkgs,
+            self.verify_size,
+            dry_run,
+            log::Level::Error,
+        )?;
+        failed |= !super::verify_online_publish(
+            &selected_pkgs,
+            crate::ops::cargo::is_offline(),
+            dry_run,
+            log::Level::Error,
+        )?;
+
         let mut double_publish = false;
-        for pkg in &selected_pkgs {
+        let mut index_urls = crate::ops::index::IndexUrls::default();
+        for pkg in &mut selected_pkgs {
             if !pkg.config.publish() {
                 continue;
             }
-            if pkg.config.registry().is_none() {
+            if let Some(index) = super::publish_index(pkg, &mut index, &mut index_urls) {
                 let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
                 let crate_name = pkg.meta.name.as_str();
-                if cargo::is_published(&index, crate_name, &version.full_version_string) {
-                    let _ = crate::ops::shell::error(format!(
-                        "{} {} is already published",
-                        crate_name, version.full_version_string
-                    ));
+                if cargo::is_published(index, pkg.published_name(), &version.full_version_string) {
+                    let content = if !pkg.config.publish_skip_identical() {
+                        cargo::PublishedContent::Unknown
+                    } else if pkg.planned_version.is_some() {
+                        // Packaged for the comparison once the version step has set it
+                        let _ = crate::ops::reporter::note(format!(
+                            "{} {} is already published, comparing content when publishing",
+                            crate_name, version.full_version_string
+                        ));
+                        continue;
+                    } else {
+                        super::published_content(&ws_meta, pkg, &*index, dry_run)?
+                    };
+                    if super::report_published_content(pkg, &content) {
+                        pkg.config.publish = Some(false);
+                        continue;
+                    }
                     double_publish = true;
                 }
             }
@@ -219,6 +326,7 @@ impl ReleaseStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Error,
         )?;
@@ -230,16 +338,91 @@ impl ReleaseStep {
             log::Level::Warn,
         )?;
 
+        failed |= !super::verify_release_pushed(
+            ws_meta.workspace_root.as_std_path(),
+            &ws_config,
+            &selected_pkgs,
+            dry_run,
+            if self.strict_unpushed {
+                log::Level::Error
+            } else {
+                log::Level::Warn
+            },
+        )?;
+
+        failed |= !super::verify_registries(
+            ws_meta.workspace_root.as_std_path(),
+            &selected_pkgs,
+            dry_run,
+            log::Level::Error,
+        )?;
+        // Dry-runs don't publish, so missing credentials shouldn't fail them
+        failed |= !super::verify_credentials(
+            &selected_pkgs,
+            dry_run,
+            if dry_run {
+                log::Level::Warn
+            } else {
+                log::Level::Error
+            },
+        )?;
+        failed |= !super::verify_package_content(&selected_pkgs, dry_run, log::Level::Error)?;
         failed |= !super::verify_metadata(&selected_pkgs, dry_run, log::Level::Error)?;
+        failed |= !super::verify_manifest_inheritance(&selected_pkgs, dry_run, log::Level::Error)?;
         failed |= !super::verify_rate_limit(&selected_pkgs, &index, dry_run, log::Level::Error)?;
+        failed |= !super::owner::verify_owners(
+            &selected_pkgs,
+            &index,
+            &mut index_urls,
+            &crate::ops::cargo::CargoOwners,
+            dry_run,
+            log::Level::Error,
+        )?;
+        // Dry-runs don't post anything, so a missing token shouldn't fail them
+        failed |= !super::github::verify_github_release(
+            &ws_config,
+            &ws_meta,
+            &selected_pkgs,
+            self.github_repo.as_ref(),
+            dry_run,
+            if dry_run {
+                log::Level::Warn
+            } else {
+                log::Level::Error
+            },
+        )?;
 
         // STEP 1: Release Confirmation
-        super::confirm("Release", &selected_pkgs, self.no_confirm, dry_run)?;
+        if self.summary == super::SummaryFormat::Table {
+            // Changes are only checked up front when the config needs them
+            for pkg in selected_pkgs.iter_mut().filter(|p| p.changed.is_none()) {
+                if let Some(prior_tag) = pkg.prior_tag.clone() {
+                    pkg.changed = super::version::changed_since(&ws_meta, pkg, &prior_tag)
+                        .map(|changed| !changed.is_empty());
+                }
+            }
+            super::print_summary_table(&selected_pkgs);
+        }
+        (selected_pkgs, excluded_pkgs) = super::confirm_with_drops(
+            "Release",
+            selected_pkgs,
+            excluded_pkgs,
+            self.no_confirm,
+            dry_run,
+        )?;
 
         // STEP 2: update current version, save and commit
+        let released: Vec<_> = selected_pkgs.iter().map(|p| p.meta.id.clone()).collect();
+        let mut overlay = crate::ops::overlay::Overlay::default();
         if consolidate_commits {
-            let update_lock =
-                super::version::update_versions(&ws_meta, &selected_pkgs, &excluded_pkgs, dry_run)?;
+            let _timer = crate::ops::timings::start("update versions");
+            let update_lock = super::version::update_versions(
+                &ws_meta,
+                &mut selected_pkgs,
+                &mut excluded_pkgs,
+                &mut overlay,
+                dry_run,
+            )?;
             if update_lock {
                 log::debug!("updating lock file");
                 if !dry_run {
@@ -249,60 +432,71 @@ impl ReleaseStep {
             }
 
             for pkg in &selected_pkgs {
-                super::replace::replace(pkg, dry_run)?;
+                let changed = super::replace::replace(pkg, &mut overlay, dry_run)?;
+                super::replace::warn_foreign_replacements(&ws_meta, &released, pkg, &changed);
 
                 // pre-release hook
                 super::hook::hook(&ws_meta, pkg, dry_run)?;
             }
 
-            super::commit::workspace_commit(&ws_meta, &ws_config, &selected_pkgs, dry_run)?;
+            let paths = super::commit::release_paths(&ws_meta, &ws_config, &selected_pkgs)?;
+            super::commit::workspace_commit(&ws_meta, &ws_config, &selected_pkgs, &paths, dry_run)?;
         } else {
-            for pkg in &selected_pkgs {
-                if let Some(version) = pkg.planned_version.as_ref() {
-                    let crate_name = pkg.meta.name.as_str();
-                    let _ = crate::ops::shell::status(
-                        "Upgrading",
-                        format!(
-                            "{} from {} to {}",
-                            crate_name,
-                            pkg.initial_version.full_version_string,
-                            version.full_version_string
-                        ),
-                    );
-                    cargo::set_package_version(
-                        &pkg.manifest_path,
-                        version.full_version_string.as_str(),
-                        dry_run,
-                    )?;
-                    crate::steps::version::update_dependent_versions(
-                        &ws_meta, pkg, version, dry_run,
-                    )?;
-                    if dry_run {
-                        log::debug!("updating lock file");
-                    } else {
-                        cargo::update_lock(&pkg.manifest_path)?;
-                    }
-                }
+            for pkg in &mut selected_pkgs {
+                super::version::update_package_version(&ws_meta, pkg, &mut overlay, dry_run)?;
 
-                super::replace::replace(pkg, dry_run)?;
+                let changed = super::replace::replace(pkg, &mut overlay, dry_run)?;
+                super::replace::warn_foreign_replacements(&ws_meta, &released, pkg, &changed);
 
                 // pre-release hook
                 super::hook::hook(&ws_meta, pkg, dry_run)?;
 
-                super::commit::pkg_commit(pkg, dry_run)?;
+                let paths =
+                    super::commit::release_paths(&ws_meta, &ws_config, std::slice::from_ref(pkg))?;
+                super::commit::pkg_commit(pkg, &paths, dry_run)?;
             }
         }
 
         // STEP 3: cargo publish
-        super::publish::publish(&ws_meta, &selected_pkgs, &mut index, dry_run)?;
-        super::owner::ensure_owners(&selected_pkgs, dry_run)?;
+        super::publish::publish(&ws_meta, &selected_pkgs, &mut index, None, dry_run)?;
+        super::owner::ensure_owners(&selected_pkgs, false, dry_run)?;
+
+        // STEP 4: Smoke-test what was published
+        super::hook::post_publish_hook(&ws_meta, &ws_config, &selected_pkgs, dry_run)?;
 
         // STEP 5: Tag
         super::tag::tag(&selected_pkgs, dry_run)?;
 
         // STEP 6: git push
         super::push::push(&ws_config, &ws_meta, &selected_pkgs, dry_run)?;
+        super::github::github_release(
+            &ws_config,
+            &ws_meta,
+            &selected_pkgs,
+            self.github_repo.as_ref(),
+            dry_run,
+        )?;
+
+        // STEP 7: Kick off anything downstream of the release
+        super::hook::post_release_hook(&ws_meta, &ws_config, &selected_pkgs, dry_run)?;
+
+        if dry_run {
+            let dependency_updates = cargo::render_dependency_updates(
+                selected_pkgs
+                    .iter()
+                    .chain(excluded_pkgs.iter())
+                    .flat_map(|p| &p.dependency_updates),
+            );
+            if !dependency_updates.is_empty() {
+                let _ = crate::ops::reporter::note(format!(
+                    "dependency requirements to update:\n{}",
+                    dependency_updates
+                ));
+            }
+        }
 
+        super::summarize(&selected_pkgs);
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 }
@@ -1,6 +1,12 @@
+use std::path::Path;
+use std::path::PathBuf;
+
 use crate::error::CliError;
 use crate::ops::git;
-use crate::ops::replace::{do_file_replacements, Template, NOW};
+use crate::ops::overlay::Overlay;
+use crate::ops::replace::{
+    check_file_replacements, do_file_replacements, DependencyLookup, Template,
+};
 use crate::steps::plan;
 
 /// Perform pre-release replacements
@@ -16,24 +22,19 @@ pub struct ReplaceStep {
     #[arg(long)]
     unpublished: bool,
 
-    /// Custom config file
-    #[arg(short, long = "config")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
+    /// Define a `{{KEY}}` template placeholder, overriding `template-vars`
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = crate::config::parse_var)]
+    vars: Vec<(String, String)>,
 
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
@@ -42,13 +43,14 @@ pub struct ReplaceStep {
 
 impl ReplaceStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
-        let index = crates_index::Index::new_cargo_default()?;
-
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
+        let index = crate::ops::index::CratesIoIndex::open(
+            crate::ops::index::IndexProtocol::from_env(),
+            false,
+            false,
+        )?;
+        index.warn_unpublished(self.unpublished);
 
         let ws_meta = self
             .manifest
@@ -84,7 +86,7 @@ impl ReplaceStep {
                 let version = &pkg.initial_version;
                 if !crate::ops::cargo::is_published(
                     &index,
-                    crate_name,
+                    pkg.published_name(),
                     &version.full_version_string,
                 ) {
                     log::debug!(
@@ -97,26 +99,24 @@ impl ReplaceStep {
             }
 
             pkg.config.pre_release_replacements = Some(vec![]);
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
         }
 
         let pkgs = plan::plan(pkgs)?;
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Warn,
         )?;
@@ -126,6 +126,7 @@ impl ReplaceStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Warn,
         )?;
@@ -141,54 +142,322 @@ impl ReplaceStep {
         super::confirm("Bump", &selected_pkgs, self.no_confirm, dry_run)?;
 
         // STEP 2: update current version, save and commit
+        let released: Vec<_> = selected_pkgs.iter().map(|p| p.meta.id.clone()).collect();
+        let mut overlay = Overlay::default();
         for pkg in &selected_pkgs {
-            replace(pkg, dry_run)?;
+            let changed = replace(pkg, &mut overlay, dry_run)?;
+            warn_foreign_replacements(&ws_meta, &released, pkg, &changed);
         }
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
+            vars: self.vars.clone(),
             ..Default::default()
         }
     }
 }
 
-pub fn replace(pkg: &plan::PackageRelease, dry_run: bool) -> Result<(), CliError> {
-    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+/// Apply `pkg`'s `pre-release-replacements`, returning the files changed
+pub fn replace(
+    pkg: &plan::PackageRelease,
+    overlay: &mut Overlay,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>, CliError> {
+    let mut changed = Vec::new();
     if !pkg.config.pre_release_replacements().is_empty() {
-        let cwd = &pkg.package_root;
-        let crate_name = pkg.meta.name.as_str();
-        let prev_version_var = pkg.initial_version.bare_version_string.as_str();
-        let prev_metadata_var = pkg.initial_version.full_version.build.as_str();
-        let version_var = version.bare_version_string.as_str();
-        let metadata_var = version.full_version.build.as_str();
         // try replacing text in configured files
-        let template = Template {
-            prev_version: Some(prev_version_var),
-            prev_metadata: Some(prev_metadata_var),
-            version: Some(version_var),
-            metadata: Some(metadata_var),
-            crate_name: Some(crate_name),
-            date: Some(NOW.as_str()),
-            tag_name: pkg.planned_tag.as_deref(),
-            ..Default::default()
-        };
-        let prerelease = version.is_prerelease();
         let noisy = true;
-        do_file_replacements(
-            pkg.config.pre_release_replacements(),
-            &template,
-            cwd,
-            prerelease,
-            noisy,
-            dry_run,
-        )?;
+        changed = with_template(pkg, |template, prerelease| {
+            do_file_replacements(
+                pkg.config.pre_release_replacements(),
+                template,
+                &pkg.package_root,
+                prerelease,
+                noisy,
+                overlay,
+                dry_run,
+            )
+        })?;
+        if !changed.is_empty() {
+            pkg.content.invalidate();
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Every problem [`replace`] would hit for `pkgs`, released in order, without modifying any file
+///
+/// Each problem is prefixed with the package's name.  A file shared between packages sees the
+/// edits of those before it, as it would in the release.
+pub fn replacement_problems(pkgs: &[plan::PackageRelease]) -> Result<Vec<String>, CliError> {
+    // Staged edits only live as long as the check
+    let mut overlay = Overlay::default();
+    let mut problems = Vec::new();
+    for pkg in pkgs {
+        if pkg.config.pre_release_replacements().is_empty() {
+            continue;
+        }
+        let mut pkg_problems = Vec::new();
+        with_template(pkg, |template, prerelease| {
+            check_file_replacements(
+                pkg.config.pre_release_replacements(),
+                template,
+                &pkg.package_root,
+                prerelease,
+                &mut overlay,
+                &mut pkg_problems,
+            )
+        })?;
+        problems.extend(
+            pkg_problems
+                .into_iter()
+                .map(|problem| format!("{}: {}", pkg.meta.name, problem)),
+        );
+    }
+    Ok(problems)
+}
+
+/// Run `f` with the template `pkg`'s replacements are rendered with, and whether it is a
+/// pre-release
+fn with_template<R>(pkg: &plan::PackageRelease, f: impl FnOnce(&Template<'_>, bool) -> R) -> R {
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    let dependency_var = |name: &str, field: &str| pkg.dependency_var(name, field);
+    let template = Template {
+        dependencies: Some(DependencyLookup(&dependency_var)),
+        ..pkg.template()
+    };
+    f(&template, version.is_prerelease())
+}
+
+/// Files among `changed`, from replacements for `pkg_id`, belonging to another workspace member
+/// that isn't among `released`
+///
+/// Returns each file with the name of the package owning it.
+pub fn foreign_replacements<'c, 'm>(
+    ws_meta: &'m cargo_metadata::Metadata,
+    released: &[cargo_metadata::PackageId],
+    pkg_id: &cargo_metadata::PackageId,
+    changed: &'c [PathBuf],
+) -> Vec<(&'c Path, &'m str)> {
+    let roots: Vec<_> = ws_meta
+        .workspace_members
+        .iter()
+        .map(|id| &ws_meta[id])
+        .filter_map(|p| {
+            let manifest_path = crate::ops::path::resolve(p.manifest_path.as_std_path());
+            Some((p, manifest_path.parent()?.to_owned()))
+        })
+        .collect();
+
+    let mut foreign = Vec::new();
+    for file in changed {
+        let resolved = crate::ops::path::resolve(file);
+        // Nested packages own their own files, so go with the deepest root
+        let owner = roots
+            .iter()
+            .filter(|(_, root)| resolved.starts_with(root))
+            .max_by_key(|(_, root)| root.components().count())
+            .map(|(p, _)| *p);
+        let owner = match owner {
+            Some(owner) => owner,
+            None => continue,
+        };
+        if owner.id == *pkg_id || released.contains(&owner.id) {
+            continue;
+        }
+        foreign.push((file.as_path(), owner.name.as_str()));
+    }
+    foreign
+}
+
+/// Warn about replacements for `pkg` that changed files of a package not being released
+///
+/// This is usually a sign of a file having moved between packages without the
+/// `pre-release-replacements` following it.
+pub fn warn_foreign_replacements(
+    ws_meta: &cargo_metadata::Metadata,
+    released: &[cargo_metadata::PackageId],
+    pkg: &plan::PackageRelease,
+    changed: &[PathBuf],
+) {
+    for (file, owner_name) in foreign_replacements(ws_meta, released, &pkg.meta.id, changed) {
+        let file = file
+            .strip_prefix(ws_meta.workspace_root.as_std_path())
+            .unwrap_or(file);
+        let _ = crate::ops::reporter::warn(format!(
+            "replacements for {} changed {}, which belongs to {}, not being released; \
+            the `pre-release-replacements` likely need moving to {}",
+            pkg.meta.name,
+            file.display(),
+            owner_name,
+            owner_name
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use assert_fs::prelude::*;
+
+    use crate::config::Replace;
+
+    fn args() -> crate::config::ConfigArgs {
+        crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        }
+    }
+
+    fn load(temp: &assert_fs::TempDir) -> (cargo_metadata::Metadata, Vec<plan::PackageRelease>) {
+        temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        let (ws_meta, pkgs) = plan::fixture::load(temp.path(), &args());
+        (ws_meta, pkgs.into_values().collect())
+    }
+
+    fn replace_in(file: &str) -> Option<Vec<Replace>> {
+        Some(vec![Replace {
+            file: PathBuf::from(file),
+            search: "2 \\+ 2".to_owned(),
+            replace: "{{version}}".to_owned(),
+            min: None,
+            max: None,
+            exactly: Some(1),
+            prerelease: false,
+            idempotent: false,
+        }])
+    }
+
+    fn ids(pkgs: &[plan::PackageRelease]) -> Vec<cargo_metadata::PackageId> {
+        pkgs.iter().map(|p| p.meta.id.clone()).collect()
+    }
+
+    #[test]
+    fn cross_package() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let (ws_meta, mut pkgs) = load(&temp);
+        let a = pkgs.iter().position(|p| p.meta.name == "a").unwrap();
+        pkgs[a].config.pre_release_replacements = replace_in("../b/src/lib.rs");
+        let released = vec![pkgs.remove(a)];
+
+        let changed = replace(&released[0], &mut Overlay::default(), false).unwrap();
+        let foreign =
+            foreign_replacements(&ws_meta, &ids(&released), &released[0].meta.id, &changed);
+        let owners: Vec<_> = foreign.iter().map(|(_, owner)| *owner).collect();
+        assert_eq!(owners, ["b"]);
+
+        temp.close().unwrap();
     }
 
-    Ok(())
+    #[test]
+    fn released_together() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let (ws_meta, mut pkgs) = load(&temp);
+        let a = pkgs.iter().position(|p| p.meta.name == "a").unwrap();
+        pkgs[a].config.pre_release_replacements = replace_in("../b/src/lib.rs");
+
+        let changed = replace(&pkgs[a], &mut Overlay::default(), false).unwrap();
+        assert!(foreign_replacements(&ws_meta, &ids(&pkgs), &pkgs[a].meta.id, &changed).is_empty());
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn dependency_versions() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        temp.child("b/CHANGELOG.md")
+            .write_str("## Unreleased\n")
+            .unwrap();
+        let (_, pkgs) = plan::fixture::planned(temp.path(), &args(), |pkg| {
+            if pkg.meta.name == "c" {
+                pkg.config.release = Some(false);
+            }
+            if pkg.meta.name == "b" {
+                pkg.config.pre_release_replacements = Some(vec![Replace {
+                    file: PathBuf::from("CHANGELOG.md"),
+                    search: "## Unreleased".to_owned(),
+                    replace: "## {{version}}\n\n- Updated a from {{dep.a.prev_version}} to {{dep.a.version}}\n- Updated c to {{dep.c.version}}".to_owned(),
+                    min: None,
+                    max: None,
+                    exactly: Some(1),
+                    prerelease: false,
+                    idempotent: false,
+                }]);
+            }
+        });
+        let b = pkgs.iter().find(|p| p.meta.name == "b").unwrap();
+
+        replace(b, &mut Overlay::default(), false).unwrap();
+        temp.child("b/CHANGELOG.md").assert(
+            "## 0.2.0\n\n- Updated a from 0.1.0 to 0.2.0\n- Updated c to {{dep.c.version}}\n",
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn compare_link() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+        temp.child("a/CHANGELOG.md")
+            .write_str("<!-- next-url -->\n")
+            .unwrap();
+        crate::ops::git::fixture::init(temp.path());
+        for args in [
+            &["add", "."][..],
+            &["commit", "--quiet", "-m", "init"],
+            &["tag", "a-v0.1.0"],
+        ] {
+            crate::ops::git::fixture::git(temp.path(), args);
+        }
+        let (_, pkgs) = plan::fixture::planned(temp.path(), &args(), |pkg| {
+            pkg.config.pre_release_replacements = Some(vec![Replace {
+                file: PathBuf::from("CHANGELOG.md"),
+                search: "<!-- next-url -->".to_owned(),
+                replace: "<!-- next-url -->\n[{{version}}]: https://github.com/me/repo/compare/{{prev_tag_name}}...{{tag_name}}".to_owned(),
+                min: None,
+                max: None,
+                exactly: Some(1),
+                prerelease: false,
+                idempotent: false,
+            }]);
+        });
+        let a = pkgs.iter().find(|p| p.meta.name == "a").unwrap();
+        assert_eq!(a.prev_tag_name(), "a-v0.1.0");
+
+        replace(a, &mut Overlay::default(), false).unwrap();
+        temp.child("a/CHANGELOG.md").assert(
+            "<!-- next-url -->\n[0.2.0]: https://github.com/me/repo/compare/a-v0.1.0...a-v0.2.0\n",
+        );
+
+        temp.close().unwrap();
+    }
+
+    #[test]
+    fn own_files() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let (ws_meta, mut pkgs) = load(&temp);
+        let a = pkgs.iter().position(|p| p.meta.name == "a").unwrap();
+        pkgs[a].config.pre_release_replacements = replace_in("src/lib.rs");
+        let released = vec![pkgs.remove(a)];
+
+        let changed = replace(&released[0], &mut Overlay::default(), false).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert!(
+            foreign_replacements(&ws_meta, &ids(&released), &released[0].meta.id, &changed)
+                .is_empty()
+        );
+
+        temp.close().unwrap();
+    }
 }
@@ -1,7 +1,12 @@
+use std::collections::BTreeMap;
 use std::collections::HashSet;
+use std::path::Path;
 
+use crate::config::TagMessageBody;
+use crate::config::TagMessageSource;
 use crate::error::CliError;
 use crate::ops::git;
+use crate::ops::replace::DependencyLookup;
 use crate::ops::replace::Template;
 use crate::ops::replace::NOW;
 use crate::steps::plan;
@@ -17,42 +22,37 @@ pub struct TagStep {
     #[command(flatten)]
     workspace: clap_cargo::Workspace,
 
-    /// Custom config file
-    #[arg(short, long = "config")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
-
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
     no_confirm: bool,
 
+    /// Check whether the branch is behind the push remote, which requires a `git fetch`
+    #[arg(long)]
+    check_remote: bool,
+
+    /// Tag this commit rather than `HEAD`; pass `PKG=REF` for packages released at different commits
+    #[arg(long, value_name = "[PKG=]REF", alias = "backdate-tag")]
+    at: Vec<String>,
+
     #[command(flatten)]
     tag: crate::config::TagArgs,
 }
 
 impl TagStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
 
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
-
         let ws_meta = self
             .manifest
             .metadata()
@@ -77,7 +77,7 @@ impl TagStep {
 
             pkg.planned_tag = None;
             pkg.config.tag = Some(false);
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
 
             let crate_name = pkg.meta.name.as_str();
             log::debug!("disabled by user, skipping {}", crate_name,);
@@ -89,32 +89,35 @@ impl TagStep {
             if let Some(tag_name) = pkg.planned_tag.as_ref() {
                 if crate::ops::git::tag_exists(ws_meta.workspace_root.as_std_path(), tag_name)? {
                     let crate_name = pkg.meta.name.as_str();
-                    let _ = crate::ops::shell::warn(format!(
+                    let _ = crate::ops::reporter::warn(format!(
                         "disabled due to existing tag ({}), skipping {}",
                         tag_name, crate_name
                     ));
                     pkg.planned_tag = None;
                     pkg.config.tag = Some(false);
-                    pkg.config.release = Some(false);
+                    pkg.skip(plan::SkipReason::TagExists);
                 }
             }
         }
 
-        let (selected_pkgs, _excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (mut selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
+        set_tag_targets(
+            ws_meta.workspace_root.as_std_path(),
+            &mut selected_pkgs,
+            &self.at,
+        )?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
             log::Level::Error,
         )?;
@@ -122,30 +125,34 @@ impl TagStep {
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Error,
         )?;
 
-        failed |= !super::verify_if_behind(
+        failed |= !super::verify_if_behind_when_asked(
+            self.check_remote,
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
             dry_run,
-            log::Level::Warn,
         )?;
 
+        failed |= !super::verify_unique_tags(&selected_pkgs, dry_run, log::Level::Error)?;
+
         // STEP 1: Release Confirmation
         super::confirm("Tag", &selected_pkgs, self.no_confirm, dry_run)?;
 
         // STEP 5: Tag
         tag(&selected_pkgs, dry_run)?;
 
+        super::report_skipped(&excluded_pkgs);
         super::finish(failed, dry_run)
     }
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
             tag: self.tag.clone(),
             ..Default::default()
@@ -153,7 +160,80 @@ impl TagStep {
     }
 }
 
+/// Point tags at the commits given by `--at`, after checking they contain the release
+fn set_tag_targets(
+    ws_root: &Path,
+    pkgs: &mut [plan::PackageRelease],
+    at: &[String],
+) -> Result<(), CliError> {
+    let mut default_rev = None;
+    let mut pkg_revs = BTreeMap::new();
+    for at in at {
+        if let Some((crate_name, rev)) = at.split_once('=') {
+            if !pkgs.iter().any(|p| p.meta.name == crate_name) {
+                let _ = crate::ops::reporter::error(format!(
+                    "`--at {}` is for {}, which isn't being tagged",
+                    at, crate_name
+                ));
+                return Err(101.into());
+            }
+            pkg_revs.insert(crate_name, rev);
+        } else if default_rev.replace(at.as_str()).is_some() {
+            let _ = crate::ops::reporter::error(
+                "`--at` can only be passed once without a package; use `--at PKG=REF` for each package",
+            );
+            return Err(101.into());
+        }
+    }
+
+    let ws_manifest_path = ws_root.join("Cargo.toml");
+    let tagged = pkgs.iter().filter(|p| p.planned_tag.is_some()).count();
+    for pkg in pkgs.iter_mut().filter(|p| p.planned_tag.is_some()) {
+        let crate_name = pkg.meta.name.as_str();
+        let rev = match pkg_revs.get(crate_name).copied().or(default_rev) {
+            Some(rev) => rev,
+            None => continue,
+        };
+        let is_shared = !pkg_revs.contains_key(crate_name) && 1 < tagged;
+
+        let commit = match git::resolve_commit(ws_root, rev) {
+            Ok(commit) => commit,
+            Err(err) => {
+                let _ =
+                    crate::ops::reporter::error(format!("`--at {}` is not a commit: {}", rev, err));
+                return Err(101.into());
+            }
+        };
+        let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+        let released =
+            git::release_version_at(ws_root, &commit, &pkg.manifest_path, &ws_manifest_path);
+        if released.as_ref() != Some(&version.full_version) {
+            let found = released
+                .map(|v| format!("version {}", v))
+                .unwrap_or_else(|| "no version".to_owned());
+            let hint = if is_shared {
+                format!(
+                    "; if its release commit differs from the others, pass `--at {}=<REF>`",
+                    crate_name
+                )
+            } else {
+                String::new()
+            };
+            let _ = crate::ops::reporter::error(format!(
+                "{} has {} at `{}`, not {}{}",
+                crate_name, found, rev, version.full_version_string, hint
+            ));
+            return Err(101.into());
+        }
+        pkg.tag_target = Some(commit);
+    }
+
+    Ok(())
+}
+
 pub fn tag(pkgs: &[plan::PackageRelease], dry_run: bool) -> Result<(), CliError> {
+    let _ = crate::ops::reporter::phase_start("tag");
+    let _timer = crate::ops::timings::start("tag");
     let mut seen_tags = HashSet::new();
     for pkg in pkgs {
         if let Some(tag_name) = pkg.planned_tag.as_ref() {
@@ -166,6 +246,29 @@ pub fn tag(pkgs: &[plan::PackageRelease], dry_run: bool) -> Result<(), CliError>
                 let prev_metadata_var = pkg.initial_version.full_version.build.as_str();
                 let version_var = version.bare_version_string.as_str();
                 let metadata_var = version.full_version.build.as_str();
+                // Packages sharing a tag share its message
+                let dependency_updates_var = crate::ops::cargo::render_dependency_updates(
+                    pkgs.iter()
+                        .filter(|p| p.planned_tag.as_ref() == Some(tag_name))
+                        .flat_map(|p| &p.dependency_updates),
+                );
+                let dependency_var = |name: &str, field: &str| pkg.dependency_var(name, field);
+                let body = pkg.config.tag_message_body();
+                // A git note could use `{{changes}}` too, so only `tag-message` lets us skip this
+                let changes_var = if body == TagMessageBody::Conventional
+                    || pkg.config.tag_message_source() == TagMessageSource::GitNotes
+                    || pkg.config.tag_message().contains("{{changes}}")
+                {
+                    match crate::steps::changes::released_commits(pkg)? {
+                        Some(commits) => crate::steps::changes::render_conventional(
+                            &commits,
+                            pkg.config.commit_types(),
+                        ),
+                        None => String::new(),
+                    }
+                } else {
+                    String::new()
+                };
                 let template = Template {
                     prev_version: Some(prev_version_var),
                     prev_metadata: Some(prev_metadata_var),
@@ -173,13 +276,65 @@ pub fn tag(pkgs: &[plan::PackageRelease], dry_run: bool) -> Result<(), CliError>
                     metadata: Some(metadata_var),
                     crate_name: Some(crate_name),
                     tag_name: Some(tag_name),
+                    prev_tag_name: Some(pkg.prev_tag_name()),
+                    changes: Some(changes_var.as_str()),
                     date: Some(NOW.as_str()),
+                    dependency_updates: Some(dependency_updates_var.as_str()),
+                    vars: pkg.config.template_vars(),
+                    dependencies: Some(DependencyLookup(&dependency_var)),
                     ..Default::default()
                 };
-                let tag_message = template.render(pkg.config.tag_message());
+                let render = |message: &str| {
+                    let mut rendered = template.render(message);
+                    // A blank message is a lightweight tag, which has no body to add to
+                    if body == TagMessageBody::Conventional
+                        && !rendered.is_empty()
+                        && !changes_var.is_empty()
+                        && !message.contains("{{changes}}")
+                    {
+                        rendered.push_str("\n\n");
+                        rendered.push_str(&changes_var);
+                    }
+                    rendered
+                };
+                let tag_message = match pkg.config.tag_message_source() {
+                    TagMessageSource::Template => render(pkg.config.tag_message()),
+                    TagMessageSource::GitNotes => {
+                        let notes_ref = pkg.config.notes_ref();
+                        match git::note(cwd, notes_ref, pkg.tag_target.as_deref())? {
+                            Some(note) => render(&note),
+                            None => {
+                                let _ = crate::ops::reporter::warn(format!(
+                                    "no note in {} for {}, falling back to `tag-message`",
+                                    notes_ref, tag_name
+                                ));
+                                render(pkg.config.tag_message())
+                            }
+                        }
+                    }
+                    TagMessageSource::Changes => {
+                        match crate::steps::changes::changes_summary(pkg)? {
+                            Some(summary) => summary,
+                            None => {
+                                let _ = crate::ops::reporter::warn(format!(
+                                    "no prior tag to summarize changes for {} from, falling back to `tag-message`",
+                                    tag_name
+                                ));
+                                render(pkg.config.tag_message())
+                            }
+                        }
+                    }
+                };
 
                 log::debug!("creating git tag {}", tag_name);
-                if !git::tag(cwd, tag_name, &tag_message, pkg.config.sign_tag(), dry_run)? {
+                if !git::tag(
+                    cwd,
+                    tag_name,
+                    &tag_message,
+                    pkg.config.sign_tag(),
+                    pkg.tag_target.as_deref(),
+                    dry_run,
+                )? {
                     // tag failed, abort release
                     return Err(101.into());
                 }
@@ -189,3 +344,275 @@ pub fn tag(pkgs: &[plan::PackageRelease], dry_run: bool) -> Result<(), CliError>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ops::git::fixture::{self, git};
+    use assert_fs::prelude::*;
+
+    fn commit(repo: &assert_fs::TempDir, version: &str, msg: &str) {
+        repo.child("Cargo.toml")
+            .write_str(&format!(
+                "[package]\nname = \"foo\"\nversion = \"{}\"\n\n[lib]\npath = \"lib.rs\"\n",
+                version
+            ))
+            .unwrap();
+        git(repo.path(), &["add", "Cargo.toml", "lib.rs"]);
+        git(repo.path(), &["commit", "--quiet", "-m", msg]);
+    }
+
+    /// `1.0.0` was released in `HEAD~1`, followed by an unrelated change
+    fn repo() -> assert_fs::TempDir {
+        let repo = assert_fs::TempDir::new().unwrap();
+        fixture::init(repo.path());
+        repo.child("lib.rs").write_str("").unwrap();
+        commit(&repo, "1.0.0", "chore: Release foo 1.0.0");
+        repo.child("lib.rs").write_str("// unrelated").unwrap();
+        commit(&repo, "1.0.0", "docs: Unrelated");
+        repo
+    }
+
+    fn load(repo: &assert_fs::TempDir) -> plan::PackageRelease {
+        let ws_meta = plan::fixture::metadata(repo.path());
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let root = git::top_level(repo.path()).unwrap();
+        let mut pkg =
+            plan::PackageRelease::load(&args, &root, &ws_meta, &ws_meta.packages[0]).unwrap();
+        pkg.planned_tag = Some(format!("v{}", pkg.initial_version.full_version_string));
+        pkg
+    }
+
+    #[test]
+    fn tags_release_commit() {
+        let repo = repo();
+        let mut pkgs = [load(&repo)];
+
+        set_tag_targets(repo.path(), &mut pkgs, &["HEAD~1".to_owned()]).unwrap();
+        tag(&pkgs, false).unwrap();
+
+        let release = git(repo.path(), &["rev-parse", "HEAD~1"]);
+        let tagged = git(repo.path(), &["rev-parse", "v1.0.0^{commit}"]);
+        assert_eq!(tagged, release);
+        assert_ne!(tagged, git(repo.path(), &["rev-parse", "HEAD"]));
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn rejects_commit_without_release() {
+        let repo = repo();
+        commit(&repo, "2.0.0", "chore: Release foo 2.0.0");
+        let mut pkgs = [load(&repo)];
+
+        assert!(set_tag_targets(repo.path(), &mut pkgs, &["HEAD~1".to_owned()]).is_err());
+        assert!(set_tag_targets(repo.path(), &mut pkgs, &["foo=HEAD".to_owned()]).is_ok());
+        assert_eq!(
+            pkgs[0].tag_target.as_deref(),
+            Some(git(repo.path(), &["rev-parse", "HEAD"]).as_str())
+        );
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn message_from_git_notes() {
+        let repo = repo();
+        git(
+            repo.path(),
+            &[
+                "notes",
+                "--ref",
+                "release",
+                "add",
+                "-m",
+                "{{crate_name}} {{version}}: faster builds",
+                "HEAD~1",
+            ],
+        );
+        let mut pkgs = [load(&repo)];
+        pkgs[0].config.tag_message_source = Some(TagMessageSource::GitNotes);
+
+        set_tag_targets(repo.path(), &mut pkgs, &["HEAD~1".to_owned()]).unwrap();
+        tag(&pkgs, false).unwrap();
+
+        let message = git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v1.0.0"],
+        );
+        assert_eq!(message, "foo 1.0.0: faster builds");
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn message_without_note_falls_back() {
+        let repo = repo();
+        let mut pkgs = [load(&repo)];
+        pkgs[0].config.tag_message_source = Some(TagMessageSource::GitNotes);
+
+        tag(&pkgs, false).unwrap();
+
+        let message = git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v1.0.0"],
+        );
+        assert_eq!(message, "chore: Release foo version 1.0.0");
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn message_from_changes() {
+        let repo = repo();
+        git(repo.path(), &["tag", "v1.0.0"]);
+        repo.child("lib.rs").write_str("pub fn fast() {}").unwrap();
+        commit(&repo, "1.0.0", "feat: Faster builds");
+        repo.child("lib.rs")
+            .write_str("pub fn fast() {}\n")
+            .unwrap();
+        commit(&repo, "1.0.0", "fix: Don't crash on empty input");
+        commit(&repo, "1.1.0", "chore: Release foo 1.1.0");
+        let mut pkgs = [load(&repo)];
+        pkgs[0].config.tag_message_source = Some(TagMessageSource::Changes);
+        assert_eq!(pkgs[0].prior_tag.as_deref(), Some("v1.0.0"));
+
+        tag(&pkgs, false).unwrap();
+
+        let message = git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v1.1.0"],
+        );
+        assert!(message.starts_with("foo 1.1.0\n"), "{}", message);
+        assert!(
+            message.contains("\nFeatures:\n- Faster builds ("),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("\nFixes:\n- Don't crash on empty input ("),
+            "{}",
+            message
+        );
+        assert!(!message.contains("Breaking Changes"), "{}", message);
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn conventional_body() {
+        let repo = repo();
+        git(repo.path(), &["tag", "v1.0.0"]);
+        git(repo.path(), &["checkout", "--quiet", "-b", "topic"]);
+        repo.child("lib.rs").write_str("pub fn fast() {}").unwrap();
+        commit(&repo, "1.0.0", "feat: Faster builds");
+        git(repo.path(), &["checkout", "--quiet", "-"]);
+        repo.child("README.md").write_str("foo").unwrap();
+        git(repo.path(), &["add", "README.md"]);
+        git(
+            repo.path(),
+            &["commit", "--quiet", "-m", "fix!: Drop the old README"],
+        );
+        git(
+            repo.path(),
+            &[
+                "merge",
+                "--quiet",
+                "--no-ff",
+                "-m",
+                "feat: Merge topic",
+                "topic",
+            ],
+        );
+        commit(&repo, "1.1.0", "chore: Release foo 1.1.0");
+        let mut pkgs = [load(&repo)];
+        pkgs[0].config.tag_message_body = Some(TagMessageBody::Conventional);
+
+        tag(&pkgs, false).unwrap();
+
+        let message = git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v1.1.0"],
+        );
+        assert!(
+            message.starts_with(
+                "chore: Release foo version 1.1.0\n\nBreaking Changes:\n- Drop the old README ("
+            ),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("\n\nFeatures:\n- Faster builds ("),
+            "{}",
+            message
+        );
+        assert!(!message.contains("Merge topic"), "{}", message);
+        assert!(!message.contains("Other"), "{}", message);
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn changes_placeholder() {
+        let repo = repo();
+        git(repo.path(), &["tag", "v1.0.0"]);
+        repo.child("lib.rs").write_str("pub fn fast() {}").unwrap();
+        commit(&repo, "1.1.0", "feat: Faster builds");
+        let mut pkgs = [load(&repo)];
+        pkgs[0].config.tag_message = Some(crate::config::MessageTemplate::Inline(
+            "{{crate_name}} {{version}}\n\n{{changes}}\n\nThanks!".to_owned(),
+        ));
+        pkgs[0].config.tag_message_body = Some(TagMessageBody::Conventional);
+
+        tag(&pkgs, false).unwrap();
+
+        let message = git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v1.1.0"],
+        );
+        assert!(
+            message.starts_with("foo 1.1.0\n\nFeatures:\n- Faster builds ("),
+            "{}",
+            message
+        );
+        assert!(message.ends_with(")\n\nThanks!"), "{}", message);
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn message_from_changes_without_prior_tag_falls_back() {
+        let repo = repo();
+        let mut pkgs = [load(&repo)];
+        pkgs[0].config.tag_message =
+            Some(crate::config::MessageTemplate::Inline("changes".to_owned()));
+        assert_eq!(
+            pkgs[0].config.tag_message_source(),
+            TagMessageSource::Changes
+        );
+
+        tag(&pkgs, false).unwrap();
+
+        let message = git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v1.0.0"],
+        );
+        assert_eq!(message, "chore: Release foo version 1.0.0");
+
+        repo.close().unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_package() {
+        let repo = repo();
+        let mut pkgs = [load(&repo)];
+
+        assert!(set_tag_targets(repo.path(), &mut pkgs, &["bar=HEAD".to_owned()]).is_err());
+
+        repo.close().unwrap();
+    }
+}
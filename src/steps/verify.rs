@@ -0,0 +1,452 @@
+use std::io::Write;
+
+use crate::error::CliError;
+use crate::ops::git;
+use crate::steps::plan;
+
+/// Run every pre-release verification and report a checklist
+///
+/// Nothing is bumped and nothing is prompted for
+#[derive(Debug, Clone, clap::Args)]
+pub struct VerifyStep {
+    #[command(flatten)]
+    manifest: clap_cargo::Manifest,
+
+    #[command(flatten)]
+    workspace: clap_cargo::Workspace,
+
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
+
+    /// Comma-separated globs of branch names a release can happen from
+    #[arg(long, value_delimiter = ',')]
+    allow_branch: Option<Vec<String>>,
+
+    #[command(flatten)]
+    dry_run: super::DryRunArgs,
+}
+
+impl VerifyStep {
+    pub fn run(&self) -> Result<(), CliError> {
+        self.dry_run.check();
+        git::git_version()?;
+
+        let ws_meta = self
+            .manifest
+            .metadata()
+            // When evaluating dependency ordering, we need to consider optional dependencies
+            .features(cargo_metadata::CargoOpt::AllFeatures)
+            .exec()?;
+        let config = self.to_config();
+        let ws_config = crate::config::load_workspace_config(&config, &ws_meta)?;
+        let mut pkgs = plan::load(&config, &ws_meta)?;
+
+        let (_selected_pkgs, excluded_pkgs) = self.workspace.partition_packages(&ws_meta);
+        for excluded_pkg in excluded_pkgs {
+            let pkg = if let Some(pkg) = pkgs.get_mut(&excluded_pkg.id) {
+                pkg
+            } else {
+                // Either not in workspace or marked as `release = false`.
+                continue;
+            };
+            pkg.skip(plan::SkipReason::Excluded);
+        }
+
+        let pkgs = plan::plan(pkgs)?;
+
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+            .into_iter()
+            .map(|(_, pkg)| pkg)
+            .partition(|p| p.config.release());
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
+
+        let index = crate::ops::index::CratesIoIndex::open(
+            crate::ops::index::IndexProtocol::from_env(),
+            false,
+            false,
+        )?;
+        let context = Context {
+            ws_meta: &ws_meta,
+            ws_config: &ws_config,
+            pkgs: &selected_pkgs,
+            index: &index,
+        };
+
+        let blocked = run_checks(&context, CHECKS, &mut std::io::stdout().lock())?;
+
+        super::report_skipped(&excluded_pkgs);
+        if blocked {
+            Err(101.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn to_config(&self) -> crate::config::ConfigArgs {
+        crate::config::ConfigArgs {
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
+            allow_branch: self.allow_branch.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+struct Context<'a> {
+    ws_meta: &'a cargo_metadata::Metadata,
+    ws_config: &'a crate::config::Config,
+    pkgs: &'a [plan::PackageRelease],
+    index: &'a crate::ops::index::CratesIoIndex,
+}
+
+impl Context<'_> {
+    fn root(&self) -> &std::path::Path {
+        self.ws_meta.workspace_root.as_std_path()
+    }
+}
+
+/// Run `checks`, writing a line to `out` for each, per package for package checks
+///
+/// A check passes when it reports no warnings.  Returns whether a blocking check failed.
+fn run_checks(
+    context: &Context<'_>,
+    checks: &[Check],
+    out: &mut dyn Write,
+) -> Result<bool, CliError> {
+    let mut blocked = false;
+    for check in checks {
+        let results = match check.scope {
+            Scope::Workspace(verify) => vec![(None, passes(|| verify(context))?)],
+            Scope::Package(verify) => context
+                .pkgs
+                .iter()
+                .map(|pkg| {
+                    Ok((
+                        Some(pkg.meta.name.as_str()),
+                        passes(|| verify(context, pkg))?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, CliError>>()?,
+        };
+        for (crate_name, passed) in results {
+            let mark = match (passed, check.blocking) {
+                (true, _) => "[x]",
+                (false, true) => "[ ]",
+                (false, false) => "[!]",
+            };
+            match crate_name {
+                Some(crate_name) => writeln!(out, "{} {} ({})", mark, check.name, crate_name)?,
+                None => writeln!(out, "{} {}", mark, check.name)?,
+            }
+            blocked |= !passed && check.blocking;
+        }
+    }
+    Ok(blocked)
+}
+
+fn passes(verify: impl FnOnce() -> Result<bool, CliError>) -> Result<bool, CliError> {
+    let (passed, problems) = crate::ops::reporter::count_problems(verify);
+    Ok(passed? && problems == 0)
+}
+
+struct Check {
+    name: &'static str,
+    /// Whether a failure would stop `cargo release`
+    blocking: bool,
+    scope: Scope,
+}
+
+enum Scope {
+    Workspace(fn(&Context<'_>) -> Result<bool, CliError>),
+    Package(fn(&Context<'_>, &plan::PackageRelease) -> Result<bool, CliError>),
+}
+
+// Checks run at `Warn`, so every failure is reported rather than aborting on the first.
+//
+// New verifications only need an entry here to show up in the checklist.
+const CHECKS: &[Check] = &[
+    Check {
+        name: "clean working tree",
+        blocking: true,
+        scope: Scope::Workspace(|cx| {
+            super::verify_git_is_clean(cx.root(), cx.ws_config, true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "allowed branch",
+        blocking: true,
+        scope: Scope::Package(|cx, pkg| {
+            super::verify_git_branch(
+                cx.root(),
+                cx.ws_config,
+                std::slice::from_ref(pkg),
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "up to date with push remote",
+        blocking: false,
+        scope: Scope::Workspace(|cx| {
+            super::verify_if_behind(cx.root(), cx.ws_config, true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "previous release pushed",
+        blocking: false,
+        scope: Scope::Workspace(|cx| {
+            super::verify_release_pushed(cx.root(), cx.ws_config, cx.pkgs, true, log::Level::Warn)
+        }),
+    },
+    Check {
+        // Without a bump, this is the current version's tag, which is expected after a release
+        name: "tag missing",
+        blocking: false,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_tags_missing(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "tags to push are signed",
+        blocking: true,
+        scope: Scope::Workspace(|cx| super::verify_tag_annotation(cx.pkgs, true, log::Level::Warn)),
+    },
+    Check {
+        name: "tags unique",
+        blocking: true,
+        scope: Scope::Workspace(|cx| super::verify_unique_tags(cx.pkgs, true, log::Level::Warn)),
+    },
+    Check {
+        name: "version increases",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_monotonically_increasing(
+                std::slice::from_ref(pkg),
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "not a placeholder version",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_placeholder_versions(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "renamed crates have no dependents",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_publish_as(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "within max-package-size",
+        blocking: true,
+        // Listing every check is the point, so pay for the `cargo package`
+        scope: Scope::Package(|cx, pkg| {
+            super::verify_package_size(
+                cx.ws_meta,
+                std::slice::from_ref(pkg),
+                true,
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "replacements apply",
+        blocking: true,
+        scope: Scope::Workspace(|cx| super::verify_replacements(cx.pkgs, true, log::Level::Warn)),
+    },
+    Check {
+        name: "package content lists",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_package_content(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "package metadata",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_metadata(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "toolchain meets rust-version",
+        blocking: false,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_rust_version(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "changelog updated",
+        blocking: false,
+        scope: Scope::Package(|cx, pkg| {
+            super::verify_changelog(
+                cx.ws_meta,
+                std::slice::from_ref(pkg),
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "cargo reads inherited fields",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_manifest_inheritance(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "path dependencies exist",
+        blocking: true,
+        scope: Scope::Package(|cx, pkg| {
+            super::verify_path_dependencies(
+                cx.ws_meta,
+                std::slice::from_ref(pkg),
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "registry defined",
+        blocking: true,
+        scope: Scope::Package(|cx, pkg| {
+            super::verify_registries(cx.root(), std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "online to publish",
+        blocking: true,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_online_publish(
+                std::slice::from_ref(pkg),
+                crate::ops::cargo::is_offline(),
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "registry token found",
+        blocking: false,
+        scope: Scope::Package(|_cx, pkg| {
+            super::verify_credentials(std::slice::from_ref(pkg), true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "GitHub release possible",
+        blocking: true,
+        scope: Scope::Workspace(|cx| {
+            super::github::verify_github_release(
+                cx.ws_config,
+                cx.ws_meta,
+                cx.pkgs,
+                None,
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+    Check {
+        name: "crates.io rate limit",
+        blocking: true,
+        scope: Scope::Workspace(|cx| {
+            super::verify_rate_limit(cx.pkgs, cx.index, true, log::Level::Warn)
+        }),
+    },
+    Check {
+        name: "configured owners present",
+        blocking: true,
+        scope: Scope::Package(|cx, pkg| {
+            super::owner::verify_owners(
+                std::slice::from_ref(pkg),
+                cx.index,
+                &mut Default::default(),
+                &crate::ops::cargo::CargoOwners,
+                true,
+                log::Level::Warn,
+            )
+        }),
+    },
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CHECKS: &[Check] = &[
+        Check {
+            name: "passes",
+            blocking: true,
+            scope: Scope::Workspace(|_cx| Ok(true)),
+        },
+        Check {
+            name: "warns",
+            blocking: false,
+            scope: Scope::Workspace(|_cx| {
+                let _ = crate::ops::reporter::warn("something is off");
+                Ok(true)
+            }),
+        },
+        Check {
+            name: "per package",
+            blocking: true,
+            scope: Scope::Package(|_cx, pkg| {
+                if pkg.meta.name == "b" {
+                    let _ = crate::ops::reporter::warn("b is off");
+                }
+                Ok(true)
+            }),
+        },
+    ];
+
+    fn checklist(checks: &[Check]) -> (String, bool) {
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let (ws_meta, pkgs) = plan::fixture::load("tests/fixtures/pure_ws", &args);
+        let pkgs: Vec<_> = pkgs.into_values().collect();
+        let ws_config = crate::config::Config::from_defaults();
+        let index = crate::ops::index::CratesIoIndex::unavailable();
+        let context = Context {
+            ws_meta: &ws_meta,
+            ws_config: &ws_config,
+            pkgs: &pkgs,
+            index: &index,
+        };
+
+        let mut out = Vec::new();
+        let blocked = run_checks(&context, checks, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), blocked)
+    }
+
+    #[test]
+    fn blocking_failure() {
+        let (out, blocked) = checklist(CHECKS);
+        assert_eq!(
+            out,
+            "\
+[x] passes
+[!] warns
+[x] per package (a)
+[ ] per package (b)
+[x] per package (c)
+"
+        );
+        assert!(blocked);
+    }
+
+    #[test]
+    fn only_warnings() {
+        let (out, blocked) = checklist(&CHECKS[..2]);
+        assert_eq!(out, "[x] passes\n[!] warns\n");
+        assert!(!blocked);
+    }
+}
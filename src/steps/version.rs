@@ -1,10 +1,13 @@
 use crate::error::CargoResult;
 use crate::error::CliError;
 use crate::ops::git;
+use crate::ops::overlay::Overlay;
+use crate::ops::replace::{Template, NOW};
 use crate::steps::plan;
 
 /// Bump crate versions
 #[derive(Debug, Clone, clap::Args)]
+#[command(mut_arg("package", |arg| arg.value_parser(super::PackageVersionSpecParser)))]
 pub struct VersionStep {
     #[command(flatten)]
     manifest: clap_cargo::Manifest,
@@ -12,51 +15,71 @@ pub struct VersionStep {
     #[command(flatten)]
     workspace: clap_cargo::Workspace,
 
-    /// Custom config file
-    #[arg(short, long = "config")]
-    custom_config: Option<std::path::PathBuf>,
-
-    /// Ignore implicit configuration files.
-    #[arg(long)]
-    isolated: bool,
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
 
     /// Comma-separated globs of branch names a release can happen from
     #[arg(long, value_delimiter = ',')]
     allow_branch: Option<Vec<String>>,
 
-    /// Actually perform a release. Dry-run mode is the default
-    #[arg(short = 'x', long)]
-    execute: bool,
+    /// Release packages whose version is a placeholder, like `0.0.0`
+    #[arg(long)]
+    allow_placeholder_versions: bool,
 
-    #[arg(short = 'n', long, conflicts_with = "execute", hide = true)]
-    dry_run: bool,
+    #[command(flatten)]
+    execute: super::ExecuteArgs,
 
     /// Skip release confirmation and version preview
     #[arg(long)]
     no_confirm: bool,
 
+    /// Check whether the branch is behind the push remote, which requires a `git fetch`
+    #[arg(long)]
+    check_remote: bool,
+
     /// Either bump by LEVEL or set the VERSION for all selected packages
-    #[arg(value_name = "LEVEL|VERSION", help_heading = "Version")]
-    level_or_version: super::TargetVersion,
+    #[arg(
+        value_name = "LEVEL|VERSION",
+        help_heading = "Version",
+        required_unless_present_any = ["sync_dependents_only", "package"]
+    )]
+    level_or_version: Option<super::TargetVersion>,
 
     /// Semver metadata
     #[arg(short, long, help_heading = "Version")]
     metadata: Option<String>,
 
+    /// Only bump packages changed since their prior tag, and dependents whose requirements change
+    #[arg(
+        long,
+        help_heading = "Version",
+        conflicts_with = "sync_dependents_only"
+    )]
+    changed_only: bool,
+
     /// The name of tag for the previous release.
     #[arg(long, value_name = "NAME", help_heading = "Version")]
     prev_tag_name: Option<String>,
+
+    /// Only raise workspace requirements that lag behind their dependency's current version
+    #[arg(
+        long,
+        help_heading = "Version",
+        conflicts_with_all = ["level_or_version", "metadata", "prev_tag_name"]
+    )]
+    sync_dependents_only: bool,
+
+    /// Commit the changes with `pre-release-commit-message`, or `sync-commit-message` for
+    /// `--sync-dependents-only`
+    #[arg(long, help_heading = "Version")]
+    commit: bool,
 }
 
 impl VersionStep {
     pub fn run(&self) -> Result<(), CliError> {
+        self.execute.check()?;
         git::git_version()?;
 
-        if self.dry_run {
-            let _ =
-                crate::ops::shell::warn("`--dry-run` is superfluous, dry-run is done by default");
-        }
-
         let ws_meta = self
             .manifest
             .metadata()
@@ -65,6 +88,22 @@ impl VersionStep {
             .exec()?;
         let config = self.to_config();
         let ws_config = crate::config::load_workspace_config(&config, &ws_meta)?;
+        if self.sync_dependents_only {
+            return self.sync(&ws_meta, &ws_config);
+        }
+        let (workspace, package_targets) = match super::split_package_targets(&self.workspace) {
+            Ok(split) => split,
+            Err(err) => {
+                let _ = crate::ops::reporter::error(err);
+                return Err(2.into());
+            }
+        };
+        if self.level_or_version.is_none() && package_targets.is_empty() {
+            let _ = crate::ops::reporter::error(
+                "no version given; pass `LEVEL|VERSION` or `--package NAME@LEVEL|VERSION`",
+            );
+            return Err(2.into());
+        }
         let mut pkgs = plan::load(&config, &ws_meta)?;
 
         for pkg in pkgs.values_mut() {
@@ -72,13 +111,23 @@ impl VersionStep {
                 // Trust the user that the tag passed in is the latest tag for the workspace and that
                 // they don't care about any changes from before this tag.
                 pkg.set_prior_tag(prev_tag.to_owned());
+                pkg.detect_changes(&ws_meta);
+            }
+            if workspace.package.contains(&pkg.meta.name) {
+                // Explicitly selected packages are released even when unchanged
+                pkg.changed = Some(true);
             }
             if pkg.config.release() {
-                pkg.bump(&self.level_or_version, self.metadata.as_deref())?;
+                let level_or_version = package_targets
+                    .get(&pkg.meta.name)
+                    .or(self.level_or_version.as_ref());
+                if let Some(level_or_version) = level_or_version {
+                    pkg.bump(level_or_version, self.metadata.as_deref())?;
+                }
             }
         }
 
-        let (_selected_pkgs, excluded_pkgs) = self.workspace.partition_packages(&ws_meta);
+        let (_selected_pkgs, excluded_pkgs) = workspace.partition_packages(&ws_meta);
         for excluded_pkg in excluded_pkgs {
             let pkg = if let Some(pkg) = pkgs.get_mut(&excluded_pkg.id) {
                 pkg
@@ -91,59 +140,163 @@ impl VersionStep {
             }
 
             pkg.planned_version = None;
-            pkg.config.release = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
+        }
+
+        if self.changed_only {
+            plan::skip_unchanged(&ws_meta, &mut pkgs);
         }
 
         let pkgs = plan::plan(pkgs)?;
 
-        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+        let (mut selected_pkgs, mut excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
             .into_iter()
             .map(|(_, pkg)| pkg)
             .partition(|p| p.config.release());
-        if selected_pkgs.is_empty() {
-            let _ = crate::ops::shell::error("no packages selected");
-            return Err(2.into());
-        }
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
 
-        let dry_run = !self.execute;
+        let dry_run = self.execute.dry_run();
         let mut failed = false;
 
         // STEP 0: Help the user make the right decisions.
+        // Committing would sweep up unrelated changes
         failed |= !super::verify_git_is_clean(
             ws_meta.workspace_root.as_std_path(),
+            &ws_config,
             dry_run,
-            log::Level::Warn,
+            if self.commit {
+                log::Level::Error
+            } else {
+                log::Level::Warn
+            },
         )?;
 
         failed |=
             !super::verify_monotonically_increasing(&selected_pkgs, dry_run, log::Level::Error)?;
 
+        failed |= !super::verify_placeholder_versions(&selected_pkgs, dry_run, log::Level::Error)?;
+
+        failed |=
+            !super::verify_path_dependencies(&ws_meta, &selected_pkgs, dry_run, log::Level::Error)?;
+
         super::warn_changed(&ws_meta, &selected_pkgs)?;
 
         failed |= !super::verify_git_branch(
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
+            &selected_pkgs,
             dry_run,
             log::Level::Warn,
         )?;
 
-        failed |= !super::verify_if_behind(
+        failed |= !super::verify_if_behind_when_asked(
+            self.check_remote,
             ws_meta.workspace_root.as_std_path(),
             &ws_config,
             dry_run,
-            log::Level::Warn,
         )?;
 
         // STEP 1: Release Confirmation
         super::confirm("Bump", &selected_pkgs, self.no_confirm, dry_run)?;
 
         // STEP 2: update current version, save and commit
-        let update_lock = update_versions(&ws_meta, &selected_pkgs, &excluded_pkgs, dry_run)?;
-        if update_lock {
+        let per_package_commits =
+            self.commit && !super::consolidate_commits(&selected_pkgs, &excluded_pkgs)?;
+        let mut overlay = Overlay::default();
+        if per_package_commits {
+            for pkg in &mut selected_pkgs {
+                update_package_version(&ws_meta, pkg, &mut overlay, dry_run)?;
+                let paths =
+                    super::commit::release_paths(&ws_meta, &ws_config, std::slice::from_ref(pkg))?;
+                super::commit::pkg_commit(pkg, &paths, dry_run)?;
+            }
+        } else {
+            let update_lock = update_versions(
+                &ws_meta,
+                &mut selected_pkgs,
+                &mut excluded_pkgs,
+                &mut overlay,
+                dry_run,
+            )?;
+            if update_lock {
+                log::debug!("Updating lock file");
+                if !dry_run {
+                    let workspace_path = ws_meta.workspace_root.as_std_path().join("Cargo.toml");
+                    crate::ops::cargo::update_lock(&workspace_path)?;
+                }
+            }
+            if self.commit {
+                let paths = super::commit::release_paths(&ws_meta, &ws_config, &selected_pkgs)?;
+                super::commit::workspace_commit(
+                    &ws_meta,
+                    &ws_config,
+                    &selected_pkgs,
+                    &paths,
+                    dry_run,
+                )?;
+            }
+        }
+
+        super::summarize(&selected_pkgs);
+        super::report_skipped(&excluded_pkgs);
+        super::finish(failed, dry_run)
+    }
+
+    /// Fix drifted requirements between workspace members, without bumping any version
+    fn sync(
+        &self,
+        ws_meta: &cargo_metadata::Metadata,
+        ws_config: &crate::config::Config,
+    ) -> Result<(), CliError> {
+        let dry_run = self.execute.dry_run();
+        let mut failed = false;
+
+        // Committing would sweep up unrelated changes
+        failed |= !super::verify_git_is_clean(
+            ws_meta.workspace_root.as_std_path(),
+            ws_config,
+            dry_run,
+            if self.commit {
+                log::Level::Error
+            } else {
+                log::Level::Warn
+            },
+        )?;
+
+        let updates = sync_dependent_versions(ws_meta, &mut Overlay::default(), dry_run)?;
+        if updates.is_empty() {
+            let _ = crate::ops::reporter::note(
+                "workspace requirements already match their dependencies' versions",
+            );
+            return super::finish(failed, dry_run);
+        }
+        if !dry_run {
             log::debug!("Updating lock file");
-            if !dry_run {
-                let workspace_path = ws_meta.workspace_root.as_std_path().join("Cargo.toml");
-                crate::ops::cargo::update_lock(&workspace_path)?;
+            let workspace_path = ws_meta.workspace_root.as_std_path().join("Cargo.toml");
+            crate::ops::cargo::update_lock(&workspace_path)?;
+        }
+
+        if self.commit {
+            let _ = crate::ops::reporter::phase_start("commit");
+            let dependency_updates_var = crate::ops::cargo::render_dependency_updates(&updates);
+            let template = Template {
+                date: Some(NOW.as_str()),
+                dependency_updates: Some(dependency_updates_var.as_str()),
+                vars: ws_config.template_vars(),
+                ..Default::default()
+            };
+            let commit_msg = template.render(ws_config.sync_commit_message());
+            let paths = super::commit::release_paths(ws_meta, ws_config, &[])?;
+            if !git::commit_paths(
+                ws_meta.workspace_root.as_std_path(),
+                &paths,
+                &commit_msg,
+                ws_config.sign_commit(),
+                ws_config.no_verify_git_hooks(),
+                dry_run,
+            )? {
+                // commit failed, abort
+                return Err(101.into());
             }
         }
 
@@ -152,9 +305,10 @@ impl VersionStep {
 
     fn to_config(&self) -> crate::config::ConfigArgs {
         crate::config::ConfigArgs {
-            custom_config: self.custom_config.clone(),
-            isolated: self.isolated,
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
             allow_branch: self.allow_branch.clone(),
+            allow_placeholder_versions: self.allow_placeholder_versions,
             ..Default::default()
         }
     }
@@ -165,36 +319,170 @@ pub fn changed_since(
     pkg: &plan::PackageRelease,
     since_ref: &str,
 ) -> Option<Vec<std::path::PathBuf>> {
-    let changed_root = if pkg.bin {
+    let outside_root = match pkg
+        .content
+        .with(|files| files.iter().any(|p| !p.starts_with(&pkg.package_root)))
+    {
+        Ok(outside_root) => outside_root,
+        Err(err) => {
+            log::debug!("failed to list {}: {:#}", pkg.meta.name, err);
+            return None;
+        }
+    };
+    let changed_root = if pkg.bin || outside_root {
         ws_meta.workspace_root.as_std_path()
     } else {
-        // Limit our lookup since we don't need to check for `Cargo.lock`
+        // Limit our lookup since we don't need to check for `Cargo.lock` or symlink targets
         &pkg.package_root
     };
     let changed = git::changed_files(changed_root, since_ref).ok().flatten()?;
+    let ignored = ignored_changes(pkg);
     let changed: Vec<_> = changed
         .into_iter()
-        .filter(|p| pkg.package_content.contains(p))
+        .filter(|p| pkg.owns_path(p))
+        .filter(|p| !is_ignored_change(&ignored, &pkg.package_root, p))
         .collect();
 
     Some(changed)
 }
 
+/// `certify-changes-ignore`, matched like `allow-branch`
+fn ignored_changes(pkg: &plan::PackageRelease) -> ignore::gitignore::Gitignore {
+    let mut ignored = ignore::gitignore::GitignoreBuilder::new(".");
+    for pattern in pkg.config.certify_changes_ignore() {
+        // Invalid globs are reported by `Config::validate`
+        if let Err(err) = ignored.add_line(None, pattern) {
+            log::debug!(
+                "ignoring `certify-changes-ignore` glob {}: {}",
+                pattern,
+                err
+            );
+        }
+    }
+    ignored.build().unwrap_or_else(|err| {
+        log::debug!("ignoring `certify-changes-ignore`: {}", err);
+        ignore::gitignore::Gitignore::empty()
+    })
+}
+
+fn is_ignored_change(
+    ignored: &ignore::gitignore::Gitignore,
+    package_root: &std::path::Path,
+    path: &std::path::Path,
+) -> bool {
+    match path.strip_prefix(package_root) {
+        Ok(relative) => {
+            let is_ignored = ignored
+                .matched_path_or_any_parents(relative, false)
+                .is_ignore();
+            if is_ignored {
+                log::trace!("{} matched `certify-changes-ignore`", path.display());
+            }
+            is_ignored
+        }
+        // Like `Cargo.lock` for a binary, outside of what the globs are relative to
+        Err(_) => false,
+    }
+}
+
+/// Write the planned version of `pkg` alone, for committing each package separately
+pub fn update_package_version(
+    ws_meta: &cargo_metadata::Metadata,
+    pkg: &mut plan::PackageRelease,
+    overlay: &mut Overlay,
+    dry_run: bool,
+) -> CargoResult<()> {
+    if let Some(version) = pkg.planned_version.clone() {
+        let crate_name = pkg.meta.name.as_str();
+        let _ = crate::ops::reporter::package(
+            "Upgrading",
+            crate_name,
+            format!(
+                "from {} to {}",
+                pkg.initial_version.full_version_string, version.full_version_string
+            ),
+        );
+        crate::ops::cargo::set_package_version(
+            &pkg.manifest_path,
+            version.full_version_string.as_str(),
+            overlay,
+            dry_run,
+        )?;
+        pkg.content.invalidate();
+        pkg.dependency_updates =
+            update_dependent_versions(ws_meta, pkg, &version, overlay, dry_run)?;
+        if dry_run {
+            log::debug!("updating lock file");
+        } else {
+            crate::ops::cargo::update_lock(&pkg.manifest_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the planned versions, restoring every manifest if any write fails
+///
+/// Without this, a failure part way through a shared-version group leaves the group with mixed
+/// versions.
 pub fn update_versions(
     ws_meta: &cargo_metadata::Metadata,
-    selected_pkgs: &[plan::PackageRelease],
-    excluded_pkgs: &[plan::PackageRelease],
+    selected_pkgs: &mut [plan::PackageRelease],
+    excluded_pkgs: &mut [plan::PackageRelease],
+    overlay: &mut Overlay,
+    dry_run: bool,
+) -> CargoResult<bool> {
+    let _ = crate::ops::reporter::phase_start("version");
+    if dry_run {
+        return apply_versions(ws_meta, selected_pkgs, excluded_pkgs, overlay, dry_run);
+    }
+
+    let workspace_path = ws_meta.workspace_root.as_std_path().join("Cargo.toml");
+    let snapshot = Snapshot::take(
+        std::iter::once(workspace_path.clone())
+            .chain(find_ws_members(ws_meta).map(|p| p.manifest_path.as_std_path().to_owned())),
+    )?;
+    for pkg in selected_pkgs.iter().filter(|p| p.planned_version.is_some()) {
+        if inherits_version(pkg)? {
+            check_writable(&workspace_path)?;
+        } else {
+            check_writable(&pkg.manifest_path)?;
+        }
+    }
+
+    match apply_versions(ws_meta, selected_pkgs, excluded_pkgs, overlay, dry_run) {
+        Ok(changed) => Ok(changed),
+        Err(err) => {
+            match snapshot.restore() {
+                Ok(()) => {
+                    let _ =
+                        crate::ops::reporter::note("restored manifests to their prior versions");
+                }
+                Err(restore_err) => {
+                    let _ = crate::ops::reporter::error(format!(
+                        "failed to restore manifests to their prior versions: {:#}",
+                        restore_err
+                    ));
+                }
+            }
+            Err(err)
+        }
+    }
+}
+
+fn apply_versions(
+    ws_meta: &cargo_metadata::Metadata,
+    selected_pkgs: &mut [plan::PackageRelease],
+    excluded_pkgs: &mut [plan::PackageRelease],
+    overlay: &mut Overlay,
     dry_run: bool,
 ) -> CargoResult<bool> {
     let mut changed = false;
 
-    let workspace_version = selected_pkgs
-        .iter()
-        .filter(|p| p.config.shared_version() == Some(crate::config::SharedVersion::WORKSPACE))
-        .find_map(|p| p.planned_version.clone());
+    let workspace_version = workspace_version(selected_pkgs, excluded_pkgs)?;
 
     if let Some(workspace_version) = &workspace_version {
-        let _ = crate::ops::shell::status(
+        let _ = crate::ops::reporter::status(
             "Upgrading",
             format!(
                 "workspace to version {}",
@@ -205,6 +493,7 @@ pub fn update_versions(
         crate::ops::cargo::set_workspace_version(
             &workspace_path,
             workspace_version.full_version_string.as_str(),
+            overlay,
             dry_run,
         )?;
         // Deferring `update_dependent_versions` to the per-package logic
@@ -212,51 +501,51 @@ pub fn update_versions(
     }
 
     for (selected, pkg) in selected_pkgs
-        .iter()
+        .iter_mut()
         .map(|s| (true, s))
-        .chain(excluded_pkgs.iter().map(|s| (false, s)))
+        .chain(excluded_pkgs.iter_mut().map(|s| (false, s)))
     {
-        let is_inherited =
-            pkg.config.shared_version() == Some(crate::config::SharedVersion::WORKSPACE);
+        let is_inherited = inherits_version(pkg)?;
         let planned_version = if is_inherited {
-            workspace_version.as_ref()
-        } else if let Some(version) = pkg.planned_version.as_ref() {
+            workspace_version.clone()
+        } else if let Some(version) = pkg.planned_version.clone() {
             assert!(selected);
             Some(version)
         } else {
             None
         };
 
-        if let Some(version) = planned_version {
+        if let Some(version) = &planned_version {
             if is_inherited {
                 let crate_name = pkg.meta.name.as_str();
-                let _ = crate::ops::shell::status(
+                let _ = crate::ops::reporter::package(
                     "Upgrading",
+                    crate_name,
                     format!(
-                        "{} from {} to {} (inherited from workspace)",
-                        crate_name,
-                        pkg.initial_version.full_version_string,
-                        version.full_version_string
+                        "from {} to {} (inherited from workspace)",
+                        pkg.initial_version.full_version_string, version.full_version_string
                     ),
                 );
             } else {
                 let crate_name = pkg.meta.name.as_str();
-                let _ = crate::ops::shell::status(
+                let _ = crate::ops::reporter::package(
                     "Upgrading",
+                    crate_name,
                     format!(
-                        "{} from {} to {}",
-                        crate_name,
-                        pkg.initial_version.full_version_string,
-                        version.full_version_string
+                        "from {} to {}",
+                        pkg.initial_version.full_version_string, version.full_version_string
                     ),
                 );
                 crate::ops::cargo::set_package_version(
                     &pkg.manifest_path,
                     version.full_version_string.as_str(),
+                    overlay,
                     dry_run,
                 )?;
             }
-            update_dependent_versions(ws_meta, pkg, version, dry_run)?;
+            pkg.content.invalidate();
+            pkg.dependency_updates =
+                update_dependent_versions(ws_meta, pkg, version, overlay, dry_run)?;
             changed = true;
         }
     }
@@ -264,44 +553,245 @@ pub fn update_versions(
     Ok(changed)
 }
 
+/// Whether `pkg` takes its version from `[workspace.package]`
+///
+/// This is read from the manifest rather than `shared-version`, as a group can mix members that
+/// inherit the version with members that declare their own.
+fn inherits_version(pkg: &plan::PackageRelease) -> CargoResult<bool> {
+    let keys = crate::config::inherited_keys(&pkg.manifest_path)?;
+    Ok(keys.iter().any(|k| k == "package.version"))
+}
+
+/// The version for `[workspace.package]`, when a member inheriting it is being released
+///
+/// Members sharing a `shared-version` group with an inheriting member set the version too, so a
+/// group with self-versioned members still moves the workspace version along with theirs.
+fn workspace_version(
+    selected_pkgs: &[plan::PackageRelease],
+    excluded_pkgs: &[plan::PackageRelease],
+) -> CargoResult<Option<plan::Version>> {
+    let mut inheriting_groups = std::collections::HashSet::new();
+    for pkg in selected_pkgs.iter().chain(excluded_pkgs) {
+        if inherits_version(pkg)? {
+            inheriting_groups.insert(pkg.config.shared_version());
+        }
+    }
+    if inheriting_groups.is_empty() {
+        return Ok(None);
+    }
+
+    let mut targets: Vec<(&str, &plan::Version)> = Vec::new();
+    for pkg in selected_pkgs {
+        let version = match pkg.planned_version.as_ref() {
+            Some(version) => version,
+            None => continue,
+        };
+        let in_group = pkg.config.shared_version().is_some()
+            && inheriting_groups.contains(&pkg.config.shared_version());
+        if in_group || inherits_version(pkg)? {
+            targets.push((pkg.meta.name.as_str(), version));
+        }
+    }
+    let (_, first) = match targets.first() {
+        Some(first) => *first,
+        None => return Ok(None),
+    };
+    if targets
+        .iter()
+        .any(|(_, v)| v.full_version != first.full_version)
+    {
+        anyhow::bail!(
+            "members sharing `workspace.package.version` are planned for different versions: {}",
+            targets
+                .iter()
+                .map(|(name, v)| format!("{} {}", name, v.full_version_string))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(Some(first.clone()))
+}
+
 pub fn update_dependent_versions(
     ws_meta: &cargo_metadata::Metadata,
     pkg: &plan::PackageRelease,
     version: &plan::Version,
+    overlay: &mut Overlay,
     dry_run: bool,
-) -> CargoResult<()> {
-    // This is redundant with iterating over `workspace_members`
-    // - As `find_dependency_tables` returns workspace dependencies
-    // - If there is a root package
+) -> CargoResult<Vec<crate::ops::cargo::DependencyUpdate>> {
+    if let Some(warning) = pin_stability_warning(pkg, version) {
+        let _ = crate::ops::reporter::warn(warning);
+    }
+    upgrade_dependents(
+        ws_meta,
+        &pkg.package_root,
+        &pkg.meta.name,
+        &version.full_version,
+        pkg.config.dependent_version(),
+        overlay,
+        dry_run,
+    )
+}
+
+/// Workspace members pinning `pkg` with `=` suggest crates outside the workspace do too, which a
+/// patch release would break
+fn pin_stability_warning(pkg: &plan::PackageRelease, version: &plan::Version) -> Option<String> {
+    if !pkg.config.pin_stability_warning() {
+        return None;
+    }
+    let old = &pkg.initial_version.full_version;
+    let new = &version.full_version;
+    let is_patch = old.major == new.major
+        && old.minor == new.minor
+        && old.pre.is_empty()
+        && new.pre.is_empty();
+    if !is_patch {
+        return None;
+    }
+
+    let pinned: Vec<_> = pkg
+        .dependents
+        .iter()
+        .filter(|dep| is_exact_pin(&dep.req, old))
+        .map(|dep| dep.pkg.name.as_str())
+        .collect();
+    if pinned.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{} {} is a patch release but {} pinned `={}`; crates outside the workspace pinning it the same way will need a coordinated update",
+        pkg.meta.name,
+        version.full_version_string,
+        pinned.join(", "),
+        pkg.initial_version.full_version_string
+    ))
+}
+
+fn is_exact_pin(req: &semver::VersionReq, version: &semver::Version) -> bool {
+    match req.comparators.as_slice() {
+        [comparator] => {
+            comparator.op == semver::Op::Exact && comparator.patch.is_some() && req.matches(version)
+        }
+        _ => false,
+    }
+}
+
+/// Raise every workspace requirement that lags behind the current version of the member it
+/// depends on
+pub fn sync_dependent_versions(
+    ws_meta: &cargo_metadata::Metadata,
+    overlay: &mut Overlay,
+    dry_run: bool,
+) -> CargoResult<Vec<crate::ops::cargo::DependencyUpdate>> {
+    let mut updates = Vec::new();
+    for member in find_ws_members(ws_meta) {
+        let package_root = member
+            .manifest_path
+            .parent()
+            .expect("always at least a parent dir")
+            .as_std_path();
+        updates.extend(upgrade_dependents(
+            ws_meta,
+            package_root,
+            &member.name,
+            &member.version,
+            // Cargo refuses requirements a path dependency doesn't satisfy, so drift is always a
+            // requirement lagging behind the current version
+            crate::config::DependentVersion::Upgrade,
+            overlay,
+            dry_run,
+        )?);
+    }
+    Ok(updates)
+}
+
+fn upgrade_dependents(
+    ws_meta: &cargo_metadata::Metadata,
+    package_root: &std::path::Path,
+    name: &str,
+    version: &semver::Version,
+    dependent_version: crate::config::DependentVersion,
+    overlay: &mut Overlay,
+    dry_run: bool,
+) -> CargoResult<Vec<crate::ops::cargo::DependencyUpdate>> {
+    let mut updates = Vec::new();
+
+    // This covers `workspace.dependencies`, which members inheriting a dependency with
+    // `workspace = true` rely on, along with a root package's own dependencies.
     //
-    // But split this out for
+    // Split this out for
     // - Virtual manifests
     // - Nicer message to the user
+    let workspace_path = ws_meta.workspace_root.as_std_path().join("Cargo.toml");
     {
-        let workspace_path = ws_meta.workspace_root.as_std_path().join("Cargo.toml");
-        crate::ops::cargo::upgrade_dependency_req(
+        updates.extend(crate::ops::cargo::upgrade_dependency_req(
             "workspace",
             &workspace_path,
-            &pkg.package_root,
-            &pkg.meta.name,
-            &version.full_version,
-            pkg.config.dependent_version(),
+            package_root,
+            name,
+            version,
+            dependent_version,
+            overlay,
             dry_run,
-        )?;
+        )?);
     }
 
-    for dep in find_ws_members(ws_meta) {
-        crate::ops::cargo::upgrade_dependency_req(
+    // Skip the root package so `workspace.dependencies` is only updated once
+    for dep in find_ws_members(ws_meta).filter(|dep| {
+        !crate::ops::path::same_path(dep.manifest_path.as_std_path(), &workspace_path)
+    }) {
+        updates.extend(crate::ops::cargo::upgrade_dependency_req(
             &dep.name,
             dep.manifest_path.as_std_path(),
-            &pkg.package_root,
-            &pkg.meta.name,
-            &version.full_version,
-            pkg.config.dependent_version(),
+            package_root,
+            name,
+            version,
+            dependent_version,
+            overlay,
             dry_run,
-        )?;
+        )?);
     }
 
+    Ok(updates)
+}
+
+/// Manifest content from before a version bump
+struct Snapshot {
+    manifests: Vec<(std::path::PathBuf, String)>,
+}
+
+impl Snapshot {
+    fn take(paths: impl IntoIterator<Item = std::path::PathBuf>) -> CargoResult<Self> {
+        let mut manifests = Vec::new();
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)?;
+            manifests.push((path, content));
+        }
+        Ok(Self { manifests })
+    }
+
+    /// Write back every manifest that no longer has its original content
+    fn restore(&self) -> CargoResult<()> {
+        for (path, original) in &self.manifests {
+            let current = std::fs::read_to_string(path)?;
+            if current != *original {
+                log::debug!("restoring {}", path.display());
+                crate::ops::path::write(path, original)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fail before the first write, rather than part way through
+fn check_writable(path: &std::path::Path) -> CargoResult<()> {
+    let metadata = std::fs::metadata(path)?;
+    if metadata.permissions().readonly() {
+        anyhow::bail!("{} is read-only, no versions were changed", path.display());
+    }
     Ok(())
 }
 
@@ -315,3 +805,665 @@ fn find_ws_members(
         .iter()
         .filter(move |p| workspace_members.contains(&p.id))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use crate::ops::reporter::Reporter;
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[allow(dead_code)] // Fields are only compared and printed
+    enum Event {
+        Phase(String),
+        Package {
+            action: String,
+            package: String,
+            message: String,
+        },
+        Status(String, String),
+        Warning(String),
+        Error(String),
+        Note(String),
+        Command(Vec<String>),
+    }
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<Event>>>);
+
+    impl Recorder {
+        fn record(&self, event: Event) -> CargoResult<()> {
+            self.0.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    impl Reporter for Recorder {
+        fn on_phase_start(&self, phase: &str) -> CargoResult<()> {
+            self.record(Event::Phase(phase.to_owned()))
+        }
+
+        fn on_package_phase(&self, action: &str, package: &str, message: &str) -> CargoResult<()> {
+            self.record(Event::Package {
+                action: action.to_owned(),
+                package: package.to_owned(),
+                message: message.to_owned(),
+            })
+        }
+
+        fn on_status(&self, action: &str, message: &str) -> CargoResult<()> {
+            self.record(Event::Status(action.to_owned(), message.to_owned()))
+        }
+
+        fn on_warning(&self, message: &str) -> CargoResult<()> {
+            self.record(Event::Warning(message.to_owned()))
+        }
+
+        fn on_error(&self, message: &str) -> CargoResult<()> {
+            self.record(Event::Error(message.to_owned()))
+        }
+
+        fn on_note(&self, message: &str) -> CargoResult<()> {
+            self.record(Event::Note(message.to_owned()))
+        }
+
+        fn on_command(&self, command: &[String], _dry_run: bool) -> CargoResult<()> {
+            self.record(Event::Command(command.to_owned()))
+        }
+    }
+
+    #[derive(Debug, clap::Parser)]
+    struct Cli {
+        #[command(flatten)]
+        step: VersionStep,
+    }
+
+    #[test]
+    fn reports_dry_run() {
+        let recorder = Recorder::default();
+        let _guard = crate::ops::reporter::set_test_reporter(recorder.clone());
+
+        let cli = <Cli as clap::Parser>::parse_from([
+            "cargo-release",
+            "--manifest-path",
+            "tests/fixtures/simple/Cargo.toml",
+            "--isolated",
+            "minor",
+        ]);
+        cli.step.run().unwrap();
+
+        let events = recorder.0.lock().unwrap();
+        assert!(
+            events.contains(&Event::Phase("version".to_owned())),
+            "{:#?}",
+            events
+        );
+        assert!(
+            events.contains(&Event::Package {
+                action: "Upgrading".to_owned(),
+                package: "simple".to_owned(),
+                message: "from 0.1.0 to 0.2.0".to_owned(),
+            }),
+            "{:#?}",
+            events
+        );
+        assert!(
+            events.contains(&Event::Warning(
+                "aborting release due to dry run; re-run with `--execute`".to_owned()
+            )),
+            "{:#?}",
+            events
+        );
+    }
+
+    mod pin_stability {
+        use super::*;
+
+        use assert_fs::prelude::*;
+
+        /// `pure_ws` where `b` pins `a` like `serde` pins `serde_derive`
+        fn load(pin_stability_warning: bool) -> (assert_fs::TempDir, Vec<plan::PackageRelease>) {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+            temp.child("b/Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "b"
+version = "0.1.0"
+authors = ["Ed Page <eopage@gmail.com>"]
+
+[dependencies]
+a = { path = "../a", version = "=0.1.0" }
+"#,
+                )
+                .unwrap();
+            git2::Repository::init(temp.path()).unwrap();
+            let (_, pkgs) = plan::fixture::load(temp.path(), &Default::default());
+            let mut pkgs: Vec<_> = pkgs.into_values().collect();
+            for pkg in &mut pkgs {
+                pkg.config.pin_stability_warning = Some(pin_stability_warning);
+            }
+            (temp, pkgs)
+        }
+
+        fn find<'p>(pkgs: &'p [plan::PackageRelease], name: &str) -> &'p plan::PackageRelease {
+            pkgs.iter().find(|p| p.meta.name == name).unwrap()
+        }
+
+        fn version(version: &str) -> plan::Version {
+            plan::Version::from(semver::Version::parse(version).unwrap())
+        }
+
+        #[test]
+        fn patch_of_pinned() {
+            let (temp, pkgs) = load(true);
+            assert_eq!(
+                pin_stability_warning(find(&pkgs, "a"), &version("0.1.1")).as_deref(),
+                Some("a 0.1.1 is a patch release but b pinned `=0.1.0`; crates outside the workspace pinning it the same way will need a coordinated update")
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn minor_or_unpinned() {
+            let (temp, pkgs) = load(true);
+            assert_eq!(
+                pin_stability_warning(find(&pkgs, "a"), &version("0.2.0")),
+                None
+            );
+            // `c` depends on `b` through a path alone
+            assert_eq!(
+                pin_stability_warning(find(&pkgs, "b"), &version("0.1.1")),
+                None
+            );
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn disabled() {
+            let (temp, pkgs) = load(false);
+            assert_eq!(
+                pin_stability_warning(find(&pkgs, "a"), &version("0.1.1")),
+                None
+            );
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod transactional {
+        use super::*;
+
+        use std::path::Path;
+
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        /// `pure_ws` with every member in one shared-version group, planned for a minor bump
+        fn workspace() -> (
+            assert_fs::TempDir,
+            cargo_metadata::Metadata,
+            Vec<plan::PackageRelease>,
+        ) {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+            git2::Repository::init(temp.path()).unwrap();
+            let (ws_meta, pkgs) = plan::fixture::planned(temp.path(), &Default::default(), |pkg| {
+                pkg.config.shared_version =
+                    Some(crate::config::SharedVersion::Name("group".to_owned()));
+            });
+            (temp, ws_meta, pkgs)
+        }
+
+        fn assert_unchanged(temp: &assert_fs::TempDir, member: &str) {
+            let manifest = format!("{}/Cargo.toml", member);
+            temp.child(&manifest).assert(predicate::path::eq_file(
+                Path::new("tests/fixtures/pure_ws").join(&manifest),
+            ));
+        }
+
+        #[test]
+        fn read_only_member_aborts_before_writing() {
+            let (temp, ws_meta, mut pkgs) = workspace();
+            let c_manifest = temp.child("c/Cargo.toml");
+            let mut permissions = std::fs::metadata(c_manifest.path()).unwrap().permissions();
+            permissions.set_readonly(true);
+            std::fs::set_permissions(c_manifest.path(), permissions.clone()).unwrap();
+
+            let err = update_versions(&ws_meta, &mut pkgs, &mut [], &mut Overlay::default(), false);
+            assert!(err.is_err());
+            assert_unchanged(&temp, "a");
+            assert_unchanged(&temp, "b");
+
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            std::fs::set_permissions(c_manifest.path(), permissions).unwrap();
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn failed_member_restores_group() {
+            let (temp, ws_meta, mut pkgs) = workspace();
+            // Break `c` after loading so the failure happens part way through the group
+            temp.child("c/Cargo.toml").write_str("[package").unwrap();
+
+            let err = update_versions(&ws_meta, &mut pkgs, &mut [], &mut Overlay::default(), false);
+            assert!(err.is_err());
+            assert_unchanged(&temp, "a");
+            assert_unchanged(&temp, "b");
+            temp.child("Cargo.toml")
+                .assert(predicate::path::eq_file(Path::new(
+                    "tests/fixtures/pure_ws/Cargo.toml",
+                )));
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod inherited_dependencies {
+        use super::*;
+
+        use std::path::Path;
+
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        #[test]
+        fn only_workspace_table_is_upgraded() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            temp.copy_from("tests/fixtures/inherited_ws", &["**"])
+                .unwrap();
+            let args = crate::config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let (ws_meta, pkgs) = plan::fixture::planned(temp.path(), &args, |pkg| {
+                pkg.config.dependent_version = Some(crate::config::DependentVersion::Upgrade);
+                if pkg.meta.name != "a" {
+                    pkg.config.release = Some(false);
+                }
+            });
+            let (mut selected, mut excluded): (Vec<_>, Vec<_>) =
+                pkgs.into_iter().partition(|p| p.config.release());
+
+            update_versions(
+                &ws_meta,
+                &mut selected,
+                &mut excluded,
+                &mut Overlay::default(),
+                false,
+            )
+            .unwrap();
+            let updates: Vec<_> = selected[0]
+                .dependency_updates
+                .iter()
+                .map(|u| u.to_string())
+                .collect();
+            assert_eq!(updates, ["workspace: a 0.1.0 -> 0.2.0"]);
+            temp.child("Cargo.toml").assert(predicate::str::contains(
+                r#"a = { path = "a", version = "0.2.0" }"#,
+            ));
+            temp.child("b/Cargo.toml")
+                .assert(predicate::path::eq_file(Path::new(
+                    "tests/fixtures/inherited_ws/b/Cargo.toml",
+                )));
+            plan::fixture::metadata(temp.path());
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod change_paths {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+        use assert_fs::prelude::*;
+
+        /// A release followed by a commit that only touches an excluded bench
+        fn repo() -> assert_fs::TempDir {
+            let repo = assert_fs::TempDir::new().unwrap();
+            fixture::init(repo.path());
+            repo.child("Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "foo"
+version = "0.1.0"
+exclude = ["benches"]
+
+[lib]
+path = "lib.rs"
+"#,
+                )
+                .unwrap();
+            repo.child("lib.rs").write_str("").unwrap();
+            repo.child("benches/bench.rs").write_str("").unwrap();
+            git(repo.path(), &["add", "Cargo.toml", "lib.rs", "benches"]);
+            git(repo.path(), &["commit", "--quiet", "-m", "Initial"]);
+            git(repo.path(), &["tag", "-a", "v0.1.0", "-m", "v0.1.0"]);
+
+            repo.child("benches/bench.rs")
+                .write_str("fn main() {}")
+                .unwrap();
+            git(repo.path(), &["commit", "--quiet", "-am", "Faster bench"]);
+            repo
+        }
+
+        fn changed(
+            repo: &assert_fs::TempDir,
+            change_paths: crate::config::ChangePaths,
+            ignore: &[&str],
+        ) -> usize {
+            let ws_meta = plan::fixture::metadata(repo.path());
+            let args = crate::config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let root = git::top_level(repo.path()).unwrap();
+            let mut pkg =
+                plan::PackageRelease::load(&args, &root, &ws_meta, &ws_meta.packages[0]).unwrap();
+            pkg.config.change_paths = Some(change_paths);
+            pkg.config.certify_changes_ignore =
+                Some(ignore.iter().map(|p| (*p).to_owned()).collect());
+            changed_since(&ws_meta, &pkg, "v0.1.0").unwrap().len()
+        }
+
+        #[test]
+        fn package_ignores_excluded_bench() {
+            let repo = repo();
+            assert_eq!(changed(&repo, crate::config::ChangePaths::Package, &[]), 0);
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn crate_dir_includes_excluded_bench() {
+            let repo = repo();
+            assert_eq!(changed(&repo, crate::config::ChangePaths::CrateDir, &[]), 1);
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn crate_dir_excludes_nested_members() {
+            let repo = assert_fs::TempDir::new().unwrap();
+            fixture::init(repo.path());
+            repo.child("Cargo.toml")
+                .write_str(
+                    r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[lib]
+path = "lib.rs"
+
+[workspace]
+members = ["nested"]
+"#,
+                )
+                .unwrap();
+            repo.child("lib.rs").write_str("").unwrap();
+            repo.child("nested/Cargo.toml")
+                .write_str("[package]\nname = \"nested\"\nversion = \"0.1.0\"\n\n[lib]\npath = \"lib.rs\"\n")
+                .unwrap();
+            repo.child("nested/lib.rs").write_str("").unwrap();
+            git(repo.path(), &["add", "."]);
+            git(repo.path(), &["commit", "--quiet", "-m", "Initial"]);
+            git(repo.path(), &["tag", "-a", "v0.1.0", "-m", "v0.1.0"]);
+            repo.child("nested/lib.rs")
+                .write_str("pub fn nested() {}")
+                .unwrap();
+            git(repo.path(), &["commit", "--quiet", "-am", "Change nested"]);
+
+            let ws_meta = plan::fixture::metadata(repo.path());
+            let args = crate::config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let root = git::top_level(repo.path()).unwrap();
+            let foo = ws_meta.packages.iter().find(|p| p.name == "foo").unwrap();
+            let mut pkg = plan::PackageRelease::load(&args, &root, &ws_meta, foo).unwrap();
+            pkg.config.change_paths = Some(crate::config::ChangePaths::CrateDir);
+            assert!(pkg.owns_path(&pkg.package_root.join("lib.rs")));
+            assert!(!pkg.owns_path(&pkg.package_root.join("nested/lib.rs")));
+            assert_eq!(changed_since(&ws_meta, &pkg, "v0.1.0").unwrap().len(), 0);
+
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn ignored_changes() {
+            let repo = repo();
+            repo.child("README.md").write_str("# foo").unwrap();
+            git(repo.path(), &["add", "README.md"]);
+            git(repo.path(), &["commit", "--quiet", "-m", "Docs"]);
+
+            let package = crate::config::ChangePaths::Package;
+            assert_eq!(changed(&repo, package, &[]), 1);
+            assert_eq!(changed(&repo, package, &["*.md"]), 0);
+            let crate_dir = crate::config::ChangePaths::CrateDir;
+            assert_eq!(changed(&repo, crate_dir, &["*.md"]), 1);
+            assert_eq!(changed(&repo, crate_dir, &["*.md", "benches/**"]), 0);
+            repo.close().unwrap();
+        }
+
+        #[test]
+        fn relisted_after_bump() {
+            let repo = repo();
+            let ws_meta = plan::fixture::metadata(repo.path());
+            let args = crate::config::ConfigArgs {
+                isolated: true,
+                ..Default::default()
+            };
+            let root = git::top_level(repo.path()).unwrap();
+            let mut pkg =
+                plan::PackageRelease::load(&args, &root, &ws_meta, &ws_meta.packages[0]).unwrap();
+            let is_extra = |p: &std::path::PathBuf| p.ends_with("extra.rs");
+
+            repo.child("extra.rs").write_str("").unwrap();
+            let cached = pkg.package_content().unwrap();
+            assert!(!cached.iter().any(is_extra), "{:?}", cached);
+
+            pkg.planned_version = Some(plan::Version::from(
+                semver::Version::parse("0.2.0").unwrap(),
+            ));
+            let mut pkgs = [pkg];
+            update_versions(&ws_meta, &mut pkgs, &mut [], &mut Overlay::default(), false).unwrap();
+            let relisted = pkgs[0].package_content().unwrap();
+            assert!(relisted.iter().any(is_extra), "{:?}", relisted);
+
+            repo.close().unwrap();
+        }
+    }
+
+    mod commit {
+        use super::*;
+
+        use crate::ops::git::fixture::{self, git};
+        use assert_fs::prelude::*;
+
+        /// `pure_ws`, committed to a fresh repo under `temp`
+        fn repo(temp: &assert_fs::TempDir) -> assert_fs::fixture::ChildPath {
+            let ws = temp.child("ws");
+            ws.copy_from("tests/fixtures/pure_ws", &["**"]).unwrap();
+            fixture::init(ws.path());
+            git(ws.path(), &["add", "."]);
+            git(ws.path(), &["commit", "--quiet", "-m", "initial"]);
+            ws
+        }
+
+        fn run(ws: &assert_fs::fixture::ChildPath, args: &[&str]) {
+            let manifest_path = ws.child("Cargo.toml");
+            let cli = <Cli as clap::Parser>::parse_from(
+                [
+                    "cargo-release",
+                    "--manifest-path",
+                    manifest_path.path().to_str().unwrap(),
+                    "--isolated",
+                    "--execute",
+                    "--no-confirm",
+                    "--commit",
+                ]
+                .iter()
+                .chain(args),
+            );
+            cli.step.run().unwrap();
+        }
+
+        /// Subject and files of each commit since `initial`, oldest first
+        fn commits(ws: &assert_fs::fixture::ChildPath) -> Vec<(String, Vec<String>)> {
+            let log = git(ws.path(), &["log", "--reverse", "--format=%H %s"]);
+            log.lines()
+                .skip(1)
+                .map(|line| {
+                    let (sha, subject) = line.split_once(' ').unwrap();
+                    let files = git(ws.path(), &["show", "--name-only", "--format=", sha]);
+                    (
+                        subject.to_owned(),
+                        files.lines().map(|f| f.to_owned()).collect(),
+                    )
+                })
+                .collect()
+        }
+
+        #[test]
+        fn consolidated() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let ws = repo(&temp);
+
+            run(&ws, &["--package", "a", "patch"]);
+            assert_eq!(
+                commits(&ws),
+                [(
+                    "chore: Release".to_owned(),
+                    vec!["Cargo.lock".to_owned(), "a/Cargo.toml".to_owned()]
+                )]
+            );
+            assert_eq!(git(ws.path(), &["status", "--porcelain"]), "");
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn per_package() {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let ws = repo(&temp);
+            let config = temp.child("release.toml");
+            config.write_str("consolidate-commits = false\n").unwrap();
+
+            run(
+                &ws,
+                &[
+                    "--config",
+                    config.path().to_str().unwrap(),
+                    "--package",
+                    "a",
+                    "--package",
+                    "b",
+                    "patch",
+                ],
+            );
+            assert_eq!(
+                commits(&ws),
+                [
+                    (
+                        "chore: Release a version 0.1.1".to_owned(),
+                        vec!["Cargo.lock".to_owned(), "a/Cargo.toml".to_owned()]
+                    ),
+                    (
+                        "chore: Release b version 0.1.1".to_owned(),
+                        vec!["Cargo.lock".to_owned(), "b/Cargo.toml".to_owned()]
+                    ),
+                ]
+            );
+            assert_eq!(git(ws.path(), &["status", "--porcelain"]), "");
+
+            temp.close().unwrap();
+        }
+    }
+
+    mod partial_inheritance {
+        use super::*;
+
+        use std::path::Path;
+
+        use assert_fs::prelude::*;
+        use predicates::prelude::*;
+
+        use crate::ops::git::fixture::{self, git};
+
+        const FIXTURE: &str = "tests/fixtures/partially_inherited_ws";
+
+        /// `a` and `b` inherit `workspace.package.version` while `c` declares its own, with all
+        /// three in `shared-version` group `group`
+        fn release(group: &str) {
+            let temp = assert_fs::TempDir::new().unwrap();
+            let ws = temp.child("ws");
+            ws.copy_from(FIXTURE, &["**"]).unwrap();
+            fixture::init(ws.path());
+            git(ws.path(), &["add", "."]);
+            git(ws.path(), &["commit", "--quiet", "-m", "initial"]);
+
+            let manifest_path = ws.child("Cargo.toml");
+            let shared_version = format!("shared-version={}", group);
+            let cli = <Cli as clap::Parser>::parse_from([
+                "cargo-release",
+                "--manifest-path",
+                manifest_path.path().to_str().unwrap(),
+                "--isolated",
+                "--execute",
+                "--no-confirm",
+                "--commit",
+                "--config",
+                shared_version.as_str(),
+                "--workspace",
+                "minor",
+            ]);
+            cli.step.run().unwrap();
+
+            ws.child("Cargo.toml")
+                .assert(predicate::str::contains(
+                    "[workspace.package]\nversion = \"0.2.0\"\n",
+                ))
+                .assert(predicate::str::contains(
+                    r#"a = { path = "a", version = "0.2.0" }"#,
+                ));
+            for inheriting in ["a", "b"] {
+                ws.child(inheriting)
+                    .child("Cargo.toml")
+                    .assert(predicate::path::eq_file(
+                        Path::new(FIXTURE).join(inheriting).join("Cargo.toml"),
+                    ));
+            }
+            ws.child("c/Cargo.toml")
+                .assert(predicate::str::contains(
+                    "name = \"c\"\nversion = \"0.2.0\"\n",
+                ))
+                .assert(predicate::str::contains(
+                    r#"b = { path = "../b", version = "0.2.0" }"#,
+                ));
+
+            let log = git(ws.path(), &["log", "--format=%s"]);
+            assert_eq!(log, "chore: Release\ninitial");
+            let files = git(ws.path(), &["show", "--name-only", "--format=", "HEAD"]);
+            assert_eq!(
+                files.lines().collect::<Vec<_>>(),
+                ["Cargo.lock", "Cargo.toml", "c/Cargo.toml"]
+            );
+            assert_eq!(git(ws.path(), &["status", "--porcelain"]), "");
+
+            temp.close().unwrap();
+        }
+
+        #[test]
+        fn self_versioned_in_workspace_group() {
+            release(crate::config::SharedVersion::WORKSPACE);
+        }
+
+        #[test]
+        fn inheriting_in_named_group() {
+            release("default");
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::error::CliError;
+use crate::ops::cargo::{DEFAULT_POLL_INTERVAL, DEFAULT_PUBLISH_TIMEOUT};
+use crate::steps::plan;
+
+/// Wait for the specified packages to appear in the registry index
+///
+/// Pairs with `cargo release publish --no-wait`
+#[derive(Debug, Clone, clap::Args)]
+pub struct WaitStep {
+    #[command(flatten)]
+    manifest: clap_cargo::Manifest,
+
+    #[command(flatten)]
+    workspace: clap_cargo::Workspace,
+
+    #[command(flatten)]
+    config: crate::config::ConfigFileArgs,
+
+    /// Seconds to wait for each package before giving up
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_PUBLISH_TIMEOUT.as_secs())]
+    timeout: u64,
+
+    /// Seconds between checks of the index
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_POLL_INTERVAL.as_secs())]
+    poll_interval: u64,
+
+    /// Re-clone the local crates.io index if it is corrupted
+    #[arg(long)]
+    repair_index: bool,
+
+    /// How to reach the crates.io index, defaulting to `CARGO_REGISTRIES_CRATES_IO_PROTOCOL`
+    #[arg(long = "index", value_name = "PROTOCOL", value_enum)]
+    index_protocol: Option<crate::ops::index::IndexProtocol>,
+
+    #[command(flatten)]
+    dry_run: super::DryRunArgs,
+}
+
+impl WaitStep {
+    pub fn run(&self) -> Result<(), CliError> {
+        self.dry_run.check();
+        let ws_meta = self
+            .manifest
+            .metadata()
+            // When evaluating dependency ordering, we need to consider optional dependencies
+            .features(cargo_metadata::CargoOpt::AllFeatures)
+            .exec()?;
+        let config = self.to_config();
+        let mut pkgs = plan::load(&config, &ws_meta)?;
+
+        let (_selected_pkgs, excluded_pkgs) = self.workspace.partition_packages(&ws_meta);
+        for excluded_pkg in excluded_pkgs {
+            let pkg = if let Some(pkg) = pkgs.get_mut(&excluded_pkg.id) {
+                pkg
+            } else {
+                // Either not in workspace or marked as `release = false`.
+                continue;
+            };
+            if !pkg.config.release() {
+                continue;
+            }
+
+            pkg.config.publish = Some(false);
+            pkg.skip(plan::SkipReason::Excluded);
+
+            let crate_name = pkg.meta.name.as_str();
+            log::debug!("disabled by user, skipping {}", crate_name,);
+        }
+
+        let pkgs = plan::plan(pkgs)?;
+
+        let (selected_pkgs, excluded_pkgs): (Vec<_>, Vec<_>) = pkgs
+            .into_iter()
+            .map(|(_, pkg)| pkg)
+            .partition(|p| p.config.release() && p.config.publish());
+        super::require_selected(&selected_pkgs, &excluded_pkgs)?;
+
+        if crate::ops::cargo::is_offline() {
+            let _ = crate::ops::reporter::error(
+                "cannot wait for the registry while offline (`CARGO_NET_OFFLINE` is set)",
+            );
+            return Err(101.into());
+        }
+
+        // Waiting is pointless without an index
+        let mut index = crate::ops::index::CratesIoIndex::open(
+            self.index_protocol
+                .unwrap_or_else(crate::ops::index::IndexProtocol::from_env),
+            self.repair_index,
+            true,
+        )?;
+        wait(
+            &selected_pkgs,
+            &mut index,
+            &mut Default::default(),
+            std::time::Duration::from_secs(self.timeout),
+            std::time::Duration::from_secs(self.poll_interval),
+        )?;
+
+        super::report_skipped(&excluded_pkgs);
+        Ok(())
+    }
+
+    fn to_config(&self) -> crate::config::ConfigArgs {
+        crate::config::ConfigArgs {
+            custom_config: self.config.custom_config.clone(),
+            isolated: self.config.isolated,
+            ..Default::default()
+        }
+    }
+}
+
+/// Wait on crates.io, or the `index-url` a package is published to
+pub fn wait(
+    pkgs: &[plan::PackageRelease],
+    index: &mut impl crate::ops::cargo::PublishIndex,
+    index_urls: &mut crate::ops::index::IndexUrls,
+    timeout: std::time::Duration,
+    poll_interval: std::time::Duration,
+) -> Result<(), CliError> {
+    for pkg in pkgs {
+        match (pkg.config.index_url(), pkg.config.registry()) {
+            (Some(index_url), _) => {
+                wait_one(pkg, index_urls.open(index_url), timeout, poll_interval)?;
+            }
+            (None, None) => wait_one(pkg, index, timeout, poll_interval)?,
+            (None, Some(_)) => {
+                log::debug!(
+                    "not waiting for {} because the registry is not crates.io and doesn't get updated automatically",
+                    pkg.meta.name
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn wait_one(
+    pkg: &plan::PackageRelease,
+    index: &mut impl crate::ops::cargo::PublishIndex,
+    timeout: std::time::Duration,
+    poll_interval: std::time::Duration,
+) -> Result<(), CliError> {
+    let crate_name = pkg.meta.name.as_str();
+    let _timer = crate::ops::timings::start(format_args!("wait for {}", crate_name));
+    let version = pkg.planned_version.as_ref().unwrap_or(&pkg.initial_version);
+    // The upload was in an earlier run, so only what shows up from here on can be someone else's
+    if let Err(err) = index.update_crate(pkg.published_name()) {
+        log::debug!("crate index update failed with {}", err);
+    }
+    let known = index.versions(pkg.published_name());
+    crate::ops::cargo::wait_for_publish(
+        index,
+        pkg.published_name(),
+        &version.full_version_string,
+        &known,
+        timeout,
+        poll_interval,
+        false,
+    )?;
+    let _ = crate::ops::reporter::package("Published", crate_name, &version.full_version_string);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::error::CargoResult;
+    use crate::ops::cargo::fixture::FlipIndex;
+
+    /// Only knows about `name`
+    struct NamedIndex(&'static str);
+
+    impl crate::ops::cargo::PublishIndex for NamedIndex {
+        fn update(&mut self) -> CargoResult<()> {
+            Ok(())
+        }
+
+        fn is_published(&self, name: &str, _version: &str) -> bool {
+            name == self.0
+        }
+    }
+
+    fn simple() -> plan::PackageRelease {
+        let args = crate::config::ConfigArgs {
+            isolated: true,
+            ..Default::default()
+        };
+        let (_, pkgs) = plan::fixture::load("tests/fixtures/simple", &args);
+        pkgs.into_values().next().unwrap()
+    }
+
+    #[test]
+    fn waits_on_published_name() {
+        let mut pkg = simple();
+        pkg.config.publish_as = Some("simple-oss".to_owned());
+        let mut index = NamedIndex("simple-oss");
+
+        wait(
+            std::slice::from_ref(&pkg),
+            &mut index,
+            &mut Default::default(),
+            std::time::Duration::from_millis(100),
+            std::time::Duration::from_millis(1),
+        )
+        .unwrap();
+
+        pkg.config.publish_as = None;
+        let err = wait(
+            std::slice::from_ref(&pkg),
+            &mut index,
+            &mut Default::default(),
+            std::time::Duration::from_millis(10),
+            std::time::Duration::from_millis(1),
+        );
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn waits_until_index_flips() {
+        let pkg = simple();
+        let mut index = FlipIndex {
+            polls: 0,
+            published_after: 3,
+        };
+
+        wait(
+            std::slice::from_ref(&pkg),
+            &mut index,
+            &mut Default::default(),
+            std::time::Duration::from_secs(60),
+            std::time::Duration::from_millis(1),
+        )
+        .unwrap();
+        assert_eq!(index.polls, 3);
+    }
+}
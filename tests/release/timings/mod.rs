@@ -0,0 +1,29 @@
+use crate::git_from;
+use crate::init_registry;
+use crate::CargoCommand;
+use cargo_test_support::curr_dir;
+
+#[cargo_test]
+fn case() {
+    init_registry();
+    let project = git_from(curr_dir!().join("in"));
+    let project_root = project.root();
+    let cwd = &project_root;
+
+    // Phase durations vary between runs, so only check which phases were recorded
+    let output = snapbox::cmd::Command::cargo_ui()
+        .arg("release")
+        .args(["--timings=json", "--index=sparse", "--no-publish"])
+        .current_dir(cwd)
+        .env("CARGO_NET_OFFLINE", "true")
+        .output()
+        .unwrap();
+    let recorded: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let phases = recorded
+        .iter()
+        .map(|timing| timing["phase"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    for expected in ["metadata", "load sample", "verify_git_is_clean"] {
+        assert!(phases.contains(&expected), "{:?}", phases);
+    }
+}
@@ -5,6 +5,7 @@
 #[macro_use]
 extern crate cargo_test_macro;
 
+mod release;
 mod version;
 
 fn init_registry() {
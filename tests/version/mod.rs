@@ -1,10 +1,14 @@
 mod downgrade_error;
 mod dry_run;
 mod ignore_dependent;
+mod quiet_summary;
+mod quiet_summary_failure;
 mod set_absolute_version;
 mod set_absolute_workspace_version;
 mod set_relative_version;
 mod set_relative_workspace_version;
+mod sync_dependents;
+mod sync_dependents_noop;
 mod upgrade_compatible_dependency;
 mod upgrade_incompatible_dependency;
 mod upgrade_workspace;